@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+
+DELETE FROM "roles" WHERE "role_id" IN (0, 1, 2, 3);
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+
+INSERT INTO "roles"("role_id", "role_name") VALUES
+	(0, 'Dev'),
+	(1, 'PropertyAdmin'),
+	(2, 'PropertyManager'),
+	(3, 'PropertyUser')
+ON CONFLICT ("role_id") DO NOTHING;
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+CREATE TABLE "property_tables"(
+	"table_id" serial NOT NULL PRIMARY KEY,
+	"property_id" UUID NOT NULL,
+	"table_name" VARCHAR NOT NULL,
+	"capacity" INT4 NOT NULL,
+	"section" VARCHAR,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	UNIQUE ("property_id", "table_name")
+);
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+ALTER TABLE "reservation" ADD COLUMN "status" INT4 NOT NULL DEFAULT 0;
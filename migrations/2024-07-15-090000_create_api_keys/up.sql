@@ -0,0 +1,14 @@
+-- Your SQL goes here
+
+CREATE TABLE "api_keys"(
+	"key_id" serial NOT NULL PRIMARY KEY,
+	"property_id" UUID NOT NULL,
+	"key_name" VARCHAR NOT NULL,
+	"key_prefix" VARCHAR NOT NULL UNIQUE,
+	"key_hash" VARCHAR NOT NULL,
+	"scopes" INT4 NOT NULL,
+	"created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+	"last_used_at" TIMESTAMP,
+	"revoked_at" TIMESTAMP,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id")
+);
@@ -0,0 +1,29 @@
+-- Your SQL goes here
+
+CREATE TABLE "webhooks"(
+	"webhook_id" serial NOT NULL PRIMARY KEY,
+	"property_id" UUID NOT NULL,
+	"url" VARCHAR NOT NULL,
+	"secret" VARCHAR NOT NULL,
+	"events" INT4 NOT NULL,
+	"active" BOOL NOT NULL DEFAULT TRUE,
+	"created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id")
+);
+
+CREATE TABLE "webhook_deliveries"(
+	"delivery_id" serial NOT NULL PRIMARY KEY,
+	"webhook_id" INT4 NOT NULL,
+	"event" VARCHAR NOT NULL,
+	"payload" JSONB NOT NULL,
+	"status" INT4 NOT NULL DEFAULT 0,
+	"attempts" INT4 NOT NULL DEFAULT 0,
+	"next_attempt_at" TIMESTAMP NOT NULL,
+	"response_status" INT4,
+	"last_error" VARCHAR,
+	"created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+	"delivered_at" TIMESTAMP,
+	FOREIGN KEY ("webhook_id") REFERENCES "webhooks"("webhook_id")
+);
+
+CREATE INDEX "webhook_deliveries_due" ON "webhook_deliveries"("status", "next_attempt_at");
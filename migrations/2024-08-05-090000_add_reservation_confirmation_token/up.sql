@@ -0,0 +1,7 @@
+-- Your SQL goes here
+
+ALTER TABLE "reservation" ADD COLUMN "confirmation_token" VARCHAR;
+UPDATE "reservation" SET "confirmation_token" = md5(random()::text || "id"::text);
+ALTER TABLE "reservation" ADD COLUMN "confirmation_code" VARCHAR;
+UPDATE "reservation" SET "confirmation_code" = upper(left(md5(random()::text || "id"::text), 8));
+CREATE UNIQUE INDEX "reservation_confirmation_token" ON "reservation"("confirmation_token");
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+
+CREATE TABLE "reservation_history"(
+	"entry_id" serial NOT NULL PRIMARY KEY,
+	"reservation_id" INT4 NOT NULL,
+	"changed_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+	"changed_by" VARCHAR NOT NULL,
+	"summary" VARCHAR NOT NULL,
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
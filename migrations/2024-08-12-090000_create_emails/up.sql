@@ -0,0 +1,31 @@
+-- Your SQL goes here
+
+CREATE TABLE "email_templates"(
+	"property_id" UUID NOT NULL,
+	"kind" INT4 NOT NULL,
+	"subject" VARCHAR NOT NULL,
+	"body" TEXT NOT NULL,
+	PRIMARY KEY ("property_id", "kind"),
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id")
+);
+
+CREATE TABLE "emails"(
+	"email_id" serial NOT NULL PRIMARY KEY,
+	"property_id" UUID NOT NULL,
+	"reservation_id" INT4,
+	"kind" INT4 NOT NULL,
+	"recipient" VARCHAR NOT NULL,
+	"reply_to" VARCHAR,
+	"subject" VARCHAR NOT NULL,
+	"body" TEXT NOT NULL,
+	"status" INT4 NOT NULL DEFAULT 0,
+	"attempts" INT4 NOT NULL DEFAULT 0,
+	"next_attempt_at" TIMESTAMP NOT NULL,
+	"last_error" VARCHAR,
+	"created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+	"sent_at" TIMESTAMP,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
+
+CREATE INDEX "emails_due" ON "emails"("status", "next_attempt_at");
@@ -0,0 +1,30 @@
+-- Your SQL goes here
+
+CREATE TABLE "sms_messages"(
+	"message_id" serial NOT NULL PRIMARY KEY,
+	"property_id" UUID NOT NULL,
+	"reservation_id" INT4,
+	"kind" INT4 NOT NULL,
+	"channel" VARCHAR NOT NULL,
+	"recipient" VARCHAR NOT NULL,
+	"body" TEXT NOT NULL,
+	"status" INT4 NOT NULL DEFAULT 0,
+	"attempts" INT4 NOT NULL DEFAULT 0,
+	"next_attempt_at" TIMESTAMP NOT NULL,
+	"provider_id" VARCHAR,
+	"last_error" VARCHAR,
+	"created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+	"sent_at" TIMESTAMP,
+	"delivered_at" TIMESTAMP,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
+
+CREATE INDEX "sms_messages_due" ON "sms_messages"("status", "next_attempt_at");
+CREATE INDEX "sms_messages_provider_id" ON "sms_messages"("provider_id");
+
+-- Numbers in E.164 that replied STOP, kept across properties.
+CREATE TABLE "sms_opt_outs"(
+	"phone" VARCHAR NOT NULL PRIMARY KEY,
+	"opted_out_at" TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,22 @@
+-- Your SQL goes here
+
+CREATE TABLE "jobs"(
+	"job_id" serial NOT NULL PRIMARY KEY,
+	"kind" INT4 NOT NULL,
+	-- Set for jobs about one reservation, periodic jobs have neither.
+	"property_id" UUID,
+	"reservation_id" INT4,
+	-- At most one job per key, scheduling it again moves the existing one.
+	"unique_key" VARCHAR UNIQUE,
+	"run_at" TIMESTAMP NOT NULL,
+	"status" INT4 NOT NULL DEFAULT 0,
+	"attempts" INT4 NOT NULL DEFAULT 0,
+	"last_error" VARCHAR,
+	"locked_until" TIMESTAMP,
+	"created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+	"finished_at" TIMESTAMP,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
+
+CREATE INDEX "jobs_due" ON "jobs"("status", "run_at");
@@ -0,0 +1,26 @@
+-- Your SQL goes here
+
+CREATE TABLE "guests"(
+	"guest_id" serial NOT NULL PRIMARY KEY,
+	"property_id" UUID NOT NULL,
+	"name" VARCHAR NOT NULL,
+	"notes" VARCHAR NOT NULL DEFAULT '',
+	-- Comma separated labels such as VIP or a nut allergy.
+	"tags" VARCHAR NOT NULL DEFAULT '',
+	"created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id")
+);
+
+-- Normalised phone numbers and email addresses bookings are matched by,
+-- each belongs to at most one guest of a property.
+CREATE TABLE "guest_contacts"(
+	"property_id" UUID NOT NULL,
+	"contact" VARCHAR NOT NULL,
+	"guest_id" INT4 NOT NULL,
+	PRIMARY KEY ("property_id", "contact"),
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	FOREIGN KEY ("guest_id") REFERENCES "guests"("guest_id")
+);
+
+ALTER TABLE "reservation" ADD COLUMN "guest_id" INT4 REFERENCES "guests"("guest_id");
+CREATE INDEX "reservation_guest" ON "reservation"("guest_id");
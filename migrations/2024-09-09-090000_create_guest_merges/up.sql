@@ -0,0 +1,21 @@
+-- Your SQL goes here
+
+-- Audit of duplicate guests merged into the guest that was kept. The merged
+-- guest is deleted, its name and contacts are kept here.
+CREATE TABLE "guest_merges"(
+	"merge_id" serial NOT NULL PRIMARY KEY,
+	"property_id" UUID NOT NULL,
+	"guest_id" INT4 NOT NULL,
+	"merged_guest_id" INT4 NOT NULL,
+	"merged_name" VARCHAR NOT NULL,
+	-- Comma separated normalised contacts.
+	"merged_contacts" VARCHAR NOT NULL,
+	-- Number of reservations moved to the kept guest.
+	"reservations" INT4 NOT NULL,
+	"merged_by" VARCHAR NOT NULL,
+	"merged_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	FOREIGN KEY ("guest_id") REFERENCES "guests"("guest_id")
+);
+
+CREATE INDEX "guest_merges_guest" ON "guest_merges"("guest_id");
@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS "reservation_notes_reservation";
+DROP TABLE IF EXISTS "reservation_notes";
+ALTER TABLE "reservation" DROP COLUMN "tags";
+ALTER TABLE "reservation" DROP COLUMN "seating_request";
+ALTER TABLE "reservation" DROP COLUMN "accessibility";
+ALTER TABLE "reservation" DROP COLUMN "high_chairs";
+ALTER TABLE "reservation" DROP COLUMN "dietary";
+ALTER TABLE "reservation" DROP COLUMN "occasion";
@@ -0,0 +1,20 @@
+-- Your SQL goes here
+
+-- What the guest asked for, shown to the floor staff on the day sheet.
+ALTER TABLE "reservation" ADD COLUMN "occasion" INT4;
+ALTER TABLE "reservation" ADD COLUMN "dietary" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "reservation" ADD COLUMN "high_chairs" INT4 NOT NULL DEFAULT 0;
+ALTER TABLE "reservation" ADD COLUMN "accessibility" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "reservation" ADD COLUMN "seating_request" VARCHAR NOT NULL DEFAULT '';
+-- Comma separated labels such as VIP or press.
+ALTER TABLE "reservation" ADD COLUMN "tags" VARCHAR NOT NULL DEFAULT '';
+
+CREATE TABLE "reservation_notes"(
+	"note_id" serial NOT NULL PRIMARY KEY,
+	"reservation_id" INT4 NOT NULL,
+	"author" VARCHAR NOT NULL,
+	"body" VARCHAR NOT NULL,
+	"created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
+CREATE INDEX "reservation_notes_reservation" ON "reservation_notes"("reservation_id");
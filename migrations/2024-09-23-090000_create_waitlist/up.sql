@@ -0,0 +1,22 @@
+-- Your SQL goes here
+
+CREATE TABLE "waitlist"(
+	"entry_id" serial NOT NULL PRIMARY KEY,
+	"property_id" UUID NOT NULL,
+	"waitlist_date" DATE NOT NULL,
+	"name" VARCHAR NOT NULL,
+	"contact" VARCHAR NOT NULL,
+	"party_size" INT4 NOT NULL,
+	-- The time the guest hoped for, NULL for walk-ins waiting for the next table.
+	"preferred_time" TIME,
+	-- Minutes the guest was told to expect.
+	"quoted_wait" INT4 NOT NULL DEFAULT 0,
+	"status" INT4 NOT NULL DEFAULT 0,
+	"notified_at" TIMESTAMP,
+	-- The booking the entry became.
+	"reservation_id" INT4,
+	"created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
+CREATE INDEX "waitlist_property_date" ON "waitlist"("property_id", "waitlist_date");
@@ -0,0 +1,37 @@
+-- Your SQL goes here
+
+-- Days a property does not open, from the first to the last day.
+CREATE TABLE "closures"(
+	"closure_id" serial NOT NULL PRIMARY KEY,
+	"property_id" UUID NOT NULL,
+	"first_day" DATE NOT NULL,
+	"last_day" DATE NOT NULL,
+	"reason" VARCHAR NOT NULL DEFAULT '',
+	"created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id")
+);
+CREATE INDEX "closures_property" ON "closures"("property_id");
+
+-- A standing reservation repeated every week, every other week or every
+-- month, until a last day or for a number of occurrences.
+CREATE TABLE "reservation_series"(
+	"series_id" serial NOT NULL PRIMARY KEY,
+	"property_id" UUID NOT NULL,
+	"name" VARCHAR NOT NULL,
+	"contact" VARCHAR NOT NULL,
+	-- A table name, empty to take the smallest free table each time.
+	"seating" VARCHAR NOT NULL DEFAULT '',
+	"party_size" INT4 NOT NULL,
+	"reservation_time" TIME NOT NULL,
+	"recurrence" INT4 NOT NULL DEFAULT 0,
+	"starts_on" DATE NOT NULL,
+	"ends_on" DATE,
+	"occurrences" INT4,
+	"cancelled_at" TIMESTAMP,
+	"created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id")
+);
+CREATE INDEX "reservation_series_property" ON "reservation_series"("property_id");
+
+ALTER TABLE "reservation" ADD COLUMN "series_id" INT4 REFERENCES "reservation_series"("series_id");
+CREATE INDEX "reservation_in_series" ON "reservation"("series_id");
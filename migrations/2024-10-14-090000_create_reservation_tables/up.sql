@@ -0,0 +1,21 @@
+-- Your SQL goes here
+
+-- The tables a group booking or event holds, beyond its single seating.
+CREATE TABLE "reservation_tables"(
+	"reservation_id" INT4 NOT NULL,
+	"table_id" INT4 NOT NULL,
+	PRIMARY KEY ("reservation_id", "table_id"),
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id"),
+	FOREIGN KEY ("table_id") REFERENCES "property_tables"("table_id")
+);
+CREATE INDEX "reservation_tables_table" ON "reservation_tables"("table_id");
+
+-- What an event booking agreed on.
+CREATE TABLE "reservation_events"(
+	"reservation_id" INT4 NOT NULL PRIMARY KEY,
+	-- The section bought out, all of its tables are held.
+	"section" VARCHAR,
+	"menu" VARCHAR NOT NULL DEFAULT '',
+	"minimum_spend" INT4,
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
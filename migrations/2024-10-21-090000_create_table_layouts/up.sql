@@ -0,0 +1,14 @@
+-- Your SQL goes here
+
+-- Where a table sits on the floor plan of its property, in plan units.
+CREATE TABLE "table_layouts"(
+	"table_id" INT4 NOT NULL PRIMARY KEY,
+	"x" INT4 NOT NULL,
+	"y" INT4 NOT NULL,
+	"width" INT4 NOT NULL,
+	"height" INT4 NOT NULL,
+	-- Clockwise, in degrees.
+	"rotation" INT4 NOT NULL DEFAULT 0,
+	"shape" INT4 NOT NULL DEFAULT 0,
+	FOREIGN KEY ("table_id") REFERENCES "property_tables"("table_id")
+);
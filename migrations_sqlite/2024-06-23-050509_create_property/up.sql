@@ -0,0 +1,9 @@
+-- Your SQL goes here
+
+CREATE TABLE "property"(
+	"property_id" TEXT NOT NULL PRIMARY KEY,
+	"property_name" TEXT NOT NULL,
+	"property_password" TEXT NOT NULL,
+	"property_email" TEXT NOT NULL,
+	"property_phone" TEXT NOT NULL
+);
@@ -0,0 +1,17 @@
+-- Your SQL goes here
+
+CREATE TABLE "reservation"(
+	"id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"name" TEXT NOT NULL,
+	"contact" TEXT NOT NULL,
+	"seating" TEXT NOT NULL,
+	"specific_seating_requested" BOOL NOT NULL,
+	"advance" BOOL NOT NULL,
+	"advance_method" TEXT NOT NULL,
+	"advance_amount" INTEGER,
+	"confirmed" BOOL NOT NULL,
+	"reservation_date" DATE NOT NULL,
+	"reservation_time" TIME NOT NULL,
+	"property_id" TEXT NOT NULL,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id")
+);
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+CREATE TABLE "roles"(
+	"role_id" INTEGER NOT NULL PRIMARY KEY,
+	"role_name" VARCHAR(15) NOT NULL
+);
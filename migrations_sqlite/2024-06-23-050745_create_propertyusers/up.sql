@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+CREATE TABLE "propertyusers"(
+	"user_id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"user_name" TEXT NOT NULL,
+	"user_password" TEXT NOT NULL,
+	"user_role" INTEGER NOT NULL,
+	"property_id" TEXT NOT NULL,
+	FOREIGN KEY ("user_role") REFERENCES "roles"("role_id"),
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id")
+);
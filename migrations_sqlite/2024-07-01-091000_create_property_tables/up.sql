@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+CREATE TABLE "property_tables"(
+	"table_id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"property_id" TEXT NOT NULL,
+	"table_name" TEXT NOT NULL,
+	"capacity" INTEGER NOT NULL,
+	"section" TEXT,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	UNIQUE ("property_id", "table_name")
+);
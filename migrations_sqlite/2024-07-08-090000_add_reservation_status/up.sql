@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+ALTER TABLE "reservation" ADD COLUMN "status" INTEGER NOT NULL DEFAULT 0;
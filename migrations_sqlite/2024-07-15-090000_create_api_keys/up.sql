@@ -0,0 +1,14 @@
+-- Your SQL goes here
+
+CREATE TABLE "api_keys"(
+	"key_id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"property_id" TEXT NOT NULL,
+	"key_name" TEXT NOT NULL,
+	"key_prefix" TEXT NOT NULL UNIQUE,
+	"key_hash" TEXT NOT NULL,
+	"scopes" INTEGER NOT NULL,
+	"created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	"last_used_at" TIMESTAMP,
+	"revoked_at" TIMESTAMP,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id")
+);
@@ -0,0 +1,29 @@
+-- Your SQL goes here
+
+CREATE TABLE "webhooks"(
+	"webhook_id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"property_id" TEXT NOT NULL,
+	"url" TEXT NOT NULL,
+	"secret" TEXT NOT NULL,
+	"events" INTEGER NOT NULL,
+	"active" BOOL NOT NULL DEFAULT TRUE,
+	"created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id")
+);
+
+CREATE TABLE "webhook_deliveries"(
+	"delivery_id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"webhook_id" INTEGER NOT NULL,
+	"event" TEXT NOT NULL,
+	"payload" TEXT NOT NULL,
+	"status" INTEGER NOT NULL DEFAULT 0,
+	"attempts" INTEGER NOT NULL DEFAULT 0,
+	"next_attempt_at" TIMESTAMP NOT NULL,
+	"response_status" INTEGER,
+	"last_error" TEXT,
+	"created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	"delivered_at" TIMESTAMP,
+	FOREIGN KEY ("webhook_id") REFERENCES "webhooks"("webhook_id")
+);
+
+CREATE INDEX "webhook_deliveries_due" ON "webhook_deliveries"("status", "next_attempt_at");
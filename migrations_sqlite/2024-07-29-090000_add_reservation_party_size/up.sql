@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+-- Older reservations did not record covers, they count as a table for two.
+ALTER TABLE "reservation" ADD COLUMN "party_size" INTEGER NOT NULL DEFAULT 2;
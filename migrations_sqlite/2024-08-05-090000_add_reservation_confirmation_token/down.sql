@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS "reservation_confirmation_token";
+ALTER TABLE "reservation" DROP COLUMN "confirmation_code";
+ALTER TABLE "reservation" DROP COLUMN "confirmation_token";
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+
+ALTER TABLE "reservation" ADD COLUMN "confirmation_token" TEXT;
+UPDATE "reservation" SET "confirmation_token" = lower(hex(randomblob(16)));
+ALTER TABLE "reservation" ADD COLUMN "confirmation_code" TEXT;
+UPDATE "reservation" SET "confirmation_code" = hex(randomblob(4));
+CREATE UNIQUE INDEX "reservation_confirmation_token" ON "reservation"("confirmation_token");
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+
+CREATE TABLE "reservation_history"(
+	"entry_id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"reservation_id" INTEGER NOT NULL,
+	"changed_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	"changed_by" TEXT NOT NULL,
+	"summary" TEXT NOT NULL,
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS "emails";
+DROP TABLE IF EXISTS "email_templates";
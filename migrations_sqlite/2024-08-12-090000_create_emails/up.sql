@@ -0,0 +1,31 @@
+-- Your SQL goes here
+
+CREATE TABLE "email_templates"(
+	"property_id" TEXT NOT NULL,
+	"kind" INTEGER NOT NULL,
+	"subject" TEXT NOT NULL,
+	"body" TEXT NOT NULL,
+	PRIMARY KEY ("property_id", "kind"),
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id")
+);
+
+CREATE TABLE "emails"(
+	"email_id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"property_id" TEXT NOT NULL,
+	"reservation_id" INTEGER,
+	"kind" INTEGER NOT NULL,
+	"recipient" TEXT NOT NULL,
+	"reply_to" TEXT,
+	"subject" TEXT NOT NULL,
+	"body" TEXT NOT NULL,
+	"status" INTEGER NOT NULL DEFAULT 0,
+	"attempts" INTEGER NOT NULL DEFAULT 0,
+	"next_attempt_at" TIMESTAMP NOT NULL,
+	"last_error" TEXT,
+	"created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	"sent_at" TIMESTAMP,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
+
+CREATE INDEX "emails_due" ON "emails"("status", "next_attempt_at");
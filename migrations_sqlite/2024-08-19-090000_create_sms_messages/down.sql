@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS "sms_opt_outs";
+DROP TABLE IF EXISTS "sms_messages";
@@ -0,0 +1,30 @@
+-- Your SQL goes here
+
+CREATE TABLE "sms_messages"(
+	"message_id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"property_id" TEXT NOT NULL,
+	"reservation_id" INTEGER,
+	"kind" INTEGER NOT NULL,
+	"channel" TEXT NOT NULL,
+	"recipient" TEXT NOT NULL,
+	"body" TEXT NOT NULL,
+	"status" INTEGER NOT NULL DEFAULT 0,
+	"attempts" INTEGER NOT NULL DEFAULT 0,
+	"next_attempt_at" TIMESTAMP NOT NULL,
+	"provider_id" TEXT,
+	"last_error" TEXT,
+	"created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	"sent_at" TIMESTAMP,
+	"delivered_at" TIMESTAMP,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
+
+CREATE INDEX "sms_messages_due" ON "sms_messages"("status", "next_attempt_at");
+CREATE INDEX "sms_messages_provider_id" ON "sms_messages"("provider_id");
+
+-- Numbers in E.164 that replied STOP, kept across properties.
+CREATE TABLE "sms_opt_outs"(
+	"phone" TEXT NOT NULL PRIMARY KEY,
+	"opted_out_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,22 @@
+-- Your SQL goes here
+
+CREATE TABLE "jobs"(
+	"job_id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"kind" INTEGER NOT NULL,
+	-- Set for jobs about one reservation, periodic jobs have neither.
+	"property_id" TEXT,
+	"reservation_id" INTEGER,
+	-- At most one job per key, scheduling it again moves the existing one.
+	"unique_key" TEXT UNIQUE,
+	"run_at" TIMESTAMP NOT NULL,
+	"status" INTEGER NOT NULL DEFAULT 0,
+	"attempts" INTEGER NOT NULL DEFAULT 0,
+	"last_error" TEXT,
+	"locked_until" TIMESTAMP,
+	"created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	"finished_at" TIMESTAMP,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
+
+CREATE INDEX "jobs_due" ON "jobs"("status", "run_at");
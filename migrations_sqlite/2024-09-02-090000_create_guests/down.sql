@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS "reservation_guest";
+ALTER TABLE "reservation" DROP COLUMN "guest_id";
+DROP TABLE IF EXISTS "guest_contacts";
+DROP TABLE IF EXISTS "guests";
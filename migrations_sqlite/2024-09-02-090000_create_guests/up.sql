@@ -0,0 +1,26 @@
+-- Your SQL goes here
+
+CREATE TABLE "guests"(
+	"guest_id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"property_id" TEXT NOT NULL,
+	"name" TEXT NOT NULL,
+	"notes" TEXT NOT NULL DEFAULT '',
+	-- Comma separated labels such as VIP or a nut allergy.
+	"tags" TEXT NOT NULL DEFAULT '',
+	"created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id")
+);
+
+-- Normalised phone numbers and email addresses bookings are matched by,
+-- each belongs to at most one guest of a property.
+CREATE TABLE "guest_contacts"(
+	"property_id" TEXT NOT NULL,
+	"contact" TEXT NOT NULL,
+	"guest_id" INTEGER NOT NULL,
+	PRIMARY KEY ("property_id", "contact"),
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	FOREIGN KEY ("guest_id") REFERENCES "guests"("guest_id")
+);
+
+ALTER TABLE "reservation" ADD COLUMN "guest_id" INTEGER REFERENCES "guests"("guest_id");
+CREATE INDEX "reservation_guest" ON "reservation"("guest_id");
@@ -0,0 +1,21 @@
+-- Your SQL goes here
+
+-- Audit of duplicate guests merged into the guest that was kept. The merged
+-- guest is deleted, its name and contacts are kept here.
+CREATE TABLE "guest_merges"(
+	"merge_id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"property_id" TEXT NOT NULL,
+	"guest_id" INTEGER NOT NULL,
+	"merged_guest_id" INTEGER NOT NULL,
+	"merged_name" TEXT NOT NULL,
+	-- Comma separated normalised contacts.
+	"merged_contacts" TEXT NOT NULL,
+	-- Number of reservations moved to the kept guest.
+	"reservations" INTEGER NOT NULL,
+	"merged_by" TEXT NOT NULL,
+	"merged_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	FOREIGN KEY ("guest_id") REFERENCES "guests"("guest_id")
+);
+
+CREATE INDEX "guest_merges_guest" ON "guest_merges"("guest_id");
@@ -0,0 +1,20 @@
+-- Your SQL goes here
+
+-- What the guest asked for, shown to the floor staff on the day sheet.
+ALTER TABLE "reservation" ADD COLUMN "occasion" INTEGER;
+ALTER TABLE "reservation" ADD COLUMN "dietary" TEXT NOT NULL DEFAULT '';
+ALTER TABLE "reservation" ADD COLUMN "high_chairs" INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE "reservation" ADD COLUMN "accessibility" TEXT NOT NULL DEFAULT '';
+ALTER TABLE "reservation" ADD COLUMN "seating_request" TEXT NOT NULL DEFAULT '';
+-- Comma separated labels such as VIP or press.
+ALTER TABLE "reservation" ADD COLUMN "tags" TEXT NOT NULL DEFAULT '';
+
+CREATE TABLE "reservation_notes"(
+	"note_id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"reservation_id" INTEGER NOT NULL,
+	"author" TEXT NOT NULL,
+	"body" TEXT NOT NULL,
+	"created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
+CREATE INDEX "reservation_notes_reservation" ON "reservation_notes"("reservation_id");
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS "waitlist_property_date";
+DROP TABLE IF EXISTS "waitlist";
@@ -0,0 +1,22 @@
+-- Your SQL goes here
+
+CREATE TABLE "waitlist"(
+	"entry_id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"property_id" TEXT NOT NULL,
+	"waitlist_date" DATE NOT NULL,
+	"name" TEXT NOT NULL,
+	"contact" TEXT NOT NULL,
+	"party_size" INTEGER NOT NULL,
+	-- The time the guest hoped for, NULL for walk-ins waiting for the next table.
+	"preferred_time" TIME,
+	-- Minutes the guest was told to expect.
+	"quoted_wait" INTEGER NOT NULL DEFAULT 0,
+	"status" INTEGER NOT NULL DEFAULT 0,
+	"notified_at" TIMESTAMP,
+	-- The booking the entry became.
+	"reservation_id" INTEGER,
+	"created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	FOREIGN KEY ("property_id") REFERENCES "property"("property_id"),
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
+CREATE INDEX "waitlist_property_date" ON "waitlist"("property_id", "waitlist_date");
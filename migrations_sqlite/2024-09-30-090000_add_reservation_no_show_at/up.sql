@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+-- When the reservation was marked as a no-show.
+ALTER TABLE "reservation" ADD COLUMN "no_show_at" TIMESTAMP;
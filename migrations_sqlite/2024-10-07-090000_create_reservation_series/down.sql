@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS "reservation_in_series";
+ALTER TABLE "reservation" DROP COLUMN "series_id";
+DROP INDEX IF EXISTS "reservation_series_property";
+DROP TABLE IF EXISTS "reservation_series";
+DROP INDEX IF EXISTS "closures_property";
+DROP TABLE IF EXISTS "closures";
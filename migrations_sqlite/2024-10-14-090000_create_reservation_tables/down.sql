@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS "reservation_events";
+DROP INDEX IF EXISTS "reservation_tables_table";
+DROP TABLE IF EXISTS "reservation_tables";
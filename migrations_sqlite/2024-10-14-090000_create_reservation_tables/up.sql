@@ -0,0 +1,21 @@
+-- Your SQL goes here
+
+-- The tables a group booking or event holds, beyond its single seating.
+CREATE TABLE "reservation_tables"(
+	"reservation_id" INTEGER NOT NULL,
+	"table_id" INTEGER NOT NULL,
+	PRIMARY KEY ("reservation_id", "table_id"),
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id"),
+	FOREIGN KEY ("table_id") REFERENCES "property_tables"("table_id")
+);
+CREATE INDEX "reservation_tables_table" ON "reservation_tables"("table_id");
+
+-- What an event booking agreed on.
+CREATE TABLE "reservation_events"(
+	"reservation_id" INTEGER NOT NULL PRIMARY KEY,
+	-- The section bought out, all of its tables are held.
+	"section" TEXT,
+	"menu" TEXT NOT NULL DEFAULT '',
+	"minimum_spend" INTEGER,
+	FOREIGN KEY ("reservation_id") REFERENCES "reservation"("id")
+);
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+
+-- Where a table sits on the floor plan of its property, in plan units.
+CREATE TABLE "table_layouts"(
+	"table_id" INTEGER NOT NULL PRIMARY KEY,
+	"x" INTEGER NOT NULL,
+	"y" INTEGER NOT NULL,
+	"width" INTEGER NOT NULL,
+	"height" INTEGER NOT NULL,
+	-- Clockwise, in degrees.
+	"rotation" INTEGER NOT NULL DEFAULT 0,
+	"shape" INTEGER NOT NULL DEFAULT 0,
+	FOREIGN KEY ("table_id") REFERENCES "property_tables"("table_id")
+);
@@ -0,0 +1,283 @@
+//! Proposes which tables the reservations of a day should sit at.
+//!
+//! Bookings made online land on the smallest table free when they were made,
+//! and those taken by staff on whatever was typed in, so by the evening the
+//! floor is rarely laid out well. The table plan places every reservation of
+//! the day still to come again, greedily and largest parties first: each
+//! goes on the free table, or free tables of one section pushed together,
+//! that leaves the fewest of its seats empty, without trying other orders,
+//! so the plan as a whole may leave more seats empty than needed. A table is
+//! free for a reservation when nothing else sits at it within
+//! [`TURN_MINUTES`](crate::booking::TURN_MINUTES) of its time.
+//!
+//! Parties already seated, reservations with a table the guest asked for and
+//! group bookings staff put on several tables keep their tables, seated
+//! parties for the whole evening. Staff accept the whole plan or move single
+//! reservations on the group bookings page.
+use crate::booking::overlaps;
+use crate::errors::RepositoryError;
+use crate::events::{seat_on, seating};
+use crate::manage::hhmm;
+use crate::models::{
+    HistoryEntry, NewHistoryEntry, PlannedSeating, PropertyTable, Reservation, ReservationStatus,
+    TablePlan, UnplacedReservation, WebhookEvent,
+};
+use crate::repository::{PropertyRepository, RepoResult, ReservationRepository};
+use crate::AppState;
+use chrono::NaiveDate;
+use leptos::{expect_context, server, ServerFnError};
+use time::Time;
+use uuid::Uuid;
+
+/// Most tables of a section pushed together for one party.
+pub const MAX_COMBINED: usize = 3;
+
+/// The single tables and combinations of up to [`MAX_COMBINED`] tables of the
+/// same section a party can be put on.
+fn options(tables: &[PropertyTable]) -> Vec<Vec<&PropertyTable>> {
+    let mut options: Vec<Vec<&PropertyTable>> = tables.iter().map(|table| vec![table]).collect();
+    let mut sections: Vec<&str> = tables
+        .iter()
+        .filter_map(|table| table.section.as_deref())
+        .collect();
+    sections.sort_unstable();
+    sections.dedup();
+    for section in sections {
+        let mut members: Vec<&PropertyTable> = tables
+            .iter()
+            .filter(|table| table.section.as_deref() == Some(section))
+            .collect();
+        members.sort_by(|a, b| a.table_name.cmp(&b.table_name));
+        let mut combined: Vec<Vec<&PropertyTable>> = vec![Vec::new()];
+        for table in members {
+            for i in 0..combined.len() {
+                if combined[i].len() < MAX_COMBINED {
+                    let mut more = combined[i].clone();
+                    more.push(table);
+                    combined.push(more);
+                }
+            }
+        }
+        options.extend(combined.into_iter().filter(|option| option.len() > 1));
+    }
+    options
+}
+
+fn capacity(option: &[&PropertyTable]) -> i32 {
+    option.iter().map(|table| table.capacity).sum()
+}
+
+/// Proposes tables for the reservations of `date` not seated yet, without
+/// changing anything.
+pub fn plan_tables(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    pid: Uuid,
+    date: NaiveDate,
+) -> RepoResult<TablePlan> {
+    let tables = properties.tables(pid)?;
+    let day: Vec<Reservation> = reservations
+        .on_date(pid, date)?
+        .into_iter()
+        .filter(|resv| {
+            matches!(
+                resv.status,
+                ReservationStatus::Booked
+                    | ReservationStatus::Requested
+                    | ReservationStatus::Seated
+            )
+        })
+        .collect();
+    let ids: Vec<i32> = day.iter().map(|resv| resv.id).collect();
+    let assignments = reservations.table_assignments(&ids)?;
+
+    // Tables taken, with the time of the reservation taking them, or none
+    // when a seated party holds them however long it stays.
+    let mut held: Vec<(i32, Option<Time>)> = Vec::new();
+    let (mut placed, mut unplaced, mut pending) = (Vec::new(), Vec::new(), Vec::new());
+    for resv in day {
+        let mut kept: Vec<&PropertyTable> = assignments
+            .iter()
+            .filter(|assignment| assignment.reservation_id == resv.id)
+            .filter_map(|assignment| {
+                tables
+                    .iter()
+                    .find(|table| table.table_id == assignment.table_id)
+            })
+            .collect();
+        let named = tables
+            .iter()
+            .find(|table| table.table_name.eq_ignore_ascii_case(resv.seating.trim()));
+        if kept.is_empty()
+            && (resv.status == ReservationStatus::Seated || resv.specific_seating_requested)
+        {
+            kept.extend(named);
+        }
+        let until = (resv.status != ReservationStatus::Seated).then_some(resv.reservation_time);
+        held.extend(kept.iter().map(|table| (table.table_id, until)));
+
+        if resv.status == ReservationStatus::Seated {
+            continue;
+        }
+        if !kept.is_empty() {
+            placed.push(PlannedSeating {
+                tables: kept.iter().map(|table| table.table_name.clone()).collect(),
+                wasted: (capacity(&kept) - resv.party_size).max(0),
+                fixed: true,
+                reservation: resv,
+            });
+        } else if resv.specific_seating_requested {
+            unplaced.push(UnplacedReservation {
+                reason: format!("asked for {}, which is not a table", resv.seating),
+                reservation: resv,
+            });
+        } else {
+            pending.push(resv);
+        }
+    }
+
+    let options = options(&tables);
+    pending.sort_by_key(|resv| (-resv.party_size, resv.reservation_time, resv.id));
+    for resv in pending {
+        let free = |option: &&Vec<&PropertyTable>| {
+            option.iter().all(|table| {
+                !held.iter().any(|&(table_id, time)| {
+                    table_id == table.table_id
+                        && time.is_none_or(|time| overlaps(time, resv.reservation_time))
+                })
+            })
+        };
+        let best = options
+            .iter()
+            .filter(|option| capacity(option) >= resv.party_size)
+            .filter(free)
+            .min_by_key(|option| {
+                (
+                    capacity(option) - resv.party_size,
+                    option.len(),
+                    !seating(option).eq_ignore_ascii_case(resv.seating.trim()),
+                    seating(option),
+                )
+            });
+        match best {
+            Some(option) => {
+                held.extend(
+                    option
+                        .iter()
+                        .map(|table| (table.table_id, Some(resv.reservation_time))),
+                );
+                placed.push(PlannedSeating {
+                    tables: option
+                        .iter()
+                        .map(|table| table.table_name.clone())
+                        .collect(),
+                    wasted: capacity(option) - resv.party_size,
+                    fixed: false,
+                    reservation: resv,
+                });
+            }
+            None => {
+                let reason = if options
+                    .iter()
+                    .any(|option| capacity(option) >= resv.party_size)
+                {
+                    format!(
+                        "every table for {} is taken around {}",
+                        resv.party_size,
+                        hhmm(resv.reservation_time)
+                    )
+                } else {
+                    format!(
+                        "no table or tables pushed together seat {}",
+                        resv.party_size
+                    )
+                };
+                unplaced.push(UnplacedReservation {
+                    reservation: resv,
+                    reason,
+                });
+            }
+        }
+    }
+
+    placed.sort_by_key(|seat| (seat.reservation.reservation_time, seat.reservation.id));
+    unplaced.sort_by_key(|seat| (seat.reservation.reservation_time, seat.reservation.id));
+    Ok(TablePlan {
+        date,
+        wasted_seats: placed.iter().map(|seat| seat.wasted).sum(),
+        placed,
+        unplaced,
+    })
+}
+
+/// Moves every reservation of `date` the plan puts elsewhere and returns
+/// them as moved.
+pub fn accept_plan(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    pid: Uuid,
+    date: NaiveDate,
+) -> RepoResult<Vec<Reservation>> {
+    let tables = properties.tables(pid)?;
+    let plan = plan_tables(properties, reservations, pid, date)?;
+    let mut moved = Vec::new();
+    for seat in plan.placed.iter().filter(|seat| seat.moves()) {
+        let chosen: Vec<&PropertyTable> = seat
+            .tables
+            .iter()
+            .map(|name| {
+                tables
+                    .iter()
+                    .find(|table| &table.table_name == name)
+                    .ok_or(RepositoryError::NotFound)
+            })
+            .collect::<RepoResult<_>>()?;
+        let resv = seat_on(reservations, &seat.reservation, &chosen)?;
+        reservations.insert_history(NewHistoryEntry {
+            reservation_id: resv.id,
+            changed_by: HistoryEntry::STAFF.to_string(),
+            summary: match seat.reservation.seating.trim() {
+                "" => format!("assigned {} by the table plan", resv.seating),
+                before => format!("moved from {before} to {} by the table plan", resv.seating),
+            },
+        })?;
+        moved.push(resv);
+    }
+    Ok(moved)
+}
+
+#[server(name = ProposeTables, prefix = "/api", endpoint = "propose_tables", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn propose_tables(pid: Uuid, date: NaiveDate) -> Result<TablePlan, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    plan_tables(
+        state.properties.as_ref(),
+        state.reservations.as_ref(),
+        pid,
+        date,
+    )
+    .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(name = AcceptTablePlan, prefix = "/api", endpoint = "accept_table_plan", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn accept_table_plan(
+    pid: Uuid,
+    date: NaiveDate,
+) -> Result<Vec<Reservation>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    match accept_plan(
+        state.properties.as_ref(),
+        state.reservations.as_ref(),
+        pid,
+        date,
+    ) {
+        Ok(moved) => {
+            for resv in &moved {
+                state.publish(WebhookEvent::ReservationDetailsChanged, resv);
+            }
+            Ok(moved)
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
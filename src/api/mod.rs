@@ -0,0 +1,283 @@
+//! Versioned public REST API for systems that cannot use the Leptos server
+//! functions.
+//!
+//! Handlers share the business rules of [`crate::crud_properties`], answer
+//! with JSON only and report failures as an [`ErrorBody`]. The OpenAPI
+//! document is built from the same handler annotations as the router, and is
+//! served at `/api/openapi.json` with a docs page at `/api/docs`.
+//!
+//! Callers authenticate either with an operator key from `RESVM_API_KEYS`,
+//! which may do everything, or with a property API key (see
+//! [`crate::api_keys`]) that only reaches its own property within its scopes.
+pub mod v1;
+
+use crate::api_keys::verify_key;
+use crate::errors::RepositoryError;
+use crate::models::{ApiKey as PropertyKey, ApiScope, Reservation};
+use crate::repository::{ApiKeyRepository, ReservationRepository};
+use crate::webhooks::{sign, verify};
+use crate::AppState;
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::{env, sync::Arc};
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi, ToSchema,
+};
+use utoipa_axum::router::OpenApiRouter;
+use uuid::Uuid;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "resvm", description = "Reservations and properties of resvm."),
+    modifiers(&SecuritySchemes),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub struct ApiDoc;
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Api-Key"))),
+        );
+    }
+}
+
+/// Error payload of every failed API request.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: ErrorDetail,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorDetail {
+    pub status: u16,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub enum ApiError {
+    Repository(RepositoryError),
+    BadRequest(String),
+    Unauthorized,
+    Forbidden,
+}
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+impl From<RepositoryError> for ApiError {
+    fn from(e: RepositoryError) -> Self {
+        ApiError::Repository(e)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::Repository(e) => (e.status_code(), e.to_string()),
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                "a valid API key is required".to_string(),
+            ),
+            ApiError::Forbidden => (
+                StatusCode::FORBIDDEN,
+                "the API key does not grant this request".to_string(),
+            ),
+        };
+        let body = ErrorBody {
+            error: ErrorDetail {
+                status: status.as_u16(),
+                message,
+            },
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+/// Keys accepted by the API, read from the comma separated `RESVM_API_KEYS`.
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeys(Arc<Vec<String>>);
+
+impl ApiKeys {
+    pub fn new(keys: Vec<String>) -> Self {
+        Self(Arc::new(keys))
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            env::var("RESVM_API_KEYS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(String::from)
+                .collect(),
+        )
+    }
+
+    /// Whether `key` is one of the keys, compared in constant time by
+    /// checking its signature under each of them.
+    pub fn accepts(&self, key: &str) -> bool {
+        let signature = sign(key, 0, b"");
+        self.0.iter().any(|known| verify(known, 0, b"", &signature))
+    }
+}
+
+/// The key sent as `Authorization: Bearer <key>` or `X-Api-Key: <key>`.
+pub fn request_key(req: &Request) -> Option<&str> {
+    let headers = req.headers();
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| {
+            headers
+                .get("x-api-key")
+                .and_then(|value| value.to_str().ok())
+        })
+        .map(str::trim)
+}
+
+/// Who is calling, added to the request extensions by the auth middleware.
+#[derive(Debug, Clone)]
+pub enum ApiCaller {
+    /// An operator key from `RESVM_API_KEYS`.
+    Operator,
+    /// A property API key.
+    Property(PropertyKey),
+}
+
+impl ApiCaller {
+    /// Fails unless the caller may use `scope` on property `pid`.
+    pub fn allow(&self, pid: Uuid, scope: ApiScope) -> ApiResult<()> {
+        match self {
+            ApiCaller::Operator => Ok(()),
+            ApiCaller::Property(key) if key.property_id == pid && key.has_scope(scope) => Ok(()),
+            ApiCaller::Property(_) => Err(ApiError::Forbidden),
+        }
+    }
+
+    /// Reservation `id` when the caller may use `scope` on its property.
+    ///
+    /// The scope is checked before the lookup, and a reservation of another
+    /// property is reported as not found like an unknown id, so a property key
+    /// cannot probe which ids exist.
+    pub fn find_reservation(
+        &self,
+        reservations: &dyn ReservationRepository,
+        id: i32,
+        scope: ApiScope,
+    ) -> ApiResult<Reservation> {
+        if let ApiCaller::Property(key) = self {
+            if !key.has_scope(scope) {
+                return Err(ApiError::Forbidden);
+            }
+        }
+        let resv = reservations.find(id)?;
+        match self {
+            ApiCaller::Property(key) if key.property_id != resv.property_id => {
+                Err(RepositoryError::NotFound.into())
+            }
+            _ => Ok(resv),
+        }
+    }
+
+    /// Fails unless the caller holds an operator key.
+    pub fn operator(&self) -> ApiResult<()> {
+        match self {
+            ApiCaller::Operator => Ok(()),
+            ApiCaller::Property(_) => Err(ApiError::Forbidden),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ApiAuth {
+    operator_keys: ApiKeys,
+    property_keys: Arc<dyn ApiKeyRepository>,
+}
+
+async fn require_api_key(
+    State(auth): State<ApiAuth>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let caller = match request_key(&req) {
+        None => return Err(ApiError::Unauthorized),
+        Some(key) if auth.operator_keys.accepts(key) => ApiCaller::Operator,
+        Some(key) => match verify_key(auth.property_keys.as_ref(), key) {
+            Ok(api_key) => ApiCaller::Property(api_key),
+            Err(RepositoryError::NotFound) => return Err(ApiError::Unauthorized),
+            Err(e) => return Err(e.into()),
+        },
+    };
+    req.extensions_mut().insert(caller);
+    Ok(next.run(req).await)
+}
+
+/// The caller set by [`require_api_key`].
+pub type Caller = Extension<ApiCaller>;
+
+fn versions() -> OpenApiRouter<AppState> {
+    OpenApiRouter::with_openapi(ApiDoc::openapi()).nest("/api/v1", v1::router())
+}
+
+/// The OpenAPI document of all API versions.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    versions().into_openapi()
+}
+
+/// All API versions, mounted at `/api/v1`, plus the OpenAPI document and its
+/// docs page.
+pub fn router(state: &AppState) -> Router<AppState> {
+    let auth = ApiAuth {
+        operator_keys: ApiKeys::from_env(),
+        property_keys: state.api_keys.clone(),
+    };
+    if auth.operator_keys.0.is_empty() {
+        leptos::logging::warn!("RESVM_API_KEYS is empty, only property API keys are accepted");
+    }
+    let (api, openapi) = versions()
+        .layer(middleware::from_fn_with_state(auth, require_api_key))
+        .split_for_parts();
+
+    api.route(
+        "/api/openapi.json",
+        get(move || async move { Json(openapi) }),
+    )
+    .route("/api/docs", get(docs))
+}
+
+/// Renders `/api/openapi.json` with a pinned Redoc release.
+async fn docs() -> Html<&'static str> {
+    Html(
+        r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>resvm API</title>
+    <meta charset="utf-8"/>
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+  </head>
+  <body>
+    <redoc spec-url="/api/openapi.json"></redoc>
+    <script src="https://cdn.redoc.ly/redoc/v2.1.5/bundles/redoc.standalone.js"></script>
+  </body>
+</html>"#,
+    )
+}
@@ -0,0 +1,347 @@
+use super::{ApiCaller, ApiError, ApiResult, Caller, ErrorBody};
+use crate::crud_properties::{cancel_reservation, create_reservation, record_payment};
+use crate::errors::RepositoryError;
+use crate::models::{
+    ApiScope, NewResv, PaymentMethod, Property, PropertyTable, Reservation, ReservationStatus,
+    WebhookEvent,
+};
+use crate::AppState;
+use axum::{
+    extract::{rejection::JsonRejection, rejection::QueryRejection, Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+
+pub fn router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(list_properties))
+        .routes(routes!(get_property))
+        .routes(routes!(list_tables))
+        .routes(routes!(list_reservations, add_reservation))
+        .routes(routes!(get_reservation))
+        .routes(routes!(cancel))
+        .routes(routes!(get_payment, put_payment))
+}
+
+fn json_body<T>(body: Result<Json<T>, JsonRejection>) -> ApiResult<T> {
+    body.map(|Json(value)| value)
+        .map_err(|e| ApiError::BadRequest(e.body_text()))
+}
+
+/// Most reservations a listing returns at once.
+pub const MAX_LIMIT: i64 = 500;
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReservationQuery {
+    /// Only reservations on this day, all of them otherwise.
+    pub date: Option<NaiveDate>,
+    /// Maximum number of reservations without `date`, 50 by default and at
+    /// most 500.
+    pub limit: Option<i64>,
+    /// Only reservations carrying this tag, ignoring case.
+    pub tag: Option<String>,
+}
+
+impl ReservationQuery {
+    /// The requested limit, or the default, refused outside `1..=MAX_LIMIT`.
+    pub fn limit(&self) -> ApiResult<i64> {
+        match self.limit.unwrap_or(50) {
+            limit @ 1..=MAX_LIMIT => Ok(limit),
+            _ => Err(ApiError::BadRequest(format!(
+                "limit must be between 1 and {MAX_LIMIT}"
+            ))),
+        }
+    }
+}
+
+/// The advance recorded for a reservation.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Payment {
+    pub advance: bool,
+    pub advance_amount: Option<i32>,
+    pub advance_method: Option<PaymentMethod>,
+}
+
+/// Body of `PUT /reservations/{id}/payment`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PaymentInput {
+    #[serde(flatten)]
+    pub method: PaymentMethod,
+    pub amount: i32,
+}
+
+impl From<Reservation> for Payment {
+    fn from(resv: Reservation) -> Self {
+        Payment {
+            advance: resv.advance,
+            advance_amount: resv.advance_amount,
+            advance_method: serde_json::from_value(resv.advance_method).ok(),
+        }
+    }
+}
+
+/// All properties, or only its own one for a property API key.
+#[utoipa::path(
+    get,
+    path = "/properties",
+    tag = "properties",
+    responses(
+        (status = 200, body = Vec<Property>),
+        (status = 401, body = ErrorBody),
+        (status = 403, body = ErrorBody),
+    )
+)]
+async fn list_properties(
+    State(state): State<AppState>,
+    Extension(caller): Caller,
+) -> ApiResult<Json<Vec<Property>>> {
+    match caller {
+        ApiCaller::Operator => Ok(Json(state.properties.all()?)),
+        ApiCaller::Property(key) => Ok(Json(vec![state.properties.find(key.property_id)?])),
+    }
+}
+
+/// A single property.
+#[utoipa::path(
+    get,
+    path = "/properties/{pid}",
+    tag = "properties",
+    params(("pid" = Uuid, Path, description = "Property id")),
+    responses(
+        (status = 200, body = Property),
+        (status = 401, body = ErrorBody),
+        (status = 403, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+    )
+)]
+async fn get_property(
+    State(state): State<AppState>,
+    Extension(caller): Caller,
+    Path(pid): Path<Uuid>,
+) -> ApiResult<Json<Property>> {
+    caller.allow(pid, ApiScope::ReadAvailability)?;
+    Ok(Json(state.properties.find(pid)?))
+}
+
+/// The predefined tables of a property.
+#[utoipa::path(
+    get,
+    path = "/properties/{pid}/tables",
+    tag = "tables",
+    params(("pid" = Uuid, Path, description = "Property id")),
+    responses(
+        (status = 200, body = Vec<PropertyTable>),
+        (status = 401, body = ErrorBody),
+        (status = 403, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+    )
+)]
+async fn list_tables(
+    State(state): State<AppState>,
+    Extension(caller): Caller,
+    Path(pid): Path<Uuid>,
+) -> ApiResult<Json<Vec<PropertyTable>>> {
+    caller.allow(pid, ApiScope::ReadAvailability)?;
+    state.properties.find(pid)?;
+    Ok(Json(state.properties.tables(pid)?))
+}
+
+/// Reservations of a property.
+#[utoipa::path(
+    get,
+    path = "/properties/{pid}/reservations",
+    tag = "reservations",
+    params(("pid" = Uuid, Path, description = "Property id"), ReservationQuery),
+    responses(
+        (status = 200, body = Vec<Reservation>),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+        (status = 403, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+    )
+)]
+async fn list_reservations(
+    State(state): State<AppState>,
+    Extension(caller): Caller,
+    Path(pid): Path<Uuid>,
+    query: Result<Query<ReservationQuery>, QueryRejection>,
+) -> ApiResult<Json<Vec<Reservation>>> {
+    caller.allow(pid, ApiScope::ReadReservations)?;
+    let Query(query) = query.map_err(|e| ApiError::BadRequest(e.body_text()))?;
+    let limit = query.limit()?;
+    state.properties.find(pid)?;
+    let tag = query.tag.as_deref().filter(|tag| !tag.trim().is_empty());
+    let reservations = match (query.date, tag) {
+        (Some(date), tag) => {
+            let mut reservations = state.reservations.on_date(pid, date)?;
+            if let Some(tag) = tag {
+                reservations.retain(|resv| resv.has_tag(tag));
+            }
+            reservations
+        }
+        (None, Some(tag)) => state.reservations.with_tag(pid, tag, limit)?,
+        (None, None) => state.reservations.for_property(pid, limit)?,
+    };
+    Ok(Json(reservations))
+}
+
+/// Creates a reservation through the same rules as the reservation form.
+#[utoipa::path(
+    post,
+    path = "/properties/{pid}/reservations",
+    tag = "reservations",
+    params(("pid" = Uuid, Path, description = "Property id")),
+    request_body = NewResv,
+    responses(
+        (status = 201, body = Reservation),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+        (status = 403, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 409, body = ErrorBody, description = "The requested seating is taken"),
+        (status = 422, body = ErrorBody),
+    )
+)]
+async fn add_reservation(
+    State(state): State<AppState>,
+    Extension(caller): Caller,
+    Path(pid): Path<Uuid>,
+    body: Result<Json<NewResv>, JsonRejection>,
+) -> ApiResult<(StatusCode, Json<Reservation>)> {
+    caller.allow(pid, ApiScope::CreateReservations)?;
+    let new_resv = json_body(body)?;
+    state.properties.find(pid)?;
+    if new_resv.property_id != pid {
+        return Err(RepositoryError::Invalid("property_id does not match the url".into()).into());
+    }
+    if !matches!(
+        new_resv.status,
+        ReservationStatus::Booked | ReservationStatus::Requested
+    ) {
+        return Err(RepositoryError::Invalid(format!(
+            "new reservations cannot be {}",
+            new_resv.status
+        ))
+        .into());
+    }
+    serde_json::from_value::<PaymentMethod>(new_resv.advance_method.clone())
+        .map_err(|e| RepositoryError::Invalid(format!("advance_method: {e}")))?;
+
+    let rid = create_reservation(
+        state.properties.as_ref(),
+        state.reservations.as_ref(),
+        state.guests.as_ref(),
+        &state.no_show_policy,
+        new_resv,
+    )?;
+    let resv = state.reservations.find(rid)?;
+    state.publish(WebhookEvent::ReservationCreated, &resv);
+    Ok((StatusCode::CREATED, Json(resv)))
+}
+
+/// A single reservation.
+#[utoipa::path(
+    get,
+    path = "/reservations/{id}",
+    tag = "reservations",
+    params(("id" = i32, Path, description = "Reservation id")),
+    responses(
+        (status = 200, body = Reservation),
+        (status = 401, body = ErrorBody),
+        (status = 403, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+    )
+)]
+async fn get_reservation(
+    State(state): State<AppState>,
+    Extension(caller): Caller,
+    Path(id): Path<i32>,
+) -> ApiResult<Json<Reservation>> {
+    let resv =
+        caller.find_reservation(state.reservations.as_ref(), id, ApiScope::ReadReservations)?;
+    Ok(Json(resv))
+}
+
+/// Cancels a reservation that has not been seated yet. Operator keys only.
+#[utoipa::path(
+    post,
+    path = "/reservations/{id}/cancel",
+    tag = "reservations",
+    params(("id" = i32, Path, description = "Reservation id")),
+    responses(
+        (status = 200, body = Reservation),
+        (status = 401, body = ErrorBody),
+        (status = 403, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 409, body = ErrorBody),
+    )
+)]
+async fn cancel(
+    State(state): State<AppState>,
+    Extension(caller): Caller,
+    Path(id): Path<i32>,
+) -> ApiResult<Json<Reservation>> {
+    caller.operator()?;
+    let resv = cancel_reservation(state.reservations.as_ref(), id)?;
+    state.publish(WebhookEvent::ReservationCancelled, &resv);
+    Ok(Json(resv))
+}
+
+/// The advance recorded for a reservation.
+#[utoipa::path(
+    get,
+    path = "/reservations/{id}/payment",
+    tag = "payments",
+    params(("id" = i32, Path, description = "Reservation id")),
+    responses(
+        (status = 200, body = Payment),
+        (status = 401, body = ErrorBody),
+        (status = 403, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+    )
+)]
+async fn get_payment(
+    State(state): State<AppState>,
+    Extension(caller): Caller,
+    Path(id): Path<i32>,
+) -> ApiResult<Json<Payment>> {
+    let resv =
+        caller.find_reservation(state.reservations.as_ref(), id, ApiScope::ReadReservations)?;
+    Ok(Json(resv.into()))
+}
+
+/// Records the advance paid for a reservation. Operator keys only.
+#[utoipa::path(
+    put,
+    path = "/reservations/{id}/payment",
+    tag = "payments",
+    params(("id" = i32, Path, description = "Reservation id")),
+    request_body = PaymentInput,
+    responses(
+        (status = 200, body = Payment),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+        (status = 403, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 409, body = ErrorBody),
+        (status = 422, body = ErrorBody),
+    )
+)]
+async fn put_payment(
+    State(state): State<AppState>,
+    Extension(caller): Caller,
+    Path(id): Path<i32>,
+    body: Result<Json<PaymentInput>, JsonRejection>,
+) -> ApiResult<Json<Payment>> {
+    caller.operator()?;
+    let input = json_body(body)?;
+    let resv = record_payment(state.reservations.as_ref(), id, input.method, input.amount)?;
+    state.publish(WebhookEvent::PaymentRecorded, &resv);
+    Ok(Json(resv.into()))
+}
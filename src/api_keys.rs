@@ -0,0 +1,160 @@
+//! API keys that let a property's own integrations use the REST API.
+//!
+//! A key reads `rvm_<prefix>_<secret>`. The prefix is stored in clear and
+//! identifies the row, the secret is only kept as an argon2 hash, so the full
+//! key is shown once when it is created and cannot be recovered afterwards.
+//! Keys are managed by the property's admins through the server functions
+//! below.
+use crate::errors::RepositoryError;
+use crate::models::{ApiKey, ApiScope, NewApiKey, Role};
+use crate::repository::{ApiKeyRepository, PropertyRepository, RepoResult};
+use crate::salt_password;
+use crate::AppState;
+use chrono::Utc;
+use leptos::{expect_context, server, ServerFnError};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const KEY_TAG: &str = "rvm";
+
+/// A freshly created key, the only time the plaintext is available.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IssuedKey {
+    pub key_id: i32,
+    pub key: String,
+}
+
+/// Splits a key into its prefix and secret.
+fn parse_key(key: &str) -> Option<(&str, &str)> {
+    let rest = key.strip_prefix(KEY_TAG)?.strip_prefix('_')?;
+    let (prefix, secret) = rest.split_once('_')?;
+    (!prefix.is_empty() && !secret.is_empty()).then_some((prefix, secret))
+}
+
+/// Creates a key for a property and returns it in full.
+pub async fn issue_key(
+    repo: &dyn ApiKeyRepository,
+    pid: Uuid,
+    name: String,
+    scopes: &[ApiScope],
+) -> RepoResult<IssuedKey> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(RepositoryError::Invalid("a key needs a name".into()));
+    }
+    if scopes.is_empty() {
+        return Err(RepositoryError::Invalid(
+            "a key needs at least one scope".into(),
+        ));
+    }
+
+    let prefix = Uuid::new_v4().simple().to_string()[..8].to_string();
+    let secret = Uuid::new_v4().simple().to_string();
+    let key_hash = salt_password(secret.clone())
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+    let key_id = repo.insert_key(NewApiKey {
+        property_id: pid,
+        key_name: name,
+        key_prefix: prefix.clone(),
+        key_hash,
+        scopes: ApiScope::mask(scopes),
+    })?;
+
+    Ok(IssuedKey {
+        key_id,
+        key: format!("{KEY_TAG}_{prefix}_{secret}"),
+    })
+}
+
+/// The active key matching `key`, recording that it was used. Unknown,
+/// malformed and revoked keys are all reported as `NotFound`.
+pub fn verify_key(repo: &dyn ApiKeyRepository, key: &str) -> RepoResult<ApiKey> {
+    let (prefix, secret) = parse_key(key).ok_or(RepositoryError::NotFound)?;
+    let api_key = repo.key_by_prefix(prefix)?;
+    if api_key.revoked_at.is_some()
+        || !argon2::verify_encoded(&api_key.key_hash, secret.as_bytes()).unwrap_or(false)
+    {
+        return Err(RepositoryError::NotFound);
+    }
+    repo.touch_key(api_key.key_id, Utc::now().naive_utc())?;
+    Ok(api_key)
+}
+
+/// Checks that `user_name` is an admin of the property and knows the password.
+pub fn verify_admin(
+    repo: &dyn PropertyRepository,
+    pid: Uuid,
+    user_name: &str,
+    password: &str,
+) -> RepoResult<()> {
+    let denied = || RepositoryError::Invalid("admin credentials are required".into());
+    let user = repo.find_user(pid, user_name).map_err(|e| match e {
+        RepositoryError::NotFound => denied(),
+        e => e,
+    })?;
+    let valid = argon2::verify_encoded(&user.user_password, password.as_bytes()).unwrap_or(false);
+    if user.user_role != Role::PropertyAdmin as i32 || !valid {
+        return Err(denied());
+    }
+    Ok(())
+}
+
+#[server(name = ListApiKeys, prefix = "/api", endpoint = "list_api_keys", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn list_api_keys(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+) -> Result<Vec<ApiKey>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| state.api_keys.keys(pid))
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(name = CreateApiKey, prefix = "/api", endpoint = "create_api_key", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn create_api_key(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    key_name: String,
+    read_availability: Option<bool>,
+    create_reservations: Option<bool>,
+    read_reservations: Option<bool>,
+) -> Result<IssuedKey, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    let scopes: Vec<ApiScope> = [
+        (read_availability, ApiScope::ReadAvailability),
+        (create_reservations, ApiScope::CreateReservations),
+        (read_reservations, ApiScope::ReadReservations),
+    ]
+    .into_iter()
+    .filter_map(|(checked, scope)| checked.unwrap_or(false).then_some(scope))
+    .collect();
+
+    let issued = match verify_admin(state.properties.as_ref(), pid, &user_name, &password) {
+        Ok(()) => issue_key(state.api_keys.as_ref(), pid, key_name, &scopes).await,
+        Err(e) => Err(e),
+    };
+    issued.map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(name = RevokeApiKey, prefix = "/api", endpoint = "revoke_api_key", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn revoke_api_key(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    key_id: i32,
+) -> Result<ApiKey, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| {
+            state
+                .api_keys
+                .revoke_key(pid, key_id, Utc::now().naive_utc())
+        })
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
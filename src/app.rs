@@ -1,16 +1,34 @@
-use crate::{
-    crud_properties::{
-        add_reservation, get_all_properties, get_property_reservations, total_resv, AddResv,
-        PropertyIdParam,
-    },
-    models::NewResv,
+use crate::allocation::{propose_tables, AcceptTablePlan};
+use crate::api_keys::{CreateApiKey, ListApiKeys, RevokeApiKey};
+use crate::booking::{booking_info, booking_slots, BookTable};
+use crate::crud_properties::{
+    get_all_properties, get_property_reservations, total_resv, AddResv, PropertyIdParam,
+    SeatWalkIn, SetResvStatus,
 };
-use leptos::*;
-use leptos_meta::{provide_meta_context, Meta, Stylesheet};
-use leptos_router::{
-    use_params, use_params_map, ActionForm, MultiActionForm, Outlet, Params, Route, Router, Routes,
-    A,
+use crate::emails::{ListEmailTemplates, ListEmails, SaveEmailTemplate, PLACEHOLDERS};
+use crate::events::{list_group_bookings, AssignTables, BookSection, SetEvent};
+use crate::floor::{get_floor_plan, get_floor_view, SaveTableLayout, FLOOR_HEIGHT, FLOOR_WIDTH};
+use crate::guests::{FindDuplicateGuests, GetGuest, ListGuests, MergeGuests, UpdateGuest};
+use crate::jobs::{ListJobs, RetryJob};
+use crate::live::{use_reservation_changes, LiveFeedToken};
+use crate::manage::{manage_booking, manage_slots, CancelBooking, ModifyBooking, TokenParam};
+use crate::models::{
+    DaySheetEntry, FloorTable, GroupBooking, Guest, GuestProfile, Occasion, Recurrence,
+    ReservationStatus, SeriesOverview, SeriesSchedule, TableShape, WaitingGuest,
+};
+use crate::series::{
+    list_closures, list_series, upcoming, AddClosure, AddSeries, CancelOccurrence, CancelSeries,
+    EditOccurrence, EditSeries, RemoveClosure,
 };
+use crate::sms::{ListSmsMessages, SetSmsOptOut};
+use crate::special_requests::{get_day_sheet, AddReservationNote, SetSpecialRequests};
+use crate::waitlist::{
+    list_waitlist, AddToWaitlist, NotifyWaitlistGuest, PromoteWaitlistEntry, RemoveWaitlistEntry,
+};
+use crate::webhooks::{CreateWebhook, ListWebhooks, ReplayDelivery, SetWebhookActive};
+use leptos::*;
+use leptos_meta::{provide_meta_context, Stylesheet};
+use leptos_router::{use_params, ActionForm, Outlet, Route, Router, Routes, A};
 
 #[component]
 pub fn ResvmApp() -> impl IntoView {
@@ -26,9 +44,24 @@ pub fn ResvmApp() -> impl IntoView {
             <main>
                 <Routes>
                     <Route path="/" view=|| view! { <h1>"Make this the homepage"</h1> }/>
+                    <Route path="/book/:pid" view=BookingPage/>
+                    <Route path="/manage/:token" view=ManagePage/>
                     <Route path="/Property" view=Properties>
                         <Route path=":pid" view=PropertyReservations>
                             <Route path="add_resv" view=AddReservation/>
+                            <Route path="api_keys" view=ApiKeys/>
+                            <Route path="webhooks" view=Webhooks/>
+                            <Route path="emails" view=Emails/>
+                            <Route path="messages" view=Messages/>
+                            <Route path="jobs" view=Jobs/>
+                            <Route path="guests" view=Guests/>
+                            <Route path="day_sheet" view=DaySheet/>
+                            <Route path="waitlist" view=Waitlist/>
+                            <Route path="series" view=StandingReservations/>
+                            <Route path="events" view=GroupBookings/>
+                            <Route path="table_plan" view=TablePlanPage/>
+                            <Route path="floor_plan" view=FloorPlanEditor/>
+                            <Route path="floor" view=LiveFloor/>
                             <Route
                                 path=""
                                 view=|| {
@@ -58,6 +91,381 @@ pub fn ResvmApp() -> impl IntoView {
     }
 }
 
+#[component]
+fn BookingPage() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    let book = create_server_action::<BookTable>();
+    let form = create_resource(pid, booking_info);
+    let (date, set_date) = create_signal(chrono::Local::now().date_naive().to_string());
+    let (party_size, set_party_size) = create_signal(2);
+    let slots = create_resource(
+        move || (pid(), date.get(), party_size.get(), book.version().get()),
+        |(pid, date, party_size, _)| async move {
+            match date.parse() {
+                Ok(date) => booking_slots(pid, date, party_size).await,
+                Err(_) => Ok(Vec::new()),
+            }
+        },
+    );
+
+    view! {
+        <Suspense fallback=move || view! { <p>"Loading"</p> }>
+            {move || {
+                form.get()
+                    .map(|form| match form {
+                        Err(_) => view! { <p>"This property does not take online bookings."</p> }
+                            .into_view(),
+                        Ok(form) => {
+                            view! {
+                                <h2>"Book a table at " {form.property_name}</h2>
+                                <ActionForm action=book class="container">
+                                    <input type="hidden" name="pid" prop:value=move || pid().to_string()/>
+                                    <input type="hidden" name="issued_at" value=form.issued_at/>
+                                    <input type="hidden" name="token" value=form.token/>
+                                    // Left empty by people, bots tend to fill every field.
+                                    <div style="position:absolute;left:-10000px" aria-hidden="true">
+                                        <label>
+                                            "Website: "
+                                            <input type="text" name="website" tabindex="-1" autocomplete="off"/>
+                                        </label>
+                                    </div>
+                                    <label>
+                                        "Date: "
+                                        <input
+                                            type="date"
+                                            name="reservation_date"
+                                            on:input=move |ev| set_date.set(event_target_value(&ev))
+                                            prop:value=date
+                                        />
+                                    </label>
+                                    <label>
+                                        "Guests: "
+                                        <input
+                                            type="number"
+                                            name="party_size"
+                                            min="1"
+                                            max=form.max_party_size
+                                            on:input=move |ev| {
+                                                set_party_size
+                                                    .set(event_target_value(&ev).parse().unwrap_or(1))
+                                            }
+                                            prop:value=party_size
+                                        />
+                                    </label>
+                                    <fieldset>
+                                        <legend>"Time"</legend>
+                                        <Transition fallback=move || view! { <p>"Loading times"</p> }>
+                                            {move || {
+                                                slots
+                                                    .get()
+                                                    .map(|slots| match slots {
+                                                        Ok(slots) if slots.is_empty() => {
+                                                            view! { <p>"No tables are free on this day."</p> }
+                                                                .into_view()
+                                                        }
+                                                        Ok(slots) => {
+                                                            slots
+                                                                .into_iter()
+                                                                .map(|slot| {
+                                                                    let label = format!("{}:{}", &slot[..2], &slot[2..]);
+                                                                    view! {
+                                                                        <label>
+                                                                            <input
+                                                                                type="radio"
+                                                                                name="reservation_time"
+                                                                                value=slot
+                                                                            />
+                                                                            {label}
+                                                                        </label>
+                                                                    }
+                                                                })
+                                                                .collect_view()
+                                                        }
+                                                        Err(e) => {
+                                                            view! { <pre class="error">{e.to_string()}</pre> }
+                                                                .into_view()
+                                                        }
+                                                    })
+                                            }}
+                                        </Transition>
+                                    </fieldset>
+                                    <label>"Name: " <input type="text" name="name" required/></label>
+                                    <label>
+                                        "Phone or email: " <input type="text" name="contact" required/>
+                                    </label>
+                                    <fieldset>
+                                        <legend>"Special requests"</legend>
+                                        <label>
+                                            "Occasion: "
+                                            <select name="occasion">
+                                                <option value="">"None"</option>
+                                                {Occasion::ALL
+                                                    .into_iter()
+                                                    .map(|occasion| {
+                                                        view! {
+                                                            <option value=occasion
+                                                                .to_string()>{occasion.to_string()}</option>
+                                                        }
+                                                    })
+                                                    .collect_view()}
+                                            </select>
+                                        </label>
+                                        <label>
+                                            "Dietary needs: " <input type="text" name="dietary" maxlength="200"/>
+                                        </label>
+                                        <label>
+                                            "High chairs: "
+                                            <input type="number" name="high_chairs" min="0" value="0"/>
+                                        </label>
+                                        <label>
+                                            "Accessibility: "
+                                            <input type="text" name="accessibility" maxlength="200"/>
+                                        </label>
+                                    </fieldset>
+                                    <button>"Request booking"</button>
+                                </ActionForm>
+                            }
+                                .into_view()
+                        }
+                    })
+            }}
+        </Suspense>
+        {move || {
+            book.value()
+                .get()
+                .map(|booked| match booked {
+                    Ok(booked) => {
+                        view! {
+                            <p>
+                                "Thank you, your request for " {booked.party_size} " on "
+                                {booked.reservation_date.to_string()} " at "
+                                {booked.reservation_time}
+                                " was received. The restaurant will confirm it shortly."
+                            </p>
+                            <p>
+                                "Your confirmation code is " {booked.confirmation_code}
+                                ". To change or cancel, use "
+                                <a href=format!(
+                                    "/manage/{}",
+                                    booked.confirmation_token,
+                                )>"your booking page"</a> "."
+                            </p>
+                        }
+                            .into_view()
+                    }
+                    Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                })
+        }}
+    }
+}
+
+#[component]
+fn ManagePage() -> impl IntoView {
+    let params = use_params::<TokenParam>();
+    let token = move || {
+        params.with(|params| {
+            params
+                .as_ref()
+                .ok()
+                .and_then(|params| params.token.clone())
+                .unwrap_or_default()
+        })
+    };
+
+    let modify = create_server_action::<ModifyBooking>();
+    let cancel = create_server_action::<CancelBooking>();
+    let booking = create_resource(
+        move || (token(), modify.version().get(), cancel.version().get()),
+        |(token, _, _)| manage_booking(token),
+    );
+    let (date, set_date) = create_signal(String::new());
+    let (party_size, set_party_size) = create_signal(0);
+    let slots = create_resource(
+        move || {
+            (
+                token(),
+                date.get(),
+                party_size.get(),
+                modify.version().get(),
+            )
+        },
+        |(token, date, party_size, _)| async move {
+            match date.parse() {
+                Ok(date) if party_size > 0 => manage_slots(token, date, party_size).await,
+                _ => Ok(Vec::new()),
+            }
+        },
+    );
+
+    view! {
+        <Suspense fallback=move || view! { <p>"Loading"</p> }>
+            {move || {
+                booking
+                    .get()
+                    .map(|booking| match booking {
+                        Err(_) => view! { <p>"This booking link is not valid."</p> }.into_view(),
+                        Ok(booking) => {
+                            if date.get_untracked().is_empty() {
+                                set_date.set(booking.reservation_date.to_string());
+                                set_party_size.set(booking.party_size);
+                            }
+                            view! {
+                                <h2>"Your booking at " {booking.property_name.clone()}</h2>
+                                <p>
+                                    {booking.name.clone()} ", " {booking.party_size} " guests on "
+                                    {booking.reservation_date.to_string()} " at "
+                                    {booking.reservation_time.clone()} ". Status: "
+                                    {booking.status.clone()} ". Code: " {booking.code.clone()}
+                                </p>
+                                <Show
+                                    when=move || booking.can_change
+                                    fallback=|| {
+                                        view! {
+                                            <p>
+                                                "This booking can no longer be changed online, please call the restaurant."
+                                            </p>
+                                        }
+                                    }
+                                >
+                                    <ActionForm action=modify class="container">
+                                        <input type="hidden" name="token" prop:value=token/>
+                                        <label>
+                                            "Date: "
+                                            <input
+                                                type="date"
+                                                name="reservation_date"
+                                                on:input=move |ev| set_date.set(event_target_value(&ev))
+                                                prop:value=date
+                                            />
+                                        </label>
+                                        <label>
+                                            "Guests: "
+                                            <input
+                                                type="number"
+                                                name="party_size"
+                                                min="1"
+                                                on:input=move |ev| {
+                                                    set_party_size
+                                                        .set(event_target_value(&ev).parse().unwrap_or(1))
+                                                }
+                                                prop:value=party_size
+                                            />
+                                        </label>
+                                        <fieldset>
+                                            <legend>"Time"</legend>
+                                            <Transition fallback=move || view! { <p>"Loading times"</p> }>
+                                                {move || {
+                                                    slots
+                                                        .get()
+                                                        .map(|slots| match slots {
+                                                            Ok(slots) if slots.is_empty() => {
+                                                                view! { <p>"No tables are free on this day."</p> }
+                                                                    .into_view()
+                                                            }
+                                                            Ok(slots) => {
+                                                                slots
+                                                                    .into_iter()
+                                                                    .map(|slot| {
+                                                                        let label = format!("{}:{}", &slot[..2], &slot[2..]);
+                                                                        view! {
+                                                                            <label>
+                                                                                <input
+                                                                                    type="radio"
+                                                                                    name="reservation_time"
+                                                                                    value=slot
+                                                                                />
+                                                                                {label}
+                                                                            </label>
+                                                                        }
+                                                                    })
+                                                                    .collect_view()
+                                                            }
+                                                            Err(e) => {
+                                                                view! { <pre class="error">{e.to_string()}</pre> }
+                                                                    .into_view()
+                                                            }
+                                                        })
+                                                }}
+                                            </Transition>
+                                        </fieldset>
+                                        <button>"Change booking"</button>
+                                    </ActionForm>
+                                    <ActionForm action=cancel>
+                                        <input type="hidden" name="token" prop:value=token/>
+                                        <button>"Cancel booking"</button>
+                                    </ActionForm>
+                                </Show>
+                                <h3>"Changes"</h3>
+                                <ul>
+                                    {booking
+                                        .history
+                                        .into_iter()
+                                        .map(|entry| {
+                                            view! {
+                                                <li>
+                                                    {entry.changed_at.format("%Y-%m-%d %H:%M").to_string()} " "
+                                                    {entry.changed_by} ": " {entry.summary}
+                                                </li>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </ul>
+                            }
+                                .into_view()
+                        }
+                    })
+            }}
+        </Suspense>
+        {move || {
+            modify
+                .value()
+                .get()
+                .and_then(Result::err)
+                .or_else(|| cancel.value().get().and_then(Result::err))
+                .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+        }}
+    }
+}
+
+/// Trades a property admin's credentials for a feed token, so the page
+/// follows the changes made elsewhere, see [`use_reservation_changes`].
+#[component]
+fn FollowLive(
+    #[prop(into)] pid: Signal<uuid::Uuid>,
+    set_token: WriteSignal<Option<String>>,
+) -> impl IntoView {
+    let follow = create_server_action::<LiveFeedToken>();
+    create_effect(move |_| {
+        if let Some(Ok(token)) = follow.value().get() {
+            set_token.set(Some(token));
+        }
+    });
+
+    view! {
+        <ActionForm action=follow class="follow-live">
+            <input type="hidden" name="pid" prop:value=move || pid.get().to_string()/>
+            <label>"Admin: " <input type="text" name="user_name"/></label>
+            <label>"Password: " <input type="password" name="password"/></label>
+            <button>"Follow live"</button>
+        </ActionForm>
+        {move || {
+            follow
+                .value()
+                .get()
+                .map(|followed| match followed {
+                    Ok(_) => view! { <p>"Following changes live."</p> }.into_view(),
+                    Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                })
+        }}
+    }
+}
+
 #[component]
 fn AddReservation() -> impl IntoView {
     let params = use_params::<PropertyIdParam>();
@@ -69,12 +477,15 @@ fn AddReservation() -> impl IntoView {
 
     let action = create_server_action::<AddResv>();
 
+    let (feed_token, set_feed_token) = create_signal(None::<String>);
+    let changes = use_reservation_changes(pid, move || feed_token.get());
     let resv_count = create_resource(
-        move || action.version().get(),
+        move || (action.version().get(), changes.get()),
         move |_| async move { total_resv(pid()).await },
     );
 
     view! {
+        <FollowLive pid=Signal::derive(pid) set_token=set_feed_token/>
         <ActionForm action class="container">
             <fieldset>
                 <legend>Fill reservation data</legend>
@@ -82,6 +493,9 @@ fn AddReservation() -> impl IntoView {
                     <label>"Name: " <input type="text" name="name"/></label>
                     <label>"Contact: " <input type="text" name="contact"/></label>
                     <label>"Seating: " <input type="text" name="seating"/></label>
+                    <label>
+                        "Party size: " <input type="number" name="party_size" value="2" min="1"/>
+                    </label>
 
                     <fieldset>
                         <legend>specific seating</legend>
@@ -170,6 +584,2491 @@ fn AddReservation() -> impl IntoView {
     }
 }
 
+#[component]
+fn ApiKeys() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    // Every request re-checks the admin credentials, they are kept here so
+    // they only have to be typed once.
+    let (user_name, set_user_name) = create_signal(String::new());
+    let (password, set_password) = create_signal(String::new());
+
+    let list = create_server_action::<ListApiKeys>();
+    let create = create_server_action::<CreateApiKey>();
+    let revoke = create_server_action::<RevokeApiKey>();
+    let refresh = move || {
+        list.dispatch(ListApiKeys {
+            pid: pid(),
+            user_name: user_name.get_untracked(),
+            password: password.get_untracked(),
+        })
+    };
+    create_effect(move |_| {
+        if create.version().get() > 0 || revoke.version().get() > 0 {
+            refresh();
+        }
+    });
+
+    view! {
+        <div class="container">
+            <fieldset>
+                <legend>"Property admin"</legend>
+                <label>
+                    "User name: "
+                    <input
+                        type="text"
+                        on:input=move |ev| set_user_name.set(event_target_value(&ev))
+                        prop:value=user_name
+                    />
+                </label>
+                <label>
+                    "Password: "
+                    <input
+                        type="password"
+                        on:input=move |ev| set_password.set(event_target_value(&ev))
+                        prop:value=password
+                    />
+                </label>
+                <button on:click=move |_| refresh()>"Show keys"</button>
+            </fieldset>
+
+            <ActionForm action=create>
+                <fieldset>
+                    <legend>"New API key"</legend>
+                    <input type="hidden" name="pid" prop:value=move || pid().to_string()/>
+                    <input type="hidden" name="user_name" prop:value=user_name/>
+                    <input type="hidden" name="password" prop:value=password/>
+                    <label>"Name: " <input type="text" name="key_name"/></label>
+                    <label>
+                        <input type="checkbox" name="read_availability" value="true"/>
+                        "Read availability"
+                    </label>
+                    <label>
+                        <input type="checkbox" name="create_reservations" value="true"/>
+                        "Create reservations"
+                    </label>
+                    <label>
+                        <input type="checkbox" name="read_reservations" value="true"/>
+                        "Read reservations"
+                    </label>
+                    <button>"Create key"</button>
+                </fieldset>
+            </ActionForm>
+            {move || {
+                create
+                    .value()
+                    .get()
+                    .map(|issued| match issued {
+                        Ok(issued) => {
+                            view! {
+                                <p>
+                                    "Copy the key now, it will not be shown again: "
+                                    <code>{issued.key}</code>
+                                </p>
+                            }
+                                .into_view()
+                        }
+                        Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                    })
+            }}
+
+            {move || {
+                revoke
+                    .value()
+                    .get()
+                    .and_then(Result::err)
+                    .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+            }}
+
+            {move || {
+                list.value()
+                    .get()
+                    .map(|keys| match keys {
+                        Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                        Ok(keys) if keys.is_empty() => {
+                            view! { <p>"No API keys were found."</p> }.into_view()
+                        }
+                        Ok(keys) => {
+                            view! {
+                                <table>
+                                    <thead>
+                                        <tr>
+                                            <th>Name</th>
+                                            <th>Prefix</th>
+                                            <th>Scopes</th>
+                                            <th>Created</th>
+                                            <th>Last used</th>
+                                            <th>Revoked</th>
+                                            <th></th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {keys
+                                            .into_iter()
+                                            .map(|key| {
+                                                let scopes = key
+                                                    .scope_list()
+                                                    .iter()
+                                                    .map(|scope| format!("{scope:?}"))
+                                                    .collect::<Vec<_>>()
+                                                    .join(", ");
+                                                let revoked = key.revoked_at.is_some();
+                                                view! {
+                                                    <tr>
+                                                        <td>{key.key_name}</td>
+                                                        <td>
+                                                            <code>{key.key_prefix}</code>
+                                                        </td>
+                                                        <td>{scopes}</td>
+                                                        <td>{key.created_at.to_string()}</td>
+                                                        <td>
+                                                            {key
+                                                                .last_used_at
+                                                                .map(|at| at.to_string())
+                                                                .unwrap_or("Never".to_string())}
+                                                        </td>
+                                                        <td>
+                                                            {key
+                                                                .revoked_at
+                                                                .map(|at| at.to_string())
+                                                                .unwrap_or_default()}
+                                                        </td>
+                                                        <td>
+                                                            <Show when=move || !revoked>
+                                                                <ActionForm action=revoke>
+                                                                    <input
+                                                                        type="hidden"
+                                                                        name="pid"
+                                                                        prop:value=move || pid().to_string()
+                                                                    />
+                                                                    <input
+                                                                        type="hidden"
+                                                                        name="user_name"
+                                                                        prop:value=user_name
+                                                                    />
+                                                                    <input
+                                                                        type="hidden"
+                                                                        name="password"
+                                                                        prop:value=password
+                                                                    />
+                                                                    <input
+                                                                        type="hidden"
+                                                                        name="key_id"
+                                                                        value=key.key_id
+                                                                    />
+                                                                    <button>"Revoke"</button>
+                                                                </ActionForm>
+                                                            </Show>
+                                                        </td>
+                                                    </tr>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </tbody>
+                                </table>
+                            }
+                                .into_view()
+                        }
+                    })
+            }}
+
+        </div>
+    }
+}
+
+#[component]
+fn Webhooks() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    // Same as for API keys, the admin credentials go along with every request.
+    let (user_name, set_user_name) = create_signal(String::new());
+    let (password, set_password) = create_signal(String::new());
+
+    let list = create_server_action::<ListWebhooks>();
+    let create = create_server_action::<CreateWebhook>();
+    let toggle = create_server_action::<SetWebhookActive>();
+    let replay = create_server_action::<ReplayDelivery>();
+    let refresh = move || {
+        list.dispatch(ListWebhooks {
+            pid: pid(),
+            user_name: user_name.get_untracked(),
+            password: password.get_untracked(),
+        })
+    };
+    create_effect(move |_| {
+        if create.version().get() > 0 || toggle.version().get() > 0 || replay.version().get() > 0 {
+            refresh();
+        }
+    });
+
+    let credentials = move || {
+        view! {
+            <input type="hidden" name="pid" prop:value=move || pid().to_string()/>
+            <input type="hidden" name="user_name" prop:value=user_name/>
+            <input type="hidden" name="password" prop:value=password/>
+        }
+    };
+    let errors = move || {
+        [
+            create.value().get().and_then(|r| r.err()),
+            toggle.value().get().and_then(|r| r.err()),
+            replay.value().get().and_then(|r| r.err()),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+        .collect_view()
+    };
+
+    view! {
+        <div class="container">
+            <fieldset>
+                <legend>"Property admin"</legend>
+                <label>
+                    "User name: "
+                    <input
+                        type="text"
+                        on:input=move |ev| set_user_name.set(event_target_value(&ev))
+                        prop:value=user_name
+                    />
+                </label>
+                <label>
+                    "Password: "
+                    <input
+                        type="password"
+                        on:input=move |ev| set_password.set(event_target_value(&ev))
+                        prop:value=password
+                    />
+                </label>
+                <button on:click=move |_| refresh()>"Show webhooks"</button>
+            </fieldset>
+
+            <ActionForm action=create>
+                <fieldset>
+                    <legend>"New webhook"</legend>
+                    {credentials}
+                    <label>"URL: " <input type="url" name="url"/></label>
+                    <label>
+                        <input type="checkbox" name="reservation_created" value="true"/>
+                        "reservation.created"
+                    </label>
+                    <label>
+                        <input type="checkbox" name="reservation_updated" value="true"/>
+                        "reservation.updated"
+                    </label>
+                    <label>
+                        <input type="checkbox" name="reservation_cancelled" value="true"/>
+                        "reservation.cancelled"
+                    </label>
+                    <label>
+                        <input type="checkbox" name="reservation_seated" value="true"/>
+                        "reservation.seated"
+                    </label>
+                    <label>
+                        <input type="checkbox" name="payment_recorded" value="true"/>
+                        "payment.recorded"
+                    </label>
+                    <label>
+                        <input
+                            type="checkbox"
+                            name="reservation_details_changed"
+                            value="true"
+                        />
+                        "reservation.details_changed"
+                    </label>
+                    <button>"Add webhook"</button>
+                </fieldset>
+            </ActionForm>
+            {errors}
+
+            {move || {
+                list.value()
+                    .get()
+                    .map(|logs| match logs {
+                        Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                        Ok(logs) if logs.is_empty() => {
+                            view! { <p>"No webhooks were found."</p> }.into_view()
+                        }
+                        Ok(logs) => {
+                            logs.into_iter()
+                                .map(|log| {
+                                    let webhook = log.webhook;
+                                    let events = webhook
+                                        .event_list()
+                                        .iter()
+                                        .map(|event| event.name())
+                                        .collect::<Vec<_>>()
+                                        .join(", ");
+                                    view! {
+                                        <section>
+                                            <h3>{webhook.url.clone()}</h3>
+                                            <p>"Events: " {events}</p>
+                                            <p>
+                                                "Signing secret: " <code>{webhook.secret.clone()}</code>
+                                            </p>
+                                            <ActionForm action=toggle>
+                                                {credentials}
+                                                <input
+                                                    type="hidden"
+                                                    name="webhook_id"
+                                                    value=webhook.webhook_id
+                                                />
+                                                <input
+                                                    type="hidden"
+                                                    name="active"
+                                                    value=(!webhook.active).to_string()
+                                                />
+                                                <button>
+                                                    {if webhook.active { "Disable" } else { "Enable" }}
+                                                </button>
+                                            </ActionForm>
+                                            <table>
+                                                <thead>
+                                                    <tr>
+                                                        <th>Id</th>
+                                                        <th>Event</th>
+                                                        <th>Status</th>
+                                                        <th>Attempts</th>
+                                                        <th>Response</th>
+                                                        <th>Next attempt</th>
+                                                        <th>Error</th>
+                                                        <th></th>
+                                                    </tr>
+                                                </thead>
+                                                <tbody>
+                                                    {log
+                                                        .deliveries
+                                                        .into_iter()
+                                                        .map(|delivery| {
+                                                            view! {
+                                                                <tr>
+                                                                    <td>{delivery.delivery_id}</td>
+                                                                    <td>{delivery.event}</td>
+                                                                    <td>{delivery.status.to_string()}</td>
+                                                                    <td>{delivery.attempts}</td>
+                                                                    <td>{delivery.response_status}</td>
+                                                                    <td>{delivery.next_attempt_at.to_string()}</td>
+                                                                    <td>{delivery.last_error}</td>
+                                                                    <td>
+                                                                        <ActionForm action=replay>
+                                                                            {credentials}
+                                                                            <input
+                                                                                type="hidden"
+                                                                                name="delivery_id"
+                                                                                value=delivery.delivery_id
+                                                                            />
+                                                                            <button>"Replay"</button>
+                                                                        </ActionForm>
+                                                                    </td>
+                                                                </tr>
+                                                            }
+                                                        })
+                                                        .collect_view()}
+                                                </tbody>
+                                            </table>
+                                        </section>
+                                    }
+                                })
+                                .collect_view()
+                        }
+                    })
+            }}
+
+        </div>
+    }
+}
+
+#[component]
+fn Emails() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    // Same as for API keys, the admin credentials go along with every request.
+    let (user_name, set_user_name) = create_signal(String::new());
+    let (password, set_password) = create_signal(String::new());
+
+    let templates = create_server_action::<ListEmailTemplates>();
+    let outbox = create_server_action::<ListEmails>();
+    let save = create_server_action::<SaveEmailTemplate>();
+    let refresh = move || {
+        templates.dispatch(ListEmailTemplates {
+            pid: pid(),
+            user_name: user_name.get_untracked(),
+            password: password.get_untracked(),
+        });
+        outbox.dispatch(ListEmails {
+            pid: pid(),
+            user_name: user_name.get_untracked(),
+            password: password.get_untracked(),
+        });
+    };
+    create_effect(move |_| {
+        if save.version().get() > 0 {
+            refresh();
+        }
+    });
+
+    let credentials = move || {
+        view! {
+            <input type="hidden" name="pid" prop:value=move || pid().to_string()/>
+            <input type="hidden" name="user_name" prop:value=user_name/>
+            <input type="hidden" name="password" prop:value=password/>
+        }
+    };
+    let placeholders = PLACEHOLDERS
+        .iter()
+        .map(|name| format!("{{{{{name}}}}}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    view! {
+        <div class="container">
+            <fieldset>
+                <legend>"Property admin"</legend>
+                <label>
+                    "User name: "
+                    <input
+                        type="text"
+                        on:input=move |ev| set_user_name.set(event_target_value(&ev))
+                        prop:value=user_name
+                    />
+                </label>
+                <label>
+                    "Password: "
+                    <input
+                        type="password"
+                        on:input=move |ev| set_password.set(event_target_value(&ev))
+                        prop:value=password
+                    />
+                </label>
+                <button on:click=move |_| refresh()>"Show emails"</button>
+            </fieldset>
+            <p>"Templates may use " <code>{placeholders}</code></p>
+            {move || {
+                save.value()
+                    .get()
+                    .and_then(Result::err)
+                    .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+            }}
+
+            {move || {
+                templates
+                    .value()
+                    .get()
+                    .map(|templates| match templates {
+                        Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                        Ok(templates) => {
+                            templates
+                                .into_iter()
+                                .map(|template| {
+                                    view! {
+                                        <ActionForm action=save>
+                                            <fieldset>
+                                                <legend>{template.kind.to_string()}</legend>
+                                                {credentials}
+                                                <input
+                                                    type="hidden"
+                                                    name="kind"
+                                                    value=template.kind.to_string()
+                                                />
+                                                <label>
+                                                    "Subject: "
+                                                    <input type="text" name="subject" value=template.subject/>
+                                                </label>
+                                                <label>
+                                                    "Body: "
+                                                    <textarea name="body" rows="8" cols="80">
+                                                        {template.body}
+                                                    </textarea>
+                                                </label>
+                                                <button>"Save"</button>
+                                            </fieldset>
+                                        </ActionForm>
+                                    }
+                                })
+                                .collect_view()
+                        }
+                    })
+            }}
+
+            {move || {
+                outbox
+                    .value()
+                    .get()
+                    .map(|emails| match emails {
+                        Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                        Ok(emails) if emails.is_empty() => {
+                            view! { <p>"No emails were sent yet."</p> }.into_view()
+                        }
+                        Ok(emails) => {
+                            view! {
+                                <table>
+                                    <thead>
+                                        <tr>
+                                            <th>Id</th>
+                                            <th>Reservation</th>
+                                            <th>Kind</th>
+                                            <th>To</th>
+                                            <th>Subject</th>
+                                            <th>Status</th>
+                                            <th>Attempts</th>
+                                            <th>Sent</th>
+                                            <th>Error</th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {emails
+                                            .into_iter()
+                                            .map(|email| {
+                                                view! {
+                                                    <tr>
+                                                        <td>{email.email_id}</td>
+                                                        <td>{email.reservation_id}</td>
+                                                        <td>{email.kind.to_string()}</td>
+                                                        <td>{email.recipient}</td>
+                                                        <td>{email.subject}</td>
+                                                        <td>{email.status.to_string()}</td>
+                                                        <td>{email.attempts}</td>
+                                                        <td>{email.sent_at.map(|at| at.to_string())}</td>
+                                                        <td>{email.last_error}</td>
+                                                    </tr>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </tbody>
+                                </table>
+                            }
+                                .into_view()
+                        }
+                    })
+            }}
+
+        </div>
+    }
+}
+
+#[component]
+fn Messages() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    let (user_name, set_user_name) = create_signal(String::new());
+    let (password, set_password) = create_signal(String::new());
+
+    let outbox = create_server_action::<ListSmsMessages>();
+    let opt_out = create_server_action::<SetSmsOptOut>();
+    let refresh = move || {
+        outbox.dispatch(ListSmsMessages {
+            pid: pid(),
+            user_name: user_name.get_untracked(),
+            password: password.get_untracked(),
+        });
+    };
+
+    view! {
+        <div class="container">
+            <fieldset>
+                <legend>"Property admin"</legend>
+                <label>
+                    "User name: "
+                    <input
+                        type="text"
+                        on:input=move |ev| set_user_name.set(event_target_value(&ev))
+                        prop:value=user_name
+                    />
+                </label>
+                <label>
+                    "Password: "
+                    <input
+                        type="password"
+                        on:input=move |ev| set_password.set(event_target_value(&ev))
+                        prop:value=password
+                    />
+                </label>
+                <button on:click=move |_| refresh()>"Show messages"</button>
+            </fieldset>
+            <ActionForm action=opt_out>
+                <fieldset>
+                    <legend>"Opt-outs"</legend>
+                    <input type="hidden" name="pid" prop:value=move || pid().to_string()/>
+                    <input type="hidden" name="user_name" prop:value=user_name/>
+                    <input type="hidden" name="password" prop:value=password/>
+                    <label>"Phone: " <input type="tel" name="phone"/></label>
+                    <select name="opted_out">
+                        <option value="true">"Stop texting"</option>
+                        <option value="false">"Text again"</option>
+                    </select>
+                    <button>"Save"</button>
+                </fieldset>
+            </ActionForm>
+            {move || {
+                opt_out
+                    .value()
+                    .get()
+                    .map(|saved| match saved {
+                        Ok(number) => view! { <p>"Saved for " {number}</p> }.into_view(),
+                        Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                    })
+            }}
+
+            {move || {
+                outbox
+                    .value()
+                    .get()
+                    .map(|messages| match messages {
+                        Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                        Ok(messages) if messages.is_empty() => {
+                            view! { <p>"No texts were sent yet."</p> }.into_view()
+                        }
+                        Ok(messages) => {
+                            view! {
+                                <table>
+                                    <thead>
+                                        <tr>
+                                            <th>Id</th>
+                                            <th>Reservation</th>
+                                            <th>Kind</th>
+                                            <th>Channel</th>
+                                            <th>To</th>
+                                            <th>Text</th>
+                                            <th>Status</th>
+                                            <th>Attempts</th>
+                                            <th>Sent</th>
+                                            <th>Delivered</th>
+                                            <th>Error</th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {messages
+                                            .into_iter()
+                                            .map(|message| {
+                                                view! {
+                                                    <tr>
+                                                        <td>{message.message_id}</td>
+                                                        <td>{message.reservation_id}</td>
+                                                        <td>{message.kind.to_string()}</td>
+                                                        <td>{message.channel}</td>
+                                                        <td>{message.recipient}</td>
+                                                        <td>{message.body}</td>
+                                                        <td>{message.status.to_string()}</td>
+                                                        <td>{message.attempts}</td>
+                                                        <td>{message.sent_at.map(|at| at.to_string())}</td>
+                                                        <td>
+                                                            {message.delivered_at.map(|at| at.to_string())}
+                                                        </td>
+                                                        <td>{message.last_error}</td>
+                                                    </tr>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </tbody>
+                                </table>
+                            }
+                                .into_view()
+                        }
+                    })
+            }}
+
+        </div>
+    }
+}
+
+#[component]
+fn Jobs() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    let (user_name, set_user_name) = create_signal(String::new());
+    let (password, set_password) = create_signal(String::new());
+
+    let jobs = create_server_action::<ListJobs>();
+    let retry = create_server_action::<RetryJob>();
+    let refresh = move || {
+        jobs.dispatch(ListJobs {
+            pid: pid(),
+            user_name: user_name.get_untracked(),
+            password: password.get_untracked(),
+        });
+    };
+    create_effect(move |_| {
+        if retry.version().get() > 0 {
+            refresh();
+        }
+    });
+
+    view! {
+        <div class="container">
+            <fieldset>
+                <legend>"Property admin"</legend>
+                <label>
+                    "User name: "
+                    <input
+                        type="text"
+                        on:input=move |ev| set_user_name.set(event_target_value(&ev))
+                        prop:value=user_name
+                    />
+                </label>
+                <label>
+                    "Password: "
+                    <input
+                        type="password"
+                        on:input=move |ev| set_password.set(event_target_value(&ev))
+                        prop:value=password
+                    />
+                </label>
+                <button on:click=move |_| refresh()>"Show jobs"</button>
+            </fieldset>
+            {move || {
+                retry
+                    .value()
+                    .get()
+                    .and_then(Result::err)
+                    .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+            }}
+
+            {move || {
+                jobs.value()
+                    .get()
+                    .map(|jobs| match jobs {
+                        Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                        Ok(jobs) if jobs.is_empty() => {
+                            view! { <p>"No jobs are scheduled."</p> }.into_view()
+                        }
+                        Ok(jobs) => {
+                            view! {
+                                <table>
+                                    <thead>
+                                        <tr>
+                                            <th>Id</th>
+                                            <th>Kind</th>
+                                            <th>Reservation</th>
+                                            <th>Run at (UTC)</th>
+                                            <th>Status</th>
+                                            <th>Attempts</th>
+                                            <th>Finished</th>
+                                            <th>Error</th>
+                                            <th></th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {jobs
+                                            .into_iter()
+                                            .map(|job| {
+                                                view! {
+                                                    <tr>
+                                                        <td>{job.job_id}</td>
+                                                        <td>{job.kind.to_string()}</td>
+                                                        <td>{job.reservation_id}</td>
+                                                        <td>{job.run_at.to_string()}</td>
+                                                        <td>{job.status.to_string()}</td>
+                                                        <td>{job.attempts}</td>
+                                                        <td>{job.finished_at.map(|at| at.to_string())}</td>
+                                                        <td>{job.last_error}</td>
+                                                        <td>
+                                                            <ActionForm action=retry>
+                                                                <input
+                                                                    type="hidden"
+                                                                    name="pid"
+                                                                    prop:value=move || pid().to_string()
+                                                                />
+                                                                <input type="hidden" name="user_name" prop:value=user_name/>
+                                                                <input type="hidden" name="password" prop:value=password/>
+                                                                <input type="hidden" name="job_id" value=job.job_id/>
+                                                                <button>"Run now"</button>
+                                                            </ActionForm>
+                                                        </td>
+                                                    </tr>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </tbody>
+                                </table>
+                            }
+                                .into_view()
+                        }
+                    })
+            }}
+
+        </div>
+    }
+}
+
+#[component]
+fn Guests() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    let (user_name, set_user_name) = create_signal(String::new());
+    let (password, set_password) = create_signal(String::new());
+    let (search, set_search) = create_signal(String::new());
+
+    let list = create_server_action::<ListGuests>();
+    let open = create_server_action::<GetGuest>();
+    let save = create_server_action::<UpdateGuest>();
+    let duplicates = create_server_action::<FindDuplicateGuests>();
+    let merge = create_server_action::<MergeGuests>();
+    let refresh = move || {
+        list.dispatch(ListGuests {
+            pid: pid(),
+            user_name: user_name.get_untracked(),
+            password: password.get_untracked(),
+            search: search.get_untracked(),
+        });
+    };
+    let find_duplicates = move || {
+        duplicates.dispatch(FindDuplicateGuests {
+            pid: pid(),
+            user_name: user_name.get_untracked(),
+            password: password.get_untracked(),
+        });
+    };
+    // The profile last opened or saved.
+    let (profile, set_profile) = create_signal(None::<Result<GuestProfile, ServerFnError>>);
+    create_effect(move |_| {
+        if let Some(opened) = open.value().get() {
+            set_profile.set(Some(opened));
+        }
+    });
+    create_effect(move |_| {
+        if let Some(saved) = save.value().get() {
+            if saved.is_ok() {
+                refresh();
+            }
+            set_profile.set(Some(saved));
+        }
+    });
+    create_effect(move |_| {
+        if let Some(merged) = merge.value().get() {
+            if merged.is_ok() {
+                refresh();
+                find_duplicates();
+            }
+            set_profile.set(Some(merged));
+        }
+    });
+
+    view! {
+        <div class="container">
+            <fieldset>
+                <legend>"Property admin"</legend>
+                <label>
+                    "User name: "
+                    <input
+                        type="text"
+                        on:input=move |ev| set_user_name.set(event_target_value(&ev))
+                        prop:value=user_name
+                    />
+                </label>
+                <label>
+                    "Password: "
+                    <input
+                        type="password"
+                        on:input=move |ev| set_password.set(event_target_value(&ev))
+                        prop:value=password
+                    />
+                </label>
+                <label>
+                    "Name, tag or contact: "
+                    <input
+                        type="search"
+                        on:input=move |ev| set_search.set(event_target_value(&ev))
+                        prop:value=search
+                    />
+                </label>
+                <button on:click=move |_| refresh()>"Find guests"</button>
+                <button on:click=move |_| find_duplicates()>"Find duplicates"</button>
+            </fieldset>
+
+            {move || {
+                duplicates
+                    .value()
+                    .get()
+                    .map(|pairs| match pairs {
+                        Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                        Ok(pairs) if pairs.is_empty() => {
+                            view! { <p>"No likely duplicates."</p> }.into_view()
+                        }
+                        Ok(pairs) => {
+                            view! {
+                                <table>
+                                    <thead>
+                                        <tr>
+                                            <th>Guest</th>
+                                            <th>Duplicate</th>
+                                            <th>Why</th>
+                                            <th></th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {pairs
+                                            .into_iter()
+                                            .map(|pair| {
+                                                let keep = |kept: Guest, merged_id: i32| {
+                                                    view! {
+                                                        <ActionForm action=merge>
+                                                            <input
+                                                                type="hidden"
+                                                                name="pid"
+                                                                prop:value=move || pid().to_string()
+                                                            />
+                                                            <input type="hidden" name="user_name" prop:value=user_name/>
+                                                            <input type="hidden" name="password" prop:value=password/>
+                                                            <input type="hidden" name="guest_id" value=kept.guest_id/>
+                                                            <input
+                                                                type="hidden"
+                                                                name="duplicate_id"
+                                                                value=merged_id
+                                                            />
+                                                            <button>"Keep " {kept.name}</button>
+                                                        </ActionForm>
+                                                    }
+                                                };
+                                                view! {
+                                                    <tr>
+                                                        <td>{pair.guest.guest_id} " " {pair.guest.name.clone()}</td>
+                                                        <td>
+                                                            {pair.duplicate.guest_id} " "
+                                                            {pair.duplicate.name.clone()}
+                                                        </td>
+                                                        <td>{pair.reasons.join("; ")}</td>
+                                                        <td>
+                                                            {keep(pair.guest.clone(), pair.duplicate.guest_id)}
+                                                            {keep(pair.duplicate.clone(), pair.guest.guest_id)}
+                                                        </td>
+                                                    </tr>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </tbody>
+                                </table>
+                            }
+                                .into_view()
+                        }
+                    })
+            }}
+
+            {move || {
+                list.value()
+                    .get()
+                    .map(|guests| match guests {
+                        Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                        Ok(guests) if guests.is_empty() => {
+                            view! { <p>"No guests were found."</p> }.into_view()
+                        }
+                        Ok(guests) => {
+                            view! {
+                                <table>
+                                    <thead>
+                                        <tr>
+                                            <th>Id</th>
+                                            <th>Name</th>
+                                            <th>Tags</th>
+                                            <th>Notes</th>
+                                            <th></th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {guests
+                                            .into_iter()
+                                            .map(|guest| {
+                                                let guest_id = guest.guest_id;
+                                                view! {
+                                                    <tr>
+                                                        <td>{guest.guest_id}</td>
+                                                        <td>{guest.name}</td>
+                                                        <td>{guest.tags}</td>
+                                                        <td>{guest.notes}</td>
+                                                        <td>
+                                                            <button on:click=move |_| {
+                                                                open.dispatch(GetGuest {
+                                                                    pid: pid(),
+                                                                    user_name: user_name.get_untracked(),
+                                                                    password: password.get_untracked(),
+                                                                    guest_id,
+                                                                })
+                                                            }>"Open"</button>
+                                                        </td>
+                                                    </tr>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </tbody>
+                                </table>
+                            }
+                                .into_view()
+                        }
+                    })
+            }}
+
+            {move || {
+                profile
+                    .get()
+                    .map(|profile| match profile {
+                        Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                        Ok(profile) => {
+                            let contacts = profile
+                                .phones
+                                .iter()
+                                .chain(&profile.emails)
+                                .cloned()
+                                .collect::<Vec<_>>()
+                                .join("\n");
+                            view! {
+                                <h2>{profile.guest.name.clone()}</h2>
+                                <p>
+                                    {profile.visits} " visits, " {profile.upcoming} " upcoming, "
+                                    {profile.no_shows} " no-shows, " {profile.total_spend}
+                                    " paid in advances"
+                                    {profile
+                                        .reliability
+                                        .map(|percent| format!(", turned up for {percent}% of bookings"))}
+                                </p>
+                                <ActionForm action=save>
+                                    <fieldset>
+                                        <legend>"Profile"</legend>
+                                        <input type="hidden" name="pid" prop:value=move || pid().to_string()/>
+                                        <input type="hidden" name="user_name" prop:value=user_name/>
+                                        <input type="hidden" name="password" prop:value=password/>
+                                        <input type="hidden" name="guest_id" value=profile.guest.guest_id/>
+                                        <label>
+                                            "Name: "
+                                            <input type="text" name="name" value=profile.guest.name/>
+                                        </label>
+                                        <label>
+                                            "Tags: "
+                                            <input
+                                                type="text"
+                                                name="tags"
+                                                placeholder="VIP, nut allergy"
+                                                value=profile.guest.tags
+                                            />
+                                        </label>
+                                        <label>
+                                            "Phones and emails: "
+                                            <textarea name="contacts" rows="3" cols="40">
+                                                {contacts}
+                                            </textarea>
+                                        </label>
+                                        <label>
+                                            "Notes: "
+                                            <textarea name="notes" rows="3" cols="60">
+                                                {profile.guest.notes}
+                                            </textarea>
+                                        </label>
+                                        <button>"Save"</button>
+                                    </fieldset>
+                                </ActionForm>
+                                <ul>
+                                    {profile
+                                        .merges
+                                        .into_iter()
+                                        .map(|merged| {
+                                            view! {
+                                                <li>
+                                                    "Merged " {merged.merged_name} " ("
+                                                    {merged.merged_contacts} ") with "
+                                                    {merged.reservations} " reservations, by "
+                                                    {merged.merged_by} " at " {merged.merged_at.to_string()}
+                                                </li>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </ul>
+                                <table>
+                                    <thead>
+                                        <tr>
+                                            <th>Id</th>
+                                            <th>Date</th>
+                                            <th>Time</th>
+                                            <th>Party</th>
+                                            <th>Seating</th>
+                                            <th>Status</th>
+                                            <th>Advance</th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {profile
+                                            .reservations
+                                            .into_iter()
+                                            .map(|resv| {
+                                                view! {
+                                                    <tr>
+                                                        <td>{resv.id}</td>
+                                                        <td>{resv.reservation_date.to_string()}</td>
+                                                        <td>
+                                                            {format!(
+                                                                "{:02}:{:02}",
+                                                                resv.reservation_time.hour(),
+                                                                resv.reservation_time.minute(),
+                                                            )}
+                                                        </td>
+                                                        <td>{resv.party_size}</td>
+                                                        <td>{resv.seating}</td>
+                                                        <td>{resv.status.to_string()}</td>
+                                                        <td>{resv.advance_amount.filter(|_| resv.advance)}</td>
+                                                    </tr>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </tbody>
+                                </table>
+                            }
+                                .into_view()
+                        }
+                    })
+            }}
+
+        </div>
+    }
+}
+
+#[component]
+fn DaySheet() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    let (date, set_date) = create_signal(chrono::Local::now().date_naive().to_string());
+    let (user_name, set_user_name) = create_signal(String::new());
+    let (password, set_password) = create_signal(String::new());
+    // Bumped by "Show day sheet", the sheet stays hidden until then.
+    let (shown, set_shown) = create_signal(0);
+    let save = create_server_action::<SetSpecialRequests>();
+    let add_note = create_server_action::<AddReservationNote>();
+    let (feed_token, set_feed_token) = create_signal(None::<String>);
+    let changes = use_reservation_changes(pid, move || feed_token.get());
+    let sheet = create_resource(
+        move || {
+            (
+                pid(),
+                date.get(),
+                user_name.get_untracked(),
+                password.get_untracked(),
+                shown.get(),
+                save.version().get(),
+                add_note.version().get(),
+                changes.get(),
+            )
+        },
+        |(pid, date, user_name, password, shown, _, _, _)| async move {
+            if shown == 0 {
+                return None;
+            }
+            Some(match date.parse() {
+                Ok(date) => get_day_sheet(pid, user_name, password, date).await,
+                Err(_) => Ok(Vec::new()),
+            })
+        },
+    );
+
+    view! {
+        <FollowLive pid=Signal::derive(pid) set_token=set_feed_token/>
+        <div class="container">
+            <h2>"Day sheet"</h2>
+            <fieldset>
+                <legend>"Property admin"</legend>
+                <label>
+                    "User name: "
+                    <input
+                        type="text"
+                        on:input=move |ev| set_user_name.set(event_target_value(&ev))
+                        prop:value=user_name
+                    />
+                </label>
+                <label>
+                    "Password: "
+                    <input
+                        type="password"
+                        on:input=move |ev| set_password.set(event_target_value(&ev))
+                        prop:value=password
+                    />
+                </label>
+                <button on:click=move |_| set_shown.update(|shown| *shown += 1)>
+                    "Show day sheet"
+                </button>
+            </fieldset>
+            <label>
+                "Date: "
+                <input
+                    type="date"
+                    on:input=move |ev| set_date.set(event_target_value(&ev))
+                    prop:value=date
+                />
+            </label>
+            {move || {
+                save.value()
+                    .get()
+                    .and_then(Result::err)
+                    .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+            }}
+            {move || {
+                add_note
+                    .value()
+                    .get()
+                    .and_then(Result::err)
+                    .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+            }}
+            <Transition fallback=move || view! { <p>"Loading"</p> }>
+                {move || {
+                    sheet
+                        .get()
+                        .flatten()
+                        .map(|sheet| match sheet {
+                            Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                            Ok(entries) if entries.is_empty() => {
+                                view! { <p>"No bookings on this day."</p> }.into_view()
+                            }
+                            Ok(entries) => {
+                                view! {
+                                    <table class="day-sheet">
+                                        <thead>
+                                            <tr>
+                                                <th>"Time"</th>
+                                                <th>"Name"</th>
+                                                <th>"Party"</th>
+                                                <th>"Table"</th>
+                                                <th>"Status"</th>
+                                                <th>"Requests"</th>
+                                                <th>"Tags"</th>
+                                                <th>"Notes"</th>
+                                            </tr>
+                                        </thead>
+                                        <tbody>
+                                            {entries
+                                                .into_iter()
+                                                .map(|entry| {
+                                                    let DaySheetEntry { reservation: resv, notes, tags, no_shows, event } = entry;
+                                                    let requests = resv.special_requests.clone();
+                                                    let occasion = requests.occasion;
+                                                    view! {
+                                                        <tr>
+                                                            <td>
+                                                                {format!(
+                                                                    "{:02}:{:02}",
+                                                                    resv.reservation_time.hour(),
+                                                                    resv.reservation_time.minute(),
+                                                                )}
+                                                            </td>
+                                                            <td>
+                                                                {resv.name.clone()}
+                                                                {(no_shows > 0)
+                                                                    .then(|| {
+                                                                        view! {
+                                                                            " "
+                                                                            <mark class="warning">{format!("{no_shows} no-shows")}</mark>
+                                                                        }
+                                                                    })}
+                                                            </td>
+                                                            <td>{resv.party_size}</td>
+                                                            <td>{resv.seating.clone()}</td>
+                                                            <td>{resv.status.to_string()}</td>
+                                                            <td>
+                                                                <strong>{requests.summary()}</strong>
+                                                                {event
+                                                                    .map(|event| {
+                                                                        view! { <p class="event">{event.summary()}</p> }
+                                                                    })}
+                                                                <details>
+                                                                    <summary>"Edit"</summary>
+                                                                    <ActionForm action=save>
+                                                                        <input type="hidden" name="pid" value=resv.property_id.to_string()/>
+                                                                        <input type="hidden" name="user_name" prop:value=user_name/>
+                                                                        <input type="hidden" name="password" prop:value=password/>
+                                                                        <input type="hidden" name="id" value=resv.id/>
+                                                                        <label>
+                                                                            "Occasion: "
+                                                                            <select name="occasion">
+                                                                                <option value="">"None"</option>
+                                                                                {Occasion::ALL
+                                                                                    .into_iter()
+                                                                                    .map(|o| {
+                                                                                        view! {
+                                                                                            <option
+                                                                                                value=o.to_string()
+                                                                                                selected=occasion == Some(o)
+                                                                                            >
+                                                                                                {o.to_string()}
+                                                                                            </option>
+                                                                                        }
+                                                                                    })
+                                                                                    .collect_view()}
+                                                                            </select>
+                                                                        </label>
+                                                                        <label>
+                                                                            "Dietary needs: "
+                                                                            <input
+                                                                                type="text"
+                                                                                name="dietary"
+                                                                                value=requests.dietary
+                                                                            />
+                                                                        </label>
+                                                                        <label>
+                                                                            "High chairs: "
+                                                                            <input
+                                                                                type="number"
+                                                                                name="high_chairs"
+                                                                                min="0"
+                                                                                value=requests.high_chairs
+                                                                            />
+                                                                        </label>
+                                                                        <label>
+                                                                            "Accessibility: "
+                                                                            <input
+                                                                                type="text"
+                                                                                name="accessibility"
+                                                                                value=requests.accessibility
+                                                                            />
+                                                                        </label>
+                                                                        <label>
+                                                                            "Seating asked for: "
+                                                                            <input
+                                                                                type="text"
+                                                                                name="seating_request"
+                                                                                value=requests.seating_request
+                                                                            />
+                                                                        </label>
+                                                                        <label>
+                                                                            "Tags: "
+                                                                            <input type="text" name="tags" value=resv.tags.clone()/>
+                                                                        </label>
+                                                                        <button>"Save"</button>
+                                                                    </ActionForm>
+                                                                </details>
+                                                            </td>
+                                                            <td>
+                                                                {tags
+                                                                    .into_iter()
+                                                                    .map(|tag| view! { <mark>{tag}</mark> " " })
+                                                                    .collect_view()}
+                                                            </td>
+                                                            <td>
+                                                                <ul>
+                                                                    {notes
+                                                                        .into_iter()
+                                                                        .map(|note| {
+                                                                            view! {
+                                                                                <li>
+                                                                                    {note.created_at.format("%Y-%m-%d %H:%M").to_string()}
+                                                                                    " " {note.author} ": " {note.body}
+                                                                                </li>
+                                                                            }
+                                                                        })
+                                                                        .collect_view()}
+                                                                </ul>
+                                                                <ActionForm action=add_note>
+                                                                    <input type="hidden" name="pid" value=resv.property_id.to_string()/>
+                                                                    <input type="hidden" name="user_name" prop:value=user_name/>
+                                                                    <input type="hidden" name="password" prop:value=password/>
+                                                                    <input type="hidden" name="id" value=resv.id/>
+                                                                    <input
+                                                                        type="text"
+                                                                        name="body"
+                                                                        placeholder="Note"
+                                                                        required
+                                                                    />
+                                                                    <button>"Add note"</button>
+                                                                </ActionForm>
+                                                            </td>
+                                                        </tr>
+                                                    }
+                                                })
+                                                .collect_view()}
+                                        </tbody>
+                                    </table>
+                                }
+                                    .into_view()
+                            }
+                        })
+                }}
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+fn Waitlist() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    let (date, set_date) = create_signal(chrono::Local::now().date_naive().to_string());
+    let add = create_server_action::<AddToWaitlist>();
+    let notify = create_server_action::<NotifyWaitlistGuest>();
+    let promote = create_server_action::<PromoteWaitlistEntry>();
+    let remove = create_server_action::<RemoveWaitlistEntry>();
+    let (feed_token, set_feed_token) = create_signal(None::<String>);
+    let changes = use_reservation_changes(pid, move || feed_token.get());
+    let waitlist = create_resource(
+        move || {
+            (
+                pid(),
+                date.get(),
+                add.version().get(),
+                notify.version().get(),
+                promote.version().get(),
+                remove.version().get(),
+                changes.get(),
+            )
+        },
+        |(pid, date, _, _, _, _, _)| async move {
+            match date.parse() {
+                Ok(date) => list_waitlist(pid, date).await,
+                Err(_) => Ok(Vec::new()),
+            }
+        },
+    );
+
+    view! {
+        <FollowLive pid=Signal::derive(pid) set_token=set_feed_token/>
+        <div class="container">
+            <h2>"Waitlist"</h2>
+            <label>
+                "Date: "
+                <input
+                    type="date"
+                    on:input=move |ev| set_date.set(event_target_value(&ev))
+                    prop:value=date
+                />
+            </label>
+            <ActionForm action=add>
+                <input type="hidden" name="pid" value=move || pid().to_string()/>
+                <input type="hidden" name="waitlist_date" value=date/>
+                <input type="text" name="name" placeholder="Name" required/>
+                <input type="text" name="contact" placeholder="Phone or email" required/>
+                <label>
+                    "Party: " <input type="number" name="party_size" min="1" value="2" required/>
+                </label>
+                <label>
+                    "Wants to sit at: " <input type="time" name="preferred_time"/>
+                </label>
+                <label>
+                    "Quoted wait (minutes): "
+                    <input type="number" name="quoted_wait" min="0" value="15" required/>
+                </label>
+                <button>"Add to waitlist"</button>
+            </ActionForm>
+            {move || {
+                [
+                    add.value().get().and_then(Result::err),
+                    notify.value().get().and_then(Result::err),
+                    promote.value().get().and_then(Result::err),
+                    remove.value().get().and_then(Result::err),
+                ]
+                    .into_iter()
+                    .flatten()
+                    .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+                    .collect_view()
+            }}
+            <Transition fallback=move || view! { <p>"Loading"</p> }>
+                {move || {
+                    waitlist
+                        .get()
+                        .map(|waitlist| match waitlist {
+                            Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                            Ok(waiting) if waiting.is_empty() => {
+                                view! { <p>"Nobody is waiting on this day."</p> }.into_view()
+                            }
+                            Ok(waiting) => {
+                                view! {
+                                    <table>
+                                        <thead>
+                                            <tr>
+                                                <th>"Added"</th>
+                                                <th>"Name"</th>
+                                                <th>"Contact"</th>
+                                                <th>"Party"</th>
+                                                <th>"Wants"</th>
+                                                <th>"Quoted"</th>
+                                                <th>"Status"</th>
+                                                <th>"Free table"</th>
+                                                <th></th>
+                                            </tr>
+                                        </thead>
+                                        <tbody>
+                                            {waiting
+                                                .into_iter()
+                                                .map(|WaitingGuest { entry, free_table }| {
+                                                    let open = entry.status.is_open();
+                                                    view! {
+                                                        <tr>
+                                                            <td>{entry.created_at.format("%H:%M").to_string()}</td>
+                                                            <td>{entry.name.clone()}</td>
+                                                            <td>{entry.contact.clone()}</td>
+                                                            <td>{entry.party_size}</td>
+                                                            <td>
+                                                                {entry
+                                                                    .preferred_time
+                                                                    .map(|t| format!("{:02}:{:02}", t.hour(), t.minute()))
+                                                                    .unwrap_or_else(|| "Now".into())}
+                                                            </td>
+                                                            <td>{format!("{} min", entry.quoted_wait)}</td>
+                                                            <td>
+                                                                {entry.status.to_string()}
+                                                                {entry
+                                                                    .notified_at
+                                                                    .map(|at| format!(" at {}", at.format("%H:%M")))}
+                                                            </td>
+                                                            <td>{free_table.clone().unwrap_or_else(|| "None".into())}</td>
+                                                            <td>
+                                                                {open
+                                                                    .then(|| {
+                                                                        view! {
+                                                                            <ActionForm action=notify>
+                                                                                <input type="hidden" name="entry_id" value=entry.entry_id/>
+                                                                                <button>"Notify"</button>
+                                                                            </ActionForm>
+                                                                            <ActionForm action=promote>
+                                                                                <input type="hidden" name="entry_id" value=entry.entry_id/>
+                                                                                <input type="time" name="reservation_time"/>
+                                                                                <input
+                                                                                    type="text"
+                                                                                    name="seating"
+                                                                                    placeholder=free_table.clone().unwrap_or_default()
+                                                                                />
+                                                                                <button>"Seat"</button>
+                                                                            </ActionForm>
+                                                                            <ActionForm action=remove>
+                                                                                <input type="hidden" name="entry_id" value=entry.entry_id/>
+                                                                                <button>"Remove"</button>
+                                                                            </ActionForm>
+                                                                        }
+                                                                    })}
+                                                            </td>
+                                                        </tr>
+                                                    }
+                                                })
+                                                .collect_view()}
+                                        </tbody>
+                                    </table>
+                                }
+                                    .into_view()
+                            }
+                        })
+                }}
+            </Transition>
+        </div>
+    }
+}
+
+/// What booking or changing a series did, with the occurrences skipped.
+fn schedule_summary(schedule: SeriesSchedule, verb: &'static str) -> impl IntoView {
+    view! {
+        <p>{format!("{} {} occurrences.", verb, schedule.booked.len())}</p>
+        {(!schedule.skipped.is_empty())
+            .then(|| {
+                view! {
+                    <ul class="error">
+                        {schedule
+                            .skipped
+                            .into_iter()
+                            .map(|skipped| {
+                                view! { <li>{format!("{} skipped, {}", skipped.date, skipped.reason)}</li> }
+                            })
+                            .collect_view()}
+                    </ul>
+                }
+            })}
+    }
+}
+
+#[component]
+fn StandingReservations() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    let add = create_server_action::<AddSeries>();
+    let edit_series = create_server_action::<EditSeries>();
+    let cancel_series = create_server_action::<CancelSeries>();
+    let edit_one = create_server_action::<EditOccurrence>();
+    let cancel_one = create_server_action::<CancelOccurrence>();
+    let add_closure = create_server_action::<AddClosure>();
+    let remove_closure = create_server_action::<RemoveClosure>();
+    let (feed_token, set_feed_token) = create_signal(None::<String>);
+    let changes = use_reservation_changes(pid, move || feed_token.get());
+    let series = create_resource(
+        move || {
+            (
+                pid(),
+                add.version().get(),
+                edit_series.version().get(),
+                cancel_series.version().get(),
+                edit_one.version().get(),
+                cancel_one.version().get(),
+                changes.get(),
+            )
+        },
+        |(pid, _, _, _, _, _, _)| list_series(pid),
+    );
+    let closures = create_resource(
+        move || {
+            (
+                pid(),
+                add_closure.version().get(),
+                remove_closure.version().get(),
+            )
+        },
+        |(pid, _, _)| list_closures(pid),
+    );
+    let today = chrono::Local::now().date_naive();
+
+    view! {
+        <FollowLive pid=Signal::derive(pid) set_token=set_feed_token/>
+        <div class="container">
+            <h2>"Standing reservations"</h2>
+            <ActionForm action=add>
+                <input type="hidden" name="pid" value=move || pid().to_string()/>
+                <input type="text" name="name" placeholder="Name" required/>
+                <input type="text" name="contact" placeholder="Phone or email" required/>
+                <label>
+                    "Party: " <input type="number" name="party_size" min="1" value="2" required/>
+                </label>
+                <label>
+                    "Table: " <input type="text" name="seating" placeholder="Any free table"/>
+                </label>
+                <label>"Time: " <input type="time" name="reservation_time" required/></label>
+                <label>
+                    "Repeats: "
+                    <select name="recurrence">
+                        {Recurrence::ALL
+                            .into_iter()
+                            .map(|recurrence| view! { <option>{recurrence.to_string()}</option> })
+                            .collect_view()}
+                    </select>
+                </label>
+                <label>"From: " <input type="date" name="starts_on" required/></label>
+                <label>"Until: " <input type="date" name="ends_on"/></label>
+                <label>
+                    "Or times: " <input type="number" name="occurrences" min="1"/>
+                </label>
+                <button>"Book series"</button>
+            </ActionForm>
+            {move || add.value().get().and_then(Result::ok).map(|schedule| schedule_summary(schedule, "Booked"))}
+            {move || {
+                edit_series.value().get().and_then(Result::ok).map(|schedule| schedule_summary(schedule, "Changed"))
+            }}
+            {move || {
+                [
+                    add.value().get().and_then(Result::err),
+                    edit_series.value().get().and_then(Result::err),
+                    cancel_series.value().get().and_then(Result::err),
+                    edit_one.value().get().and_then(Result::err),
+                    cancel_one.value().get().and_then(Result::err),
+                    add_closure.value().get().and_then(Result::err),
+                    remove_closure.value().get().and_then(Result::err),
+                ]
+                    .into_iter()
+                    .flatten()
+                    .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+                    .collect_view()
+            }}
+            <Transition fallback=move || view! { <p>"Loading"</p> }>
+                {move || {
+                    series
+                        .get()
+                        .map(|series| match series {
+                            Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                            Ok(series) if series.is_empty() => {
+                                view! { <p>"No standing reservations yet."</p> }.into_view()
+                            }
+                            Ok(series) => {
+                                series
+                                    .into_iter()
+                                    .map(|SeriesOverview { series, occurrences }| {
+                                        let open = series.cancelled_at.is_none();
+                                        view! {
+                                            <h3>
+                                                {format!(
+                                                    "{} ({}), {} at {:02}:{:02} for {} on {}",
+                                                    series.name,
+                                                    series.contact,
+                                                    series.recurrence,
+                                                    series.reservation_time.hour(),
+                                                    series.reservation_time.minute(),
+                                                    series.party_size,
+                                                    if series.seating.is_empty() { "any table" } else { &series.seating },
+                                                )}
+                                                {series
+                                                    .cancelled_at
+                                                    .map(|at| format!(", cancelled {}", at.format("%Y-%m-%d")))}
+                                            </h3>
+                                            {open
+                                                .then(|| {
+                                                    view! {
+                                                        <ActionForm action=edit_series>
+                                                            <input type="hidden" name="series_id" value=series.series_id/>
+                                                            <input
+                                                                type="time"
+                                                                name="reservation_time"
+                                                                value=format!(
+                                                                    "{:02}:{:02}",
+                                                                    series.reservation_time.hour(),
+                                                                    series.reservation_time.minute(),
+                                                                )
+                                                                required
+                                                            />
+                                                            <input
+                                                                type="text"
+                                                                name="seating"
+                                                                value=series.seating.clone()
+                                                                placeholder="Any free table"
+                                                            />
+                                                            <input
+                                                                type="number"
+                                                                name="party_size"
+                                                                min="1"
+                                                                value=series.party_size
+                                                                required
+                                                            />
+                                                            <button>"Change all upcoming"</button>
+                                                        </ActionForm>
+                                                        <ActionForm action=cancel_series>
+                                                            <input type="hidden" name="series_id" value=series.series_id/>
+                                                            <button>"Cancel series"</button>
+                                                        </ActionForm>
+                                                    }
+                                                })}
+                                            <table>
+                                                <thead>
+                                                    <tr>
+                                                        <th>"Date"</th>
+                                                        <th>"Time"</th>
+                                                        <th>"Table"</th>
+                                                        <th>"Party"</th>
+                                                        <th>"Status"</th>
+                                                        <th></th>
+                                                    </tr>
+                                                </thead>
+                                                <tbody>
+                                                    {occurrences
+                                                        .into_iter()
+                                                        .map(|resv| {
+                                                            let time = format!(
+                                                                "{:02}:{:02}",
+                                                                resv.reservation_time.hour(),
+                                                                resv.reservation_time.minute(),
+                                                            );
+                                                            let upcoming = upcoming(&resv, today);
+                                                            view! {
+                                                                <tr>
+                                                                    <td>{resv.reservation_date.to_string()}</td>
+                                                                    <td>{time.clone()}</td>
+                                                                    <td>{resv.seating.clone()}</td>
+                                                                    <td>{resv.party_size}</td>
+                                                                    <td>{resv.status.to_string()}</td>
+                                                                    <td>
+                                                                        {upcoming
+                                                                            .then(|| {
+                                                                                view! {
+                                                                                    <ActionForm action=edit_one>
+                                                                                        <input type="hidden" name="id" value=resv.id/>
+                                                                                        <input type="time" name="reservation_time" value=time required/>
+                                                                                        <input type="text" name="seating" value=resv.seating.clone()/>
+                                                                                        <input
+                                                                                            type="number"
+                                                                                            name="party_size"
+                                                                                            min="1"
+                                                                                            value=resv.party_size
+                                                                                            required
+                                                                                        />
+                                                                                        <button>"Change this one"</button>
+                                                                                    </ActionForm>
+                                                                                    <ActionForm action=cancel_one>
+                                                                                        <input type="hidden" name="id" value=resv.id/>
+                                                                                        <button>"Cancel this one"</button>
+                                                                                    </ActionForm>
+                                                                                }
+                                                                            })}
+                                                                    </td>
+                                                                </tr>
+                                                            }
+                                                        })
+                                                        .collect_view()}
+                                                </tbody>
+                                            </table>
+                                        }
+                                    })
+                                    .collect_view()
+                            }
+                        })
+                }}
+            </Transition>
+            <h3>"Closures"</h3>
+            <ActionForm action=add_closure>
+                <input type="hidden" name="pid" value=move || pid().to_string()/>
+                <label>"From: " <input type="date" name="first_day" required/></label>
+                <label>"Until: " <input type="date" name="last_day"/></label>
+                <input type="text" name="reason" placeholder="Reason"/>
+                <button>"Close"</button>
+            </ActionForm>
+            <Transition fallback=move || view! { <p>"Loading"</p> }>
+                {move || {
+                    closures
+                        .get()
+                        .map(|closures| match closures {
+                            Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                            Ok(closures) => {
+                                view! {
+                                    <ul>
+                                        {closures
+                                            .into_iter()
+                                            .map(|closure| {
+                                                view! {
+                                                    <li>
+                                                        {if closure.first_day == closure.last_day {
+                                                            closure.first_day.to_string()
+                                                        } else {
+                                                            format!("{} to {}", closure.first_day, closure.last_day)
+                                                        }}
+                                                        {(!closure.reason.is_empty())
+                                                            .then(|| format!(", {}", closure.reason))}
+                                                        <ActionForm action=remove_closure>
+                                                            <input type="hidden" name="pid" value=move || pid().to_string()/>
+                                                            <input type="hidden" name="closure_id" value=closure.closure_id/>
+                                                            <button>"Reopen"</button>
+                                                        </ActionForm>
+                                                    </li>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </ul>
+                                }
+                                    .into_view()
+                            }
+                        })
+                }}
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+fn GroupBookings() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    let (date, set_date) = create_signal(chrono::Local::now().date_naive().to_string());
+    let assign = create_server_action::<AssignTables>();
+    let buyout = create_server_action::<BookSection>();
+    let set_event = create_server_action::<SetEvent>();
+    let (feed_token, set_feed_token) = create_signal(None::<String>);
+    let changes = use_reservation_changes(pid, move || feed_token.get());
+    let bookings = create_resource(
+        move || {
+            (
+                pid(),
+                date.get(),
+                assign.version().get(),
+                buyout.version().get(),
+                set_event.version().get(),
+                changes.get(),
+            )
+        },
+        |(pid, date, _, _, _, _)| async move {
+            match date.parse() {
+                Ok(date) => list_group_bookings(pid, date).await,
+                Err(_) => Ok(Vec::new()),
+            }
+        },
+    );
+
+    view! {
+        <FollowLive pid=Signal::derive(pid) set_token=set_feed_token/>
+        <div class="container">
+            <h2>"Group bookings and events"</h2>
+            <label>
+                "Date: "
+                <input
+                    type="date"
+                    on:input=move |ev| set_date.set(event_target_value(&ev))
+                    prop:value=date
+                />
+            </label>
+            {move || {
+                [
+                    assign.value().get().and_then(Result::err),
+                    buyout.value().get().and_then(Result::err),
+                    set_event.value().get().and_then(Result::err),
+                ]
+                    .into_iter()
+                    .flatten()
+                    .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+                    .collect_view()
+            }}
+            <Transition fallback=move || view! { <p>"Loading"</p> }>
+                {move || {
+                    bookings
+                        .get()
+                        .map(|bookings| match bookings {
+                            Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                            Ok(bookings) if bookings.is_empty() => {
+                                view! { <p>"No bookings on this day."</p> }.into_view()
+                            }
+                            Ok(bookings) => {
+                                bookings
+                                    .into_iter()
+                                    .map(|GroupBooking { reservation: resv, tables, event }| {
+                                        let event = event.unwrap_or_default();
+                                        view! {
+                                            <h3>
+                                                {format!(
+                                                    "{:02}:{:02} {} for {} on {}",
+                                                    resv.reservation_time.hour(),
+                                                    resv.reservation_time.minute(),
+                                                    resv.name,
+                                                    resv.party_size,
+                                                    resv.seating,
+                                                )}
+                                            </h3>
+                                            <p class="event">{event.summary()}</p>
+                                            <ActionForm action=assign>
+                                                <input type="hidden" name="id" value=resv.id/>
+                                                <input
+                                                    type="text"
+                                                    name="tables"
+                                                    value=tables.join(", ")
+                                                    placeholder="T1, T2"
+                                                    required
+                                                />
+                                                <button>"Assign tables"</button>
+                                            </ActionForm>
+                                            <ActionForm action=buyout>
+                                                <input type="hidden" name="id" value=resv.id/>
+                                                <input
+                                                    type="text"
+                                                    name="section"
+                                                    value=event.section.clone().unwrap_or_default()
+                                                    placeholder="Section"
+                                                    required
+                                                />
+                                                <button>"Book out section"</button>
+                                            </ActionForm>
+                                            <ActionForm action=set_event>
+                                                <input type="hidden" name="id" value=resv.id/>
+                                                <input
+                                                    type="text"
+                                                    name="menu"
+                                                    value=event.menu.clone()
+                                                    placeholder="Menu"
+                                                />
+                                                <input
+                                                    type="number"
+                                                    name="minimum_spend"
+                                                    min="0"
+                                                    value=event.minimum_spend.map(|spend| spend.to_string()).unwrap_or_default()
+                                                    placeholder="Minimum spend"
+                                                />
+                                                <button>"Save event"</button>
+                                            </ActionForm>
+                                        }
+                                    })
+                                    .collect_view()
+                            }
+                        })
+                }}
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+fn TablePlanPage() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    let (date, set_date) = create_signal(chrono::Local::now().date_naive().to_string());
+    let accept = create_server_action::<AcceptTablePlan>();
+    let assign = create_server_action::<AssignTables>();
+    let (feed_token, set_feed_token) = create_signal(None::<String>);
+    let changes = use_reservation_changes(pid, move || feed_token.get());
+    let plan = create_resource(
+        move || {
+            (
+                pid(),
+                date.get(),
+                accept.version().get(),
+                assign.version().get(),
+                changes.get(),
+            )
+        },
+        |(pid, date, _, _, _)| async move {
+            match date.parse() {
+                Ok(date) => propose_tables(pid, date).await.map(Some),
+                Err(_) => Ok(None),
+            }
+        },
+    );
+
+    view! {
+        <FollowLive pid=Signal::derive(pid) set_token=set_feed_token/>
+        <div class="container">
+            <h2>"Table plan"</h2>
+            <label>
+                "Date: "
+                <input
+                    type="date"
+                    on:input=move |ev| set_date.set(event_target_value(&ev))
+                    prop:value=date
+                />
+            </label>
+            <ActionForm action=accept>
+                <input type="hidden" name="pid" value=move || pid().to_string()/>
+                <input type="hidden" name="date" value=date/>
+                <button>"Accept plan"</button>
+            </ActionForm>
+            {move || {
+                accept
+                    .value()
+                    .get()
+                    .map(|moved| match moved {
+                        Ok(moved) => view! { <p>{format!("{} reservations moved.", moved.len())}</p> }.into_view(),
+                        Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                    })
+            }}
+            {move || {
+                assign
+                    .value()
+                    .get()
+                    .and_then(Result::err)
+                    .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+            }}
+            <Transition fallback=move || view! { <p>"Loading"</p> }>
+                {move || {
+                    plan.get()
+                        .map(|plan| match plan {
+                            Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                            Ok(None) => view! { <p>"Pick a day."</p> }.into_view(),
+                            Ok(Some(plan)) => {
+                                view! {
+                                    <p>{format!("{} seats left empty.", plan.wasted_seats)}</p>
+                                    <table>
+                                        <thead>
+                                            <tr>
+                                                <th>"Time"</th>
+                                                <th>"Name"</th>
+                                                <th>"Party"</th>
+                                                <th>"Now"</th>
+                                                <th>"Proposed"</th>
+                                                <th>"Empty seats"</th>
+                                            </tr>
+                                        </thead>
+                                        <tbody>
+                                            {plan
+                                                .placed
+                                                .into_iter()
+                                                .map(|seat| {
+                                                    let resv = seat.reservation;
+                                                    view! {
+                                                        <tr>
+                                                            <td>
+                                                                {format!(
+                                                                    "{:02}:{:02}",
+                                                                    resv.reservation_time.hour(),
+                                                                    resv.reservation_time.minute(),
+                                                                )}
+                                                            </td>
+                                                            <td>{resv.name.clone()}</td>
+                                                            <td>{resv.party_size}</td>
+                                                            <td>{resv.seating.clone()}</td>
+                                                            <td>
+                                                                {if seat.fixed {
+                                                                    format!("{} (kept)", seat.tables.join("+")).into_view()
+                                                                } else {
+                                                                    view! {
+                                                                        <ActionForm action=assign>
+                                                                            <input type="hidden" name="id" value=resv.id/>
+                                                                            <input type="text" name="tables" value=seat.tables.join("+") required/>
+                                                                            <button>"Apply"</button>
+                                                                        </ActionForm>
+                                                                    }
+                                                                        .into_view()
+                                                                }}
+                                                            </td>
+                                                            <td>{seat.wasted}</td>
+                                                        </tr>
+                                                    }
+                                                })
+                                                .collect_view()}
+                                        </tbody>
+                                    </table>
+                                    <ul>
+                                        {plan
+                                            .unplaced
+                                            .into_iter()
+                                            .map(|unplaced| {
+                                                view! {
+                                                    <li class="warning">
+                                                        {format!(
+                                                            "{} for {}: {}",
+                                                            unplaced.reservation.name,
+                                                            unplaced.reservation.party_size,
+                                                            unplaced.reason,
+                                                        )}
+                                                    </li>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </ul>
+                                }
+                                    .into_view()
+                            }
+                        })
+                }}
+            </Transition>
+        </div>
+    }
+}
+
+/// A table on the floor plan, drawn at `(x, y)` with its class giving the
+/// colour.
+fn floor_table(spot: &FloorTable, x: i32, y: i32, class: &'static str) -> impl IntoView {
+    let layout = spot.layout;
+    let (width, height) = (layout.width, layout.height);
+    let shape = match layout.shape {
+        TableShape::Rectangle => {
+            view! { <rect class=class width=width height=height rx="4"></rect> }.into_view()
+        }
+        TableShape::Round => view! {
+            <ellipse
+                class=class
+                cx=width / 2
+                cy=height / 2
+                rx=width / 2
+                ry=height / 2
+            ></ellipse>
+        }
+        .into_view(),
+    };
+    let guest = spot
+        .reservation
+        .as_ref()
+        .map(|resv| format!("{} ({})", resv.name, resv.party_size));
+    view! {
+        <g transform=format!(
+            "translate({x} {y}) rotate({} {} {})",
+            layout.rotation,
+            width / 2,
+            height / 2,
+        )>
+            {shape}
+            <text x=width / 2 y=height / 2 text-anchor="middle">
+                {format!("{} ({})", spot.table.table_name, spot.table.capacity)}
+            </text>
+            {guest
+                .map(|guest| {
+                    view! {
+                        <text x=width / 2 y=height / 2 + 14 text-anchor="middle">
+                            {guest}
+                        </text>
+                    }
+                })}
+        </g>
+    }
+}
+
+/// A table being dragged: where it was, where the pointer went down and where
+/// the table is now.
+#[derive(Clone)]
+struct Drag {
+    spot: FloorTable,
+    pointer: (i32, i32),
+    at: (i32, i32),
+}
+
+#[component]
+fn FloorPlanEditor() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    let save = create_server_action::<SaveTableLayout>();
+    let plan = create_resource(
+        move || (pid(), save.version().get()),
+        |(pid, _)| get_floor_plan(pid),
+    );
+    let drag = create_rw_signal(None::<Drag>);
+    let (selected, set_selected) = create_signal(None::<FloorTable>);
+    let drop_table = move || {
+        if let Some(Drag { spot, at, .. }) = drag.get_untracked() {
+            drag.set(None);
+            if at != (spot.layout.x, spot.layout.y) {
+                save.dispatch(SaveTableLayout {
+                    pid: pid(),
+                    table_id: spot.table.table_id,
+                    x: at.0,
+                    y: at.1,
+                    width: spot.layout.width,
+                    height: spot.layout.height,
+                    rotation: spot.layout.rotation,
+                    shape: spot.layout.shape.to_string(),
+                    section: spot.table.section.clone().unwrap_or_default(),
+                });
+            }
+        }
+    };
+
+    view! {
+        <div class="container">
+            <h2>"Floor plan"</h2>
+            <p>"Drag tables where they stand, pick one to change its shape, size or section."</p>
+            {move || {
+                save.value()
+                    .get()
+                    .and_then(Result::err)
+                    .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+            }}
+            <Transition fallback=move || view! { <p>"Loading"</p> }>
+                {move || {
+                    plan.get()
+                        .map(|plan| match plan {
+                            Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                            Ok(plan) => {
+                                view! {
+                                    <svg
+                                        class="floor"
+                                        width=FLOOR_WIDTH
+                                        height=FLOOR_HEIGHT
+                                        on:pointermove=move |ev| {
+                                            drag.update(|drag| {
+                                                if let Some(drag) = drag {
+                                                    let layout = drag.spot.layout;
+                                                    drag.at = (
+                                                        (layout.x + ev.client_x() - drag.pointer.0)
+                                                            .clamp(0, FLOOR_WIDTH - layout.width),
+                                                        (layout.y + ev.client_y() - drag.pointer.1)
+                                                            .clamp(0, FLOOR_HEIGHT - layout.height),
+                                                    );
+                                                }
+                                            })
+                                        }
+                                        on:pointerup=move |_| drop_table()
+                                        on:pointerleave=move |_| drop_table()
+                                    >
+                                        {plan
+                                            .into_iter()
+                                            .map(|spot| {
+                                                let table_id = spot.table.table_id;
+                                                let grabbed = spot.clone();
+                                                view! {
+                                                    <g
+                                                        class="movable"
+                                                        on:pointerdown=move |ev| {
+                                                            ev.prevent_default();
+                                                            set_selected.set(Some(grabbed.clone()));
+                                                            drag.set(
+                                                                Some(Drag {
+                                                                    spot: grabbed.clone(),
+                                                                    pointer: (ev.client_x(), ev.client_y()),
+                                                                    at: (grabbed.layout.x, grabbed.layout.y),
+                                                                }),
+                                                            );
+                                                        }
+                                                    >
+                                                        {move || {
+                                                            let (x, y) = drag
+                                                                .with(|drag| {
+                                                                    drag.as_ref()
+                                                                        .filter(|drag| drag.spot.table.table_id == table_id)
+                                                                        .map(|drag| drag.at)
+                                                                })
+                                                                .unwrap_or((spot.layout.x, spot.layout.y));
+                                                            floor_table(&spot, x, y, "free")
+                                                        }}
+                                                    </g>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </svg>
+                                }
+                                    .into_view()
+                            }
+                        })
+                }}
+            </Transition>
+            {move || {
+                selected
+                    .get()
+                    .map(|spot| {
+                        let layout = spot.layout;
+                        view! {
+                            <h3>{spot.table.table_name.clone()}</h3>
+                            <ActionForm action=save>
+                                <input type="hidden" name="pid" value=move || pid().to_string()/>
+                                <input type="hidden" name="table_id" value=spot.table.table_id/>
+                                <input type="hidden" name="x" value=layout.x/>
+                                <input type="hidden" name="y" value=layout.y/>
+                                <label>
+                                    "Shape: "
+                                    <select name="shape">
+                                        {TableShape::ALL
+                                            .into_iter()
+                                            .map(|shape| {
+                                                view! {
+                                                    <option selected=shape == layout.shape>
+                                                        {shape.to_string()}
+                                                    </option>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </select>
+                                </label>
+                                <label>
+                                    "Width: "
+                                    <input type="number" name="width" min="20" max="300" value=layout.width required/>
+                                </label>
+                                <label>
+                                    "Depth: "
+                                    <input type="number" name="height" min="20" max="300" value=layout.height required/>
+                                </label>
+                                <label>
+                                    "Rotation: "
+                                    <input type="number" name="rotation" step="15" value=layout.rotation required/>
+                                </label>
+                                <label>
+                                    "Section: "
+                                    <input
+                                        type="text"
+                                        name="section"
+                                        value=spot.table.section.clone().unwrap_or_default()
+                                    />
+                                </label>
+                                <button>"Save table"</button>
+                            </ActionForm>
+                        }
+                    })
+            }}
+        </div>
+    }
+}
+
+#[component]
+fn LiveFloor() -> impl IntoView {
+    let params = use_params::<PropertyIdParam>();
+    let pid = move || {
+        params
+            .with(|params| params.as_ref().map(|params| params.pid).unwrap_or_default())
+            .expect("Failed to get pid.")
+    };
+
+    let now = chrono::Local::now();
+    let (date, set_date) = create_signal(now.date_naive().to_string());
+    let (time, set_time) = create_signal(now.format("%H:%M").to_string());
+    let (feed_token, set_feed_token) = create_signal(None::<String>);
+    let changes = use_reservation_changes(pid, move || feed_token.get());
+    let floor = create_resource(
+        move || (pid(), date.get(), time.get(), changes.get()),
+        |(pid, date, time, _)| async move {
+            match date.parse() {
+                Ok(date) => get_floor_view(pid, date, time).await,
+                Err(_) => Ok(Vec::new()),
+            }
+        },
+    );
+
+    view! {
+        <FollowLive pid=Signal::derive(pid) set_token=set_feed_token/>
+        <div class="container">
+            <h2>"Floor"</h2>
+            <label>
+                "Date: "
+                <input
+                    type="date"
+                    on:input=move |ev| set_date.set(event_target_value(&ev))
+                    prop:value=date
+                />
+            </label>
+            <label>
+                "Time: "
+                <input
+                    type="time"
+                    on:input=move |ev| set_time.set(event_target_value(&ev))
+                    prop:value=time
+                />
+            </label>
+            <p class="floor-legend">
+                <span class="free">"Free"</span>
+                " "
+                <span class="booked">"Booked"</span>
+                " "
+                <span class="seated">"Seated"</span>
+            </p>
+            <Transition fallback=move || view! { <p>"Loading"</p> }>
+                {move || {
+                    floor
+                        .get()
+                        .map(|floor| match floor {
+                            Err(e) => view! { <pre class="error">{e.to_string()}</pre> }.into_view(),
+                            Ok(floor) => {
+                                view! {
+                                    <svg class="floor" width=FLOOR_WIDTH height=FLOOR_HEIGHT>
+                                        {floor
+                                            .iter()
+                                            .map(|spot| {
+                                                let class = match spot.reservation.as_ref().map(|resv| resv.status) {
+                                                    None => "free",
+                                                    Some(ReservationStatus::Seated) => "seated",
+                                                    Some(_) => "booked",
+                                                };
+                                                floor_table(spot, spot.layout.x, spot.layout.y, class)
+                                            })
+                                            .collect_view()}
+                                    </svg>
+                                }
+                                    .into_view()
+                            }
+                        })
+                }}
+            </Transition>
+        </div>
+    }
+}
+
 #[component]
 fn PropertyReservations() -> impl IntoView {
     let params = use_params::<PropertyIdParam>();
@@ -179,13 +3078,89 @@ fn PropertyReservations() -> impl IntoView {
             .expect("Failed to get pid.")
     };
 
+    let set_status = create_server_action::<SetResvStatus>();
+    let seat_walk_in = create_server_action::<SeatWalkIn>();
+    let (feed_token, set_feed_token) = create_signal(None::<String>);
+    let changes = use_reservation_changes(pid, move || feed_token.get());
+    let (tag, set_tag) = create_signal(String::new());
     let reservations = create_resource(
-        || (),
-        move |_| async move { get_property_reservations(pid()).await },
+        move || {
+            (
+                set_status.version().get(),
+                seat_walk_in.version().get(),
+                changes.get(),
+                tag.get(),
+            )
+        },
+        move |(_, _, _, tag)| async move { get_property_reservations(pid(), Some(tag)).await },
     );
 
     view! {
+        <FollowLive pid=Signal::derive(pid) set_token=set_feed_token/>
         <div>
+            <nav>
+                <A href="add_resv">"Add reservation"</A>
+                " "
+                <A href="api_keys">"API keys"</A>
+                " "
+                <A href="webhooks">"Webhooks"</A>
+                " "
+                <A href="emails">"Emails"</A>
+                " "
+                <A href="messages">"Messages"</A>
+                " "
+                <A href="jobs">"Jobs"</A>
+                " "
+                <A href="guests">"Guests"</A>
+                " "
+                <A href="day_sheet">"Day sheet"</A>
+                " "
+                <A href="waitlist">"Waitlist"</A>
+                " "
+                <A href="series">"Standing reservations"</A>
+                " "
+                <A href="events">"Group bookings"</A>
+                " "
+                <A href="table_plan">"Table plan"</A>
+                " "
+                <A href="floor_plan">"Floor plan"</A>
+                " "
+                <A href="floor">"Floor"</A>
+            </nav>
+            <ActionForm action=seat_walk_in>
+                <input type="hidden" name="pid" value=move || pid().to_string()/>
+                <label>
+                    "Party: " <input type="number" name="party_size" min="1" value="2" required/>
+                </label>
+                <label>
+                    "Table: " <input type="text" name="seating" placeholder="Any free table"/>
+                </label>
+                <input type="text" name="name" placeholder="Name (optional)"/>
+                <input type="text" name="contact" placeholder="Phone or email (optional)"/>
+                <button>"Seat walk-in"</button>
+            </ActionForm>
+            {move || {
+                seat_walk_in
+                    .value()
+                    .get()
+                    .and_then(Result::err)
+                    .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+            }}
+            <label>
+                "Tag: "
+                <input
+                    type="search"
+                    on:change=move |ev| set_tag.set(event_target_value(&ev))
+                    prop:value=tag
+                />
+            </label>
+            {move || {
+                set_status
+                    .value()
+                    .get()
+                    .and_then(Result::err)
+                    .map(|e| view! { <pre class="error">{e.to_string()}</pre> })
+            }}
             <Suspense fallback=move || view! { <p>"Loading data"</p> }>
                 <ErrorBoundary fallback=|_| {
                     view! { <p>"Something went wrong"</p> }
@@ -207,6 +3182,7 @@ fn PropertyReservations() -> impl IntoView {
                                         vecc_resv
                                             .into_iter()
                                             .map(|resv| {
+                                                let code = resv.confirmation_code.clone().unwrap_or_default();
                                                 view! {
                                                     <div>
                                                         <table>
@@ -214,6 +3190,7 @@ fn PropertyReservations() -> impl IntoView {
                                                                 <tr>
                                                                     <th>Id</th>
                                                                     <th>Name</th>
+                                                                    <th>Party</th>
                                                                     <th>Contact</th>
                                                                     <th>Seating</th>
                                                                     <th>Specific seating</th>
@@ -226,6 +3203,10 @@ fn PropertyReservations() -> impl IntoView {
                                                                     <th>Reservation date</th>
                                                                     <th>Reservation time</th>
                                                                     <th>Property Id</th>
+                                                                    <th>Status</th>
+                                                                    <th>Requests</th>
+                                                                    <th>Tags</th>
+                                                                    <th>Guest link</th>
                                                                 </tr>
                                                             </thead>
 
@@ -237,6 +3218,9 @@ fn PropertyReservations() -> impl IntoView {
                                                                     <td>
                                                                         <p>{resv.name}</p>
                                                                     </td>
+                                                                    <td>
+                                                                        <p>{resv.party_size}</p>
+                                                                    </td>
                                                                     <td>
                                                                         <p>{resv.contact}</p>
                                                                     </td>
@@ -257,7 +3241,7 @@ fn PropertyReservations() -> impl IntoView {
                                                                                         resv
                                                                                             .advance_method["mode_of_payment"]
                                                                                             .as_str()
-                                                                                            .unwrap_or_else(|| "Payment not received"),
+                                                                                            .unwrap_or("Payment not received"),
                                                                                     )}
 
                                                                                 </td>
@@ -266,7 +3250,7 @@ fn PropertyReservations() -> impl IntoView {
                                                                                         resv
                                                                                             .advance_method["payment_transaction_id"]
                                                                                             .as_str()
-                                                                                            .unwrap_or_else(|| "Not transaction id"),
+                                                                                            .unwrap_or("Not transaction id"),
                                                                                     )}
 
                                                                                 </td>
@@ -275,7 +3259,7 @@ fn PropertyReservations() -> impl IntoView {
                                                                                         resv
                                                                                             .advance_method["payment_received_date"]
                                                                                             .as_str()
-                                                                                            .unwrap_or_else(|| "Payment not reveived"),
+                                                                                            .unwrap_or("Payment not reveived"),
                                                                                     )}
 
                                                                                 </td>
@@ -296,6 +3280,32 @@ fn PropertyReservations() -> impl IntoView {
                                                                     <td>
                                                                         <p>{resv.confirmed}</p>
                                                                     </td>
+                                                                    <td>
+                                                                        <ActionForm action=set_status>
+                                                                            <input
+                                                                                type="hidden"
+                                                                                name="id"
+                                                                                value=resv.id
+                                                                            />
+                                                                            <select name="status">
+                                                                                <option value="Booked">"Booked"</option>
+                                                                                <option value="Seated">"Seated"</option>
+                                                                                <option value="Completed">"Completed"</option>
+                                                                                <option value="NoShow">"NoShow"</option>
+                                                                                <option value="Cancelled">"Cancelled"</option>
+                                                                            </select>
+                                                                            <button>"Set"</button>
+                                                                        </ActionForm>
+                                                                        {resv.status.to_string()}
+                                                                        {resv
+                                                                            .no_show_at
+                                                                            .map(|at| {
+                                                                                format!(" since {} UTC", at.format("%Y-%m-%d %H:%M"))
+                                                                            })}
+                                                                    </td>
+                                                                    <td>{resv.special_requests.summary()}</td>
+                                                                    <td>{resv.tags}</td>
+                                                                    <td>{code}</td>
                                                                 // <p>{resv.reservation_date}</p>
                                                                 // <p>{resv.reservation_time}</p>
                                                                 </tr>
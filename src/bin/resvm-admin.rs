@@ -0,0 +1,244 @@
+//! Operational tasks that would otherwise need the web UI or raw SQL.
+//!
+//! Uses the same `DATABASE_URL` and repositories as the server. Every command
+//! exits with a non-zero code when it fails.
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand};
+use leptos::LeptosOptions;
+use resvm::{
+    crud_properties::cancel_reservation,
+    get_connection_pool, guests, migrations,
+    models::{NewProperty, WebhookEvent},
+    salt_password, seed, AppState,
+};
+use serde_json::json;
+use std::process::ExitCode;
+use uuid::Uuid;
+
+#[derive(Parser)]
+#[command(name = "resvm-admin", about = "Administration tasks for resvm")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Apply the embedded migrations, or only compare them with `--check`.
+    Migrate {
+        #[arg(long)]
+        check: bool,
+    },
+    #[command(flatten)]
+    Checked(CheckedCommand),
+}
+
+/// The commands that need the database at the schema of this build.
+#[derive(Subcommand)]
+enum CheckedCommand {
+    /// Insert the canonical roles, or the demo property with `--demo`.
+    Seed {
+        #[arg(long)]
+        demo: bool,
+    },
+    #[command(subcommand)]
+    Property(PropertyCommand),
+    #[command(subcommand)]
+    Staff(StaffCommand),
+    #[command(subcommand)]
+    Reservations(ReservationCommand),
+    /// Print all properties with their tables and reservations as JSON.
+    Export,
+}
+
+#[derive(Subcommand)]
+enum PropertyCommand {
+    /// Register a new property and print its id.
+    Create {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        email: String,
+        #[arg(long)]
+        phone: String,
+        #[command(flatten)]
+        password: PasswordArg,
+    },
+    /// List all properties.
+    List,
+    /// Replace the property password.
+    ResetPassword {
+        pid: Uuid,
+        #[command(flatten)]
+        password: PasswordArg,
+    },
+}
+
+#[derive(Subcommand)]
+enum StaffCommand {
+    /// Replace the password of a staff member of a property.
+    ResetPassword {
+        pid: Uuid,
+        user_name: String,
+        #[command(flatten)]
+        password: PasswordArg,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReservationCommand {
+    /// List the reservations of a property, optionally for a single day.
+    List {
+        pid: Uuid,
+        #[arg(long)]
+        date: Option<NaiveDate>,
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+    },
+    /// Cancel a reservation by id.
+    Cancel { id: i32 },
+    /// Link reservations stored before guest profiles to their guests.
+    LinkGuests { pid: Uuid },
+}
+
+#[derive(Args)]
+struct PasswordArg {
+    /// New password, read from RESVM_ADMIN_PASSWORD when omitted.
+    #[arg(long, env = "RESVM_ADMIN_PASSWORD")]
+    password: String,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Command) -> anyhow::Result<()> {
+    let pool = get_connection_pool();
+
+    // Only `migrate` may run against a database with a different schema.
+    let command = match command {
+        Command::Migrate { check: true } => {
+            migrations::check_schema(&pool)?;
+            println!("schema is up to date");
+            return Ok(());
+        }
+        Command::Migrate { check: false } => {
+            for version in migrations::run_migrations(&pool)? {
+                println!("applied migration {version}");
+            }
+            return Ok(());
+        }
+        Command::Checked(command) => command,
+    };
+    migrations::check_schema(&pool)?;
+
+    let state = AppState::new(LeptosOptions::default(), pool);
+    match command {
+        CheckedCommand::Seed { demo: false } => {
+            let inserted = seed::seed_roles(&state.pool)?;
+            println!("inserted {inserted} roles");
+        }
+        CheckedCommand::Seed { demo: true } => {
+            let pid = seed::seed_demo(
+                state.properties.as_ref(),
+                state.reservations.as_ref(),
+                state.guests.as_ref(),
+                &state.no_show_policy,
+            )
+            .await?;
+            println!("{pid}");
+        }
+        CheckedCommand::Property(PropertyCommand::Create {
+            name,
+            email,
+            phone,
+            password,
+        }) => {
+            let pid = state.properties.insert(NewProperty::new(
+                Uuid::new_v4(),
+                name,
+                salt_password(password.password).await?,
+                email,
+                phone,
+            ))?;
+            println!("{pid}");
+        }
+        CheckedCommand::Property(PropertyCommand::List) => {
+            for p in state.properties.all()? {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    p.property_id, p.property_name, p.property_email, p.property_phone
+                );
+            }
+        }
+        CheckedCommand::Property(PropertyCommand::ResetPassword { pid, password }) => {
+            state
+                .properties
+                .set_password(pid, salt_password(password.password).await?)?;
+            println!("password of property {pid} reset");
+        }
+        CheckedCommand::Staff(StaffCommand::ResetPassword {
+            pid,
+            user_name,
+            password,
+        }) => {
+            state.properties.set_user_password(
+                pid,
+                &user_name,
+                salt_password(password.password).await?,
+            )?;
+            println!("password of {user_name} reset");
+        }
+        CheckedCommand::Reservations(ReservationCommand::List { pid, date, limit }) => {
+            let reservations = match date {
+                Some(date) => state.reservations.on_date(pid, date)?,
+                None => state.reservations.for_property(pid, limit)?,
+            };
+            for r in reservations.into_iter().take(limit.max(0) as usize) {
+                println!(
+                    "{}\t{} {}\t{}\t{}\t{}\t{}",
+                    r.id,
+                    r.reservation_date,
+                    r.reservation_time,
+                    r.status,
+                    r.name,
+                    r.contact,
+                    r.seating
+                );
+            }
+        }
+        CheckedCommand::Reservations(ReservationCommand::Cancel { id }) => {
+            let resv = cancel_reservation(state.reservations.as_ref(), id)?;
+            state.publish(WebhookEvent::ReservationCancelled, &resv);
+            println!("reservation {} is {}", resv.id, resv.status);
+        }
+        CheckedCommand::Reservations(ReservationCommand::LinkGuests { pid }) => {
+            let linked =
+                guests::link_reservations(state.guests.as_ref(), state.reservations.as_ref(), pid)?;
+            println!("linked {linked} reservations to guests");
+        }
+        CheckedCommand::Export => {
+            let mut properties = Vec::new();
+            for p in state.properties.all()? {
+                properties.push(json!({
+                    "tables": state.properties.tables(p.property_id)?,
+                    "reservations": state.reservations.for_property(p.property_id, i64::MAX)?,
+                    "property": p,
+                }));
+            }
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&json!({ "properties": properties }))?
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,635 @@
+//! Online booking for guests at `/book/:pid`.
+//!
+//! Guests pick a date, a party size and one of the free slots, and the booking
+//! is stored as a `Requested` reservation through [`create_reservation`] for
+//! the staff to confirm. A slot is free when a table large enough for the
+//! party is not held by another reservation within [`TURN_MINUTES`], and
+//! days covered by a closure of the property offer no slots at all.
+//!
+//! Bots are kept out by a hidden honeypot field and a signed form token that
+//! must be at least [`MIN_FILL_SECS`] old, and every client address may only
+//! attempt [`BOOKINGS_PER_WINDOW`] bookings per [`RATE_WINDOW`].
+use crate::crud_properties::{create_reservation, parse_reservation_time};
+use crate::errors::RepositoryError;
+use crate::guests::NoShowPolicy;
+use crate::manage::hhmm;
+use crate::models::{
+    Closure, NewClosure, NewResv, PaymentMethod, PaymentMode, PropertyTable, Reservation,
+    ReservationStatus, SpecialRequests, WebhookEvent,
+};
+use crate::repository::{GuestRepository, PropertyRepository, RepoResult, ReservationRepository};
+use crate::special_requests::parse_occasion;
+use crate::webhooks::{sign, verify};
+use crate::AppState;
+use chrono::{Days, Local, NaiveDate, Utc};
+use leptos::{expect_context, server, ServerFnError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+use time::{macros::time, Time};
+use uuid::Uuid;
+
+/// First and last slot offered to guests.
+pub const OPENING: Time = time!(12:00);
+pub const LAST_SEATING: Time = time!(21:30);
+/// Minutes between two offered slots.
+pub const SLOT_MINUTES: i64 = 30;
+/// Minutes a table stays taken by a reservation.
+pub const TURN_MINUTES: i64 = 120;
+/// How far ahead guests can book.
+pub const BOOKING_DAYS: u64 = 90;
+
+/// A form submitted faster than this is assumed to come from a bot.
+pub const MIN_FILL_SECS: i64 = 3;
+/// Form tokens older than this are rejected, the page has to be reloaded.
+pub const MAX_FORM_AGE_SECS: i64 = 2 * 60 * 60;
+
+pub const BOOKINGS_PER_WINDOW: usize = 5;
+pub const RATE_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// The slots offered on any day.
+pub fn slots() -> Vec<Time> {
+    let mut slots = vec![OPENING];
+    while let Some(last) = slots.last().copied() {
+        let next = last + time::Duration::minutes(SLOT_MINUTES);
+        if next > LAST_SEATING || next < last {
+            break;
+        }
+        slots.push(next);
+    }
+    slots
+}
+
+/// Whether reservations starting at `a` and `b` would share a table.
+pub(crate) fn overlaps(a: Time, b: Time) -> bool {
+    (a - b).whole_minutes().abs() < TURN_MINUTES
+}
+
+/// The smallest table that can seat `party_size` at `time`, given the active
+/// reservations of that day.
+///
+/// Reservations on a named table hold it, the others are placed on the
+/// smallest table that fits them first.
+pub fn free_table(
+    tables: &[PropertyTable],
+    reservations: &[Reservation],
+    time: Time,
+    party_size: i32,
+) -> Option<PropertyTable> {
+    let mut tables: Vec<&PropertyTable> = tables.iter().collect();
+    tables.sort_by_key(|table| table.capacity);
+    let mut held = vec![false; tables.len()];
+
+    let (seated, unseated): (Vec<&Reservation>, Vec<&Reservation>) = reservations
+        .iter()
+        .filter(|resv| resv.status.is_active() && overlaps(resv.reservation_time, time))
+        .partition(|resv| {
+            tables
+                .iter()
+                .any(|table| table.table_name.eq_ignore_ascii_case(resv.seating.trim()))
+        });
+    for resv in seated {
+        if let Some(i) = tables
+            .iter()
+            .position(|table| table.table_name.eq_ignore_ascii_case(resv.seating.trim()))
+        {
+            held[i] = true;
+        }
+    }
+    for resv in unseated {
+        if let Some(i) =
+            (0..tables.len()).find(|&i| !held[i] && tables[i].capacity >= resv.party_size)
+        {
+            held[i] = true;
+        }
+    }
+
+    (0..tables.len())
+        .find(|&i| !held[i] && tables[i].capacity >= party_size)
+        .map(|i| tables[i].clone())
+}
+
+/// The table a booking asks for, as the property names it, or why it cannot
+/// seat `party_size`. Left empty, the booking takes the smallest free table.
+pub(crate) fn check_seating(
+    tables: &[PropertyTable],
+    seating: &str,
+    party_size: i32,
+) -> RepoResult<String> {
+    let seating = seating.trim();
+    if seating.is_empty() || tables.is_empty() {
+        return Ok(seating.to_string());
+    }
+    match tables
+        .iter()
+        .find(|table| table.table_name.eq_ignore_ascii_case(seating))
+    {
+        Some(table) if table.capacity < party_size => Err(RepositoryError::Invalid(format!(
+            "{} only seats {}",
+            table.table_name, table.capacity
+        ))),
+        Some(table) => Ok(table.table_name.clone()),
+        None => Err(RepositoryError::Invalid(format!(
+            "{seating} is not a table of this property"
+        ))),
+    }
+}
+
+/// A [`RepositoryError::Conflict`] when a party still seated holds
+/// `seating`. Seated guests keep their table however long they stay, until
+/// they are completed.
+pub(crate) fn check_not_seated(booked: &[Reservation], seating: &str) -> RepoResult<()> {
+    match booked
+        .iter()
+        .find(|resv| is_seated_at(resv, seating.trim()))
+    {
+        Some(seated) => Err(RepositoryError::Conflict(format!(
+            "{} is still taken by {}",
+            seating.trim(),
+            seated.name
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// `tables` and `booked` without the tables of parties still seated and
+/// without those parties, so that [`free_table`] and [`table_for`] never
+/// offer a table taken however long ago.
+pub(crate) fn without_seated(
+    tables: &[PropertyTable],
+    booked: &[Reservation],
+) -> (Vec<PropertyTable>, Vec<Reservation>) {
+    let free = tables
+        .iter()
+        .filter(|table| check_not_seated(booked, &table.table_name).is_ok())
+        .cloned()
+        .collect();
+    let others = booked
+        .iter()
+        .filter(|resv| {
+            !tables
+                .iter()
+                .any(|table| is_seated_at(resv, &table.table_name))
+        })
+        .cloned()
+        .collect();
+    (free, others)
+}
+
+fn is_seated_at(resv: &Reservation, seating: &str) -> bool {
+    !seating.is_empty()
+        && resv.status == ReservationStatus::Seated
+        && resv.seating.trim().eq_ignore_ascii_case(seating)
+}
+
+/// Where a booking at `time` sits given the other reservations of its day:
+/// on `seating` unless another reservation holds it, or on the smallest free
+/// table. A [`RepositoryError::Conflict`] says why neither is possible.
+pub(crate) fn table_for(
+    tables: &[PropertyTable],
+    booked: &[Reservation],
+    seating: &str,
+    party_size: i32,
+    time: Time,
+) -> RepoResult<String> {
+    if seating.is_empty() {
+        return free_table(tables, booked, time, party_size)
+            .map(|table| table.table_name)
+            .ok_or_else(|| {
+                RepositoryError::Conflict(format!(
+                    "no table for {party_size} is free at {}",
+                    hhmm(time)
+                ))
+            });
+    }
+    match booked.iter().find(|resv| {
+        resv.status.is_active()
+            && resv.seating.eq_ignore_ascii_case(seating)
+            && overlaps(resv.reservation_time, time)
+    }) {
+        Some(other) => Err(RepositoryError::Conflict(format!(
+            "{seating} is taken by {} at {}",
+            other.name,
+            hhmm(other.reservation_time)
+        ))),
+        None => Ok(seating.to_string()),
+    }
+}
+
+/// The reservations of `date` as they hold tables: a group booking assigned
+/// several tables appears once for each, seated on it, so [`free_table`] and
+/// clash checks see all of them taken.
+pub fn day_holds(
+    reservations: &dyn ReservationRepository,
+    tables: &[PropertyTable],
+    pid: Uuid,
+    date: NaiveDate,
+) -> RepoResult<Vec<Reservation>> {
+    let booked = reservations.on_date(pid, date)?;
+    let ids: Vec<i32> = booked.iter().map(|resv| resv.id).collect();
+    let assignments = reservations.table_assignments(&ids)?;
+    let mut holds = Vec::with_capacity(booked.len());
+    for resv in booked {
+        let held: Vec<&PropertyTable> = assignments
+            .iter()
+            .filter(|assignment| assignment.reservation_id == resv.id)
+            .filter_map(|assignment| {
+                tables
+                    .iter()
+                    .find(|table| table.table_id == assignment.table_id)
+            })
+            .collect();
+        if held.is_empty() {
+            holds.push(resv);
+            continue;
+        }
+        for table in held {
+            holds.push(Reservation {
+                seating: table.table_name.clone(),
+                ..resv.clone()
+            });
+        }
+    }
+    Ok(holds)
+}
+
+/// The slots of `date` at which a table for `party_size` is free.
+pub fn availability(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    pid: Uuid,
+    date: NaiveDate,
+    party_size: i32,
+) -> RepoResult<Vec<Time>> {
+    availability_except(properties, reservations, pid, date, party_size, None)
+}
+
+/// Like [`availability`], but leaving the reservation `except` out, so a
+/// booking being moved does not block its own table.
+pub fn availability_except(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    pid: Uuid,
+    date: NaiveDate,
+    party_size: i32,
+    except: Option<i32>,
+) -> RepoResult<Vec<Time>> {
+    let now = Local::now().naive_local();
+    let last_day = now.date() + Days::new(BOOKING_DAYS);
+    if date < now.date()
+        || date > last_day
+        || party_size < 1
+        || closure_on(properties, pid, date)?.is_some()
+    {
+        return Ok(Vec::new());
+    }
+    let tables = properties.tables(pid)?;
+    let mut booked = day_holds(reservations, &tables, pid, date)?;
+    booked.retain(|resv| Some(resv.id) != except);
+    Ok(slots()
+        .into_iter()
+        .filter(|slot| date > now.date() || slot_after(*slot, now.time()))
+        .filter(|slot| free_table(&tables, &booked, *slot, party_size).is_some())
+        .collect())
+}
+
+/// The closure of the property covering `date`, if it is closed that day.
+pub fn closure_on(
+    properties: &dyn PropertyRepository,
+    pid: Uuid,
+    date: NaiveDate,
+) -> RepoResult<Option<Closure>> {
+    Ok(properties
+        .closures(pid)?
+        .into_iter()
+        .find(|closure| closure.covers(date)))
+}
+
+/// Closes a property from the first to the last day of `new_closure` and
+/// returns the stored closure. Bookings already made for those days stay.
+pub fn add_closure(
+    properties: &dyn PropertyRepository,
+    mut new_closure: NewClosure,
+) -> RepoResult<Closure> {
+    if new_closure.last_day < new_closure.first_day {
+        return Err(RepositoryError::Invalid(
+            "a closure cannot end before it starts".into(),
+        ));
+    }
+    new_closure.reason = new_closure.reason.trim().to_string();
+    let pid = new_closure.property_id;
+    let closure_id = properties.insert_closure(new_closure)?;
+    properties
+        .closures(pid)?
+        .into_iter()
+        .find(|closure| closure.closure_id == closure_id)
+        .ok_or(RepositoryError::NotFound)
+}
+
+fn slot_after(slot: Time, now: chrono::NaiveTime) -> bool {
+    use chrono::Timelike;
+    (slot.hour() as u32, slot.minute() as u32) > (now.hour(), now.minute())
+}
+
+fn form_secret() -> &'static str {
+    static SECRET: OnceLock<String> = OnceLock::new();
+    SECRET.get_or_init(|| {
+        std::env::var("RESVM_BOOKING_SECRET").unwrap_or_else(|_| Uuid::new_v4().to_string())
+    })
+}
+
+/// Token proving the booking form of `pid` was served at `issued_at`.
+pub fn form_token(pid: Uuid, issued_at: i64) -> String {
+    sign(form_secret(), issued_at, pid.as_bytes())
+}
+
+/// Rejects submissions that filled the honeypot, were sent too fast, or
+/// carry a token this server did not issue.
+pub fn check_form(
+    pid: Uuid,
+    issued_at: i64,
+    token: &str,
+    honeypot: Option<&str>,
+    now: i64,
+) -> RepoResult<()> {
+    if honeypot.is_some_and(|value| !value.is_empty())
+        || !verify(form_secret(), issued_at, pid.as_bytes(), token)
+        || now - issued_at < MIN_FILL_SECS
+    {
+        return Err(RepositoryError::Invalid(
+            "the booking could not be accepted".into(),
+        ));
+    }
+    if now - issued_at > MAX_FORM_AGE_SECS {
+        return Err(RepositoryError::Invalid(
+            "the booking form expired, please reload the page".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Held while a table is picked and booked. It only guards this process,
+/// servers sharing a database can still race each other.
+static BOOKING: Mutex<()> = Mutex::new(());
+
+/// Books `new_resv` on the smallest table free for its party at its time,
+/// once the time is checked to be one still offered. The check and the
+/// booking happen under one lock, so two guests cannot both get the last
+/// table.
+pub fn request_table(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    guests: &dyn GuestRepository,
+    policy: &NoShowPolicy,
+    mut new_resv: NewResv,
+) -> RepoResult<i32> {
+    let _booking = BOOKING.lock().unwrap_or_else(PoisonError::into_inner);
+    let (pid, date, time) = (
+        new_resv.property_id,
+        new_resv.reservation_date,
+        new_resv.reservation_time,
+    );
+    let gone = || RepositoryError::Conflict("this time is no longer available".into());
+    if !slots().contains(&time)
+        || !availability(properties, reservations, pid, date, new_resv.party_size)?.contains(&time)
+    {
+        return Err(gone());
+    }
+    let tables = properties.tables(pid)?;
+    let booked = day_holds(reservations, &tables, pid, date)?;
+    new_resv.seating = free_table(&tables, &booked, time, new_resv.party_size)
+        .ok_or_else(gone)?
+        .table_name;
+    create_reservation(properties, reservations, guests, policy, new_resv)
+}
+
+/// Sliding window limit of attempts per client address.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    limit: usize,
+    window: Duration,
+    hits: Arc<Mutex<HashMap<IpAddr, Vec<Instant>>>>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(BOOKINGS_PER_WINDOW, RATE_WINDOW)
+    }
+}
+
+impl RateLimiter {
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            hits: Arc::default(),
+        }
+    }
+
+    /// Records an attempt of `client`, false when it is over the limit.
+    pub fn allow(&self, client: IpAddr) -> bool {
+        let now = Instant::now();
+        let mut hits = self.hits.lock().unwrap();
+        hits.retain(|_, times| {
+            times.retain(|at| now.duration_since(*at) < self.window);
+            !times.is_empty()
+        });
+        let times = hits.entry(client).or_default();
+        if times.len() >= self.limit {
+            return false;
+        }
+        times.push(now);
+        true
+    }
+}
+
+/// The address of the client, taken from `X-Forwarded-For` when
+/// `RESVM_TRUST_PROXY` is set.
+async fn client_ip() -> Option<IpAddr> {
+    use axum::extract::ConnectInfo;
+    use http::HeaderMap;
+    use std::net::SocketAddr;
+
+    if std::env::var("RESVM_TRUST_PROXY").is_ok_and(|v| v == "true" || v == "1") {
+        let forwarded = leptos_axum::extract::<HeaderMap>()
+            .await
+            .ok()
+            .and_then(|headers| {
+                headers
+                    .get("x-forwarded-for")?
+                    .to_str()
+                    .ok()?
+                    .split(',')
+                    .next()?
+                    .trim()
+                    .parse()
+                    .ok()
+            });
+        if forwarded.is_some() {
+            return forwarded;
+        }
+    }
+    leptos_axum::extract::<ConnectInfo<SocketAddr>>()
+        .await
+        .ok()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+/// What the public booking page needs to render.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BookingForm {
+    pub property_name: String,
+    pub max_party_size: i32,
+    pub issued_at: i64,
+    pub token: String,
+}
+
+/// The outcome shown to the guest.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BookingRequest {
+    pub reservation_id: i32,
+    pub reservation_date: NaiveDate,
+    pub reservation_time: String,
+    pub party_size: i32,
+    /// Secret of the manage-booking link at `/manage/<token>`.
+    pub confirmation_token: String,
+    pub confirmation_code: String,
+}
+
+#[server(BookingInfo, "/api", "GetJson", "booking_info")]
+pub async fn booking_info(pid: Uuid) -> Result<BookingForm, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    let form = state.properties.find(pid).and_then(|property| {
+        let issued_at = Utc::now().timestamp();
+        Ok(BookingForm {
+            property_name: property.property_name,
+            max_party_size: state
+                .properties
+                .tables(pid)?
+                .iter()
+                .map(|table| table.capacity)
+                .max()
+                .unwrap_or(0),
+            issued_at,
+            token: form_token(pid, issued_at),
+        })
+    });
+    form.map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(BookingSlots, "/api", "GetJson", "booking_slots")]
+pub async fn booking_slots(
+    pid: Uuid,
+    date: NaiveDate,
+    party_size: i32,
+) -> Result<Vec<String>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    match availability(
+        state.properties.as_ref(),
+        state.reservations.as_ref(),
+        pid,
+        date,
+        party_size,
+    ) {
+        Ok(slots) => Ok(slots
+            .into_iter()
+            .map(|slot| format!("{:02}{:02}", slot.hour(), slot.minute()))
+            .collect()),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[server(name = BookTable, prefix = "/api", endpoint = "book_table", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn book_table(
+    pid: Uuid,
+    reservation_date: NaiveDate,
+    reservation_time: String,
+    party_size: i32,
+    name: String,
+    contact: String,
+    issued_at: i64,
+    token: String,
+    website: Option<String>,
+    occasion: Option<String>,
+    dietary: Option<String>,
+    high_chairs: Option<i32>,
+    accessibility: Option<String>,
+) -> Result<BookingRequest, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    if let Some(client) = client_ip().await {
+        if !state.booking_limiter.allow(client) {
+            return Err(ServerFnError::ServerError(
+                "too many booking attempts, please try again later".into(),
+            ));
+        }
+    }
+
+    let booked = check_form(
+        pid,
+        issued_at,
+        &token,
+        website.as_deref(),
+        Utc::now().timestamp(),
+    )
+    .and_then(|_| parse_reservation_time(&reservation_time))
+    .and_then(|time| {
+        let advance_method =
+            serde_json::to_value(PaymentMethod::new(PaymentMode::NotPaid, None, None, None))
+                .map_err(|e| RepositoryError::Invalid(e.to_string()))?;
+        let new_resv = NewResv::new(
+            name,
+            contact,
+            String::new(),
+            false,
+            false,
+            advance_method,
+            None,
+            false,
+            reservation_date,
+            time,
+            pid,
+        )
+        .with_status(ReservationStatus::Requested)
+        .with_party_size(party_size)
+        .with_special_requests(SpecialRequests {
+            occasion: parse_occasion(occasion.as_deref().unwrap_or_default())?,
+            dietary: dietary.unwrap_or_default(),
+            high_chairs: high_chairs.unwrap_or_default(),
+            accessibility: accessibility.unwrap_or_default(),
+            seating_request: String::new(),
+        });
+        let rid = request_table(
+            state.properties.as_ref(),
+            state.reservations.as_ref(),
+            state.guests.as_ref(),
+            &state.no_show_policy,
+            new_resv,
+        )?;
+        state.reservations.find(rid)
+    });
+
+    match booked {
+        Ok(resv) => {
+            state.publish(WebhookEvent::ReservationCreated, &resv);
+            Ok(BookingRequest {
+                reservation_id: resv.id,
+                reservation_date: resv.reservation_date,
+                reservation_time: format!(
+                    "{:02}:{:02}",
+                    resv.reservation_time.hour(),
+                    resv.reservation_time.minute()
+                ),
+                party_size: resv.party_size,
+                confirmation_code: resv.confirmation_code.unwrap_or_default(),
+                confirmation_token: resv.confirmation_token.unwrap_or_default(),
+            })
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
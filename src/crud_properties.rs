@@ -1,8 +1,15 @@
-use crate::models::{NewProperty, NewResv, PaymentMethod, PaymentMode, Property, Reservation};
+use crate::booking::{check_not_seated, check_seating, day_holds, table_for, without_seated};
+use crate::errors::RepositoryError;
+use crate::guests::{check_deposit, match_guest, normalize_tags, repeat_no_shows, NoShowPolicy};
+use crate::models::{
+    HistoryEntry, NewHistoryEntry, NewProperty, NewResv, PaymentMethod, PaymentMode, Property,
+    Reservation, ReservationStatus, WebhookEvent,
+};
+use crate::repository::{GuestRepository, PropertyRepository, RepoResult, ReservationRepository};
 use crate::salt_password;
+use crate::special_requests::check_requests;
 use crate::AppState;
-use chrono::NaiveDate;
-use diesel::prelude::*;
+use chrono::{Local, NaiveDate, NaiveDateTime, Timelike};
 use leptos::{expect_context, server, Params, ServerFnError};
 use leptos_router::Params;
 use time::Time;
@@ -10,13 +17,9 @@ use uuid::Uuid;
 
 #[server(AllProperties, "/api", "GetJson")]
 pub async fn get_all_properties() -> Result<Vec<Property>, ServerFnError> {
-    use crate::schema::property::dsl::{property, property_id};
     let state = expect_context::<AppState>();
 
-    match property
-        .select(Property::as_select())
-        .load(&mut state.pool.try_get().unwrap())
-    {
+    match state.properties.all() {
         Ok(_result) => Ok(_result),
         Err(e) => Err(ServerFnError::ServerError(e.to_string())),
     }
@@ -27,20 +30,21 @@ pub struct PropertyIdParam {
     pub pid: Option<Uuid>,
 }
 
-#[server(PropertyReservations, "/api", "GetJson")]
-pub async fn get_property_reservations(pid: Uuid) -> Result<Vec<Reservation>, ServerFnError> {
-    use crate::schema::reservation::dsl::{property_id, reservation, reservation_date};
+/// Most reservations a tag filter returns.
+pub const TAG_SEARCH_LIMIT: i64 = 500;
 
+#[server(PropertyReservations, "/api", "GetJson")]
+pub async fn get_property_reservations(
+    pid: Uuid,
+    tag: Option<String>,
+) -> Result<Vec<Reservation>, ServerFnError> {
     let state = expect_context::<AppState>();
-    // let conn: AppState = extract_with_state(&state).await.unwrap();
-
-    match reservation
-        .filter(property_id.eq(pid))
-        // .filter(reservation_date.eq(chrono::offset::Local::now().date_naive()))
-        .limit(5)
-        .select(Reservation::as_select())
-        .load(&mut state.pool.try_get().unwrap())
-    {
+
+    let found = match tag.filter(|tag| !tag.trim().is_empty()) {
+        Some(tag) => state.reservations.with_tag(pid, &tag, TAG_SEARCH_LIMIT),
+        None => state.reservations.for_property(pid, 5),
+    };
+    match found {
         Ok(_result) => Ok(_result),
         Err(e) => Err(ServerFnError::ServerError(e.to_string())),
     }
@@ -52,8 +56,6 @@ pub async fn add_property(
     property_email: String,
     property_phone: String,
 ) -> Result<Uuid, ServerFnError> {
-    use crate::schema::property::dsl::{property, property_id};
-
     let state = expect_context::<AppState>();
 
     let new_property = NewProperty::new(
@@ -66,16 +68,13 @@ pub async fn add_property(
         property_phone,
     );
 
-    match diesel::insert_into(property)
-        .values(&new_property)
-        .returning(property_id)
-        .get_result::<Uuid>(&mut state.pool.try_get().unwrap())
-    {
+    match state.properties.insert(new_property) {
         Ok(_pid) => Ok(_pid),
         Err(e) => Err(ServerFnError::ServerError(e.to_string())),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 #[server(name = AddResv, prefix = "/api", endpoint = "add_resv", input = Json, output = Json, encoding = "Url", impl_from = true)]
 pub async fn add_reservation(
     name: String,
@@ -92,31 +91,19 @@ pub async fn add_reservation(
     reservation_date: NaiveDate,
     reservation_time: String,
     property_id: Uuid,
+    party_size: i32,
 ) -> Result<i32, ServerFnError> {
-    use crate::schema::reservation::dsl::{id, reservation};
-
     let state = expect_context::<AppState>();
 
     let advance_method = serde_json::to_value(PaymentMethod::new(
-        match mode_of_payment {
-            mode_of_payment if mode_of_payment == String::from("NotPaid") => PaymentMode::NotPaid,
-            mode_of_payment if mode_of_payment == String::from("Cash") => PaymentMode::Cash,
-            mode_of_payment if mode_of_payment == String::from("Card") => PaymentMode::Card,
-            mode_of_payment if mode_of_payment == String::from("Gpay") => PaymentMode::Gpay,
-            _ => PaymentMode::NotPaid,
-        },
+        parse_payment_mode(&mode_of_payment),
         payment_transaction_id,
         payment_receiver,
         payment_received_date,
     ))
     .expect("failed to convert struct into json");
 
-    // Constructing time with only needed properties.
-    // In the front end the max length of input should be 4.
-    let resvt = reservation_time.trim();
-    let hour: u8 = resvt[..2].parse::<u8>()?;
-    let min: u8 = resvt[2..4].parse::<u8>()?;
-    let reservation_time = Time::from_hms(hour, min, 0u8)?;
+    let reservation_time = parse_reservation_time(&reservation_time)?;
 
     let new_resv = NewResv::new(
         name,
@@ -130,34 +117,356 @@ pub async fn add_reservation(
         reservation_date,
         reservation_time,
         property_id,
-    );
+    )
+    .with_party_size(party_size);
 
-    match diesel::insert_into(reservation)
-        .values(&new_resv)
-        .returning(id)
-        .get_result::<i32>(&mut state.pool.try_get().unwrap())
+    match create_reservation(
+        state.properties.as_ref(),
+        state.reservations.as_ref(),
+        state.guests.as_ref(),
+        &state.no_show_policy,
+        new_resv,
+    )
+    .and_then(|rid| state.reservations.find(rid))
     {
-        Ok(rid) => Ok(rid),
+        Ok(resv) => {
+            state.publish(WebhookEvent::ReservationCreated, &resv);
+            Ok(resv.id)
+        }
         Err(e) => Err(ServerFnError::ServerError(e.to_string())),
     }
 }
 
-#[server(TotalResv, "/api", "Url")]
-pub async fn total_resv(pid: Uuid) -> Result<i64, ServerFnError> {
-    use crate::schema::reservation::dsl::{property_id, reservation};
+#[server(name = SeatWalkIn, prefix = "/api", endpoint = "seat_walk_in", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn seat_walk_in_now(
+    pid: Uuid,
+    party_size: i32,
+    seating: String,
+    name: String,
+    contact: String,
+) -> Result<Reservation, ServerFnError> {
+    let state = expect_context::<AppState>();
 
+    match seat_walk_in(
+        state.properties.as_ref(),
+        state.reservations.as_ref(),
+        state.guests.as_ref(),
+        &state.no_show_policy,
+        pid,
+        party_size,
+        &seating,
+        &name,
+        &contact,
+        Local::now().naive_local(),
+    ) {
+        Ok(resv) => {
+            state.publish(WebhookEvent::ReservationCreated, &resv);
+            Ok(resv)
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+#[server(SetResvStatus, "/api", "Url", "set_resv_status")]
+pub async fn set_resv_status(id: i32, status: String) -> Result<Reservation, ServerFnError> {
     let state = expect_context::<AppState>();
 
-    match reservation
-        .filter(property_id.eq(pid))
-        .count()
-        .get_result(&mut state.pool.try_get().unwrap())
+    let status: ReservationStatus = match serde_json::from_value(serde_json::Value::String(status))
     {
+        Ok(status) => status,
+        Err(e) => return Err(ServerFnError::ServerError(e.to_string())),
+    };
+    let changed = match status {
+        ReservationStatus::Cancelled => cancel_reservation(state.reservations.as_ref(), id),
+        ReservationStatus::Booked => state
+            .reservations
+            .find(id)
+            .and_then(|resv| check_deposit(state.guests.as_ref(), &state.no_show_policy, &resv))
+            .and_then(|_| change_status(state.reservations.as_ref(), id, status)),
+        _ => change_status(state.reservations.as_ref(), id, status),
+    };
+    let changed = changed.and_then(|resv| {
+        state.reservations.insert_history(NewHistoryEntry {
+            reservation_id: resv.id,
+            changed_by: HistoryEntry::STAFF.to_string(),
+            summary: format!("status set to {}", resv.status),
+        })?;
+        Ok(resv)
+    });
+    match changed {
+        Ok(resv) => {
+            state.publish(status_event(resv.status), &resv);
+            Ok(resv)
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+#[server(TotalResv, "/api", "Url")]
+pub async fn total_resv(pid: Uuid) -> Result<i64, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    match state.reservations.count_for_property(pid) {
         Ok(count) => Ok(count),
         Err(e) => Err(ServerFnError::ServerError(e.to_string())),
     }
 }
 
+/// Maps the payment radio values of the reservation form, anything unknown
+/// is treated as not paid.
+pub fn parse_payment_mode(mode_of_payment: &str) -> PaymentMode {
+    match mode_of_payment {
+        "Cash" => PaymentMode::Cash,
+        "Card" => PaymentMode::Card,
+        "Gpay" => PaymentMode::Gpay,
+        _ => PaymentMode::NotPaid,
+    }
+}
+
+/// Constructing time with only needed properties.
+/// In the front end the max length of input should be 4 (`HHMM`).
+pub fn parse_reservation_time(reservation_time: &str) -> RepoResult<Time> {
+    let resvt = reservation_time.trim();
+    let invalid = || RepositoryError::Invalid(format!("reservation time {resvt:?} is not HHMM"));
+    if resvt.len() != 4 || !resvt.is_ascii() {
+        return Err(invalid());
+    }
+    let hour: u8 = resvt[..2].parse().map_err(|_| invalid())?;
+    let min: u8 = resvt[2..4].parse().map_err(|_| invalid())?;
+    Time::from_hms(hour, min, 0u8).map_err(|_| invalid())
+}
+
+/// `now` to the minute, as reservations store times.
+pub fn time_of_day(now: NaiveDateTime) -> Time {
+    Time::from_hms(now.hour() as u8, now.minute() as u8, 0).expect("a time of day is valid")
+}
+
+/// Checks the booking rules and stores the reservation.
+///
+/// A reservation needs a name, a contact and at least one guest, an advance
+/// must carry a positive amount and a mode of payment, and a specifically
+/// requested seating cannot be booked within
+/// [`TURN_MINUTES`](crate::booking::TURN_MINUTES) of another booking holding
+/// it. Special requests are checked by [`check_requests`]. A booking of a
+/// repeat no-show is noted in its history and held as a request while it
+/// owes the deposit of `policy`.
+pub fn create_reservation(
+    properties: &dyn PropertyRepository,
+    repo: &dyn ReservationRepository,
+    guests: &dyn GuestRepository,
+    policy: &NoShowPolicy,
+    mut new_resv: NewResv,
+) -> RepoResult<i32> {
+    if new_resv.name.trim().is_empty() {
+        return Err(RepositoryError::Invalid("name is required".into()));
+    }
+    if new_resv.contact.trim().is_empty() {
+        return Err(RepositoryError::Invalid("contact is required".into()));
+    }
+    if new_resv.party_size < 1 {
+        return Err(RepositoryError::Invalid(
+            "a reservation needs at least one guest".into(),
+        ));
+    }
+    if new_resv.advance {
+        if !matches!(new_resv.advance_amount, Some(amount) if amount > 0) {
+            return Err(RepositoryError::Invalid(
+                "an advance needs a positive amount".into(),
+            ));
+        }
+        if new_resv.advance_method["mode_of_payment"] == "NotPaid" {
+            return Err(RepositoryError::Invalid(
+                "an advance needs a mode of payment".into(),
+            ));
+        }
+    }
+    let seating = new_resv.seating.trim();
+    if new_resv.specific_seating_requested && !seating.is_empty() {
+        let (pid, date) = (new_resv.property_id, new_resv.reservation_date);
+        let tables = properties.tables(pid)?;
+        table_for(
+            &tables,
+            &day_holds(repo, &tables, pid, date)?,
+            seating,
+            new_resv.party_size,
+            new_resv.reservation_time,
+        )?;
+    }
+
+    new_resv.special_requests = check_requests(new_resv.special_requests, new_resv.party_size)?;
+    new_resv.tags = normalize_tags(&new_resv.tags);
+    new_resv.guest_id = match_guest(
+        guests,
+        new_resv.property_id,
+        &new_resv.name,
+        &new_resv.contact,
+    )?;
+
+    let mut warning = None;
+    if new_resv.status != ReservationStatus::Seated {
+        if let Some(no_shows) = repeat_no_shows(guests, policy, new_resv.guest_id)? {
+            let paid = new_resv
+                .advance
+                .then_some(new_resv.advance_amount)
+                .flatten();
+            warning = Some(match policy.deposit_due(paid) {
+                Some(deposit) => {
+                    if new_resv.status == ReservationStatus::Booked {
+                        new_resv.status = ReservationStatus::Requested;
+                        new_resv.confirmed = false;
+                    }
+                    format!(
+                        "guest did not turn up {no_shows} times, held as a request until an advance of {deposit} is paid"
+                    )
+                }
+                None => format!("guest did not turn up {no_shows} times"),
+            });
+        }
+    }
+    let rid = repo.insert(new_resv)?;
+    if let Some(summary) = warning {
+        repo.insert_history(NewHistoryEntry {
+            reservation_id: rid,
+            changed_by: HistoryEntry::SYSTEM.to_string(),
+            summary,
+        })?;
+    }
+    Ok(rid)
+}
+
+/// Name and contact of walk-ins who did not leave theirs.
+pub const WALK_IN: &str = "Walk-in";
+
+/// Seats a party that just walked in on `seating`, or on the smallest table
+/// free for it, and returns the reservation, already `Seated` at `now`. A
+/// table asked for must seat the party and not be held by a guest still
+/// seated or booked within [`TURN_MINUTES`](crate::booking::TURN_MINUTES).
+///
+/// The name and contact are optional, a walk-in leaving a phone number or
+/// email is matched to its guest profile like any booking.
+#[allow(clippy::too_many_arguments)]
+pub fn seat_walk_in(
+    properties: &dyn PropertyRepository,
+    repo: &dyn ReservationRepository,
+    guests: &dyn GuestRepository,
+    policy: &NoShowPolicy,
+    pid: Uuid,
+    party_size: i32,
+    seating: &str,
+    name: &str,
+    contact: &str,
+    now: NaiveDateTime,
+) -> RepoResult<Reservation> {
+    let (date, time) = (now.date(), time_of_day(now));
+    let tables = properties.tables(pid)?;
+    let seating = check_seating(&tables, seating, party_size)?;
+    let booked: Vec<Reservation> = day_holds(repo, &tables, pid, date)?
+        .into_iter()
+        .filter(|resv| resv.status != ReservationStatus::Completed)
+        .collect();
+    check_not_seated(&booked, &seating)?;
+    let (free, others) = without_seated(&tables, &booked);
+    let seating = table_for(&free, &others, &seating, party_size, time)?;
+
+    let or_walk_in = |value: &str| match value.trim() {
+        "" => WALK_IN.to_string(),
+        value => value.to_string(),
+    };
+    let advance_method =
+        serde_json::to_value(PaymentMethod::new(PaymentMode::NotPaid, None, None, None))
+            .map_err(|e| RepositoryError::Invalid(e.to_string()))?;
+    let new_resv = NewResv::new(
+        or_walk_in(name),
+        or_walk_in(contact),
+        seating,
+        false,
+        false,
+        advance_method,
+        None,
+        true,
+        date,
+        time,
+        pid,
+    )
+    .with_status(ReservationStatus::Seated)
+    .with_party_size(party_size);
+    let rid = create_reservation(properties, repo, guests, policy, new_resv)?;
+    repo.find(rid)
+}
+
+/// Cancels a reservation that has not been seated or finished yet.
+pub fn cancel_reservation(repo: &dyn ReservationRepository, id: i32) -> RepoResult<Reservation> {
+    let resv = repo.find(id)?;
+    match resv.status {
+        ReservationStatus::Booked | ReservationStatus::Requested => {
+            repo.set_status(id, ReservationStatus::Cancelled)
+        }
+        status => Err(RepositoryError::Conflict(format!(
+            "reservation {id} is {status} and cannot be cancelled"
+        ))),
+    }
+}
+
+/// Moves a reservation along the service: a request is confirmed, a booking
+/// is seated or marked as a no-show and a seated table is completed.
+/// Cancelling goes through [`cancel_reservation`].
+pub fn change_status(
+    repo: &dyn ReservationRepository,
+    id: i32,
+    status: ReservationStatus,
+) -> RepoResult<Reservation> {
+    use ReservationStatus::*;
+
+    let resv = repo.find(id)?;
+    match (resv.status, status) {
+        (Requested, Booked)
+        | (Booked | Requested, Seated)
+        | (Booked, NoShow)
+        | (Seated, Completed) => repo.set_status(id, status),
+        (current, _) => Err(RepositoryError::Conflict(format!(
+            "reservation {id} is {current} and cannot become {status}"
+        ))),
+    }
+}
+
+/// The webhook event announcing that a reservation reached `status`.
+pub fn status_event(status: ReservationStatus) -> WebhookEvent {
+    match status {
+        ReservationStatus::Seated => WebhookEvent::ReservationSeated,
+        ReservationStatus::Cancelled => WebhookEvent::ReservationCancelled,
+        _ => WebhookEvent::ReservationUpdated,
+    }
+}
+
+/// Records the advance paid for an active reservation.
+pub fn record_payment(
+    repo: &dyn ReservationRepository,
+    id: i32,
+    payment: PaymentMethod,
+    amount: i32,
+) -> RepoResult<Reservation> {
+    if amount <= 0 {
+        return Err(RepositoryError::Invalid(
+            "a payment needs a positive amount".into(),
+        ));
+    }
+    if *payment.mode_of_payment() == PaymentMode::NotPaid {
+        return Err(RepositoryError::Invalid(
+            "a payment needs a mode of payment".into(),
+        ));
+    }
+    let resv = repo.find(id)?;
+    if !resv.status.is_active() {
+        return Err(RepositoryError::Conflict(format!(
+            "reservation {id} is {}",
+            resv.status
+        )));
+    }
+    let advance_method =
+        serde_json::to_value(payment).map_err(|e| RepositoryError::Invalid(e.to_string()))?;
+    repo.set_payment(id, advance_method, amount)
+}
+
 // async fn add_reservation(user_name: String, user_role: String) -> Result<(), ServerFnError> {
 //     use crate::schema::myusers::dsl::{myusers, name};
 //     use diesel::*;
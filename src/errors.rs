@@ -17,3 +17,59 @@ impl MyUsersError {
         }
     }
 }
+
+/// Errors returned by the storage repositories and the business rules built on
+/// top of them.
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum RepositoryError {
+    #[error("Not Found")]
+    NotFound,
+    #[error("Invalid input: {0}")]
+    Invalid(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+impl RepositoryError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            RepositoryError::NotFound => StatusCode::NOT_FOUND,
+            RepositoryError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            RepositoryError::Conflict(_) => StatusCode::CONFLICT,
+            RepositoryError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<diesel::result::Error> for RepositoryError {
+    fn from(e: diesel::result::Error) -> Self {
+        match e {
+            diesel::result::Error::NotFound => RepositoryError::NotFound,
+            diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                info,
+            ) => RepositoryError::Conflict(info.message().to_string()),
+            e => RepositoryError::Database(e.to_string()),
+        }
+    }
+}
+
+impl From<diesel::r2d2::PoolError> for RepositoryError {
+    fn from(e: diesel::r2d2::PoolError) -> Self {
+        RepositoryError::Database(e.to_string())
+    }
+}
+
+/// Startup errors raised when the database schema does not match the
+/// migrations embedded in this build.
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum SchemaError {
+    #[error("database schema is older than this build, pending migrations: {}. Start with --migrate to apply them", .0.join(", "))]
+    Outdated(Vec<String>),
+    #[error("database schema is newer than this build, unknown migrations: {}. Upgrade resvm before starting it", .0.join(", "))]
+    Newer(Vec<String>),
+    #[error("Migration error: {0}")]
+    Migration(String),
+}
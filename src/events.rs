@@ -0,0 +1,372 @@
+//! Group bookings and events spanning several tables.
+//!
+//! A party too large for any one table is assigned a set of tables, which it
+//! holds for its slot like any reservation holds its own table. A private
+//! event can buy out a whole section instead, holding every table of it.
+//! Either way the reservation keeps a single row, its `seating` naming the
+//! tables joined with `+`, and the menu and minimum spend agreed for the
+//! event are kept with it and shown on the day sheet.
+use crate::booking::{day_holds, overlaps};
+use crate::errors::RepositoryError;
+use crate::manage::hhmm;
+use crate::models::{
+    EventDetails, GroupBooking, HistoryEntry, NewHistoryEntry, PropertyTable, Reservation,
+    WebhookEvent,
+};
+use crate::repository::{PropertyRepository, RepoResult, ReservationRepository};
+use crate::AppState;
+use chrono::NaiveDate;
+use leptos::{expect_context, server, ServerFnError};
+use uuid::Uuid;
+
+/// A reservation with the names of the tables it holds and its event
+/// details.
+pub fn group_booking(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    id: i32,
+) -> RepoResult<GroupBooking> {
+    let reservation = reservations.find(id)?;
+    let tables = properties.tables(reservation.property_id)?;
+    with_tables(reservations, &tables, reservation)
+}
+
+fn with_tables(
+    reservations: &dyn ReservationRepository,
+    tables: &[PropertyTable],
+    reservation: Reservation,
+) -> RepoResult<GroupBooking> {
+    let held: Vec<String> = reservations
+        .table_assignments(&[reservation.id])?
+        .into_iter()
+        .filter_map(|assignment| {
+            tables
+                .iter()
+                .find(|table| table.table_id == assignment.table_id)
+                .map(|table| table.table_name.clone())
+        })
+        .collect();
+    Ok(GroupBooking {
+        event: reservations.event_details(reservation.id)?,
+        tables: held,
+        reservation,
+    })
+}
+
+/// The active reservations of a day by time, with their tables and events.
+pub fn group_bookings(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    pid: Uuid,
+    date: NaiveDate,
+) -> RepoResult<Vec<GroupBooking>> {
+    let tables = properties.tables(pid)?;
+    let mut booked: Vec<Reservation> = reservations
+        .on_date(pid, date)?
+        .into_iter()
+        .filter(|resv| resv.status.is_active())
+        .collect();
+    booked.sort_by_key(|resv| (resv.reservation_time, resv.id));
+    booked
+        .into_iter()
+        .map(|resv| with_tables(reservations, &tables, resv))
+        .collect()
+}
+
+/// Puts a reservation on `chosen`, checking together they seat the party and
+/// none of them is held by another reservation around its time.
+fn hold(
+    reservations: &dyn ReservationRepository,
+    tables: &[PropertyTable],
+    resv: &Reservation,
+    chosen: &[&PropertyTable],
+) -> RepoResult<Reservation> {
+    if !resv.status.is_active() {
+        return Err(RepositoryError::Conflict(format!(
+            "the reservation is {}",
+            resv.status
+        )));
+    }
+    let seating = seating(chosen);
+    let capacity: i32 = chosen.iter().map(|table| table.capacity).sum();
+    if capacity < resv.party_size {
+        return Err(RepositoryError::Invalid(format!(
+            "{seating} seat {capacity}, the party is {}",
+            resv.party_size
+        )));
+    }
+    let booked = day_holds(
+        reservations,
+        tables,
+        resv.property_id,
+        resv.reservation_date,
+    )?;
+    for table in chosen {
+        if let Some(other) = booked.iter().find(|other| {
+            other.id != resv.id
+                && other.status.is_active()
+                && other.seating.eq_ignore_ascii_case(&table.table_name)
+                && overlaps(other.reservation_time, resv.reservation_time)
+        }) {
+            return Err(RepositoryError::Conflict(format!(
+                "{} is taken by {} at {}",
+                table.table_name,
+                other.name,
+                hhmm(other.reservation_time)
+            )));
+        }
+    }
+
+    seat_on(reservations, resv, chosen)
+}
+
+/// Puts a reservation on `chosen` without checking them, holding the tables
+/// of a combination through assignments and a single one through `seating`.
+pub(crate) fn seat_on(
+    reservations: &dyn ReservationRepository,
+    resv: &Reservation,
+    chosen: &[&PropertyTable],
+) -> RepoResult<Reservation> {
+    let ids: Vec<i32> = match chosen {
+        [_] => Vec::new(),
+        _ => chosen.iter().map(|table| table.table_id).collect(),
+    };
+    reservations.set_table_assignments(resv.id, &ids)?;
+    reservations.reschedule(
+        resv.id,
+        resv.reservation_date,
+        resv.reservation_time,
+        seating(chosen),
+        resv.party_size,
+    )
+}
+
+/// The names of `chosen` as a reservation's seating, e.g. `T1+T2`.
+pub(crate) fn seating(chosen: &[&PropertyTable]) -> String {
+    chosen
+        .iter()
+        .map(|table| table.table_name.as_str())
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// Assigns a reservation the named tables, letting go of any section it
+/// had bought out.
+pub fn assign_tables(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    id: i32,
+    names: &[String],
+) -> RepoResult<GroupBooking> {
+    let resv = reservations.find(id)?;
+    let tables = properties.tables(resv.property_id)?;
+    let mut chosen: Vec<&PropertyTable> = Vec::new();
+    for name in names
+        .iter()
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+    {
+        let table = tables
+            .iter()
+            .find(|table| table.table_name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                RepositoryError::Invalid(format!("{name} is not a table of this property"))
+            })?;
+        if !chosen.iter().any(|other| other.table_id == table.table_id) {
+            chosen.push(table);
+        }
+    }
+    if chosen.is_empty() {
+        return Err(RepositoryError::Invalid("pick at least one table".into()));
+    }
+
+    let held = hold(reservations, &tables, &resv, &chosen)?;
+    if let Some(mut event) = reservations.event_details(id)? {
+        if event.section.take().is_some() {
+            reservations.save_event_details(&event)?;
+        }
+    }
+    reservations.insert_history(NewHistoryEntry {
+        reservation_id: id,
+        changed_by: HistoryEntry::STAFF.to_string(),
+        summary: match resv.seating.trim() {
+            "" => format!("assigned {}", held.seating),
+            seating => format!("moved from {seating} to {}", held.seating),
+        },
+    })?;
+    with_tables(reservations, &tables, held)
+}
+
+/// Books out every table of `section` for a reservation.
+pub fn book_section(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    id: i32,
+    section: &str,
+) -> RepoResult<GroupBooking> {
+    let resv = reservations.find(id)?;
+    let tables = properties.tables(resv.property_id)?;
+    let section = section.trim();
+    let chosen: Vec<&PropertyTable> = tables
+        .iter()
+        .filter(|table| {
+            table
+                .section
+                .as_deref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(section))
+        })
+        .collect();
+    let Some(name) = chosen.first().and_then(|table| table.section.clone()) else {
+        return Err(RepositoryError::Invalid(format!(
+            "{section:?} is not a section of this property"
+        )));
+    };
+
+    let held = hold(reservations, &tables, &resv, &chosen)?;
+    let event = EventDetails {
+        reservation_id: id,
+        section: Some(name.clone()),
+        ..reservations.event_details(id)?.unwrap_or_default()
+    };
+    reservations.save_event_details(&event)?;
+    reservations.insert_history(NewHistoryEntry {
+        reservation_id: id,
+        changed_by: HistoryEntry::STAFF.to_string(),
+        summary: format!("booked out {name} ({})", held.seating),
+    })?;
+    with_tables(reservations, &tables, held)
+}
+
+/// Records the menu and minimum spend agreed for an event.
+pub fn set_event(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    id: i32,
+    menu: &str,
+    minimum_spend: Option<i32>,
+) -> RepoResult<GroupBooking> {
+    if minimum_spend.is_some_and(|spend| spend < 0) {
+        return Err(RepositoryError::Invalid(
+            "a minimum spend cannot be negative".into(),
+        ));
+    }
+    let resv = reservations.find(id)?;
+    let event = EventDetails {
+        reservation_id: id,
+        section: reservations
+            .event_details(id)?
+            .and_then(|event| event.section),
+        menu: menu.trim().to_string(),
+        minimum_spend,
+    };
+    reservations.save_event_details(&event)?;
+    reservations.insert_history(NewHistoryEntry {
+        reservation_id: id,
+        changed_by: HistoryEntry::STAFF.to_string(),
+        summary: match event.summary() {
+            summary if summary.is_empty() => "event details cleared".to_string(),
+            summary => format!("event details set to {summary}"),
+        },
+    })?;
+    let tables = properties.tables(resv.property_id)?;
+    with_tables(reservations, &tables, resv)
+}
+
+#[server(name = ListGroupBookings, prefix = "/api", endpoint = "list_group_bookings", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn list_group_bookings(
+    pid: Uuid,
+    date: NaiveDate,
+) -> Result<Vec<GroupBooking>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    group_bookings(
+        state.properties.as_ref(),
+        state.reservations.as_ref(),
+        pid,
+        date,
+    )
+    .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Takes the tables as a list separated by commas or `+`.
+#[server(name = AssignTables, prefix = "/api", endpoint = "assign_tables", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn assign_reservation_tables(
+    id: i32,
+    tables: String,
+) -> Result<GroupBooking, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    let names: Vec<String> = tables.split([',', '+']).map(str::to_string).collect();
+    published(
+        &state,
+        assign_tables(
+            state.properties.as_ref(),
+            state.reservations.as_ref(),
+            id,
+            &names,
+        ),
+    )
+}
+
+#[server(name = BookSection, prefix = "/api", endpoint = "book_section", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn book_out_section(id: i32, section: String) -> Result<GroupBooking, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    published(
+        &state,
+        book_section(
+            state.properties.as_ref(),
+            state.reservations.as_ref(),
+            id,
+            &section,
+        ),
+    )
+}
+
+/// Takes the minimum spend as text, left empty for none.
+#[server(name = SetEvent, prefix = "/api", endpoint = "set_event", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn set_event_details(
+    id: i32,
+    menu: String,
+    minimum_spend: String,
+) -> Result<GroupBooking, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    let spend = match minimum_spend.trim() {
+        "" => Ok(None),
+        raw => raw
+            .parse()
+            .map(Some)
+            .map_err(|_| RepositoryError::Invalid(format!("{raw:?} is not a valid minimum spend"))),
+    };
+    published(
+        &state,
+        spend.and_then(|spend| {
+            set_event(
+                state.properties.as_ref(),
+                state.reservations.as_ref(),
+                id,
+                &menu,
+                spend,
+            )
+        }),
+    )
+}
+
+/// Tells webhooks and open dashboards about a changed group booking. The
+/// guest is not told, the tables and event details are the staff's.
+fn published(
+    state: &AppState,
+    changed: RepoResult<GroupBooking>,
+) -> Result<GroupBooking, ServerFnError> {
+    match changed {
+        Ok(booking) => {
+            state.publish(
+                WebhookEvent::ReservationDetailsChanged,
+                &booking.reservation,
+            );
+            Ok(booking)
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
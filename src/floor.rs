@@ -0,0 +1,228 @@
+//! The floor plan of a property and the live floor view drawn on it.
+//!
+//! Each table is placed on a plan [`FLOOR_WIDTH`] by [`FLOOR_HEIGHT`] units
+//! large, with its size, rotation and shape. Tables not placed yet are laid
+//! out in rows until staff drag them where they stand. The floor view colours
+//! every table by the reservation holding it at a time of the day, the way
+//! [`free_table`](crate::booking::free_table) sees it, and by the party
+//! seated at it until they are completed, so hosts see at a glance which
+//! tables are free, booked or seated.
+use crate::booking::{day_holds, overlaps};
+use crate::crud_properties::time_of_day;
+use crate::errors::RepositoryError;
+use crate::models::{FloorTable, PropertyTable, ReservationStatus, TableLayout, TableShape};
+use crate::repository::{PropertyRepository, RepoResult, ReservationRepository};
+use crate::waitlist::parse_time;
+use crate::AppState;
+use chrono::{Local, NaiveDate};
+use leptos::{expect_context, server, ServerFnError};
+use time::Time;
+use uuid::Uuid;
+
+/// Width of the floor plan, in plan units.
+pub const FLOOR_WIDTH: i32 = 800;
+/// Height of the floor plan, in plan units.
+pub const FLOOR_HEIGHT: i32 = 600;
+/// Smallest and largest side of a table, in plan units.
+pub const TABLE_SIDES: (i32, i32) = (20, 300);
+
+/// Where a table not placed yet is drawn: in rows of six, the `index`th of
+/// the property's `count` tables by name, wider the more it seats. Rows draw
+/// closer together and tables flatter when there are too many rows for the
+/// plan, so every table stays on it.
+fn default_layout(table: &PropertyTable, index: usize, count: usize) -> TableLayout {
+    let (column, row) = ((index % 6) as i32, (index / 6) as i32);
+    let rows = count.div_ceil(6).max(1) as i32;
+    let pitch = ((FLOOR_HEIGHT - 20) / rows).min(100);
+    let (width, height) = (
+        (40 + 8 * table.capacity).clamp(TABLE_SIDES.0, 110),
+        (pitch - 10).clamp(TABLE_SIDES.0, 60),
+    );
+    TableLayout {
+        table_id: table.table_id,
+        x: 20 + column * 130,
+        y: (20 + row * pitch).min(FLOOR_HEIGHT - height),
+        width,
+        height,
+        rotation: 0,
+        shape: TableShape::default(),
+    }
+}
+
+/// The tables of a property where they sit on its floor plan.
+pub fn floor_plan(properties: &dyn PropertyRepository, pid: Uuid) -> RepoResult<Vec<FloorTable>> {
+    let layouts = properties.layouts(pid)?;
+    let tables = properties.tables(pid)?;
+    let count = tables.len();
+    Ok(tables
+        .into_iter()
+        .enumerate()
+        .map(|(index, table)| FloorTable {
+            layout: layouts
+                .iter()
+                .find(|layout| layout.table_id == table.table_id)
+                .copied()
+                .unwrap_or_else(|| default_layout(&table, index, count)),
+            table,
+            reservation: None,
+        })
+        .collect())
+}
+
+/// The floor plan with the reservation holding each table around `time`,
+/// a seated party before one still expected, the nearest in time first.
+/// Seated parties hold their table however long they stay.
+pub fn floor_view(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    pid: Uuid,
+    date: NaiveDate,
+    time: Time,
+) -> RepoResult<Vec<FloorTable>> {
+    let mut floor = floor_plan(properties, pid)?;
+    let tables: Vec<PropertyTable> = floor.iter().map(|spot| spot.table.clone()).collect();
+    let holds = day_holds(reservations, &tables, pid, date)?;
+    for spot in &mut floor {
+        spot.reservation = holds
+            .iter()
+            .filter(|resv| {
+                resv.seating.eq_ignore_ascii_case(&spot.table.table_name)
+                    && match resv.status {
+                        ReservationStatus::Seated => true,
+                        ReservationStatus::Booked | ReservationStatus::Requested => {
+                            overlaps(resv.reservation_time, time)
+                        }
+                        _ => false,
+                    }
+            })
+            .min_by_key(|resv| {
+                (
+                    resv.status != ReservationStatus::Seated,
+                    (resv.reservation_time - time).whole_minutes().abs(),
+                )
+            })
+            .cloned();
+    }
+    Ok(floor)
+}
+
+/// Places a table of the property on the floor plan and moves it to
+/// `section`, none when blank.
+pub fn place_table(
+    properties: &dyn PropertyRepository,
+    pid: Uuid,
+    layout: TableLayout,
+    section: &str,
+) -> RepoResult<FloorTable> {
+    let mut table = properties
+        .tables(pid)?
+        .into_iter()
+        .find(|table| table.table_id == layout.table_id)
+        .ok_or(RepositoryError::NotFound)?;
+    let (smallest, largest) = TABLE_SIDES;
+    if !(smallest..=largest).contains(&layout.width)
+        || !(smallest..=largest).contains(&layout.height)
+    {
+        return Err(RepositoryError::Invalid(format!(
+            "a table's sides are {smallest} to {largest} long"
+        )));
+    }
+    if layout.x < 0
+        || layout.y < 0
+        || layout.x > FLOOR_WIDTH - layout.width
+        || layout.y > FLOOR_HEIGHT - layout.height
+    {
+        return Err(RepositoryError::Invalid(format!(
+            "{} does not fit on the floor plan there",
+            table.table_name
+        )));
+    }
+    let layout = TableLayout {
+        rotation: layout.rotation.rem_euclid(360),
+        ..layout
+    };
+    properties.save_layout(&layout)?;
+
+    let section = match section.trim() {
+        "" => None,
+        section => Some(section.to_string()),
+    };
+    if section != table.section {
+        properties.set_table_section(table.table_id, section.clone())?;
+        table.section = section;
+    }
+    Ok(FloorTable {
+        table,
+        layout,
+        reservation: None,
+    })
+}
+
+fn parse_shape(raw: &str) -> RepoResult<TableShape> {
+    TableShape::ALL
+        .into_iter()
+        .find(|shape| shape.to_string().eq_ignore_ascii_case(raw.trim()))
+        .ok_or_else(|| RepositoryError::Invalid(format!("{raw:?} is not a table shape")))
+}
+
+#[server(name = GetFloorPlan, prefix = "/api", endpoint = "floor_plan", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn get_floor_plan(pid: Uuid) -> Result<Vec<FloorTable>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    floor_plan(state.properties.as_ref(), pid)
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[server(name = SaveTableLayout, prefix = "/api", endpoint = "save_table_layout", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn save_table_layout(
+    pid: Uuid,
+    table_id: i32,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    rotation: i32,
+    shape: String,
+    section: String,
+) -> Result<FloorTable, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    parse_shape(&shape)
+        .and_then(|shape| {
+            let layout = TableLayout {
+                table_id,
+                x,
+                y,
+                width,
+                height,
+                rotation,
+                shape,
+            };
+            place_table(state.properties.as_ref(), pid, layout, &section)
+        })
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Shows the floor at `time` on `date`, now when the time is left blank.
+#[server(name = GetFloorView, prefix = "/api", endpoint = "floor_view", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn get_floor_view(
+    pid: Uuid,
+    date: NaiveDate,
+    time: String,
+) -> Result<Vec<FloorTable>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    parse_time(&time)
+        .and_then(|time| {
+            let time = time.unwrap_or_else(|| time_of_day(Local::now().naive_local()));
+            floor_view(
+                state.properties.as_ref(),
+                state.reservations.as_ref(),
+                pid,
+                date,
+                time,
+            )
+        })
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
@@ -0,0 +1,570 @@
+//! Guest profiles.
+//!
+//! Reservations keep the name and contact they were booked with, and
+//! [`create_reservation`](crate::crud_properties::create_reservation) links
+//! each to a guest of the property through [`match_guest`]: the contact is
+//! normalised with [`normalize_contact`] and looked up in `guest_contacts`,
+//! a contact seen for the first time gets a new guest. Contacts that are
+//! neither a phone number nor an email address are not matched.
+//!
+//! Reservations stored before guests existed are linked by
+//! `resvm-admin reservations link-guests`, see [`link_reservations`].
+//!
+//! Typos and contacts written in another form still split a guest in two.
+//! [`find_duplicates`] pairs guests whose contacts look alike or whose names
+//! are similar, and [`merge`] folds one into the other, recording a
+//! [`GuestMerge`].
+//!
+//! Guests who did not turn up [`NoShowPolicy::repeat_after`] times are
+//! repeat no-shows: their new bookings get a warning in the history and,
+//! when the property asks for a deposit, stay requests until it is paid.
+use crate::api_keys::verify_admin;
+use crate::emails;
+use crate::errors::RepositoryError;
+use crate::models::{
+    DuplicateGuests, Guest, GuestMerge, GuestProfile, NewGuest, NewGuestMerge, Reservation,
+    ReservationStatus,
+};
+use crate::repository::{GuestRepository, RepoResult, ReservationRepository};
+use crate::sms::{default_country_code, normalize_phone};
+use crate::AppState;
+use chrono::{Local, NaiveDate};
+use leptos::{expect_context, server, ServerFnError};
+use uuid::Uuid;
+
+/// The key guests are matched by: a lowercase email address or an E.164
+/// phone number, `None` for anything else.
+pub fn normalize_contact(raw: &str) -> Option<String> {
+    match emails::address(raw) {
+        Some(address) => Some(address.to_lowercase()),
+        None => normalize_phone(raw, default_country_code().as_deref()),
+    }
+}
+
+/// `raw` as comma separated tags, trimmed and without blanks or repeats.
+pub fn normalize_tags(raw: &str) -> String {
+    let mut tags: Vec<&str> = Vec::new();
+    for tag in raw.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+        if !tags.iter().any(|seen| seen.eq_ignore_ascii_case(tag)) {
+            tags.push(tag);
+        }
+    }
+    tags.join(", ")
+}
+
+/// The guest of the property booking as `name` with `contact`, created when
+/// the contact is new. `None` when the contact cannot be matched.
+pub fn match_guest(
+    repo: &dyn GuestRepository,
+    pid: Uuid,
+    name: &str,
+    contact: &str,
+) -> RepoResult<Option<i32>> {
+    let Some(contact) = normalize_contact(contact) else {
+        return Ok(None);
+    };
+    match repo.guest_by_contact(pid, &contact) {
+        Ok(guest) => Ok(Some(guest.guest_id)),
+        Err(RepositoryError::NotFound) => {
+            match repo.insert_guest(
+                NewGuest::new(pid, name.trim().to_string()),
+                std::slice::from_ref(&contact),
+            ) {
+                Ok(guest_id) => Ok(Some(guest_id)),
+                // Another booking with the same contact got there first.
+                Err(RepositoryError::Conflict(_)) => repo
+                    .guest_by_contact(pid, &contact)
+                    .map(|g| Some(g.guest_id)),
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Links the reservations of a property that have no guest yet and returns
+/// how many were linked.
+pub fn link_reservations(
+    guests: &dyn GuestRepository,
+    reservations: &dyn ReservationRepository,
+    pid: Uuid,
+) -> RepoResult<usize> {
+    let mut linked = 0;
+    for resv in reservations.for_property(pid, i64::MAX)? {
+        if resv.guest_id.is_some() {
+            continue;
+        }
+        if let Some(guest_id) = match_guest(guests, pid, &resv.name, &resv.contact)? {
+            guests.set_reservation_guest(resv.id, guest_id)?;
+            linked += 1;
+        }
+    }
+    Ok(linked)
+}
+
+/// A guest with their contacts, bookings and what they add up to, counting
+/// bookings from `today` on as upcoming.
+pub fn profile(
+    repo: &dyn GuestRepository,
+    guest_id: i32,
+    today: NaiveDate,
+) -> RepoResult<GuestProfile> {
+    let guest = repo.find_guest(guest_id)?;
+    let (emails, phones) = repo
+        .guest_contacts(guest_id)?
+        .into_iter()
+        .partition(|contact| contact.contains('@'));
+    let reservations = repo.guest_reservations(guest_id)?;
+    let with_status = |statuses: &[ReservationStatus]| {
+        reservations
+            .iter()
+            .filter(|resv| statuses.contains(&resv.status))
+            .count()
+    };
+    let visits = with_status(&[ReservationStatus::Seated, ReservationStatus::Completed]);
+    let no_shows = with_status(&[ReservationStatus::NoShow]);
+    Ok(GuestProfile {
+        upcoming: reservations
+            .iter()
+            .filter(|resv| {
+                resv.reservation_date >= today
+                    && matches!(
+                        resv.status,
+                        ReservationStatus::Booked | ReservationStatus::Requested
+                    )
+            })
+            .count(),
+        visits,
+        no_shows,
+        reliability: reliability(visits, no_shows),
+        total_spend: reservations
+            .iter()
+            .filter(|resv| resv.advance)
+            .filter_map(|resv| resv.advance_amount)
+            .map(i64::from)
+            .sum(),
+        merges: repo.guest_merges(guest_id)?,
+        guest,
+        phones,
+        emails,
+        reservations,
+    })
+}
+
+/// Share of the bookings a guest was expected at that they turned up for, in
+/// percent, `None` before the first.
+pub fn reliability(visits: usize, no_shows: usize) -> Option<u8> {
+    let expected = visits + no_shows;
+    (expected > 0).then(|| (visits * 100 / expected) as u8)
+}
+
+/// Minutes after its time a booking nobody turned up for is a no-show,
+/// unless `RESVM_NO_SHOW_GRACE_MINUTES` says otherwise.
+pub const NO_SHOW_GRACE_MINUTES: i64 = 30;
+
+/// No-shows from which a guest is a repeat no-show, unless
+/// `RESVM_REPEAT_NO_SHOWS` says otherwise.
+pub const REPEAT_NO_SHOWS: usize = 2;
+
+/// When a booking nobody turned up for is a no-show, and what is asked of
+/// guests who did not turn up before.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NoShowPolicy {
+    /// Minutes after its time a booking still `Booked` is a no-show.
+    pub grace_minutes: i64,
+    /// No-shows from which a guest is a repeat no-show, 0 for never.
+    pub repeat_after: usize,
+    /// Advance a repeat no-show pays before their booking is confirmed.
+    pub deposit: Option<i32>,
+}
+
+impl Default for NoShowPolicy {
+    fn default() -> Self {
+        Self {
+            grace_minutes: NO_SHOW_GRACE_MINUTES,
+            repeat_after: REPEAT_NO_SHOWS,
+            deposit: None,
+        }
+    }
+}
+
+impl NoShowPolicy {
+    /// The policy set by `RESVM_NO_SHOW_GRACE_MINUTES`,
+    /// `RESVM_REPEAT_NO_SHOWS` and `RESVM_NO_SHOW_DEPOSIT`, read once when
+    /// the server starts.
+    pub fn from_env() -> Self {
+        let var = |name| std::env::var(name).ok();
+        let default = Self::default();
+        Self {
+            grace_minutes: var("RESVM_NO_SHOW_GRACE_MINUTES")
+                .and_then(|minutes| minutes.parse().ok())
+                .filter(|minutes| *minutes >= 0)
+                .unwrap_or(default.grace_minutes),
+            repeat_after: var("RESVM_REPEAT_NO_SHOWS")
+                .and_then(|count| count.parse().ok())
+                .unwrap_or(default.repeat_after),
+            deposit: var("RESVM_NO_SHOW_DEPOSIT")
+                .and_then(|amount| amount.parse().ok())
+                .filter(|amount| *amount > 0),
+        }
+    }
+
+    /// Whether `no_shows` make a guest a repeat no-show.
+    pub fn is_repeat(&self, no_shows: usize) -> bool {
+        self.repeat_after > 0 && no_shows >= self.repeat_after
+    }
+
+    /// The deposit a repeat no-show still owes on a booking with `paid` in
+    /// advance.
+    pub fn deposit_due(&self, paid: Option<i32>) -> Option<i32> {
+        self.deposit
+            .filter(|deposit| paid.unwrap_or_default() < *deposit)
+    }
+}
+
+/// How many bookings of the guest were no-shows.
+pub fn no_show_count(repo: &dyn GuestRepository, guest_id: i32) -> RepoResult<usize> {
+    Ok(repo
+        .guest_reservations(guest_id)?
+        .iter()
+        .filter(|resv| resv.status == ReservationStatus::NoShow)
+        .count())
+}
+
+/// The no-shows of the guest `guest_id`, when they make them a repeat
+/// no-show.
+pub fn repeat_no_shows(
+    repo: &dyn GuestRepository,
+    policy: &NoShowPolicy,
+    guest_id: Option<i32>,
+) -> RepoResult<Option<usize>> {
+    let Some(guest_id) = guest_id else {
+        return Ok(None);
+    };
+    let no_shows = no_show_count(repo, guest_id)?;
+    Ok(policy.is_repeat(no_shows).then_some(no_shows))
+}
+
+/// Fails when `resv` is the booking of a repeat no-show who did not pay the
+/// deposit yet, before it is confirmed.
+pub fn check_deposit(
+    repo: &dyn GuestRepository,
+    policy: &NoShowPolicy,
+    resv: &Reservation,
+) -> RepoResult<()> {
+    let Some(no_shows) = repeat_no_shows(repo, policy, resv.guest_id)? else {
+        return Ok(());
+    };
+    let paid = resv.advance.then_some(resv.advance_amount).flatten();
+    match policy.deposit_due(paid) {
+        Some(deposit) => Err(RepositoryError::Conflict(format!(
+            "the guest did not turn up {no_shows} times, an advance of {deposit} is needed to confirm"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Name similarity from which two guests are reported as likely duplicates.
+pub const NAME_SIMILARITY: f64 = 0.85;
+
+/// Digits two phone numbers have to end alike in to be taken for the same
+/// line, e.g. when a trunk `0` ended up after the calling code.
+const PHONE_SUFFIX_DIGITS: usize = 9;
+
+fn name_tokens(name: &str) -> Vec<String> {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1)
+                .min(row[j] + 1)
+                .min(diagonal + usize::from(ca != *cb));
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// How alike two names are, from 0 to 1. Case, punctuation and spacing do
+/// not count. Names with the same first word whose other words are
+/// abbreviations of each other, like `Rahul K` and `Rahul Kumar`, score 0.9,
+/// other names by their edit distance.
+pub fn name_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (name_tokens(a), name_tokens(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+    if a[0] == b[0]
+        && a.iter()
+            .zip(b.iter())
+            .all(|(a, b)| a.starts_with(b.as_str()) || b.starts_with(a.as_str()))
+    {
+        return 0.9;
+    }
+    let (a, b) = (a.join(" "), b.join(" "));
+    let longest = a.chars().count().max(b.chars().count());
+    1.0 - levenshtein(&a, &b) as f64 / longest as f64
+}
+
+/// The mailbox an address delivers to: without dots and a `+tag` in its
+/// local part.
+fn mailbox(address: &str) -> Option<String> {
+    let (local, domain) = address.split_once('@')?;
+    let local = local.split('+').next().unwrap_or(local).replace('.', "");
+    Some(format!("{local}@{domain}"))
+}
+
+/// Why two different normalised contacts are likely the same, if they are.
+fn contacts_alike(a: &str, b: &str) -> Option<String> {
+    if a == b {
+        return None;
+    }
+    let alike = match (mailbox(a), mailbox(b)) {
+        (Some(a), Some(b)) => a == b,
+        (None, None) => {
+            let (a, b) = (&a[1..], &b[1..]);
+            a.len() >= PHONE_SUFFIX_DIGITS
+                && b.len() >= PHONE_SUFFIX_DIGITS
+                && a[a.len() - PHONE_SUFFIX_DIGITS..] == b[b.len() - PHONE_SUFFIX_DIGITS..]
+        }
+        _ => false,
+    };
+    alike.then(|| format!("{a} looks like {b}"))
+}
+
+/// Pairs of guests of a property that are likely the same person, those with
+/// alike contacts first, then by how similar their names are.
+pub fn find_duplicates(repo: &dyn GuestRepository, pid: Uuid) -> RepoResult<Vec<DuplicateGuests>> {
+    let mut guests = repo.guests(pid)?;
+    guests.sort_by_key(|guest| guest.guest_id);
+    let contacts = repo.property_guest_contacts(pid)?;
+    let contacts_of = |guest_id: i32| {
+        contacts
+            .iter()
+            .filter(move |(owner, _)| *owner == guest_id)
+            .map(|(_, contact)| contact.as_str())
+    };
+
+    let mut found = Vec::new();
+    for (n, guest) in guests.iter().enumerate() {
+        for duplicate in &guests[n + 1..] {
+            let mut reasons: Vec<String> = contacts_of(guest.guest_id)
+                .flat_map(|a| {
+                    contacts_of(duplicate.guest_id).filter_map(move |b| contacts_alike(a, b))
+                })
+                .collect();
+            let by_contact = !reasons.is_empty();
+            let similarity = name_similarity(&guest.name, &duplicate.name);
+            if similarity >= NAME_SIMILARITY {
+                reasons.push(format!(
+                    "names {} and {} are alike",
+                    guest.name, duplicate.name
+                ));
+            }
+            if reasons.is_empty() {
+                continue;
+            }
+            found.push((
+                by_contact,
+                similarity,
+                DuplicateGuests {
+                    guest: guest.clone(),
+                    duplicate: duplicate.clone(),
+                    reasons,
+                },
+            ));
+        }
+    }
+    found.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.total_cmp(&a.1)));
+    Ok(found.into_iter().map(|(_, _, pair)| pair).collect())
+}
+
+/// Merges the guest `duplicate_id` into `guest_id` on behalf of `merged_by`:
+/// reservations, and the advances paid with them, and contacts move over,
+/// notes are appended and tags combined. Returns the recorded merge.
+pub fn merge(
+    repo: &dyn GuestRepository,
+    guest_id: i32,
+    duplicate_id: i32,
+    merged_by: &str,
+) -> RepoResult<GuestMerge> {
+    if guest_id == duplicate_id {
+        return Err(RepositoryError::Invalid(
+            "a guest cannot be merged into itself".into(),
+        ));
+    }
+    let mut guest = repo.find_guest(guest_id)?;
+    let duplicate = repo.find_guest(duplicate_id)?;
+    if guest.property_id != duplicate.property_id {
+        return Err(RepositoryError::Invalid(
+            "guests of different properties cannot be merged".into(),
+        ));
+    }
+    guest.notes = [guest.notes.trim(), duplicate.notes.trim()]
+        .into_iter()
+        .filter(|notes| !notes.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    guest.tags = normalize_tags(&format!("{},{}", guest.tags, duplicate.tags));
+    repo.merge_guests(
+        &guest,
+        NewGuestMerge {
+            property_id: guest.property_id,
+            guest_id,
+            merged_guest_id: duplicate_id,
+            merged_name: duplicate.name,
+            merged_contacts: repo.guest_contacts(duplicate_id)?.join(", "),
+            merged_by: merged_by.to_string(),
+        },
+    )
+}
+
+/// The guest, when it belongs to the property.
+fn guest_of(repo: &dyn GuestRepository, pid: Uuid, guest_id: i32) -> RepoResult<Guest> {
+    let guest = repo.find_guest(guest_id)?;
+    if guest.property_id != pid {
+        return Err(RepositoryError::NotFound);
+    }
+    Ok(guest)
+}
+
+/// Guests of the property whose name or tags contain `search`, or whose
+/// contact it is. An empty search lists every guest.
+#[server(name = ListGuests, prefix = "/api", endpoint = "list_guests", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn list_guests(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    search: String,
+) -> Result<Vec<Guest>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| {
+            let search = search.trim().to_lowercase();
+            if let Some(contact) = normalize_contact(&search) {
+                return match state.guests.guest_by_contact(pid, &contact) {
+                    Ok(guest) => Ok(vec![guest]),
+                    Err(RepositoryError::NotFound) => Ok(Vec::new()),
+                    Err(e) => Err(e),
+                };
+            }
+            Ok(state
+                .guests
+                .guests(pid)?
+                .into_iter()
+                .filter(|guest| {
+                    guest.name.to_lowercase().contains(&search)
+                        || guest.tags.to_lowercase().contains(&search)
+                })
+                .collect())
+        })
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(name = GetGuest, prefix = "/api", endpoint = "get_guest", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn get_guest(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    guest_id: i32,
+) -> Result<GuestProfile, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| guest_of(state.guests.as_ref(), pid, guest_id))
+        .and_then(|_| profile(state.guests.as_ref(), guest_id, Local::now().date_naive()))
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Replaces the name, notes, tags and contacts of a guest. `contacts` holds
+/// phone numbers and email addresses separated by commas or new lines.
+#[allow(clippy::too_many_arguments)]
+#[server(name = UpdateGuest, prefix = "/api", endpoint = "update_guest", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn update_guest(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    guest_id: i32,
+    name: String,
+    notes: String,
+    tags: String,
+    contacts: String,
+) -> Result<GuestProfile, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| guest_of(state.guests.as_ref(), pid, guest_id))
+        .and_then(|mut guest| {
+            if name.trim().is_empty() {
+                return Err(RepositoryError::Invalid("name is required".into()));
+            }
+            let mut normalized = Vec::new();
+            for raw in contacts
+                .split([',', '\n'])
+                .map(str::trim)
+                .filter(|raw| !raw.is_empty())
+            {
+                let contact = normalize_contact(raw).ok_or_else(|| {
+                    RepositoryError::Invalid(format!(
+                        "{raw} is neither a phone number nor an email address"
+                    ))
+                })?;
+                if !normalized.contains(&contact) {
+                    normalized.push(contact);
+                }
+            }
+            state.guests.set_guest_contacts(guest_id, &normalized)?;
+            guest.name = name.trim().to_string();
+            guest.notes = notes.trim().to_string();
+            guest.tags = normalize_tags(&tags);
+            state.guests.update_guest(&guest)?;
+            profile(state.guests.as_ref(), guest_id, Local::now().date_naive())
+        })
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(name = FindDuplicateGuests, prefix = "/api", endpoint = "find_duplicate_guests", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn find_duplicate_guests(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+) -> Result<Vec<DuplicateGuests>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| find_duplicates(state.guests.as_ref(), pid))
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Merges `duplicate_id` into `guest_id` and returns the kept guest.
+#[server(name = MergeGuests, prefix = "/api", endpoint = "merge_guests", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn merge_guests(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    guest_id: i32,
+    duplicate_id: i32,
+) -> Result<GuestProfile, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| guest_of(state.guests.as_ref(), pid, guest_id))
+        .and_then(|_| guest_of(state.guests.as_ref(), pid, duplicate_id))
+        .and_then(|_| merge(state.guests.as_ref(), guest_id, duplicate_id, &user_name))
+        .and_then(|_| profile(state.guests.as_ref(), guest_id, Local::now().date_naive()))
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
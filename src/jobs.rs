@@ -0,0 +1,363 @@
+//! Background jobs.
+//!
+//! Work that has to happen outside a request is stored as a row of `jobs`
+//! and run by the worker started by [`spawn_worker`] once its `run_at`
+//! passed. A worker claims due jobs for [`LEASE_SECS`], so several server
+//! processes can share the queue and a job whose worker died is picked up
+//! again when the lease ran out. A failed run is retried with [`backoff`],
+//! a one-off job is given up after [`MAX_ATTEMPTS`].
+//!
+//! Each booking gets a [`JobKind::Reminder`] running [`REMINDER_HOURS`]
+//! before it, moved along when the booking is. The periodic jobs of
+//! [`JobKind::PERIODIC`] are rescheduled by every run, see
+//! [`JobKind::period`]: no-shows are marked the grace period of the
+//! [`NoShowPolicy`] after the booking, and finished jobs and sent emails and
+//! texts are deleted after `RESVM_RETENTION_DAYS` (default
+//! [`RETENTION_DAYS`]). resvm keeps no login sessions, admin
+//! credentials go along with every request, so there are none to expire.
+use crate::api_keys::verify_admin;
+use crate::crud_properties::{change_status, status_event};
+use crate::emails::{self, REMINDER_HOURS};
+use crate::errors::RepositoryError;
+use crate::guests::NoShowPolicy;
+use crate::models::{
+    HistoryEntry, Job, JobKind, JobStatus, NewHistoryEntry, NewJob, Reservation, ReservationStatus,
+};
+use crate::repository::{
+    EmailRepository, JobRepository, PropertyRepository, RepoResult, ReservationRepository,
+    SmsRepository,
+};
+use crate::sms;
+use crate::webhooks::backoff;
+use crate::AppState;
+use chrono::{Days, Duration, Local, NaiveDateTime, TimeZone, Utc};
+use leptos::{expect_context, logging, server, ServerFnError};
+use uuid::Uuid;
+
+/// Attempts after which a one-off job is given up and marked failed.
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// Seconds a worker holds the jobs it claimed.
+pub const LEASE_SECS: i64 = 5 * 60;
+
+/// Days finished jobs and sent emails and texts are kept.
+pub const RETENTION_DAYS: i64 = 90;
+
+/// Jobs claimed per worker pass.
+const BATCH: i64 = 20;
+
+/// `at`, a local time, in UTC like every `run_at`.
+fn local_to_utc(at: NaiveDateTime) -> NaiveDateTime {
+    Local
+        .from_local_datetime(&at)
+        .earliest()
+        .map(|at| at.naive_utc())
+        .unwrap_or(at)
+}
+
+fn reminder_key(rid: i32) -> String {
+    format!("reminder:{rid}")
+}
+
+/// Schedules the reminder job of a booking, or moves it when the booking
+/// moved, and returns its id. Reservations that are not `Booked` get none,
+/// a reminder job left from before finds nothing to do.
+pub fn schedule_reminder(repo: &dyn JobRepository, resv: &Reservation) -> RepoResult<Option<i32>> {
+    if resv.status != ReservationStatus::Booked {
+        return Ok(None);
+    }
+    repo.schedule_job(NewJob {
+        kind: JobKind::Reminder,
+        property_id: Some(resv.property_id),
+        reservation_id: Some(resv.id),
+        unique_key: Some(reminder_key(resv.id)),
+        run_at: local_to_utc(resv.starts_at() - Duration::hours(REMINDER_HOURS)),
+    })
+    .map(Some)
+}
+
+/// Schedules a reminder job for every `Booked` reservation starting after
+/// `now`, a local time, that has none, such as bookings made before
+/// reminders were jobs, and returns how many were scheduled.
+pub fn schedule_missing_reminders(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    repo: &dyn JobRepository,
+    now: NaiveDateTime,
+) -> RepoResult<usize> {
+    let mut scheduled = 0;
+    for property in properties.all()? {
+        for resv in reservations.for_property(property.property_id, i64::MAX)? {
+            if resv.status == ReservationStatus::Booked
+                && resv.starts_at() > now
+                && !repo.has_job(&reminder_key(resv.id))?
+            {
+                schedule_reminder(repo, &resv)?;
+                scheduled += 1;
+            }
+        }
+    }
+    Ok(scheduled)
+}
+
+/// Makes sure every periodic job is scheduled, to run at `now`.
+pub fn schedule_periodic(repo: &dyn JobRepository, now: NaiveDateTime) -> RepoResult<()> {
+    for kind in JobKind::PERIODIC {
+        repo.schedule_job(NewJob {
+            kind,
+            property_id: None,
+            reservation_id: None,
+            unique_key: Some(kind.to_string()),
+            run_at: now,
+        })?;
+    }
+    Ok(())
+}
+
+/// Marks the `Booked` reservations of every property that started more than
+/// the grace period of `policy` before `now`, a local time, as no-shows and
+/// returns them. A reservation that cannot be marked is logged and left for
+/// the next run.
+pub fn mark_no_shows(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    policy: &NoShowPolicy,
+    now: NaiveDateTime,
+) -> RepoResult<Vec<Reservation>> {
+    let grace = policy.grace_minutes;
+    let cutoff = now - Duration::minutes(grace);
+    let mut marked = Vec::new();
+    for property in properties.all()? {
+        for day in [cutoff.date() - Days::new(1), cutoff.date()] {
+            for resv in reservations.on_date(property.property_id, day)? {
+                if resv.status != ReservationStatus::Booked || resv.starts_at() > cutoff {
+                    continue;
+                }
+                let id = resv.id;
+                let marking =
+                    change_status(reservations, id, ReservationStatus::NoShow).and_then(|resv| {
+                        reservations.insert_history(NewHistoryEntry {
+                            reservation_id: resv.id,
+                            changed_by: HistoryEntry::SYSTEM.to_string(),
+                            summary: format!("marked as no-show {grace} minutes after the booking"),
+                        })?;
+                        Ok(resv)
+                    });
+                match marking {
+                    Ok(resv) => marked.push(resv),
+                    Err(e) => logging::warn!("cannot mark reservation {id} as no-show: {e}"),
+                }
+            }
+        }
+    }
+    Ok(marked)
+}
+
+/// Deletes finished jobs and sent emails and texts older than `before`, and
+/// returns how many rows went.
+pub fn purge_expired(
+    jobs: &dyn JobRepository,
+    emails: &dyn EmailRepository,
+    sms: &dyn SmsRepository,
+    before: NaiveDateTime,
+) -> RepoResult<usize> {
+    Ok(jobs.purge_jobs(before)? + emails.purge_emails(before)? + sms.purge_messages(before)?)
+}
+
+fn retention_days() -> i64 {
+    std::env::var("RESVM_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(RETENTION_DAYS)
+}
+
+/// Runs one claimed job.
+pub fn run(state: &AppState, job: &Job) -> RepoResult<()> {
+    match job.kind {
+        JobKind::Reminder => {
+            let Some(rid) = job.reservation_id else {
+                return Err(RepositoryError::Invalid(
+                    "a reminder job needs a reservation".into(),
+                ));
+            };
+            let resv = state.reservations.find(rid)?;
+            if resv.status != ReservationStatus::Booked
+                || resv.starts_at() <= Local::now().naive_local()
+            {
+                return Ok(());
+            }
+            if state.mail.is_some() {
+                emails::remind(state.properties.as_ref(), state.emails.as_ref(), &resv)?;
+            }
+            if let Some(channel) = &state.channel {
+                sms::remind(
+                    state.properties.as_ref(),
+                    state.sms.as_ref(),
+                    channel.name(),
+                    &resv,
+                )?;
+            }
+            Ok(())
+        }
+        JobKind::MarkNoShows => {
+            let marked = mark_no_shows(
+                state.properties.as_ref(),
+                state.reservations.as_ref(),
+                &state.no_show_policy,
+                Local::now().naive_local(),
+            )?;
+            for resv in marked {
+                state.publish(status_event(resv.status), &resv);
+            }
+            Ok(())
+        }
+        JobKind::PurgeExpired => {
+            let purged = purge_expired(
+                state.jobs.as_ref(),
+                state.emails.as_ref(),
+                state.sms.as_ref(),
+                Utc::now().naive_utc() - Duration::days(retention_days()),
+            )?;
+            if purged > 0 {
+                logging::log!("purged {purged} expired jobs, emails and texts");
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Returns a claimed job with the outcome of its run applied. A periodic
+/// job is due again one period later, or sooner to retry a failure.
+pub fn finish(mut job: Job, outcome: Result<(), String>, now: NaiveDateTime) -> Job {
+    job.locked_until = None;
+    match (outcome, job.kind.period()) {
+        (Ok(()), None) => {
+            job.status = JobStatus::Done;
+            job.last_error = None;
+            job.finished_at = Some(now);
+        }
+        (Ok(()), Some(period)) => {
+            job.status = JobStatus::Pending;
+            job.attempts = 0;
+            job.last_error = None;
+            job.finished_at = Some(now);
+            job.run_at = now + Duration::seconds(period);
+        }
+        (Err(error), None) if job.attempts >= MAX_ATTEMPTS => {
+            job.status = JobStatus::Failed;
+            job.last_error = Some(error);
+            job.finished_at = Some(now);
+        }
+        (Err(error), period) => {
+            job.status = JobStatus::Pending;
+            job.last_error = Some(error);
+            let retry = backoff(job.attempts);
+            job.run_at = now
+                + period
+                    .map(|period| retry.min(Duration::seconds(period)))
+                    .unwrap_or(retry);
+        }
+    }
+    job
+}
+
+/// Claims the jobs that are due, runs them and returns how many ran.
+pub fn run_due(state: &AppState) -> RepoResult<usize> {
+    let now = Utc::now().naive_utc();
+    let claimed = state
+        .jobs
+        .claim_jobs(now, now + Duration::seconds(LEASE_SECS), BATCH)?;
+    let ran = claimed.len();
+    for job in claimed {
+        let outcome = run(state, &job).map_err(|e| e.to_string());
+        if let Err(e) = &outcome {
+            logging::warn!("job {} ({}) failed: {e}", job.job_id, job.kind);
+        }
+        state
+            .jobs
+            .update_job(&finish(job, outcome, Utc::now().naive_utc()))?;
+    }
+    Ok(ran)
+}
+
+/// Schedules the periodic jobs and the reminders of bookings without a
+/// reminder job, then runs due jobs every `interval` for the lifetime of
+/// the server.
+pub fn spawn_worker(state: AppState, interval: std::time::Duration) {
+    tokio::spawn(async move {
+        let setup = state.clone();
+        let started = tokio::task::spawn_blocking(move || {
+            schedule_periodic(setup.jobs.as_ref(), Utc::now().naive_utc())?;
+            schedule_missing_reminders(
+                setup.properties.as_ref(),
+                setup.reservations.as_ref(),
+                setup.jobs.as_ref(),
+                Local::now().naive_local(),
+            )
+        })
+        .await;
+        match started {
+            Ok(Err(e)) => logging::warn!("cannot schedule jobs: {e}"),
+            Err(e) => logging::warn!("job worker stopped while starting: {e}"),
+            Ok(Ok(scheduled)) if scheduled > 0 => {
+                logging::log!("scheduled {scheduled} missing reminders")
+            }
+            Ok(Ok(_)) => {}
+        }
+
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let state = state.clone();
+            match tokio::task::spawn_blocking(move || run_due(&state)).await {
+                Ok(Err(e)) => logging::warn!("job run failed: {e}"),
+                Err(e) => logging::warn!("job worker stopped a pass: {e}"),
+                Ok(Ok(_)) => {}
+            }
+        }
+    });
+}
+
+#[server(name = ListJobs, prefix = "/api", endpoint = "list_jobs", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn list_jobs(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+) -> Result<Vec<Job>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| state.jobs.jobs(pid, 100))
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Makes a job of the property, or a periodic one, due right away with a
+/// fresh set of attempts.
+#[server(name = RetryJob, prefix = "/api", endpoint = "retry_job", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn retry_job(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    job_id: i32,
+) -> Result<Job, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| state.jobs.find_job(job_id))
+        .and_then(|mut job| {
+            if job.property_id.is_some_and(|job_pid| job_pid != pid) {
+                return Err(RepositoryError::NotFound);
+            }
+            if job.status == JobStatus::Running {
+                return Err(RepositoryError::Conflict(format!(
+                    "job {job_id} is running"
+                )));
+            }
+            job.status = JobStatus::Pending;
+            job.attempts = 0;
+            job.run_at = Utc::now().naive_utc();
+            state.jobs.update_job(&job)?;
+            Ok(job)
+        })
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
@@ -1,33 +1,97 @@
+pub mod allocation;
+pub mod api;
+pub mod api_keys;
 pub mod app;
+pub mod booking;
 pub mod crud_properties;
+pub mod emails;
 pub mod error_template;
 pub mod errors;
+pub mod events;
+pub mod floor;
+pub mod guests;
+pub mod jobs;
+pub mod live;
+pub mod manage;
+pub mod migrations;
 pub mod models;
+pub mod repository;
 pub mod schema;
+#[cfg(feature = "sqlite")]
+pub mod schema_sqlite;
+pub mod seed;
+pub mod series;
+pub mod sms;
+pub mod special_requests;
+pub mod waitlist;
+pub mod webhooks;
 use argon2::{self, Config};
 use axum::extract::FromRef;
+use booking::RateLimiter;
 use diesel::{
     r2d2::{ConnectionManager, Pool},
     PgConnection,
 };
 use dotenvy::dotenv;
+use emails::MailTransport;
+use guests::NoShowPolicy;
 use leptos::LeptosOptions;
+use live::ReservationFeed;
+use models::{Reservation, WebhookEvent};
 use rand::RngCore;
+#[cfg(feature = "sqlite")]
+use repository::SqliteRepository;
+use repository::{
+    ApiKeyRepository, EmailRepository, GuestRepository, JobRepository, PgRepository,
+    PropertyRepository, ReservationRepository, SeriesRepository, SmsRepository, WaitlistRepository,
+    WebhookRepository,
+};
+use sms::NotificationChannel;
 use std::{env, sync::Arc};
 
 type SharedPooledConnection = Arc<Pool<ConnectionManager<PgConnection>>>;
+#[cfg(feature = "sqlite")]
+type SharedSqlitePool = Arc<Pool<ConnectionManager<diesel::SqliteConnection>>>;
+
+/// Connection pool of the backend picked from `DATABASE_URL`.
+#[derive(Debug, Clone)]
+pub enum Database {
+    Postgres(SharedPooledConnection),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SharedSqlitePool),
+}
 
-pub fn get_connection_pool() -> SharedPooledConnection {
+/// Builds the pool for `DATABASE_URL`.
+///
+/// `postgres://` and `postgresql://` urls use Postgres. With the `sqlite`
+/// feature, `sqlite://` urls (or a plain file path) open a SQLite database.
+pub fn get_connection_pool() -> Database {
     dotenv().ok();
     let url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let manager = ConnectionManager::<PgConnection>::new(url);
-
-    Arc::new(
-        Pool::builder()
-            .test_on_check_out(true)
-            .build(manager)
-            .expect("Could not build connection pool"),
-    )
+
+    if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+        let manager = ConnectionManager::<PgConnection>::new(url);
+        return Database::Postgres(Arc::new(
+            Pool::builder()
+                .test_on_check_out(true)
+                .build(manager)
+                .expect("Could not build connection pool"),
+        ));
+    }
+
+    #[cfg(feature = "sqlite")]
+    {
+        let path = url.strip_prefix("sqlite://").unwrap_or(&url);
+        let manager = ConnectionManager::<diesel::SqliteConnection>::new(path);
+        Database::Sqlite(Arc::new(
+            Pool::builder()
+                .test_on_check_out(true)
+                .build(manager)
+                .expect("Could not build connection pool"),
+        ))
+    }
+    #[cfg(not(feature = "sqlite"))]
+    panic!("DATABASE_URL must be a postgres url, build with the `sqlite` feature for SQLite")
 }
 
 /// Derive FromRef to allow multiple items in state, using Axum’s
@@ -35,7 +99,127 @@ pub fn get_connection_pool() -> SharedPooledConnection {
 #[derive(FromRef, Debug, Clone)]
 pub struct AppState {
     pub leptos_options: LeptosOptions,
-    pub pool: SharedPooledConnection,
+    pub pool: Database,
+    pub properties: Arc<dyn PropertyRepository>,
+    pub reservations: Arc<dyn ReservationRepository>,
+    pub api_keys: Arc<dyn ApiKeyRepository>,
+    pub webhooks: Arc<dyn WebhookRepository>,
+    pub emails: Arc<dyn EmailRepository>,
+    /// Where guest emails go, `None` while no transport is configured.
+    pub mail: Option<Arc<dyn MailTransport>>,
+    pub sms: Arc<dyn SmsRepository>,
+    /// Where guest texts go, `None` while no gateway is configured.
+    pub channel: Option<Arc<dyn NotificationChannel>>,
+    pub jobs: Arc<dyn JobRepository>,
+    pub guests: Arc<dyn GuestRepository>,
+    pub waitlist: Arc<dyn WaitlistRepository>,
+    pub series: Arc<dyn SeriesRepository>,
+    pub feed: ReservationFeed,
+    pub booking_limiter: RateLimiter,
+    pub no_show_policy: NoShowPolicy,
+}
+
+impl AppState {
+    /// State backed by the repositories of the backend `pool` points at.
+    pub fn new(leptos_options: LeptosOptions, pool: Database) -> Self {
+        match &pool {
+            Database::Postgres(pg) => {
+                let repository = Arc::new(PgRepository::new(pg.clone()));
+                Self::with_repository(leptos_options, pool, repository)
+            }
+            #[cfg(feature = "sqlite")]
+            Database::Sqlite(sqlite) => {
+                let repository = Arc::new(SqliteRepository::new(sqlite.clone()));
+                Self::with_repository(leptos_options, pool, repository)
+            }
+        }
+    }
+
+    /// State whose repositories are all served by `repository`, such as an
+    /// [`repository::InMemoryRepository`] in tests.
+    pub fn with_repository<R>(
+        leptos_options: LeptosOptions,
+        pool: Database,
+        repository: Arc<R>,
+    ) -> Self
+    where
+        R: PropertyRepository
+            + ReservationRepository
+            + ApiKeyRepository
+            + WebhookRepository
+            + EmailRepository
+            + SmsRepository
+            + JobRepository
+            + GuestRepository
+            + WaitlistRepository
+            + SeriesRepository
+            + 'static,
+    {
+        Self {
+            leptos_options,
+            pool,
+            properties: repository.clone(),
+            reservations: repository.clone(),
+            api_keys: repository.clone(),
+            webhooks: repository.clone(),
+            emails: repository.clone(),
+            mail: None,
+            sms: repository.clone(),
+            channel: None,
+            jobs: repository.clone(),
+            guests: repository.clone(),
+            waitlist: repository.clone(),
+            series: repository,
+            feed: ReservationFeed::new(),
+            booking_limiter: RateLimiter::default(),
+            no_show_policy: NoShowPolicy::from_env(),
+        }
+    }
+
+    /// Sends guest emails through `transport`.
+    pub fn with_mail(mut self, transport: Arc<dyn MailTransport>) -> Self {
+        self.mail = Some(transport);
+        self
+    }
+
+    /// Sends guest texts through `channel`.
+    pub fn with_channel(mut self, channel: Arc<dyn NotificationChannel>) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Announces a reservation change to webhooks, open dashboards and, when
+    /// mail or texts are configured, the guest, whose reminder is scheduled
+    /// or moved along with the booking.
+    ///
+    /// The change itself already happened, so a failure to queue any of the
+    /// announcements is only logged.
+    pub fn publish(&self, event: WebhookEvent, resv: &Reservation) {
+        if (self.mail.is_some() || self.channel.is_some())
+            && matches!(
+                event,
+                WebhookEvent::ReservationCreated | WebhookEvent::ReservationUpdated
+            )
+        {
+            if let Err(e) = jobs::schedule_reminder(self.jobs.as_ref(), resv) {
+                leptos::logging::warn!("cannot schedule the reminder of {}: {e}", resv.id);
+            }
+        }
+        webhooks::notify(self.webhooks.as_ref(), event, resv);
+        if self.mail.is_some() {
+            emails::notify(self.properties.as_ref(), self.emails.as_ref(), event, resv);
+        }
+        if let Some(channel) = &self.channel {
+            sms::notify(
+                self.properties.as_ref(),
+                self.sms.as_ref(),
+                channel.name(),
+                event,
+                resv,
+            );
+        }
+        self.feed.publish(resv.property_id, resv.id, event);
+    }
 }
 
 /// Password hasher.
@@ -0,0 +1,201 @@
+//! Live reservation changes for open dashboards.
+//!
+//! Every change published through [`crate::AppState::publish`] is broadcast
+//! in process and streamed as server-sent events at
+//! `/events/properties/:pid?token=<token>`. Messages only name the event and
+//! the reservation, views use them as a signal to refetch what they show.
+//!
+//! Browsers cannot send headers with an event stream, so a property admin
+//! trades their credentials for a [`feed_token`] through [`live_feed_token`]
+//! first. The token is signed with `RESVM_FEED_SECRET`, a fresh secret of the
+//! process when unset, and lets its holder follow that one property for
+//! [`FEED_TOKEN_HOURS`].
+use crate::api_keys::verify_admin;
+use crate::models::WebhookEvent;
+use crate::webhooks::{sign, verify};
+use crate::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::get,
+    Router,
+};
+use chrono::Utc;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+use uuid::Uuid;
+
+/// Hours a feed token lets a dashboard connect, about a long service.
+pub const FEED_TOKEN_HOURS: i64 = 12;
+
+/// Changes a slow subscriber may fall behind before it is told to resync.
+const CAPACITY: usize = 256;
+
+/// One change of a reservation, as sent to subscribers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReservationChange {
+    pub property_id: Uuid,
+    pub reservation_id: i32,
+    pub event: String,
+}
+
+/// In-process broadcast of reservation changes to every open dashboard.
+#[derive(Clone, Debug)]
+pub struct ReservationFeed {
+    sender: broadcast::Sender<ReservationChange>,
+}
+
+impl Default for ReservationFeed {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(CAPACITY).0,
+        }
+    }
+}
+
+impl ReservationFeed {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Broadcasts a change, dropped when nobody is listening.
+    pub fn publish(&self, pid: Uuid, reservation_id: i32, event: WebhookEvent) {
+        let _ = self.sender.send(ReservationChange {
+            property_id: pid,
+            reservation_id,
+            event: event.name().to_string(),
+        });
+    }
+
+    /// The changes of one property. A subscriber that lagged behind gets a
+    /// `resync` change instead of the ones it missed.
+    pub fn subscribe(&self, pid: Uuid) -> impl Stream<Item = ReservationChange> {
+        BroadcastStream::new(self.sender.subscribe()).filter_map(move |change| match change {
+            Ok(change) if change.property_id == pid => Some(change),
+            Ok(_) => None,
+            Err(_) => Some(ReservationChange {
+                property_id: pid,
+                reservation_id: 0,
+                event: "resync".to_string(),
+            }),
+        })
+    }
+}
+
+fn feed_secret() -> &'static str {
+    static SECRET: OnceLock<String> = OnceLock::new();
+    SECRET.get_or_init(|| {
+        std::env::var("RESVM_FEED_SECRET").unwrap_or_else(|_| Uuid::new_v4().to_string())
+    })
+}
+
+/// Token letting its holder follow the changes of `pid` until `expires_at`,
+/// in seconds since the epoch.
+pub fn feed_token(pid: Uuid, expires_at: i64) -> String {
+    format!(
+        "{expires_at}.{}",
+        sign(feed_secret(), expires_at, pid.as_bytes())
+    )
+}
+
+/// Whether `token` still lets its holder follow `pid` at `now`.
+pub fn check_feed_token(pid: Uuid, token: &str, now: i64) -> bool {
+    let Some((expires_at, signature)) = token.split_once('.') else {
+        return false;
+    };
+    let Ok(expires_at) = expires_at.parse::<i64>() else {
+        return false;
+    };
+    now < expires_at && verify(feed_secret(), expires_at, pid.as_bytes(), signature)
+}
+
+#[derive(Deserialize)]
+struct FeedQuery {
+    token: Option<String>,
+}
+
+async fn property_events(
+    State(feed): State<ReservationFeed>,
+    Path(pid): Path<Uuid>,
+    Query(query): Query<FeedQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let token = query.token.unwrap_or_default();
+    if !check_feed_token(pid, &token, Utc::now().timestamp()) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    let events = feed.subscribe(pid).map(|change| {
+        Ok(Event::default()
+            .json_data(change)
+            .expect("a reservation change is valid json"))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// The server-sent event streams, one per property.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/events/properties/:pid", get(property_events))
+}
+
+#[server(name = LiveFeedToken, prefix = "/api", endpoint = "live_feed_token", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn live_feed_token(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+) -> Result<String, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .map(|_| feed_token(pid, Utc::now().timestamp() + FEED_TOKEN_HOURS * 60 * 60))
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Counts the changes pushed for the property `pid` returns, meant as a
+/// resource source so a view refetches when another device changes a
+/// reservation. Listens only while `token` holds a [`feed_token`] and only
+/// counts in the browser, effects do not run on the server.
+pub fn use_reservation_changes(
+    pid: impl Fn() -> Uuid + 'static,
+    token: impl Fn() -> Option<String> + 'static,
+) -> ReadSignal<usize> {
+    use wasm_bindgen::{closure::Closure, JsCast};
+
+    let (changes, set_changes) = create_signal(0usize);
+    let source = store_value(None::<web_sys::EventSource>);
+    // The handler has to live as long as its stream, it is replaced along
+    // with it and dropped on cleanup.
+    let handler = store_value(None::<Closure<dyn FnMut(web_sys::MessageEvent)>>);
+    let close = move || {
+        if let Some(previous) = source.get_value() {
+            previous.set_onmessage(None);
+            previous.close();
+        }
+        source.set_value(None);
+        handler.set_value(None);
+    };
+
+    create_effect(move |_| {
+        let (pid, token) = (pid(), token());
+        close();
+        let Some(token) = token else {
+            return;
+        };
+        let url = format!("/events/properties/{pid}?token={token}");
+        let Ok(events) = web_sys::EventSource::new(&url) else {
+            return;
+        };
+        let on_message = Closure::<dyn FnMut(web_sys::MessageEvent)>::new(move |_| {
+            set_changes.update(|count| *count += 1)
+        });
+        events.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        source.set_value(Some(events));
+        handler.set_value(Some(on_message));
+    });
+    on_cleanup(close);
+
+    changes
+}
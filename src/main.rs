@@ -1,6 +1,6 @@
 use axum::{
     body::Body,
-    extract::{FromRef, Path, State},
+    extract::{Path, State},
     http::Request,
     response::{IntoResponse, Response},
     routing::get,
@@ -8,7 +8,11 @@ use axum::{
 };
 use leptos::*;
 use leptos_axum::{generate_route_list, LeptosRoutes};
-use resvm::{app::ResvmApp, get_connection_pool, AppState};
+use resvm::{
+    api, app::ResvmApp, emails, get_connection_pool, jobs, live, migrations, seed, sms, webhooks,
+    AppState,
+};
+use std::{env, net::SocketAddr, time::Duration};
 
 //Define a handler to test extractor with state
 async fn custom_handler(
@@ -34,14 +38,94 @@ async fn main() {
     let addr = leptos_options.site_addr;
     let routes = generate_route_list(ResvmApp);
 
-    let app_state = AppState {
-        leptos_options,
-        pool: get_connection_pool(),
+    let pool = get_connection_pool();
+    // `--migrate` (or RESVM_MIGRATE=true) applies the embedded migrations,
+    // otherwise the schema is only checked against this build.
+    let migrate = env::args().any(|arg| arg == "--migrate")
+        || env::var("RESVM_MIGRATE").is_ok_and(|v| v == "true" || v == "1");
+    let schema = if migrate {
+        migrations::run_migrations(&pool).map(|applied| {
+            for version in applied {
+                logging::log!("applied migration {}", version);
+            }
+        })
+    } else {
+        migrations::check_schema(&pool)
     };
+    if let Err(e) = schema {
+        eprintln!("resvm cannot start: {e}");
+        std::process::exit(1);
+    }
+
+    let mut app_state = AppState::new(leptos_options, pool);
+    match emails::transport_from_env() {
+        Ok(Some(transport)) => app_state = app_state.with_mail(transport),
+        Ok(None) => logging::log!("no RESVM_SMTP_URL or RESVM_MAIL_DIR, guest emails are off"),
+        Err(e) => {
+            eprintln!("resvm cannot set up email: {e}");
+            std::process::exit(1);
+        }
+    }
+
+    match sms::channel_from_env() {
+        Ok(Some(channel)) => app_state = app_state.with_channel(channel),
+        Ok(None) => logging::log!("no RESVM_SMS_URL or RESVM_SMS_DIR, guest texts are off"),
+        Err(e) => {
+            eprintln!("resvm cannot set up texts: {e}");
+            std::process::exit(1);
+        }
+    }
+
+    // RESVM_SEED_DEMO=true adds the demo property for local development.
+    if env::var("RESVM_SEED_DEMO").is_ok_and(|v| v == "true" || v == "1") {
+        match seed::seed_demo(
+            app_state.properties.as_ref(),
+            app_state.reservations.as_ref(),
+            app_state.guests.as_ref(),
+            &app_state.no_show_policy,
+        )
+        .await
+        {
+            Ok(pid) => logging::log!("demo property seeded: {}", pid),
+            Err(e) => {
+                eprintln!("resvm cannot seed the demo property: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // RESVM_WEBHOOK_INTERVAL is the number of seconds between delivery passes,
+    // of webhooks, emails and texts, and between two checks for due jobs.
+    let webhook_interval = env::var("RESVM_WEBHOOK_INTERVAL")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(5);
+    webhooks::spawn_worker(
+        app_state.webhooks.clone(),
+        Duration::from_secs(webhook_interval),
+    );
+    if let Some(transport) = app_state.mail.clone() {
+        emails::spawn_worker(
+            app_state.clone(),
+            transport,
+            Duration::from_secs(webhook_interval),
+        );
+    }
+    jobs::spawn_worker(app_state.clone(), Duration::from_secs(webhook_interval));
+    if let Some(channel) = app_state.channel.clone() {
+        sms::spawn_worker(
+            app_state.clone(),
+            channel,
+            Duration::from_secs(webhook_interval),
+        );
+    }
 
     // build our application with a route
     let app = Router::new()
         .route("/something", get(custom_handler))
+        .merge(api::router(&app_state))
+        .merge(live::router())
+        .merge(sms::router(&app_state))
         .leptos_routes_with_context(
             &app_state,
             routes,
@@ -57,7 +141,10 @@ async fn main() {
     // `axum::Server` is a re-export of `hyper::Server`
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
     logging::log!("listening on http://{}", &addr);
-    axum::serve(listener, app.into_make_service())
-        .await
-        .unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
@@ -0,0 +1,278 @@
+//! Guest self-service at `/manage/:token`.
+//!
+//! Every reservation carries an unguessable confirmation token, the link to
+//! this page is shown to the guest once the booking is made. Until
+//! [`CHANGE_CUTOFF_HOURS`] before the reservation, a `Booked` or `Requested`
+//! reservation can be moved to another free slot, resized or cancelled.
+//! Each change is written to the reservation history and announced to staff
+//! dashboards and webhooks like any other change.
+use crate::booking::{availability_except, day_holds, free_table, slots};
+use crate::crud_properties::{cancel_reservation, parse_reservation_time};
+use crate::errors::RepositoryError;
+use crate::models::{HistoryEntry, NewHistoryEntry, Reservation, ReservationStatus, WebhookEvent};
+use crate::repository::{PropertyRepository, RepoResult, ReservationRepository};
+use crate::AppState;
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use leptos::{expect_context, server, Params, ServerFnError};
+use leptos_router::Params;
+use serde::{Deserialize, Serialize};
+use time::Time;
+use uuid::Uuid;
+
+/// Hours before the reservation after which guests have to call instead.
+pub const CHANGE_CUTOFF_HOURS: i64 = 2;
+
+fn chrono_time(time: Time) -> NaiveTime {
+    NaiveTime::from_hms_opt(
+        time.hour() as u32,
+        time.minute() as u32,
+        time.second() as u32,
+    )
+    .expect("a time of day is valid")
+}
+
+pub(crate) fn hhmm(time: Time) -> String {
+    format!("{:02}:{:02}", time.hour(), time.minute())
+}
+
+fn within_policy(date: NaiveDate, time: Time, now: NaiveDateTime) -> bool {
+    date.and_time(chrono_time(time)) - now >= Duration::hours(CHANGE_CUTOFF_HOURS)
+}
+
+/// Whether the guest may still change or cancel the reservation at `now`.
+pub fn can_change(resv: &Reservation, now: NaiveDateTime) -> bool {
+    matches!(
+        resv.status,
+        ReservationStatus::Booked | ReservationStatus::Requested
+    ) && within_policy(resv.reservation_date, resv.reservation_time, now)
+}
+
+fn check_policy(resv: &Reservation, now: NaiveDateTime) -> RepoResult<()> {
+    if can_change(resv, now) {
+        Ok(())
+    } else {
+        Err(RepositoryError::Conflict(format!(
+            "this booking can no longer be changed online, please call the restaurant \
+             (changes close {CHANGE_CUTOFF_HOURS} hours before)"
+        )))
+    }
+}
+
+/// The slots a booking could be moved to on `date` for `party_size`.
+pub fn free_slots(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    resv: &Reservation,
+    date: NaiveDate,
+    party_size: i32,
+    now: NaiveDateTime,
+) -> RepoResult<Vec<Time>> {
+    Ok(availability_except(
+        properties,
+        reservations,
+        resv.property_id,
+        date,
+        party_size,
+        Some(resv.id),
+    )?
+    .into_iter()
+    .filter(|slot| within_policy(date, *slot, now))
+    .collect())
+}
+
+/// Moves the booking behind `token` to another slot or party size, on the
+/// smallest table that is free then, and records the change.
+pub fn modify(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    token: &str,
+    date: NaiveDate,
+    time: Time,
+    party_size: i32,
+    now: NaiveDateTime,
+) -> RepoResult<Reservation> {
+    let resv = reservations.find_by_token(token)?;
+    check_policy(&resv, now)?;
+    if resv.reservation_date == date
+        && resv.reservation_time == time
+        && resv.party_size == party_size
+    {
+        return Err(RepositoryError::Invalid("nothing to change".into()));
+    }
+    if !slots().contains(&time)
+        || !free_slots(properties, reservations, &resv, date, party_size, now)?.contains(&time)
+    {
+        return Err(RepositoryError::Conflict(
+            "this time is not available".into(),
+        ));
+    }
+    let tables = properties.tables(resv.property_id)?;
+    let mut booked = day_holds(reservations, &tables, resv.property_id, date)?;
+    booked.retain(|other| other.id != resv.id);
+    let table = free_table(&tables, &booked, time, party_size)
+        .ok_or_else(|| RepositoryError::Conflict("this time is not available".into()))?;
+
+    let moved = reservations.reschedule(resv.id, date, time, table.table_name, party_size)?;
+    reservations.set_table_assignments(resv.id, &[])?;
+    reservations.insert_history(NewHistoryEntry {
+        reservation_id: resv.id,
+        changed_by: HistoryEntry::GUEST.to_string(),
+        summary: format!(
+            "moved from {} {} for {} to {} {} for {}",
+            resv.reservation_date,
+            hhmm(resv.reservation_time),
+            resv.party_size,
+            moved.reservation_date,
+            hhmm(moved.reservation_time),
+            moved.party_size
+        ),
+    })?;
+    Ok(moved)
+}
+
+/// Cancels the booking behind `token` and records the change.
+pub fn cancel(
+    reservations: &dyn ReservationRepository,
+    token: &str,
+    now: NaiveDateTime,
+) -> RepoResult<Reservation> {
+    let resv = reservations.find_by_token(token)?;
+    check_policy(&resv, now)?;
+    let cancelled = cancel_reservation(reservations, resv.id)?;
+    reservations.insert_history(NewHistoryEntry {
+        reservation_id: resv.id,
+        changed_by: HistoryEntry::GUEST.to_string(),
+        summary: "cancelled".to_string(),
+    })?;
+    Ok(cancelled)
+}
+
+#[derive(PartialEq, Params, Debug)]
+pub struct TokenParam {
+    pub token: Option<String>,
+}
+
+/// What the manage-booking page shows to the guest.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ManagedBooking {
+    pub property_id: Uuid,
+    pub property_name: String,
+    pub code: String,
+    pub name: String,
+    pub reservation_date: NaiveDate,
+    pub reservation_time: String,
+    pub party_size: i32,
+    pub status: String,
+    pub can_change: bool,
+    pub history: Vec<HistoryEntry>,
+}
+
+/// The changes the guest made through the manage link. Staff, system and
+/// table plan entries stay on the dashboard.
+pub fn guest_history(
+    reservations: &dyn ReservationRepository,
+    id: i32,
+) -> RepoResult<Vec<HistoryEntry>> {
+    let mut history = reservations.history(id)?;
+    history.retain(|entry| entry.changed_by == HistoryEntry::GUEST);
+    Ok(history)
+}
+
+fn managed_booking(state: &AppState, resv: Reservation) -> RepoResult<ManagedBooking> {
+    Ok(ManagedBooking {
+        property_id: resv.property_id,
+        property_name: state.properties.find(resv.property_id)?.property_name,
+        code: resv.confirmation_code.clone().unwrap_or_default(),
+        can_change: can_change(&resv, Local::now().naive_local()),
+        history: guest_history(state.reservations.as_ref(), resv.id)?,
+        reservation_time: hhmm(resv.reservation_time),
+        status: resv.status.to_string(),
+        name: resv.name,
+        reservation_date: resv.reservation_date,
+        party_size: resv.party_size,
+    })
+}
+
+#[server(ManageBooking, "/api", "GetJson", "manage_booking")]
+pub async fn manage_booking(token: String) -> Result<ManagedBooking, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    state
+        .reservations
+        .find_by_token(&token)
+        .and_then(|resv| managed_booking(&state, resv))
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(ManageSlots, "/api", "GetJson", "manage_slots")]
+pub async fn manage_slots(
+    token: String,
+    date: NaiveDate,
+    party_size: i32,
+) -> Result<Vec<String>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    let slots = state.reservations.find_by_token(&token).and_then(|resv| {
+        free_slots(
+            state.properties.as_ref(),
+            state.reservations.as_ref(),
+            &resv,
+            date,
+            party_size,
+            Local::now().naive_local(),
+        )
+    });
+    match slots {
+        Ok(slots) => Ok(slots
+            .into_iter()
+            .map(|slot| format!("{:02}{:02}", slot.hour(), slot.minute()))
+            .collect()),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+#[server(name = ModifyBooking, prefix = "/api", endpoint = "modify_booking", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn modify_booking(
+    token: String,
+    reservation_date: NaiveDate,
+    reservation_time: String,
+    party_size: i32,
+) -> Result<ManagedBooking, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    let moved = parse_reservation_time(&reservation_time).and_then(|time| {
+        modify(
+            state.properties.as_ref(),
+            state.reservations.as_ref(),
+            &token,
+            reservation_date,
+            time,
+            party_size,
+            Local::now().naive_local(),
+        )
+    });
+    match moved {
+        Ok(resv) => {
+            state.publish(WebhookEvent::ReservationUpdated, &resv);
+            managed_booking(&state, resv).map_err(|e| ServerFnError::ServerError(e.to_string()))
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+#[server(name = CancelBooking, prefix = "/api", endpoint = "cancel_booking", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn cancel_booking(token: String) -> Result<ManagedBooking, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    match cancel(
+        state.reservations.as_ref(),
+        &token,
+        Local::now().naive_local(),
+    ) {
+        Ok(resv) => {
+            state.publish(WebhookEvent::ReservationCancelled, &resv);
+            managed_booking(&state, resv).map_err(|e| ServerFnError::ServerError(e.to_string()))
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
@@ -0,0 +1,100 @@
+//! Migrations embedded in the binary and the startup schema version check.
+use crate::errors::SchemaError;
+use crate::Database;
+use diesel::backend::Backend;
+use diesel::migration::MigrationSource;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+#[cfg(feature = "sqlite")]
+pub const SQLITE_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_sqlite");
+
+/// Versions known to this build but missing in the database, and versions
+/// applied to the database that this build does not know about.
+fn compare<DB, C>(
+    conn: &mut C,
+    source: &EmbeddedMigrations,
+) -> Result<(Vec<String>, Vec<String>), SchemaError>
+where
+    DB: Backend,
+    C: MigrationHarness<DB>,
+{
+    let embedded: Vec<String> = MigrationSource::<DB>::migrations(source)
+        .map_err(|e| SchemaError::Migration(e.to_string()))?
+        .iter()
+        .map(|m| m.name().version().to_string())
+        .collect();
+    let applied: Vec<String> = conn
+        .applied_migrations()
+        .map_err(|e| SchemaError::Migration(e.to_string()))?
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+
+    let pending = embedded
+        .iter()
+        .filter(|v| !applied.contains(v))
+        .cloned()
+        .collect();
+    let unknown = applied
+        .iter()
+        .filter(|v| !embedded.contains(v))
+        .cloned()
+        .collect();
+    Ok((pending, unknown))
+}
+
+fn check<DB, C>(conn: &mut C, source: &EmbeddedMigrations) -> Result<(), SchemaError>
+where
+    DB: Backend,
+    C: MigrationHarness<DB>,
+{
+    let (pending, unknown) = compare(conn, source)?;
+    if !unknown.is_empty() {
+        return Err(SchemaError::Newer(unknown));
+    }
+    if !pending.is_empty() {
+        return Err(SchemaError::Outdated(pending));
+    }
+    Ok(())
+}
+
+fn run<DB, C>(conn: &mut C, source: EmbeddedMigrations) -> Result<Vec<String>, SchemaError>
+where
+    DB: Backend,
+    C: MigrationHarness<DB>,
+{
+    // Never touch a database that a newer build already migrated.
+    let (_, unknown) = compare(conn, &source)?;
+    if !unknown.is_empty() {
+        return Err(SchemaError::Newer(unknown));
+    }
+    Ok(conn
+        .run_pending_migrations(source)
+        .map_err(|e| SchemaError::Migration(e.to_string()))?
+        .iter()
+        .map(|v| v.to_string())
+        .collect())
+}
+
+/// Fails unless the database has exactly the migrations embedded in this build.
+pub fn check_schema(db: &Database) -> Result<(), SchemaError> {
+    match db {
+        Database::Postgres(pool) => check(&mut pool.get().map_err(pool_error)?, &MIGRATIONS),
+        #[cfg(feature = "sqlite")]
+        Database::Sqlite(pool) => check(&mut pool.get().map_err(pool_error)?, &SQLITE_MIGRATIONS),
+    }
+}
+
+/// Applies the pending embedded migrations and returns their versions.
+pub fn run_migrations(db: &Database) -> Result<Vec<String>, SchemaError> {
+    match db {
+        Database::Postgres(pool) => run(&mut pool.get().map_err(pool_error)?, MIGRATIONS),
+        #[cfg(feature = "sqlite")]
+        Database::Sqlite(pool) => run(&mut pool.get().map_err(pool_error)?, SQLITE_MIGRATIONS),
+    }
+}
+
+fn pool_error(e: diesel::r2d2::PoolError) -> SchemaError {
+    SchemaError::Migration(e.to_string())
+}
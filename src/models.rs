@@ -1,28 +1,24 @@
 use crate::schema::property;
 use crate::SharedPooledConnection;
-use axum::{
-    extract::{self, Json, Path, Query, State},
-    response::{Html, IntoResponse},
-    routing::get,
-};
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
 use diesel::*;
 use diesel::{
     deserialize::{self, FromSql, FromSqlRow},
     expression::AsExpression,
     serialize::{self, Output, ToSql},
-    sql_types::{self, Date, Integer, Jsonb, Text},
+    sql_types::{Date, Integer, Jsonb, Text},
     Associations, Identifiable, Insertable, Queryable, Selectable,
 };
 use leptos::{IntoView, View};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use time::Time;
+use utoipa::ToSchema;
 use uuid::Uuid;
 type DB = diesel::pg::Pg;
 
 #[repr(i32)]
-#[derive(Debug, Serialize, Deserialize, Clone, AsExpression)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, AsExpression, ToSchema)]
 #[diesel(sql_type = Integer)]
 pub enum PaymentMode {
     NotPaid = 0,
@@ -62,9 +58,205 @@ where
     }
 }
 
+/// Lifecycle of a reservation, stored as its discriminant.
+#[repr(i32)]
+#[derive(
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    AsExpression,
+    FromSqlRow,
+    ToSchema,
+)]
+#[diesel(sql_type = Integer)]
+pub enum ReservationStatus {
+    #[default]
+    Booked = 0,
+    Requested = 1,
+    Seated = 2,
+    Completed = 3,
+    Cancelled = 4,
+    NoShow = 5,
+}
+
+impl<DB> ToSql<Integer, DB> for ReservationStatus
+where
+    DB: diesel::backend::Backend,
+    i32: ToSql<Integer, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        match self {
+            ReservationStatus::Booked => 0.to_sql(out),
+            ReservationStatus::Requested => 1.to_sql(out),
+            ReservationStatus::Seated => 2.to_sql(out),
+            ReservationStatus::Completed => 3.to_sql(out),
+            ReservationStatus::Cancelled => 4.to_sql(out),
+            ReservationStatus::NoShow => 5.to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<Integer, DB> for ReservationStatus
+where
+    DB: diesel::backend::Backend,
+    i32: FromSql<Integer, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            0 => Ok(ReservationStatus::Booked),
+            1 => Ok(ReservationStatus::Requested),
+            2 => Ok(ReservationStatus::Seated),
+            3 => Ok(ReservationStatus::Completed),
+            4 => Ok(ReservationStatus::Cancelled),
+            5 => Ok(ReservationStatus::NoShow),
+            x => Err(format!("Unrecognized variant {}", x).into()),
+        }
+    }
+}
+
+impl ReservationStatus {
+    /// Cancelled and no-show reservations no longer hold their seating.
+    pub fn is_active(&self) -> bool {
+        !matches!(
+            self,
+            ReservationStatus::Cancelled | ReservationStatus::NoShow
+        )
+    }
+}
+
+impl Display for ReservationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// What a booking celebrates, so the floor can prepare for it.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, AsExpression, FromSqlRow, ToSchema,
+)]
+#[diesel(sql_type = Integer)]
+pub enum Occasion {
+    Birthday = 0,
+    Anniversary = 1,
+    Business = 2,
+    Date = 3,
+    Celebration = 4,
+}
+
+impl<DB> ToSql<Integer, DB> for Occasion
+where
+    DB: diesel::backend::Backend,
+    i32: ToSql<Integer, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        match self {
+            Occasion::Birthday => 0.to_sql(out),
+            Occasion::Anniversary => 1.to_sql(out),
+            Occasion::Business => 2.to_sql(out),
+            Occasion::Date => 3.to_sql(out),
+            Occasion::Celebration => 4.to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<Integer, DB> for Occasion
+where
+    DB: diesel::backend::Backend,
+    i32: FromSql<Integer, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            0 => Ok(Occasion::Birthday),
+            1 => Ok(Occasion::Anniversary),
+            2 => Ok(Occasion::Business),
+            3 => Ok(Occasion::Date),
+            4 => Ok(Occasion::Celebration),
+            x => Err(format!("Unrecognized variant {}", x).into()),
+        }
+    }
+}
+
+impl Display for Occasion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Occasion {
+    pub const ALL: [Occasion; 5] = [
+        Occasion::Birthday,
+        Occasion::Anniversary,
+        Occasion::Business,
+        Occasion::Date,
+        Occasion::Celebration,
+    ];
+}
+
+/// What a guest asked for beyond a table, all optional.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Queryable,
+    Selectable,
+    Insertable,
+    AsChangeset,
+    Serialize,
+    Deserialize,
+    ToSchema,
+)]
+#[diesel(table_name = crate::schema::reservation)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+#[serde(default)]
+pub struct SpecialRequests {
+    pub occasion: Option<Occasion>,
+    /// Allergies and diets, such as `vegan, no nuts`.
+    pub dietary: String,
+    pub high_chairs: i32,
+    /// Step-free access, a wheelchair space and the like.
+    pub accessibility: String,
+    /// The seating asked for when `specific_seating_requested` is set, such
+    /// as `by the window`.
+    pub seating_request: String,
+}
+
+impl SpecialRequests {
+    /// Whether the guest asked for anything at all.
+    pub fn is_empty(&self) -> bool {
+        *self == SpecialRequests::default()
+    }
+
+    /// One line for the day sheet, such as `Birthday; 1 high chair; vegan`.
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if let Some(occasion) = self.occasion {
+            parts.push(occasion.to_string());
+        }
+        match self.high_chairs {
+            0 => {}
+            1 => parts.push("1 high chair".into()),
+            n => parts.push(format!("{n} high chairs")),
+        }
+        for text in [&self.dietary, &self.accessibility, &self.seating_request] {
+            if !text.is_empty() {
+                parts.push(text.clone());
+            }
+        }
+        parts.join("; ")
+    }
+}
+
 //NOTE: AsExpression is converting this struct to sql type Jsonb, which is the data type in our db.
 // String type is stored as Text, NaiveDate type is stored as Date.
-#[derive(AsExpression, Queryable, Debug, Clone, Serialize, Deserialize)]
+#[derive(AsExpression, Queryable, Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[diesel(sql_type = Jsonb)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct PaymentMethod {
@@ -90,6 +282,10 @@ impl FromSqlRow<Jsonb, diesel::pg::Pg> for PaymentMethod {
 }
 
 impl PaymentMethod {
+    pub fn mode_of_payment(&self) -> &PaymentMode {
+        &self.mode_of_payment
+    }
+
     pub fn new(
         mode_of_payment: PaymentMode,
         payment_transaction_id: Option<String>,
@@ -105,23 +301,117 @@ impl PaymentMethod {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, Insertable, Debug)]
+/// Serializes reservation times as `HH:MM`, also accepting `HH:MM:SS`.
+pub mod hhmm {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use time::Time;
+
+    pub fn serialize<S: Serializer>(t: &Time, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:02}:{:02}", t.hour(), t.minute()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Time, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        let mut parts = value.split(':').map(|part| part.parse::<u8>());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(hour)), Some(Ok(minute)), second, None) => {
+                let second = match second {
+                    Some(Ok(second)) => second,
+                    Some(Err(_)) => return Err(D::Error::custom("invalid seconds")),
+                    None => 0,
+                };
+                Time::from_hms(hour, minute, second).map_err(D::Error::custom)
+            }
+            _ => Err(D::Error::custom(format!("{value:?} is not HH:MM"))),
+        }
+    }
+
+    /// The same for optional times, `null` when absent.
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+        use time::Time;
+
+        pub fn serialize<S: Serializer>(
+            t: &Option<Time>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match t {
+                Some(t) => super::serialize(t, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Time>, D::Error> {
+            #[derive(Deserialize)]
+            struct Hhmm(#[serde(with = "super")] Time);
+
+            Ok(Option::<Hhmm>::deserialize(deserializer)?.map(|Hhmm(t)| t))
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Insertable, Debug, ToSchema)]
 #[diesel(table_name = crate::schema::reservation)]
 pub struct NewResv {
-    name: String,
-    contact: String,
-    seating: String,
-    specific_seating_requested: bool,
-    advance: bool,
-    advance_method: serde_json::Value,
-    advance_amount: Option<i32>,
-    confirmed: bool,
-    reservation_date: NaiveDate,
-    reservation_time: Time,
-    property_id: Uuid,
+    pub name: String,
+    pub contact: String,
+    pub seating: String,
+    pub specific_seating_requested: bool,
+    pub advance: bool,
+    #[schema(value_type = PaymentMethod)]
+    pub advance_method: serde_json::Value,
+    pub advance_amount: Option<i32>,
+    pub confirmed: bool,
+    pub reservation_date: NaiveDate,
+    #[serde(with = "hhmm")]
+    #[schema(value_type = String, example = "19:30")]
+    pub reservation_time: Time,
+    pub property_id: Uuid,
+    #[serde(default)]
+    pub status: ReservationStatus,
+    /// Number of guests, 2 when left out.
+    #[serde(default = "default_party_size")]
+    #[schema(default = 2, minimum = 1)]
+    pub party_size: i32,
+    /// Secret of the guest's manage-booking link, always generated here.
+    #[serde(skip, default = "confirmation_token")]
+    pub confirmation_token: Option<String>,
+    /// Short code the guest can quote on the phone, always generated here.
+    #[serde(skip, default = "confirmation_code")]
+    pub confirmation_code: Option<String>,
+    /// The guest profile the contact matched, set when the booking is stored.
+    #[serde(skip)]
+    pub guest_id: Option<i32>,
+    #[serde(default)]
+    #[diesel(embed)]
+    pub special_requests: SpecialRequests,
+    /// Comma separated labels such as `VIP` or `press`.
+    #[serde(default)]
+    pub tags: String,
+    /// The recurring series the booking is an occurrence of.
+    #[serde(skip)]
+    pub series_id: Option<i32>,
+}
+
+fn default_party_size() -> i32 {
+    2
+}
+
+/// A fresh, unguessable token for a manage-booking link.
+pub fn confirmation_token() -> Option<String> {
+    Some(Uuid::new_v4().simple().to_string())
+}
+
+/// A fresh code for the guest to quote, unrelated to the token so that it
+/// gives nothing of the manage-booking link away.
+pub fn confirmation_code() -> Option<String> {
+    Some(Uuid::new_v4().simple().to_string()[..8].to_uppercase())
 }
 
 impl NewResv {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         name: String,
         contact: String,
@@ -147,11 +437,66 @@ impl NewResv {
             reservation_date,
             reservation_time,
             property_id,
+            status: ReservationStatus::Booked,
+            party_size: default_party_size(),
+            confirmation_token: confirmation_token(),
+            confirmation_code: confirmation_code(),
+            guest_id: None,
+            special_requests: SpecialRequests::default(),
+            tags: String::new(),
+            series_id: None,
+        }
+    }
+
+    pub fn with_status(mut self, status: ReservationStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_party_size(mut self, party_size: i32) -> Self {
+        self.party_size = party_size;
+        self
+    }
+
+    pub fn with_special_requests(mut self, special_requests: SpecialRequests) -> Self {
+        self.special_requests = special_requests;
+        self
+    }
+
+    pub fn in_series(mut self, series_id: i32) -> Self {
+        self.series_id = Some(series_id);
+        self
+    }
+
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_reservation(self, id: i32) -> Reservation {
+        Reservation {
+            id,
+            name: self.name,
+            contact: self.contact,
+            seating: self.seating,
+            specific_seating_requested: self.specific_seating_requested,
+            advance: self.advance,
+            advance_method: self.advance_method,
+            advance_amount: self.advance_amount,
+            confirmed: self.confirmed,
+            reservation_date: self.reservation_date,
+            reservation_time: self.reservation_time,
+            property_id: self.property_id,
+            status: self.status,
+            party_size: self.party_size,
+            confirmation_token: self.confirmation_token,
+            confirmation_code: self.confirmation_code,
+            guest_id: self.guest_id,
+            special_requests: self.special_requests,
+            tags: self.tags,
+            no_show_at: None,
+            series_id: self.series_id,
         }
     }
 }
 
-#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize, ToSchema)]
 #[diesel(table_name = crate::schema::reservation)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Reservation {
@@ -161,12 +506,61 @@ pub struct Reservation {
     pub seating: String,
     pub specific_seating_requested: bool,
     pub advance: bool,
+    #[schema(value_type = PaymentMethod)]
     pub advance_method: serde_json::Value,
     pub advance_amount: Option<i32>,
     pub confirmed: bool,
     pub reservation_date: NaiveDate,
+    #[serde(with = "hhmm")]
+    #[schema(value_type = String, example = "19:30")]
     pub reservation_time: Time,
     pub property_id: Uuid,
+    pub status: ReservationStatus,
+    pub party_size: i32,
+    /// Secret of the guest's manage-booking link at `/manage/<token>`, only
+    /// ever shown to the guest and never serialized.
+    #[serde(skip_serializing, default)]
+    #[schema(ignore)]
+    pub confirmation_token: Option<String>,
+    /// Short code a guest can quote on the phone.
+    pub confirmation_code: Option<String>,
+    /// The guest profile the booking belongs to.
+    pub guest_id: Option<i32>,
+    #[diesel(embed)]
+    pub special_requests: SpecialRequests,
+    /// Comma separated labels such as `VIP` or `press`.
+    pub tags: String,
+    /// When the reservation was marked as a no-show, in UTC.
+    pub no_show_at: Option<NaiveDateTime>,
+    /// The recurring series the reservation is an occurrence of.
+    pub series_id: Option<i32>,
+}
+
+impl Reservation {
+    /// The labels of [`Reservation::tags`], without blanks.
+    pub fn tag_list(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+    }
+
+    /// Whether `tag` is one of the reservation's tags, ignoring case.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tag_list().any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+
+    /// When the guest is expected, in the property's local time.
+    pub fn starts_at(&self) -> NaiveDateTime {
+        self.reservation_date.and_time(
+            chrono::NaiveTime::from_hms_opt(
+                self.reservation_time.hour() as u32,
+                self.reservation_time.minute() as u32,
+                0,
+            )
+            .expect("a time of day is valid"),
+        )
+    }
 }
 
 impl IntoView for Reservation {
@@ -179,7 +573,7 @@ impl Display for Reservation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "({}, {}, {}, {}, {}, {},{}, {:?}, {:?}, {}, {}, {} )",
+            "({}, {}, {}, {}, {}, {},{}, {:?}, {:?}, {}, {}, {}, {} )",
             self.id,
             self.name,
             self.contact,
@@ -192,11 +586,102 @@ impl Display for Reservation {
             self.reservation_date,
             self.reservation_time,
             self.property_id,
+            self.status,
         )
     }
 }
 
-#[derive(Clone, Identifiable, Selectable, Debug, Serialize, Deserialize)]
+/// One change made to a reservation, kept so staff can see who changed what.
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::reservation_history)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct HistoryEntry {
+    pub entry_id: i32,
+    pub reservation_id: i32,
+    pub changed_at: NaiveDateTime,
+    /// `guest` for changes made through the manage-booking link, else `staff`.
+    pub changed_by: String,
+    pub summary: String,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = crate::schema::reservation_history)]
+pub struct NewHistoryEntry {
+    pub reservation_id: i32,
+    pub changed_by: String,
+    pub summary: String,
+}
+
+impl HistoryEntry {
+    /// `changed_by` of changes made through the manage-booking link.
+    pub const GUEST: &'static str = "guest";
+    /// `changed_by` of changes made from the dashboard.
+    pub const STAFF: &'static str = "staff";
+    /// `changed_by` of changes made by background jobs.
+    pub const SYSTEM: &'static str = "system";
+}
+
+impl NewHistoryEntry {
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_entry(self, entry_id: i32, changed_at: NaiveDateTime) -> HistoryEntry {
+        HistoryEntry {
+            entry_id,
+            reservation_id: self.reservation_id,
+            changed_at,
+            changed_by: self.changed_by,
+            summary: self.summary,
+        }
+    }
+}
+
+/// An internal note staff left on a reservation, never shown to the guest.
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::reservation_notes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ReservationNote {
+    pub note_id: i32,
+    pub reservation_id: i32,
+    pub author: String,
+    pub body: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = crate::schema::reservation_notes)]
+pub struct NewReservationNote {
+    pub reservation_id: i32,
+    pub author: String,
+    pub body: String,
+}
+
+/// A reservation as the floor staff see it on the day sheet.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DaySheetEntry {
+    pub reservation: Reservation,
+    pub notes: Vec<ReservationNote>,
+    /// The reservation's tags followed by those of its guest, highlighted on
+    /// the sheet.
+    pub tags: Vec<String>,
+    /// Bookings of the guest nobody turned up for.
+    pub no_shows: usize,
+    /// The menu and minimum spend agreed for an event.
+    pub event: Option<EventDetails>,
+}
+
+impl NewReservationNote {
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_note(self, note_id: i32, created_at: NaiveDateTime) -> ReservationNote {
+        ReservationNote {
+            note_id,
+            reservation_id: self.reservation_id,
+            created_at,
+            author: self.author,
+            body: self.body,
+        }
+    }
+}
+
+#[derive(Clone, Identifiable, Selectable, Debug, Serialize, Deserialize, ToSchema)]
 #[diesel(table_name = crate::schema::property)]
 #[diesel(primary_key(property_id))]
 pub struct Property {
@@ -270,6 +755,141 @@ impl NewProperty {
             property_phone,
         }
     }
+
+    /// Builds the stored row for storages without a database.
+    pub(crate) fn into_property(self) -> Property {
+        Property {
+            property_id: self.property_id,
+            property_name: self.property_name,
+            property_password: self.property_password,
+            property_email: self.property_email,
+            property_phone: self.property_phone,
+        }
+    }
+}
+
+/// A predefined table of a property, reservations can only be seated on these.
+#[derive(Clone, Debug, Queryable, Selectable, PartialEq, Serialize, Deserialize, ToSchema)]
+#[diesel(table_name = crate::schema::property_tables)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PropertyTable {
+    pub table_id: i32,
+    pub property_id: Uuid,
+    pub table_name: String,
+    pub capacity: i32,
+    pub section: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::property_tables)]
+pub struct NewPropertyTable {
+    pub property_id: Uuid,
+    pub table_name: String,
+    pub capacity: i32,
+    pub section: Option<String>,
+}
+
+impl NewPropertyTable {
+    pub fn new(
+        property_id: Uuid,
+        table_name: String,
+        capacity: i32,
+        section: Option<String>,
+    ) -> Self {
+        Self {
+            property_id,
+            table_name,
+            capacity,
+            section,
+        }
+    }
+
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_table(self, table_id: i32) -> PropertyTable {
+        PropertyTable {
+            table_id,
+            property_id: self.property_id,
+            table_name: self.table_name,
+            capacity: self.capacity,
+            section: self.section,
+        }
+    }
+}
+
+/// How a table is drawn on the floor plan.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Integer)]
+pub enum TableShape {
+    #[default]
+    Rectangle = 0,
+    /// An ellipse filling the table's width and height.
+    Round = 1,
+}
+
+impl<DB> ToSql<Integer, DB> for TableShape
+where
+    DB: diesel::backend::Backend,
+    i32: ToSql<Integer, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        match self {
+            TableShape::Rectangle => 0.to_sql(out),
+            TableShape::Round => 1.to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<Integer, DB> for TableShape
+where
+    DB: diesel::backend::Backend,
+    i32: FromSql<Integer, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            0 => Ok(TableShape::Rectangle),
+            1 => Ok(TableShape::Round),
+            x => Err(format!("Unrecognized variant {}", x).into()),
+        }
+    }
+}
+
+impl Display for TableShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl TableShape {
+    pub const ALL: [TableShape; 2] = [TableShape::Rectangle, TableShape::Round];
+}
+
+/// Where a table sits on the floor plan, in plan units from the top left
+/// corner to the table's.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Queryable, Selectable, Insertable, Serialize, Deserialize,
+)]
+#[diesel(table_name = crate::schema::table_layouts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TableLayout {
+    pub table_id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    /// Clockwise around the table's centre, in degrees.
+    pub rotation: i32,
+    pub shape: TableShape,
+}
+
+/// A table as drawn on the floor, with the reservation holding it at the
+/// time looked at.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FloorTable {
+    pub table: PropertyTable,
+    pub layout: TableLayout,
+    pub reservation: Option<Reservation>,
 }
 
 #[derive(
@@ -279,12 +899,12 @@ impl NewProperty {
 #[diesel(table_name = crate::schema::propertyusers)]
 #[diesel(belongs_to(Property, foreign_key = property_id))]
 pub struct PropertyUsers {
-    user_id: i32,
-    user_name: String,
+    pub user_id: i32,
+    pub user_name: String,
     #[serde(skip_serializing)]
-    user_password: String,
-    user_role: i32,
-    property_id: Uuid,
+    pub user_password: String,
+    pub user_role: i32,
+    pub property_id: Uuid,
 }
 
 impl PropertyUsers {
@@ -309,11 +929,11 @@ impl PropertyUsers {
 #[derive(Serialize, Deserialize, Insertable)]
 #[diesel(table_name = crate::schema::propertyusers)]
 pub struct NewPropertyUser {
-    user_name: String,
+    pub user_name: String,
     #[serde(skip_serializing)]
-    user_password: String,
-    user_role: i32,
-    property_id: Uuid,
+    pub user_password: String,
+    pub user_role: i32,
+    pub property_id: Uuid,
 }
 
 impl NewPropertyUser {
@@ -356,7 +976,7 @@ impl Roles {
 
     /// Pass users id it will return role name for that id.
     pub fn get_role(user_role_id: i32, conn: SharedPooledConnection) -> anyhow::Result<i32> {
-        use crate::schema::roles::dsl::{role_id, role_name, roles};
+        use crate::schema::roles::dsl::{role_id, roles};
         Ok(roles
             .filter(role_id.eq(user_role_id))
             .select(role_id)
@@ -364,3 +984,1258 @@ impl Roles {
             .unwrap())
     }
 }
+
+/// What a property API key may do, stored as a bitmask in `api_keys.scopes`.
+#[repr(i32)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum ApiScope {
+    ReadAvailability = 1,
+    CreateReservations = 2,
+    ReadReservations = 4,
+}
+
+impl ApiScope {
+    pub const ALL: [ApiScope; 3] = [
+        ApiScope::ReadAvailability,
+        ApiScope::CreateReservations,
+        ApiScope::ReadReservations,
+    ];
+
+    /// Bitmask of `scopes` as stored in the database.
+    pub fn mask(scopes: &[ApiScope]) -> i32 {
+        scopes.iter().fold(0, |mask, scope| mask | *scope as i32)
+    }
+}
+
+/// An API key of a property. Only the argon2 hash of the secret part is
+/// stored, the prefix identifies the key.
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::api_keys)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ApiKey {
+    pub key_id: i32,
+    pub property_id: Uuid,
+    pub key_name: String,
+    pub key_prefix: String,
+    #[serde(skip_serializing, default)]
+    pub key_hash: String,
+    pub scopes: i32,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+impl ApiKey {
+    pub fn has_scope(&self, scope: ApiScope) -> bool {
+        self.scopes & scope as i32 != 0
+    }
+
+    pub fn scope_list(&self) -> Vec<ApiScope> {
+        ApiScope::ALL
+            .into_iter()
+            .filter(|scope| self.has_scope(*scope))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = crate::schema::api_keys)]
+pub struct NewApiKey {
+    pub property_id: Uuid,
+    pub key_name: String,
+    pub key_prefix: String,
+    pub key_hash: String,
+    pub scopes: i32,
+}
+
+impl NewApiKey {
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_api_key(self, key_id: i32, created_at: NaiveDateTime) -> ApiKey {
+        ApiKey {
+            key_id,
+            property_id: self.property_id,
+            key_name: self.key_name,
+            key_prefix: self.key_prefix,
+            key_hash: self.key_hash,
+            scopes: self.scopes,
+            created_at,
+            last_used_at: None,
+            revoked_at: None,
+        }
+    }
+}
+
+/// Reservation changes a webhook can subscribe to, stored as a bitmask in
+/// `webhooks.events`.
+#[repr(i32)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum WebhookEvent {
+    ReservationCreated = 1,
+    ReservationUpdated = 2,
+    ReservationCancelled = 4,
+    ReservationSeated = 8,
+    PaymentRecorded = 16,
+    /// Staff changed the tables, event details or special requests of a
+    /// booking. Guests are not told.
+    ReservationDetailsChanged = 32,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 6] = [
+        WebhookEvent::ReservationCreated,
+        WebhookEvent::ReservationUpdated,
+        WebhookEvent::ReservationCancelled,
+        WebhookEvent::ReservationSeated,
+        WebhookEvent::PaymentRecorded,
+        WebhookEvent::ReservationDetailsChanged,
+    ];
+
+    /// Bitmask of `events` as stored in the database.
+    pub fn mask(events: &[WebhookEvent]) -> i32 {
+        events.iter().fold(0, |mask, event| mask | *event as i32)
+    }
+
+    /// The name sent in payloads and the `X-Resvm-Event` header.
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::ReservationCreated => "reservation.created",
+            WebhookEvent::ReservationUpdated => "reservation.updated",
+            WebhookEvent::ReservationCancelled => "reservation.cancelled",
+            WebhookEvent::ReservationSeated => "reservation.seated",
+            WebhookEvent::PaymentRecorded => "payment.recorded",
+            WebhookEvent::ReservationDetailsChanged => "reservation.details_changed",
+        }
+    }
+}
+
+/// A URL of a property that receives signed reservation events.
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::webhooks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Webhook {
+    pub webhook_id: i32,
+    pub property_id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub events: i32,
+    pub active: bool,
+    pub created_at: NaiveDateTime,
+}
+
+impl Webhook {
+    pub fn subscribes(&self, event: WebhookEvent) -> bool {
+        self.events & event as i32 != 0
+    }
+
+    pub fn event_list(&self) -> Vec<WebhookEvent> {
+        WebhookEvent::ALL
+            .into_iter()
+            .filter(|event| self.subscribes(*event))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = crate::schema::webhooks)]
+pub struct NewWebhook {
+    pub property_id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub events: i32,
+}
+
+impl NewWebhook {
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_webhook(self, webhook_id: i32, created_at: NaiveDateTime) -> Webhook {
+        Webhook {
+            webhook_id,
+            property_id: self.property_id,
+            url: self.url,
+            secret: self.secret,
+            events: self.events,
+            active: true,
+            created_at,
+        }
+    }
+}
+
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Integer)]
+pub enum DeliveryStatus {
+    #[default]
+    Pending = 0,
+    Delivered = 1,
+    Failed = 2,
+}
+
+impl<DB> ToSql<Integer, DB> for DeliveryStatus
+where
+    DB: diesel::backend::Backend,
+    i32: ToSql<Integer, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        match self {
+            DeliveryStatus::Pending => 0.to_sql(out),
+            DeliveryStatus::Delivered => 1.to_sql(out),
+            DeliveryStatus::Failed => 2.to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<Integer, DB> for DeliveryStatus
+where
+    DB: diesel::backend::Backend,
+    i32: FromSql<Integer, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            0 => Ok(DeliveryStatus::Pending),
+            1 => Ok(DeliveryStatus::Delivered),
+            2 => Ok(DeliveryStatus::Failed),
+            x => Err(format!("Unrecognized variant {}", x).into()),
+        }
+    }
+}
+
+impl Display for DeliveryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// One event sent, or still to be sent, to a webhook.
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::webhook_deliveries)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookDelivery {
+    pub delivery_id: i32,
+    pub webhook_id: i32,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: NaiveDateTime,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub delivered_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = crate::schema::webhook_deliveries)]
+pub struct NewWebhookDelivery {
+    pub webhook_id: i32,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub next_attempt_at: NaiveDateTime,
+}
+
+impl NewWebhookDelivery {
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_delivery(
+        self,
+        delivery_id: i32,
+        created_at: NaiveDateTime,
+    ) -> WebhookDelivery {
+        WebhookDelivery {
+            delivery_id,
+            webhook_id: self.webhook_id,
+            event: self.event,
+            payload: self.payload,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: self.next_attempt_at,
+            response_status: None,
+            last_error: None,
+            created_at,
+            delivered_at: None,
+        }
+    }
+}
+
+/// Which reservation event an email or text tells the guest about.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Integer)]
+pub enum NotificationKind {
+    Confirmation = 1,
+    Modification = 2,
+    Cancellation = 3,
+    Reminder = 4,
+    /// Tells a guest on the waitlist that a table became free.
+    TableReady = 5,
+}
+
+impl NotificationKind {
+    /// The message a reservation event calls for, if any. Changes only tell
+    /// the guest while the booking is still ahead, walk-ins seated straight
+    /// away are not confirmed.
+    pub fn for_event(event: WebhookEvent, status: ReservationStatus) -> Option<Self> {
+        match event {
+            WebhookEvent::ReservationCreated if status != ReservationStatus::Seated => {
+                Some(NotificationKind::Confirmation)
+            }
+            WebhookEvent::ReservationUpdated
+                if matches!(
+                    status,
+                    ReservationStatus::Booked | ReservationStatus::Requested
+                ) =>
+            {
+                Some(NotificationKind::Modification)
+            }
+            WebhookEvent::ReservationCancelled => Some(NotificationKind::Cancellation),
+            _ => None,
+        }
+    }
+
+    pub const ALL: [NotificationKind; 5] = [
+        NotificationKind::Confirmation,
+        NotificationKind::Modification,
+        NotificationKind::Cancellation,
+        NotificationKind::Reminder,
+        NotificationKind::TableReady,
+    ];
+}
+
+impl<DB> ToSql<Integer, DB> for NotificationKind
+where
+    DB: diesel::backend::Backend,
+    i32: ToSql<Integer, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        match self {
+            NotificationKind::Confirmation => 1.to_sql(out),
+            NotificationKind::Modification => 2.to_sql(out),
+            NotificationKind::Cancellation => 3.to_sql(out),
+            NotificationKind::Reminder => 4.to_sql(out),
+            NotificationKind::TableReady => 5.to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<Integer, DB> for NotificationKind
+where
+    DB: diesel::backend::Backend,
+    i32: FromSql<Integer, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            1 => Ok(NotificationKind::Confirmation),
+            2 => Ok(NotificationKind::Modification),
+            3 => Ok(NotificationKind::Cancellation),
+            4 => Ok(NotificationKind::Reminder),
+            5 => Ok(NotificationKind::TableReady),
+            x => Err(format!("Unrecognized variant {}", x).into()),
+        }
+    }
+}
+
+impl Display for NotificationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A property's own wording for one kind of email.
+#[derive(Clone, Debug, Queryable, Selectable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::email_templates)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EmailTemplate {
+    pub property_id: Uuid,
+    pub kind: NotificationKind,
+    pub subject: String,
+    pub body: String,
+}
+
+/// An email sent, or still to be sent, to a guest.
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::emails)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Email {
+    pub email_id: i32,
+    pub property_id: Uuid,
+    pub reservation_id: Option<i32>,
+    pub kind: NotificationKind,
+    pub recipient: String,
+    pub reply_to: Option<String>,
+    pub subject: String,
+    pub body: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: NaiveDateTime,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub sent_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = crate::schema::emails)]
+pub struct NewEmail {
+    pub property_id: Uuid,
+    pub reservation_id: Option<i32>,
+    pub kind: NotificationKind,
+    pub recipient: String,
+    pub reply_to: Option<String>,
+    pub subject: String,
+    pub body: String,
+    pub next_attempt_at: NaiveDateTime,
+}
+
+impl NewEmail {
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_email(self, email_id: i32, created_at: NaiveDateTime) -> Email {
+        Email {
+            email_id,
+            property_id: self.property_id,
+            reservation_id: self.reservation_id,
+            kind: self.kind,
+            recipient: self.recipient,
+            reply_to: self.reply_to,
+            subject: self.subject,
+            body: self.body,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: self.next_attempt_at,
+            last_error: None,
+            created_at,
+            sent_at: None,
+        }
+    }
+}
+
+/// Where a text message stands, as far as resvm and the gateway know.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Integer)]
+pub enum MessageStatus {
+    /// Waiting for the worker, or for a retry.
+    #[default]
+    Queued = 0,
+    /// Accepted by the gateway.
+    Sent = 1,
+    /// Reported delivered to the handset by the gateway.
+    Delivered = 2,
+    Failed = 3,
+    /// Not sent, the number opted out.
+    OptedOut = 4,
+}
+
+impl<DB> ToSql<Integer, DB> for MessageStatus
+where
+    DB: diesel::backend::Backend,
+    i32: ToSql<Integer, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        match self {
+            MessageStatus::Queued => 0.to_sql(out),
+            MessageStatus::Sent => 1.to_sql(out),
+            MessageStatus::Delivered => 2.to_sql(out),
+            MessageStatus::Failed => 3.to_sql(out),
+            MessageStatus::OptedOut => 4.to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<Integer, DB> for MessageStatus
+where
+    DB: diesel::backend::Backend,
+    i32: FromSql<Integer, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            0 => Ok(MessageStatus::Queued),
+            1 => Ok(MessageStatus::Sent),
+            2 => Ok(MessageStatus::Delivered),
+            3 => Ok(MessageStatus::Failed),
+            4 => Ok(MessageStatus::OptedOut),
+            x => Err(format!("Unrecognized variant {}", x).into()),
+        }
+    }
+}
+
+impl Display for MessageStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A text message sent, or still to be sent, to a guest's phone.
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::sms_messages)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SmsMessage {
+    pub message_id: i32,
+    pub property_id: Uuid,
+    pub reservation_id: Option<i32>,
+    pub kind: NotificationKind,
+    /// `sms` or `whatsapp`.
+    pub channel: String,
+    /// E.164 number.
+    pub recipient: String,
+    pub body: String,
+    pub status: MessageStatus,
+    pub attempts: i32,
+    pub next_attempt_at: NaiveDateTime,
+    /// The gateway's id, used to match its status callbacks.
+    pub provider_id: Option<String>,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub sent_at: Option<NaiveDateTime>,
+    pub delivered_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = crate::schema::sms_messages)]
+pub struct NewSmsMessage {
+    pub property_id: Uuid,
+    pub reservation_id: Option<i32>,
+    pub kind: NotificationKind,
+    pub channel: String,
+    pub recipient: String,
+    pub body: String,
+    pub status: MessageStatus,
+    pub next_attempt_at: NaiveDateTime,
+}
+
+impl NewSmsMessage {
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_message(self, message_id: i32, created_at: NaiveDateTime) -> SmsMessage {
+        SmsMessage {
+            message_id,
+            property_id: self.property_id,
+            reservation_id: self.reservation_id,
+            kind: self.kind,
+            channel: self.channel,
+            recipient: self.recipient,
+            body: self.body,
+            status: self.status,
+            attempts: 0,
+            next_attempt_at: self.next_attempt_at,
+            provider_id: None,
+            last_error: None,
+            created_at,
+            sent_at: None,
+            delivered_at: None,
+        }
+    }
+}
+
+/// What a background job does.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Integer)]
+pub enum JobKind {
+    /// Queues the reminders of one reservation.
+    #[default]
+    Reminder = 0,
+    /// Marks bookings nobody turned up for as no-shows, periodic.
+    MarkNoShows = 1,
+    /// Deletes finished jobs and sent emails and texts past retention, periodic.
+    PurgeExpired = 2,
+}
+
+impl<DB> ToSql<Integer, DB> for JobKind
+where
+    DB: diesel::backend::Backend,
+    i32: ToSql<Integer, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        match self {
+            JobKind::Reminder => 0.to_sql(out),
+            JobKind::MarkNoShows => 1.to_sql(out),
+            JobKind::PurgeExpired => 2.to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<Integer, DB> for JobKind
+where
+    DB: diesel::backend::Backend,
+    i32: FromSql<Integer, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            0 => Ok(JobKind::Reminder),
+            1 => Ok(JobKind::MarkNoShows),
+            2 => Ok(JobKind::PurgeExpired),
+            x => Err(format!("Unrecognized variant {}", x).into()),
+        }
+    }
+}
+
+impl Display for JobKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl JobKind {
+    /// Seconds between two runs of a periodic job, `None` for one-off jobs.
+    pub fn period(&self) -> Option<i64> {
+        match self {
+            JobKind::Reminder => None,
+            JobKind::MarkNoShows => Some(5 * 60),
+            JobKind::PurgeExpired => Some(24 * 60 * 60),
+        }
+    }
+
+    pub const PERIODIC: [JobKind; 2] = [JobKind::MarkNoShows, JobKind::PurgeExpired];
+}
+
+/// Where a background job is in its life.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Integer)]
+pub enum JobStatus {
+    /// Waiting for `run_at`, or for a retry.
+    #[default]
+    Pending = 0,
+    /// Claimed by a worker until `locked_until`.
+    Running = 1,
+    Done = 2,
+    /// Gave up after the last attempt.
+    Failed = 3,
+}
+
+impl<DB> ToSql<Integer, DB> for JobStatus
+where
+    DB: diesel::backend::Backend,
+    i32: ToSql<Integer, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        match self {
+            JobStatus::Pending => 0.to_sql(out),
+            JobStatus::Running => 1.to_sql(out),
+            JobStatus::Done => 2.to_sql(out),
+            JobStatus::Failed => 3.to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<Integer, DB> for JobStatus
+where
+    DB: diesel::backend::Backend,
+    i32: FromSql<Integer, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            0 => Ok(JobStatus::Pending),
+            1 => Ok(JobStatus::Running),
+            2 => Ok(JobStatus::Done),
+            3 => Ok(JobStatus::Failed),
+            x => Err(format!("Unrecognized variant {}", x).into()),
+        }
+    }
+}
+
+impl Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A unit of background work, run by the worker once `run_at` passed.
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::jobs)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Job {
+    pub job_id: i32,
+    pub kind: JobKind,
+    pub property_id: Option<Uuid>,
+    pub reservation_id: Option<i32>,
+    pub unique_key: Option<String>,
+    pub run_at: NaiveDateTime,
+    pub status: JobStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// A worker that claimed the job holds it until then, after which the
+    /// job is claimed again, e.g. when the worker died.
+    pub locked_until: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = crate::schema::jobs)]
+pub struct NewJob {
+    pub kind: JobKind,
+    pub property_id: Option<Uuid>,
+    pub reservation_id: Option<i32>,
+    pub unique_key: Option<String>,
+    pub run_at: NaiveDateTime,
+}
+
+impl NewJob {
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_job(self, job_id: i32, created_at: NaiveDateTime) -> Job {
+        Job {
+            job_id,
+            kind: self.kind,
+            property_id: self.property_id,
+            reservation_id: self.reservation_id,
+            unique_key: self.unique_key,
+            run_at: self.run_at,
+            status: JobStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            locked_until: None,
+            created_at,
+            finished_at: None,
+        }
+    }
+}
+
+/// A guest of a property, recognised across bookings by their contacts.
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::guests)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Guest {
+    pub guest_id: i32,
+    pub property_id: Uuid,
+    pub name: String,
+    pub notes: String,
+    /// Comma separated labels such as `VIP` or `nut allergy`.
+    pub tags: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl Guest {
+    /// The labels of [`Guest::tags`], without blanks.
+    pub fn tag_list(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+    }
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = crate::schema::guests)]
+pub struct NewGuest {
+    pub property_id: Uuid,
+    pub name: String,
+    pub notes: String,
+    pub tags: String,
+}
+
+impl NewGuest {
+    pub fn new(property_id: Uuid, name: String) -> Self {
+        Self {
+            property_id,
+            name,
+            notes: String::new(),
+            tags: String::new(),
+        }
+    }
+
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_guest(self, guest_id: i32, created_at: NaiveDateTime) -> Guest {
+        Guest {
+            guest_id,
+            property_id: self.property_id,
+            name: self.name,
+            notes: self.notes,
+            tags: self.tags,
+            created_at,
+        }
+    }
+}
+
+/// A guest with their contacts and bookings at the property.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GuestProfile {
+    pub guest: Guest,
+    /// E.164 phone numbers.
+    pub phones: Vec<String>,
+    /// Lowercase email addresses.
+    pub emails: Vec<String>,
+    /// All bookings, the latest first.
+    pub reservations: Vec<Reservation>,
+    /// Bookings still ahead that were not cancelled.
+    pub upcoming: usize,
+    /// Bookings the guest was seated for.
+    pub visits: usize,
+    pub no_shows: usize,
+    /// Percentage of visits among visits and no-shows, `None` before the
+    /// first.
+    pub reliability: Option<u8>,
+    /// Advances paid over all bookings.
+    pub total_spend: i64,
+    /// Duplicates merged into the guest, the latest first.
+    pub merges: Vec<GuestMerge>,
+}
+
+/// Record of a duplicate guest merged into the one that was kept.
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::guest_merges)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct GuestMerge {
+    pub merge_id: i32,
+    pub property_id: Uuid,
+    /// The guest that was kept.
+    pub guest_id: i32,
+    /// The deleted duplicate, with its name and contacts at the time.
+    pub merged_guest_id: i32,
+    pub merged_name: String,
+    pub merged_contacts: String,
+    /// Reservations moved over to the kept guest.
+    pub reservations: i32,
+    pub merged_by: String,
+    pub merged_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = crate::schema::guest_merges)]
+pub struct NewGuestMerge {
+    pub property_id: Uuid,
+    pub guest_id: i32,
+    pub merged_guest_id: i32,
+    pub merged_name: String,
+    pub merged_contacts: String,
+    pub merged_by: String,
+}
+
+impl NewGuestMerge {
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_merge(
+        self,
+        merge_id: i32,
+        reservations: i32,
+        merged_at: NaiveDateTime,
+    ) -> GuestMerge {
+        GuestMerge {
+            merge_id,
+            property_id: self.property_id,
+            guest_id: self.guest_id,
+            merged_guest_id: self.merged_guest_id,
+            merged_name: self.merged_name,
+            merged_contacts: self.merged_contacts,
+            reservations,
+            merged_by: self.merged_by,
+            merged_at,
+        }
+    }
+}
+
+/// Two guests of a property that are likely the same person.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DuplicateGuests {
+    /// The guest known the longest, suggested to keep.
+    pub guest: Guest,
+    pub duplicate: Guest,
+    /// What the two have in common.
+    pub reasons: Vec<String>,
+}
+
+/// Where a waitlist entry is in its life.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Integer)]
+pub enum WaitlistStatus {
+    #[default]
+    Waiting = 0,
+    /// Told that a table is free, waiting for them to turn up.
+    Notified = 1,
+    /// Became a reservation.
+    Promoted = 2,
+    /// Left, or no longer wants a table.
+    Removed = 3,
+}
+
+impl<DB> ToSql<Integer, DB> for WaitlistStatus
+where
+    DB: diesel::backend::Backend,
+    i32: ToSql<Integer, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        match self {
+            WaitlistStatus::Waiting => 0.to_sql(out),
+            WaitlistStatus::Notified => 1.to_sql(out),
+            WaitlistStatus::Promoted => 2.to_sql(out),
+            WaitlistStatus::Removed => 3.to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<Integer, DB> for WaitlistStatus
+where
+    DB: diesel::backend::Backend,
+    i32: FromSql<Integer, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            0 => Ok(WaitlistStatus::Waiting),
+            1 => Ok(WaitlistStatus::Notified),
+            2 => Ok(WaitlistStatus::Promoted),
+            3 => Ok(WaitlistStatus::Removed),
+            x => Err(format!("Unrecognized variant {}", x).into()),
+        }
+    }
+}
+
+impl Display for WaitlistStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl WaitlistStatus {
+    /// Waiting and notified guests still want a table.
+    pub fn is_open(&self) -> bool {
+        matches!(self, WaitlistStatus::Waiting | WaitlistStatus::Notified)
+    }
+}
+
+/// A guest waiting for a table on a day that is full.
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::waitlist)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WaitlistEntry {
+    pub entry_id: i32,
+    pub property_id: Uuid,
+    pub waitlist_date: NaiveDate,
+    pub name: String,
+    pub contact: String,
+    pub party_size: i32,
+    /// The time hoped for, `None` for walk-ins waiting for the next table.
+    #[serde(with = "hhmm::option")]
+    pub preferred_time: Option<Time>,
+    /// Minutes the guest was told to expect.
+    pub quoted_wait: i32,
+    pub status: WaitlistStatus,
+    pub notified_at: Option<NaiveDateTime>,
+    /// The reservation the entry was promoted to.
+    pub reservation_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = crate::schema::waitlist)]
+pub struct NewWaitlistEntry {
+    pub property_id: Uuid,
+    pub waitlist_date: NaiveDate,
+    pub name: String,
+    pub contact: String,
+    pub party_size: i32,
+    pub preferred_time: Option<Time>,
+    pub quoted_wait: i32,
+}
+
+impl NewWaitlistEntry {
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_entry(self, entry_id: i32, created_at: NaiveDateTime) -> WaitlistEntry {
+        WaitlistEntry {
+            entry_id,
+            property_id: self.property_id,
+            waitlist_date: self.waitlist_date,
+            name: self.name,
+            contact: self.contact,
+            party_size: self.party_size,
+            preferred_time: self.preferred_time,
+            quoted_wait: self.quoted_wait,
+            status: WaitlistStatus::Waiting,
+            notified_at: None,
+            reservation_id: None,
+            created_at,
+        }
+    }
+}
+
+/// A waitlist entry with the table that could seat it right now, if any.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WaitingGuest {
+    pub entry: WaitlistEntry,
+    pub free_table: Option<String>,
+}
+
+/// Days a property does not open, bookings and series skip them.
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::closures)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Closure {
+    pub closure_id: i32,
+    pub property_id: Uuid,
+    pub first_day: NaiveDate,
+    /// The last day closed, the same as `first_day` for a single day.
+    pub last_day: NaiveDate,
+    pub reason: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl Closure {
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        self.first_day <= date && date <= self.last_day
+    }
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = crate::schema::closures)]
+pub struct NewClosure {
+    pub property_id: Uuid,
+    pub first_day: NaiveDate,
+    pub last_day: NaiveDate,
+    pub reason: String,
+}
+
+impl NewClosure {
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_closure(self, closure_id: i32, created_at: NaiveDateTime) -> Closure {
+        Closure {
+            closure_id,
+            property_id: self.property_id,
+            first_day: self.first_day,
+            last_day: self.last_day,
+            reason: self.reason,
+            created_at,
+        }
+    }
+}
+
+/// How often a standing reservation comes back.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Integer)]
+pub enum Recurrence {
+    #[default]
+    Weekly = 0,
+    /// Every other week.
+    Biweekly = 1,
+    /// On the same day of every month, the last day of shorter months.
+    Monthly = 2,
+}
+
+impl<DB> ToSql<Integer, DB> for Recurrence
+where
+    DB: diesel::backend::Backend,
+    i32: ToSql<Integer, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        match self {
+            Recurrence::Weekly => 0.to_sql(out),
+            Recurrence::Biweekly => 1.to_sql(out),
+            Recurrence::Monthly => 2.to_sql(out),
+        }
+    }
+}
+
+impl<DB> FromSql<Integer, DB> for Recurrence
+where
+    DB: diesel::backend::Backend,
+    i32: FromSql<Integer, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            0 => Ok(Recurrence::Weekly),
+            1 => Ok(Recurrence::Biweekly),
+            2 => Ok(Recurrence::Monthly),
+            x => Err(format!("Unrecognized variant {}", x).into()),
+        }
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Recurrence {
+    pub const ALL: [Recurrence; 3] = [
+        Recurrence::Weekly,
+        Recurrence::Biweekly,
+        Recurrence::Monthly,
+    ];
+
+    /// The date of occurrence `n` of a series starting on `first`, counting
+    /// from 0.
+    pub fn nth(&self, first: NaiveDate, n: u32) -> Option<NaiveDate> {
+        match self {
+            Recurrence::Weekly => first.checked_add_days(chrono::Days::new(7 * n as u64)),
+            Recurrence::Biweekly => first.checked_add_days(chrono::Days::new(14 * n as u64)),
+            Recurrence::Monthly => first.checked_add_months(chrono::Months::new(n)),
+        }
+    }
+}
+
+/// A standing reservation, booked as one reservation per occurrence.
+#[derive(Clone, Debug, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::reservation_series)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ReservationSeries {
+    pub series_id: i32,
+    pub property_id: Uuid,
+    pub name: String,
+    pub contact: String,
+    /// A table name, empty to take the smallest free table each time.
+    pub seating: String,
+    pub party_size: i32,
+    #[serde(with = "hhmm")]
+    pub reservation_time: Time,
+    pub recurrence: Recurrence,
+    pub starts_on: NaiveDate,
+    /// The last day an occurrence may fall on.
+    pub ends_on: Option<NaiveDate>,
+    /// How many occurrences the series has at most.
+    pub occurrences: Option<i32>,
+    pub cancelled_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = crate::schema::reservation_series)]
+pub struct NewReservationSeries {
+    pub property_id: Uuid,
+    pub name: String,
+    pub contact: String,
+    pub seating: String,
+    pub party_size: i32,
+    pub reservation_time: Time,
+    pub recurrence: Recurrence,
+    pub starts_on: NaiveDate,
+    pub ends_on: Option<NaiveDate>,
+    pub occurrences: Option<i32>,
+}
+
+impl NewReservationSeries {
+    /// Builds the stored row for storages that assign ids themselves.
+    pub(crate) fn into_series(
+        self,
+        series_id: i32,
+        created_at: NaiveDateTime,
+    ) -> ReservationSeries {
+        ReservationSeries {
+            series_id,
+            property_id: self.property_id,
+            name: self.name,
+            contact: self.contact,
+            seating: self.seating,
+            party_size: self.party_size,
+            reservation_time: self.reservation_time,
+            recurrence: self.recurrence,
+            starts_on: self.starts_on,
+            ends_on: self.ends_on,
+            occurrences: self.occurrences,
+            cancelled_at: None,
+            created_at,
+        }
+    }
+}
+
+/// An occurrence of a series that was not booked or changed, and why.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SkippedOccurrence {
+    pub date: NaiveDate,
+    pub reason: String,
+}
+
+/// The outcome of booking or changing a series: the occurrences booked or
+/// changed and those skipped.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SeriesSchedule {
+    pub series: ReservationSeries,
+    pub booked: Vec<Reservation>,
+    pub skipped: Vec<SkippedOccurrence>,
+}
+
+/// A series with its occurrences, the earliest first.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SeriesOverview {
+    pub series: ReservationSeries,
+    pub occurrences: Vec<Reservation>,
+}
+
+/// A table held by a group booking or event.
+#[derive(Clone, Copy, Debug, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::reservation_tables)]
+pub struct TableAssignment {
+    pub reservation_id: i32,
+    pub table_id: i32,
+}
+
+/// What an event booking agreed on.
+#[derive(
+    Clone, Debug, Default, PartialEq, Queryable, Selectable, Insertable, Serialize, Deserialize,
+)]
+#[diesel(table_name = crate::schema::reservation_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EventDetails {
+    pub reservation_id: i32,
+    /// The section bought out, all of its tables are held.
+    pub section: Option<String>,
+    pub menu: String,
+    pub minimum_spend: Option<i32>,
+}
+
+impl EventDetails {
+    /// One line for the day sheet, e.g. "buyout of Terrace; set menu; minimum spend 1500".
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if let Some(section) = &self.section {
+            parts.push(format!("buyout of {section}"));
+        }
+        if !self.menu.is_empty() {
+            parts.push(self.menu.clone());
+        }
+        if let Some(spend) = self.minimum_spend {
+            parts.push(format!("minimum spend {spend}"));
+        }
+        parts.join("; ")
+    }
+}
+
+/// A reservation with the tables it holds and its event details.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GroupBooking {
+    pub reservation: Reservation,
+    /// Names of the tables held, empty when only `seating` is.
+    pub tables: Vec<String>,
+    pub event: Option<EventDetails>,
+}
+
+/// Where the table plan of a day puts a reservation.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlannedSeating {
+    pub reservation: Reservation,
+    /// Names of the tables, more than one when they are pushed together.
+    pub tables: Vec<String>,
+    /// Seats left empty at these tables.
+    pub wasted: i32,
+    /// Kept where the guest asked to sit or staff put the party.
+    pub fixed: bool,
+}
+
+impl PlannedSeating {
+    /// Whether accepting the plan moves the reservation.
+    pub fn moves(&self) -> bool {
+        !self.fixed
+            && !self
+                .reservation
+                .seating
+                .trim()
+                .eq_ignore_ascii_case(&self.tables.join("+"))
+    }
+}
+
+/// A reservation the table plan has no tables for, and why.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnplacedReservation {
+    pub reservation: Reservation,
+    pub reason: String,
+}
+
+/// A proposed placement of the reservations of a day still to come.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TablePlan {
+    pub date: NaiveDate,
+    /// By time.
+    pub placed: Vec<PlannedSeating>,
+    pub unplaced: Vec<UnplacedReservation>,
+    /// Seats left empty over all placed reservations.
+    pub wasted_seats: i32,
+}
@@ -0,0 +1,1173 @@
+use super::{
+    ApiKeyRepository, EmailRepository, GuestRepository, JobRepository, PropertyRepository,
+    RepoResult, ReservationRepository, SeriesRepository, SmsRepository, WaitlistRepository,
+    WebhookRepository,
+};
+use crate::errors::RepositoryError;
+use crate::models::{
+    ApiKey, Closure, DeliveryStatus, Email, EmailTemplate, EventDetails, Guest, GuestMerge,
+    HistoryEntry, Job, JobStatus, MessageStatus, NewApiKey, NewClosure, NewEmail, NewGuest,
+    NewGuestMerge, NewHistoryEntry, NewJob, NewProperty, NewPropertyTable, NewPropertyUser,
+    NewReservationNote, NewReservationSeries, NewResv, NewSmsMessage, NewWaitlistEntry, NewWebhook,
+    NewWebhookDelivery, NotificationKind, Property, PropertyTable, PropertyUsers, Reservation,
+    ReservationNote, ReservationSeries, ReservationStatus, SmsMessage, SpecialRequests,
+    TableAssignment, TableLayout, WaitlistEntry, Webhook, WebhookDelivery,
+};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use std::sync::Mutex;
+use time::Time;
+use uuid::Uuid;
+
+/// Repository keeping everything in process memory, used to exercise the
+/// business rules without Postgres.
+#[derive(Debug, Default)]
+pub struct InMemoryRepository {
+    properties: Mutex<Vec<Property>>,
+    tables: Mutex<Vec<PropertyTable>>,
+    layouts: Mutex<Vec<TableLayout>>,
+    users: Mutex<Vec<PropertyUsers>>,
+    reservations: Mutex<Vec<Reservation>>,
+    history: Mutex<Vec<HistoryEntry>>,
+    notes: Mutex<Vec<ReservationNote>>,
+    table_assignments: Mutex<Vec<TableAssignment>>,
+    events: Mutex<Vec<EventDetails>>,
+    api_keys: Mutex<Vec<ApiKey>>,
+    webhooks: Mutex<Vec<Webhook>>,
+    deliveries: Mutex<Vec<WebhookDelivery>>,
+    email_templates: Mutex<Vec<EmailTemplate>>,
+    emails: Mutex<Vec<Email>>,
+    sms_messages: Mutex<Vec<SmsMessage>>,
+    sms_opt_outs: Mutex<Vec<String>>,
+    jobs: Mutex<Vec<Job>>,
+    guests: Mutex<Vec<Guest>>,
+    /// Property, normalised contact and the guest it belongs to.
+    guest_contacts: Mutex<Vec<(Uuid, String, i32)>>,
+    guest_merges: Mutex<Vec<GuestMerge>>,
+    waitlist: Mutex<Vec<WaitlistEntry>>,
+    closures: Mutex<Vec<Closure>>,
+    series: Mutex<Vec<ReservationSeries>>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl PropertyRepository for InMemoryRepository {
+    fn all(&self) -> RepoResult<Vec<Property>> {
+        Ok(self.properties.lock().unwrap().clone())
+    }
+
+    fn find(&self, pid: Uuid) -> RepoResult<Property> {
+        self.properties
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|p| p.property_id == pid)
+            .cloned()
+            .ok_or(RepositoryError::NotFound)
+    }
+
+    fn insert(&self, new_property: NewProperty) -> RepoResult<Uuid> {
+        let stored = new_property.into_property();
+        let pid = stored.property_id;
+        self.properties.lock().unwrap().push(stored);
+        Ok(pid)
+    }
+
+    fn tables(&self, pid: Uuid) -> RepoResult<Vec<PropertyTable>> {
+        let mut found: Vec<PropertyTable> = self
+            .tables
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|t| t.property_id == pid)
+            .cloned()
+            .collect();
+        found.sort_by(|a, b| a.table_name.cmp(&b.table_name));
+        Ok(found)
+    }
+
+    fn insert_table(&self, new_table: NewPropertyTable) -> RepoResult<i32> {
+        let mut tables = self.tables.lock().unwrap();
+        if tables
+            .iter()
+            .any(|t| t.property_id == new_table.property_id && t.table_name == new_table.table_name)
+        {
+            return Err(RepositoryError::Conflict(format!(
+                "table {} already exists",
+                new_table.table_name
+            )));
+        }
+        let tid = tables.iter().map(|t| t.table_id).max().unwrap_or(0) + 1;
+        tables.push(new_table.into_table(tid));
+        Ok(tid)
+    }
+
+    fn set_table_section(&self, table_id: i32, section: Option<String>) -> RepoResult<()> {
+        let mut tables = self.tables.lock().unwrap();
+        let table = tables
+            .iter_mut()
+            .find(|t| t.table_id == table_id)
+            .ok_or(RepositoryError::NotFound)?;
+        table.section = section;
+        Ok(())
+    }
+
+    fn layouts(&self, pid: Uuid) -> RepoResult<Vec<TableLayout>> {
+        let tables: Vec<i32> = self
+            .tables
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|t| t.property_id == pid)
+            .map(|t| t.table_id)
+            .collect();
+        Ok(self
+            .layouts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|l| tables.contains(&l.table_id))
+            .copied()
+            .collect())
+    }
+
+    fn save_layout(&self, layout: &TableLayout) -> RepoResult<()> {
+        let mut layouts = self.layouts.lock().unwrap();
+        layouts.retain(|l| l.table_id != layout.table_id);
+        layouts.push(*layout);
+        Ok(())
+    }
+
+    fn insert_user(&self, new_user: NewPropertyUser) -> RepoResult<i32> {
+        let mut users = self.users.lock().unwrap();
+        let uid = users.len() as i32 + 1;
+        users.push(PropertyUsers::new(
+            uid,
+            new_user.user_name,
+            new_user.user_password,
+            new_user.user_role,
+            new_user.property_id,
+        ));
+        Ok(uid)
+    }
+
+    fn find_user(&self, pid: Uuid, user_name: &str) -> RepoResult<PropertyUsers> {
+        self.users
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|u| u.property_id == pid && u.user_name == user_name)
+            .map(|u| {
+                PropertyUsers::new(
+                    u.user_id,
+                    u.user_name.clone(),
+                    u.user_password.clone(),
+                    u.user_role,
+                    u.property_id,
+                )
+            })
+            .ok_or(RepositoryError::NotFound)
+    }
+
+    fn set_password(&self, pid: Uuid, password_hash: String) -> RepoResult<()> {
+        let mut properties = self.properties.lock().unwrap();
+        let stored = properties
+            .iter_mut()
+            .find(|p| p.property_id == pid)
+            .ok_or(RepositoryError::NotFound)?;
+        stored.property_password = password_hash;
+        Ok(())
+    }
+
+    fn set_user_password(
+        &self,
+        pid: Uuid,
+        user_name: &str,
+        password_hash: String,
+    ) -> RepoResult<()> {
+        let mut users = self.users.lock().unwrap();
+        let stored = users
+            .iter_mut()
+            .find(|u| u.property_id == pid && u.user_name == user_name)
+            .ok_or(RepositoryError::NotFound)?;
+        stored.user_password = password_hash;
+        Ok(())
+    }
+
+    fn closures(&self, pid: Uuid) -> RepoResult<Vec<Closure>> {
+        let mut found: Vec<Closure> = self
+            .closures
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|c| c.property_id == pid)
+            .cloned()
+            .collect();
+        found.sort_by_key(|c| (c.first_day, c.closure_id));
+        Ok(found)
+    }
+
+    fn insert_closure(&self, new_closure: NewClosure) -> RepoResult<i32> {
+        let mut closures = self.closures.lock().unwrap();
+        let cid = closures.iter().map(|c| c.closure_id).max().unwrap_or(0) + 1;
+        closures.push(new_closure.into_closure(cid, Utc::now().naive_utc()));
+        Ok(cid)
+    }
+
+    fn delete_closure(&self, pid: Uuid, closure_id: i32) -> RepoResult<()> {
+        let mut closures = self.closures.lock().unwrap();
+        let before = closures.len();
+        closures.retain(|c| !(c.property_id == pid && c.closure_id == closure_id));
+        if closures.len() == before {
+            return Err(RepositoryError::NotFound);
+        }
+        Ok(())
+    }
+}
+
+impl ReservationRepository for InMemoryRepository {
+    fn for_property(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<Reservation>> {
+        Ok(self
+            .reservations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.property_id == pid)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    fn with_tag(&self, pid: Uuid, tag: &str, limit: i64) -> RepoResult<Vec<Reservation>> {
+        Ok(self
+            .reservations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.property_id == pid && r.has_tag(tag))
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    fn on_date(&self, pid: Uuid, date: NaiveDate) -> RepoResult<Vec<Reservation>> {
+        let mut found: Vec<Reservation> = self
+            .reservations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.property_id == pid && r.reservation_date == date)
+            .cloned()
+            .collect();
+        found.sort_by_key(|r| r.reservation_time);
+        Ok(found)
+    }
+
+    fn count_for_property(&self, pid: Uuid) -> RepoResult<i64> {
+        Ok(self
+            .reservations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.property_id == pid)
+            .count() as i64)
+    }
+
+    fn insert(&self, new_resv: NewResv) -> RepoResult<i32> {
+        let mut reservations = self.reservations.lock().unwrap();
+        let rid = reservations.iter().map(|r| r.id).max().unwrap_or(0) + 1;
+        reservations.push(new_resv.into_reservation(rid));
+        Ok(rid)
+    }
+
+    fn find(&self, id: i32) -> RepoResult<Reservation> {
+        self.reservations
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|r| r.id == id)
+            .cloned()
+            .ok_or(RepositoryError::NotFound)
+    }
+
+    fn set_status(&self, id: i32, status: ReservationStatus) -> RepoResult<Reservation> {
+        let mut reservations = self.reservations.lock().unwrap();
+        let stored = reservations
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or(RepositoryError::NotFound)?;
+        stored.status = status;
+        if status == ReservationStatus::NoShow {
+            stored.no_show_at = Some(Utc::now().naive_utc());
+        }
+        Ok(stored.clone())
+    }
+
+    fn set_payment(
+        &self,
+        id: i32,
+        advance_method: serde_json::Value,
+        advance_amount: i32,
+    ) -> RepoResult<Reservation> {
+        let mut reservations = self.reservations.lock().unwrap();
+        let stored = reservations
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or(RepositoryError::NotFound)?;
+        stored.advance = true;
+        stored.advance_method = advance_method;
+        stored.advance_amount = Some(advance_amount);
+        Ok(stored.clone())
+    }
+
+    fn find_by_token(&self, token: &str) -> RepoResult<Reservation> {
+        self.reservations
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|r| r.confirmation_token.as_deref() == Some(token))
+            .cloned()
+            .ok_or(RepositoryError::NotFound)
+    }
+
+    fn reschedule(
+        &self,
+        id: i32,
+        date: NaiveDate,
+        time: Time,
+        seating: String,
+        party_size: i32,
+    ) -> RepoResult<Reservation> {
+        let mut reservations = self.reservations.lock().unwrap();
+        let stored = reservations
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or(RepositoryError::NotFound)?;
+        stored.reservation_date = date;
+        stored.reservation_time = time;
+        stored.seating = seating;
+        stored.party_size = party_size;
+        Ok(stored.clone())
+    }
+
+    fn insert_history(&self, entry: NewHistoryEntry) -> RepoResult<i32> {
+        let mut history = self.history.lock().unwrap();
+        let eid = history.iter().map(|e| e.entry_id).max().unwrap_or(0) + 1;
+        history.push(entry.into_entry(eid, Utc::now().naive_utc()));
+        Ok(eid)
+    }
+
+    fn history(&self, id: i32) -> RepoResult<Vec<HistoryEntry>> {
+        Ok(self
+            .history
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| e.reservation_id == id)
+            .cloned()
+            .collect())
+    }
+
+    fn set_requests(
+        &self,
+        id: i32,
+        special_requests: SpecialRequests,
+        tags: String,
+    ) -> RepoResult<Reservation> {
+        let mut reservations = self.reservations.lock().unwrap();
+        let stored = reservations
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or(RepositoryError::NotFound)?;
+        stored.special_requests = special_requests;
+        stored.tags = tags;
+        Ok(stored.clone())
+    }
+
+    fn insert_note(&self, note: NewReservationNote) -> RepoResult<i32> {
+        let mut notes = self.notes.lock().unwrap();
+        let nid = notes.iter().map(|n| n.note_id).max().unwrap_or(0) + 1;
+        notes.push(note.into_note(nid, Utc::now().naive_utc()));
+        Ok(nid)
+    }
+
+    fn notes(&self, id: i32) -> RepoResult<Vec<ReservationNote>> {
+        Ok(self
+            .notes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|n| n.reservation_id == id)
+            .cloned()
+            .collect())
+    }
+
+    fn table_assignments(&self, ids: &[i32]) -> RepoResult<Vec<TableAssignment>> {
+        Ok(self
+            .table_assignments
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|a| ids.contains(&a.reservation_id))
+            .copied()
+            .collect())
+    }
+
+    fn set_table_assignments(&self, id: i32, table_ids: &[i32]) -> RepoResult<()> {
+        let mut assignments = self.table_assignments.lock().unwrap();
+        assignments.retain(|a| a.reservation_id != id);
+        assignments.extend(table_ids.iter().map(|&table_id| TableAssignment {
+            reservation_id: id,
+            table_id,
+        }));
+        Ok(())
+    }
+
+    fn event_details(&self, id: i32) -> RepoResult<Option<EventDetails>> {
+        Ok(self
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|e| e.reservation_id == id)
+            .cloned())
+    }
+
+    fn save_event_details(&self, details: &EventDetails) -> RepoResult<()> {
+        let mut events = self.events.lock().unwrap();
+        events.retain(|e| e.reservation_id != details.reservation_id);
+        events.push(details.clone());
+        Ok(())
+    }
+}
+
+impl ApiKeyRepository for InMemoryRepository {
+    fn keys(&self, pid: Uuid) -> RepoResult<Vec<ApiKey>> {
+        let mut found: Vec<ApiKey> = self
+            .api_keys
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|k| k.property_id == pid)
+            .cloned()
+            .collect();
+        found.sort_by_key(|k| std::cmp::Reverse(k.created_at));
+        Ok(found)
+    }
+
+    fn key_by_prefix(&self, prefix: &str) -> RepoResult<ApiKey> {
+        self.api_keys
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|k| k.key_prefix == prefix)
+            .cloned()
+            .ok_or(RepositoryError::NotFound)
+    }
+
+    fn insert_key(&self, new_key: NewApiKey) -> RepoResult<i32> {
+        let mut keys = self.api_keys.lock().unwrap();
+        if keys.iter().any(|k| k.key_prefix == new_key.key_prefix) {
+            return Err(RepositoryError::Conflict(
+                "key prefix already exists".into(),
+            ));
+        }
+        let kid = keys.iter().map(|k| k.key_id).max().unwrap_or(0) + 1;
+        keys.push(new_key.into_api_key(kid, Utc::now().naive_utc()));
+        Ok(kid)
+    }
+
+    fn revoke_key(&self, pid: Uuid, key_id: i32, at: NaiveDateTime) -> RepoResult<ApiKey> {
+        let mut keys = self.api_keys.lock().unwrap();
+        let stored = keys
+            .iter_mut()
+            .find(|k| k.key_id == key_id && k.property_id == pid)
+            .ok_or(RepositoryError::NotFound)?;
+        stored.revoked_at = Some(at);
+        Ok(stored.clone())
+    }
+
+    fn touch_key(&self, key_id: i32, at: NaiveDateTime) -> RepoResult<()> {
+        let mut keys = self.api_keys.lock().unwrap();
+        let stored = keys
+            .iter_mut()
+            .find(|k| k.key_id == key_id)
+            .ok_or(RepositoryError::NotFound)?;
+        stored.last_used_at = Some(at);
+        Ok(())
+    }
+}
+
+impl WebhookRepository for InMemoryRepository {
+    fn webhooks(&self, pid: Uuid) -> RepoResult<Vec<Webhook>> {
+        Ok(self
+            .webhooks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|w| w.property_id == pid)
+            .cloned()
+            .collect())
+    }
+
+    fn find_webhook(&self, webhook_id: i32) -> RepoResult<Webhook> {
+        self.webhooks
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|w| w.webhook_id == webhook_id)
+            .cloned()
+            .ok_or(RepositoryError::NotFound)
+    }
+
+    fn insert_webhook(&self, new_webhook: NewWebhook) -> RepoResult<i32> {
+        let mut webhooks = self.webhooks.lock().unwrap();
+        let wid = webhooks.iter().map(|w| w.webhook_id).max().unwrap_or(0) + 1;
+        webhooks.push(new_webhook.into_webhook(wid, Utc::now().naive_utc()));
+        Ok(wid)
+    }
+
+    fn set_webhook_active(&self, pid: Uuid, webhook_id: i32, active: bool) -> RepoResult<Webhook> {
+        let mut webhooks = self.webhooks.lock().unwrap();
+        let stored = webhooks
+            .iter_mut()
+            .find(|w| w.webhook_id == webhook_id && w.property_id == pid)
+            .ok_or(RepositoryError::NotFound)?;
+        stored.active = active;
+        Ok(stored.clone())
+    }
+
+    fn insert_delivery(&self, new_delivery: NewWebhookDelivery) -> RepoResult<i32> {
+        let mut deliveries = self.deliveries.lock().unwrap();
+        let did = deliveries.iter().map(|d| d.delivery_id).max().unwrap_or(0) + 1;
+        deliveries.push(new_delivery.into_delivery(did, Utc::now().naive_utc()));
+        Ok(did)
+    }
+
+    fn find_delivery(&self, delivery_id: i32) -> RepoResult<WebhookDelivery> {
+        self.deliveries
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|d| d.delivery_id == delivery_id)
+            .cloned()
+            .ok_or(RepositoryError::NotFound)
+    }
+
+    fn deliveries(&self, webhook_id: i32, limit: i64) -> RepoResult<Vec<WebhookDelivery>> {
+        Ok(self
+            .deliveries
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|d| d.webhook_id == webhook_id)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    fn due_deliveries(&self, now: NaiveDateTime, limit: i64) -> RepoResult<Vec<WebhookDelivery>> {
+        let mut due: Vec<WebhookDelivery> = self
+            .deliveries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|d| d.status == DeliveryStatus::Pending && d.next_attempt_at <= now)
+            .cloned()
+            .collect();
+        due.sort_by_key(|d| d.next_attempt_at);
+        due.truncate(limit as usize);
+        Ok(due)
+    }
+
+    fn update_delivery(&self, delivery: &WebhookDelivery) -> RepoResult<()> {
+        let mut deliveries = self.deliveries.lock().unwrap();
+        let stored = deliveries
+            .iter_mut()
+            .find(|d| d.delivery_id == delivery.delivery_id)
+            .ok_or(RepositoryError::NotFound)?;
+        *stored = delivery.clone();
+        Ok(())
+    }
+}
+
+impl EmailRepository for InMemoryRepository {
+    fn email_templates(&self, pid: Uuid) -> RepoResult<Vec<EmailTemplate>> {
+        Ok(self
+            .email_templates
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|t| t.property_id == pid)
+            .cloned()
+            .collect())
+    }
+
+    fn save_email_template(&self, template: EmailTemplate) -> RepoResult<()> {
+        let mut templates = self.email_templates.lock().unwrap();
+        templates.retain(|t| !(t.property_id == template.property_id && t.kind == template.kind));
+        templates.push(template);
+        Ok(())
+    }
+
+    fn insert_email(&self, new_email: NewEmail) -> RepoResult<i32> {
+        let mut emails = self.emails.lock().unwrap();
+        let eid = emails.iter().map(|e| e.email_id).max().unwrap_or(0) + 1;
+        emails.push(new_email.into_email(eid, Utc::now().naive_utc()));
+        Ok(eid)
+    }
+
+    fn emails(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<Email>> {
+        Ok(self
+            .emails
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|e| e.property_id == pid)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    fn due_emails(&self, now: NaiveDateTime, limit: i64) -> RepoResult<Vec<Email>> {
+        let mut due: Vec<Email> = self
+            .emails
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| e.status == DeliveryStatus::Pending && e.next_attempt_at <= now)
+            .cloned()
+            .collect();
+        due.sort_by_key(|e| e.next_attempt_at);
+        due.truncate(limit as usize);
+        Ok(due)
+    }
+
+    fn update_email(&self, email: &Email) -> RepoResult<()> {
+        let mut emails = self.emails.lock().unwrap();
+        let stored = emails
+            .iter_mut()
+            .find(|e| e.email_id == email.email_id)
+            .ok_or(RepositoryError::NotFound)?;
+        *stored = email.clone();
+        Ok(())
+    }
+
+    fn has_email(&self, reservation_id: i32, kind: NotificationKind) -> RepoResult<bool> {
+        Ok(self
+            .emails
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|e| e.reservation_id == Some(reservation_id) && e.kind == kind))
+    }
+
+    fn purge_emails(&self, before: NaiveDateTime) -> RepoResult<usize> {
+        let mut emails = self.emails.lock().unwrap();
+        let count = emails.len();
+        emails.retain(|e| e.status == DeliveryStatus::Pending || e.created_at >= before);
+        Ok(count - emails.len())
+    }
+}
+
+impl SmsRepository for InMemoryRepository {
+    fn insert_message(&self, new_message: NewSmsMessage) -> RepoResult<i32> {
+        let mut messages = self.sms_messages.lock().unwrap();
+        let mid = messages.iter().map(|m| m.message_id).max().unwrap_or(0) + 1;
+        messages.push(new_message.into_message(mid, Utc::now().naive_utc()));
+        Ok(mid)
+    }
+
+    fn messages(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<SmsMessage>> {
+        Ok(self
+            .sms_messages
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|m| m.property_id == pid)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    fn due_messages(&self, now: NaiveDateTime, limit: i64) -> RepoResult<Vec<SmsMessage>> {
+        let mut due: Vec<SmsMessage> = self
+            .sms_messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| m.status == MessageStatus::Queued && m.next_attempt_at <= now)
+            .cloned()
+            .collect();
+        due.sort_by_key(|m| m.next_attempt_at);
+        due.truncate(limit as usize);
+        Ok(due)
+    }
+
+    fn message_by_provider_id(&self, provider_id: &str) -> RepoResult<SmsMessage> {
+        self.sms_messages
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|m| m.provider_id.as_deref() == Some(provider_id))
+            .cloned()
+            .ok_or(RepositoryError::NotFound)
+    }
+
+    fn update_message(&self, message: &SmsMessage) -> RepoResult<()> {
+        let mut messages = self.sms_messages.lock().unwrap();
+        let stored = messages
+            .iter_mut()
+            .find(|m| m.message_id == message.message_id)
+            .ok_or(RepositoryError::NotFound)?;
+        *stored = message.clone();
+        Ok(())
+    }
+
+    fn has_message(&self, reservation_id: i32, kind: NotificationKind) -> RepoResult<bool> {
+        Ok(self
+            .sms_messages
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|m| m.reservation_id == Some(reservation_id) && m.kind == kind))
+    }
+
+    fn opted_out(&self, phone: &str) -> RepoResult<bool> {
+        Ok(self.sms_opt_outs.lock().unwrap().iter().any(|p| p == phone))
+    }
+
+    fn set_opted_out(&self, phone: &str, opted_out: bool, _at: NaiveDateTime) -> RepoResult<()> {
+        let mut opt_outs = self.sms_opt_outs.lock().unwrap();
+        opt_outs.retain(|p| p != phone);
+        if opted_out {
+            opt_outs.push(phone.to_string());
+        }
+        Ok(())
+    }
+
+    fn purge_messages(&self, before: NaiveDateTime) -> RepoResult<usize> {
+        let mut messages = self.sms_messages.lock().unwrap();
+        let count = messages.len();
+        messages.retain(|m| m.status == MessageStatus::Queued || m.created_at >= before);
+        Ok(count - messages.len())
+    }
+}
+
+impl JobRepository for InMemoryRepository {
+    fn schedule_job(&self, new_job: NewJob) -> RepoResult<i32> {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(job) = jobs
+            .iter_mut()
+            .find(|job| new_job.unique_key.is_some() && job.unique_key == new_job.unique_key)
+        {
+            job.run_at = new_job.run_at;
+            job.status = JobStatus::Pending;
+            job.attempts = 0;
+            job.last_error = None;
+            job.locked_until = None;
+            return Ok(job.job_id);
+        }
+        let job_id = jobs.iter().map(|job| job.job_id).max().unwrap_or(0) + 1;
+        jobs.push(new_job.into_job(job_id, Utc::now().naive_utc()));
+        Ok(job_id)
+    }
+
+    fn claim_jobs(
+        &self,
+        now: NaiveDateTime,
+        locked_until: NaiveDateTime,
+        limit: i64,
+    ) -> RepoResult<Vec<Job>> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let mut due: Vec<&mut Job> = jobs
+            .iter_mut()
+            .filter(|job| match job.status {
+                JobStatus::Pending => job.run_at <= now,
+                JobStatus::Running => job.locked_until.is_some_and(|lock| lock < now),
+                _ => false,
+            })
+            .collect();
+        due.sort_by_key(|job| job.run_at);
+        Ok(due
+            .into_iter()
+            .take(limit as usize)
+            .map(|job| {
+                job.status = JobStatus::Running;
+                job.locked_until = Some(locked_until);
+                job.attempts += 1;
+                job.clone()
+            })
+            .collect())
+    }
+
+    fn find_job(&self, job_id: i32) -> RepoResult<Job> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|job| job.job_id == job_id)
+            .cloned()
+            .ok_or(RepositoryError::NotFound)
+    }
+
+    fn has_job(&self, unique_key: &str) -> RepoResult<bool> {
+        Ok(self
+            .jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|job| job.unique_key.as_deref() == Some(unique_key)))
+    }
+
+    fn jobs(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<Job>> {
+        Ok(self
+            .jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|job| job.property_id.is_none_or(|job_pid| job_pid == pid))
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    fn update_job(&self, job: &Job) -> RepoResult<()> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let stored = jobs
+            .iter_mut()
+            .find(|stored| stored.job_id == job.job_id)
+            .ok_or(RepositoryError::NotFound)?;
+        *stored = job.clone();
+        Ok(())
+    }
+
+    fn purge_jobs(&self, before: NaiveDateTime) -> RepoResult<usize> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let count = jobs.len();
+        jobs.retain(|job| {
+            !matches!(job.status, JobStatus::Done | JobStatus::Failed)
+                || job.finished_at.is_none_or(|at| at >= before)
+        });
+        Ok(count - jobs.len())
+    }
+}
+
+/// Fails with [`RepositoryError::Conflict`] when one of `contacts` belongs to
+/// a guest of the property other than `guest_id`.
+fn check_contacts_free(
+    stored: &[(Uuid, String, i32)],
+    pid: Uuid,
+    guest_id: Option<i32>,
+    contacts: &[String],
+) -> RepoResult<()> {
+    match stored.iter().find(|(property_id, contact, owner)| {
+        *property_id == pid && contacts.contains(contact) && Some(*owner) != guest_id
+    }) {
+        Some((_, contact, _)) => Err(RepositoryError::Conflict(format!(
+            "{contact} belongs to another guest"
+        ))),
+        None => Ok(()),
+    }
+}
+
+impl GuestRepository for InMemoryRepository {
+    fn guests(&self, pid: Uuid) -> RepoResult<Vec<Guest>> {
+        let mut found: Vec<Guest> = self
+            .guests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|guest| guest.property_id == pid)
+            .cloned()
+            .collect();
+        found.sort_by(|a, b| (&a.name, a.guest_id).cmp(&(&b.name, b.guest_id)));
+        Ok(found)
+    }
+
+    fn find_guest(&self, guest_id: i32) -> RepoResult<Guest> {
+        self.guests
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|guest| guest.guest_id == guest_id)
+            .cloned()
+            .ok_or(RepositoryError::NotFound)
+    }
+
+    fn guest_by_contact(&self, pid: Uuid, contact: &str) -> RepoResult<Guest> {
+        let guest_id = self
+            .guest_contacts
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(property_id, stored, _)| *property_id == pid && stored == contact)
+            .map(|(_, _, guest_id)| *guest_id)
+            .ok_or(RepositoryError::NotFound)?;
+        self.find_guest(guest_id)
+    }
+
+    fn insert_guest(&self, new_guest: NewGuest, contacts: &[String]) -> RepoResult<i32> {
+        let mut stored = self.guest_contacts.lock().unwrap();
+        check_contacts_free(&stored, new_guest.property_id, None, contacts)?;
+        let mut guests = self.guests.lock().unwrap();
+        let guest_id = guests.iter().map(|g| g.guest_id).max().unwrap_or(0) + 1;
+        let pid = new_guest.property_id;
+        guests.push(new_guest.into_guest(guest_id, Utc::now().naive_utc()));
+        stored.extend(
+            contacts
+                .iter()
+                .map(|contact| (pid, contact.clone(), guest_id)),
+        );
+        Ok(guest_id)
+    }
+
+    fn update_guest(&self, guest: &Guest) -> RepoResult<()> {
+        let mut guests = self.guests.lock().unwrap();
+        let stored = guests
+            .iter_mut()
+            .find(|g| g.guest_id == guest.guest_id)
+            .ok_or(RepositoryError::NotFound)?;
+        stored.name = guest.name.clone();
+        stored.notes = guest.notes.clone();
+        stored.tags = guest.tags.clone();
+        Ok(())
+    }
+
+    fn guest_contacts(&self, guest_id: i32) -> RepoResult<Vec<String>> {
+        let mut found: Vec<String> = self
+            .guest_contacts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, _, owner)| *owner == guest_id)
+            .map(|(_, contact, _)| contact.clone())
+            .collect();
+        found.sort();
+        Ok(found)
+    }
+
+    fn set_guest_contacts(&self, guest_id: i32, contacts: &[String]) -> RepoResult<()> {
+        let pid = self.find_guest(guest_id)?.property_id;
+        let mut stored = self.guest_contacts.lock().unwrap();
+        check_contacts_free(&stored, pid, Some(guest_id), contacts)?;
+        stored.retain(|(_, _, owner)| *owner != guest_id);
+        stored.extend(
+            contacts
+                .iter()
+                .map(|contact| (pid, contact.clone(), guest_id)),
+        );
+        Ok(())
+    }
+
+    fn guest_reservations(&self, guest_id: i32) -> RepoResult<Vec<Reservation>> {
+        let mut found: Vec<Reservation> = self
+            .reservations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.guest_id == Some(guest_id))
+            .cloned()
+            .collect();
+        found.sort_by(|a, b| {
+            (b.reservation_date, b.reservation_time).cmp(&(a.reservation_date, a.reservation_time))
+        });
+        Ok(found)
+    }
+
+    fn set_reservation_guest(&self, id: i32, guest_id: i32) -> RepoResult<()> {
+        let mut reservations = self.reservations.lock().unwrap();
+        let stored = reservations
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or(RepositoryError::NotFound)?;
+        stored.guest_id = Some(guest_id);
+        Ok(())
+    }
+    fn property_guest_contacts(&self, pid: Uuid) -> RepoResult<Vec<(i32, String)>> {
+        let mut found: Vec<(i32, String)> = self
+            .guest_contacts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(property_id, _, _)| *property_id == pid)
+            .map(|(_, contact, guest_id)| (*guest_id, contact.clone()))
+            .collect();
+        found.sort();
+        Ok(found)
+    }
+
+    fn merge_guests(&self, guest: &Guest, merge: NewGuestMerge) -> RepoResult<GuestMerge> {
+        let duplicate = merge.merged_guest_id;
+        let kept = guest.guest_id;
+        self.update_guest(guest)?;
+        let mut moved = 0;
+        for resv in self.reservations.lock().unwrap().iter_mut() {
+            if resv.guest_id == Some(duplicate) {
+                resv.guest_id = Some(kept);
+                moved += 1;
+            }
+        }
+        for (_, _, owner) in self.guest_contacts.lock().unwrap().iter_mut() {
+            if *owner == duplicate {
+                *owner = kept;
+            }
+        }
+        let mut merges = self.guest_merges.lock().unwrap();
+        for earlier in merges.iter_mut() {
+            if earlier.guest_id == duplicate {
+                earlier.guest_id = kept;
+            }
+        }
+        let merge_id = merges.iter().map(|m| m.merge_id).max().unwrap_or(0) + 1;
+        let recorded = merge.into_merge(merge_id, moved, Utc::now().naive_utc());
+        merges.push(recorded.clone());
+        self.guests
+            .lock()
+            .unwrap()
+            .retain(|g| g.guest_id != duplicate);
+        Ok(recorded)
+    }
+
+    fn guest_merges(&self, guest_id: i32) -> RepoResult<Vec<GuestMerge>> {
+        let mut found: Vec<GuestMerge> = self
+            .guest_merges
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| m.guest_id == guest_id)
+            .cloned()
+            .collect();
+        found.sort_by_key(|m| std::cmp::Reverse(m.merge_id));
+        Ok(found)
+    }
+}
+
+impl WaitlistRepository for InMemoryRepository {
+    fn waitlist(&self, pid: Uuid, date: NaiveDate) -> RepoResult<Vec<WaitlistEntry>> {
+        Ok(self
+            .waitlist
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| e.property_id == pid && e.waitlist_date == date)
+            .cloned()
+            .collect())
+    }
+
+    fn find_waitlist_entry(&self, entry_id: i32) -> RepoResult<WaitlistEntry> {
+        self.waitlist
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|e| e.entry_id == entry_id)
+            .cloned()
+            .ok_or(RepositoryError::NotFound)
+    }
+
+    fn insert_waitlist_entry(&self, new_entry: NewWaitlistEntry) -> RepoResult<i32> {
+        let mut waitlist = self.waitlist.lock().unwrap();
+        let eid = waitlist.iter().map(|e| e.entry_id).max().unwrap_or(0) + 1;
+        waitlist.push(new_entry.into_entry(eid, Utc::now().naive_utc()));
+        Ok(eid)
+    }
+
+    fn update_waitlist_entry(&self, entry: &WaitlistEntry) -> RepoResult<()> {
+        let mut waitlist = self.waitlist.lock().unwrap();
+        let stored = waitlist
+            .iter_mut()
+            .find(|e| e.entry_id == entry.entry_id)
+            .ok_or(RepositoryError::NotFound)?;
+        stored.quoted_wait = entry.quoted_wait;
+        stored.status = entry.status;
+        stored.notified_at = entry.notified_at;
+        stored.reservation_id = entry.reservation_id;
+        Ok(())
+    }
+}
+
+impl SeriesRepository for InMemoryRepository {
+    fn series(&self, pid: Uuid) -> RepoResult<Vec<ReservationSeries>> {
+        let mut found: Vec<ReservationSeries> = self
+            .series
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| s.property_id == pid)
+            .cloned()
+            .collect();
+        found.sort_by_key(|s| std::cmp::Reverse(s.series_id));
+        Ok(found)
+    }
+
+    fn find_series(&self, series_id: i32) -> RepoResult<ReservationSeries> {
+        self.series
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|s| s.series_id == series_id)
+            .cloned()
+            .ok_or(RepositoryError::NotFound)
+    }
+
+    fn insert_series(&self, new_series: NewReservationSeries) -> RepoResult<i32> {
+        let mut series = self.series.lock().unwrap();
+        let sid = series.iter().map(|s| s.series_id).max().unwrap_or(0) + 1;
+        series.push(new_series.into_series(sid, Utc::now().naive_utc()));
+        Ok(sid)
+    }
+
+    fn update_series(&self, series: &ReservationSeries) -> RepoResult<()> {
+        let mut stored_series = self.series.lock().unwrap();
+        let stored = stored_series
+            .iter_mut()
+            .find(|s| s.series_id == series.series_id)
+            .ok_or(RepositoryError::NotFound)?;
+        stored.seating = series.seating.clone();
+        stored.party_size = series.party_size;
+        stored.reservation_time = series.reservation_time;
+        stored.cancelled_at = series.cancelled_at;
+        Ok(())
+    }
+
+    fn occurrences(&self, series_id: i32) -> RepoResult<Vec<Reservation>> {
+        let mut found: Vec<Reservation> = self
+            .reservations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.series_id == Some(series_id))
+            .cloned()
+            .collect();
+        found.sort_by_key(|r| (r.reservation_date, r.reservation_time));
+        Ok(found)
+    }
+
+    fn delete_series(&self, series_id: i32) -> RepoResult<()> {
+        let mut series = self.series.lock().unwrap();
+        let before = series.len();
+        series.retain(|s| s.series_id != series_id);
+        if series.len() == before {
+            return Err(RepositoryError::NotFound);
+        }
+        let mut reservations = self.reservations.lock().unwrap();
+        let deleted: Vec<i32> = reservations
+            .iter()
+            .filter(|r| r.series_id == Some(series_id))
+            .map(|r| r.id)
+            .collect();
+        reservations.retain(|r| r.series_id != Some(series_id));
+        self.history
+            .lock()
+            .unwrap()
+            .retain(|entry| !deleted.contains(&entry.reservation_id));
+        Ok(())
+    }
+}
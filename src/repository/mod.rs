@@ -0,0 +1,385 @@
+//! Storage abstraction over properties and reservations.
+//!
+//! Server functions talk to the traits held in [`crate::AppState`] instead of
+//! the diesel DSL, so the business rules in [`crate::crud_properties`] can run
+//! against [`InMemoryRepository`] without a database.
+mod memory;
+mod postgres;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use memory::InMemoryRepository;
+pub use postgres::PgRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteRepository;
+
+use crate::errors::RepositoryError;
+use crate::models::{
+    ApiKey, Closure, Email, EmailTemplate, EventDetails, Guest, GuestMerge, HistoryEntry, Job,
+    NewApiKey, NewClosure, NewEmail, NewGuest, NewGuestMerge, NewHistoryEntry, NewJob, NewProperty,
+    NewPropertyTable, NewPropertyUser, NewReservationNote, NewReservationSeries, NewResv,
+    NewSmsMessage, NewWaitlistEntry, NewWebhook, NewWebhookDelivery, NotificationKind, Property,
+    PropertyTable, PropertyUsers, Reservation, ReservationNote, ReservationSeries,
+    ReservationStatus, SmsMessage, SpecialRequests, TableAssignment, TableLayout, WaitlistEntry,
+    Webhook, WebhookDelivery,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use std::fmt::Debug;
+use time::Time;
+use uuid::Uuid;
+
+pub type RepoResult<T> = Result<T, RepositoryError>;
+
+/// A `LIKE` pattern matching text that contains `text`, escaping wildcards
+/// with `\`.
+fn containing(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+pub trait PropertyRepository: Debug + Send + Sync {
+    /// All registered properties.
+    fn all(&self) -> RepoResult<Vec<Property>>;
+
+    /// A single property, or [`RepositoryError::NotFound`].
+    fn find(&self, pid: Uuid) -> RepoResult<Property>;
+
+    /// Stores a new property and returns its id.
+    fn insert(&self, new_property: NewProperty) -> RepoResult<Uuid>;
+
+    /// The predefined tables of a property, ordered by name.
+    fn tables(&self, pid: Uuid) -> RepoResult<Vec<PropertyTable>>;
+
+    /// Stores a new table and returns its id.
+    fn insert_table(&self, new_table: NewPropertyTable) -> RepoResult<i32>;
+
+    /// Moves a table to another section, or out of any.
+    fn set_table_section(&self, table_id: i32, section: Option<String>) -> RepoResult<()>;
+
+    /// Where the tables of a property sit on its floor plan, for those
+    /// placed on it.
+    fn layouts(&self, pid: Uuid) -> RepoResult<Vec<TableLayout>>;
+
+    /// Stores where a table sits on the floor plan, replacing earlier ones.
+    fn save_layout(&self, layout: &TableLayout) -> RepoResult<()>;
+
+    /// Stores a new staff member of a property and returns the user id.
+    fn insert_user(&self, new_user: NewPropertyUser) -> RepoResult<i32>;
+
+    /// A staff member of a property by user name.
+    fn find_user(&self, pid: Uuid, user_name: &str) -> RepoResult<PropertyUsers>;
+
+    /// Replaces the property password with an already salted hash.
+    fn set_password(&self, pid: Uuid, password_hash: String) -> RepoResult<()>;
+
+    /// Replaces a staff password with an already salted hash.
+    fn set_user_password(
+        &self,
+        pid: Uuid,
+        user_name: &str,
+        password_hash: String,
+    ) -> RepoResult<()>;
+
+    /// The closures of a property, the earliest first.
+    fn closures(&self, pid: Uuid) -> RepoResult<Vec<Closure>>;
+
+    /// Stores a new closure and returns its id.
+    fn insert_closure(&self, new_closure: NewClosure) -> RepoResult<i32>;
+
+    /// Removes a closure of a property.
+    fn delete_closure(&self, pid: Uuid, closure_id: i32) -> RepoResult<()>;
+}
+
+pub trait ReservationRepository: Debug + Send + Sync {
+    /// Reservations of a property, at most `limit` of them.
+    fn for_property(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<Reservation>>;
+
+    /// Reservations of a property carrying `tag`, ignoring case, at most
+    /// `limit` of them.
+    fn with_tag(&self, pid: Uuid, tag: &str, limit: i64) -> RepoResult<Vec<Reservation>>;
+
+    /// Reservations of a property on the given day.
+    fn on_date(&self, pid: Uuid, date: NaiveDate) -> RepoResult<Vec<Reservation>>;
+
+    /// Number of reservations a property holds.
+    fn count_for_property(&self, pid: Uuid) -> RepoResult<i64>;
+
+    /// Stores a new reservation and returns its id.
+    fn insert(&self, new_resv: NewResv) -> RepoResult<i32>;
+
+    /// A single reservation, or [`RepositoryError::NotFound`].
+    fn find(&self, id: i32) -> RepoResult<Reservation>;
+
+    /// Moves a reservation to `status` and returns the updated row. A
+    /// no-show is stamped with the current time in UTC, taken from the
+    /// server's clock by every backend.
+    fn set_status(&self, id: i32, status: ReservationStatus) -> RepoResult<Reservation>;
+
+    /// Records the advance paid for a reservation and returns the updated row.
+    fn set_payment(
+        &self,
+        id: i32,
+        advance_method: serde_json::Value,
+        advance_amount: i32,
+    ) -> RepoResult<Reservation>;
+
+    /// The reservation a manage-booking link points at.
+    fn find_by_token(&self, token: &str) -> RepoResult<Reservation>;
+
+    /// Moves a reservation to another day, time, table or party size and
+    /// returns the updated row.
+    fn reschedule(
+        &self,
+        id: i32,
+        date: NaiveDate,
+        time: Time,
+        seating: String,
+        party_size: i32,
+    ) -> RepoResult<Reservation>;
+
+    /// Records a change made to a reservation and returns its id.
+    fn insert_history(&self, entry: NewHistoryEntry) -> RepoResult<i32>;
+
+    /// The changes made to a reservation, oldest first.
+    fn history(&self, id: i32) -> RepoResult<Vec<HistoryEntry>>;
+
+    /// Replaces what the guest asked for and the staff tags of a reservation
+    /// and returns the updated row.
+    fn set_requests(
+        &self,
+        id: i32,
+        special_requests: SpecialRequests,
+        tags: String,
+    ) -> RepoResult<Reservation>;
+
+    /// Stores a staff note on a reservation and returns its id.
+    fn insert_note(&self, note: NewReservationNote) -> RepoResult<i32>;
+
+    /// The staff notes on a reservation, oldest first.
+    fn notes(&self, id: i32) -> RepoResult<Vec<ReservationNote>>;
+
+    /// The tables held by any of the given reservations.
+    fn table_assignments(&self, ids: &[i32]) -> RepoResult<Vec<TableAssignment>>;
+
+    /// Replaces the tables a reservation holds, none to let go of them all.
+    fn set_table_assignments(&self, id: i32, table_ids: &[i32]) -> RepoResult<()>;
+
+    /// The event details of a reservation, if it has any.
+    fn event_details(&self, id: i32) -> RepoResult<Option<EventDetails>>;
+
+    /// Stores the event details of a reservation, replacing earlier ones.
+    fn save_event_details(&self, details: &EventDetails) -> RepoResult<()>;
+}
+
+pub trait ApiKeyRepository: Debug + Send + Sync {
+    /// All keys of a property, revoked ones included.
+    fn keys(&self, pid: Uuid) -> RepoResult<Vec<ApiKey>>;
+
+    /// The key identified by the public prefix of an API key.
+    fn key_by_prefix(&self, prefix: &str) -> RepoResult<ApiKey>;
+
+    /// Stores a new key and returns its id.
+    fn insert_key(&self, new_key: NewApiKey) -> RepoResult<i32>;
+
+    /// Revokes a key of a property and returns the updated row.
+    fn revoke_key(&self, pid: Uuid, key_id: i32, at: NaiveDateTime) -> RepoResult<ApiKey>;
+
+    /// Records that a key authenticated a request.
+    fn touch_key(&self, key_id: i32, at: NaiveDateTime) -> RepoResult<()>;
+}
+
+pub trait WebhookRepository: Debug + Send + Sync {
+    /// All webhooks of a property, disabled ones included.
+    fn webhooks(&self, pid: Uuid) -> RepoResult<Vec<Webhook>>;
+
+    fn find_webhook(&self, webhook_id: i32) -> RepoResult<Webhook>;
+
+    /// Stores a new webhook and returns its id.
+    fn insert_webhook(&self, new_webhook: NewWebhook) -> RepoResult<i32>;
+
+    /// Enables or disables a webhook of a property and returns the updated row.
+    fn set_webhook_active(&self, pid: Uuid, webhook_id: i32, active: bool) -> RepoResult<Webhook>;
+
+    /// Queues a delivery and returns its id.
+    fn insert_delivery(&self, new_delivery: NewWebhookDelivery) -> RepoResult<i32>;
+
+    fn find_delivery(&self, delivery_id: i32) -> RepoResult<WebhookDelivery>;
+
+    /// The latest deliveries of a webhook, newest first.
+    fn deliveries(&self, webhook_id: i32, limit: i64) -> RepoResult<Vec<WebhookDelivery>>;
+
+    /// Pending deliveries whose next attempt is due at `now`, oldest first.
+    fn due_deliveries(&self, now: NaiveDateTime, limit: i64) -> RepoResult<Vec<WebhookDelivery>>;
+
+    /// Stores the outcome of a delivery attempt.
+    fn update_delivery(&self, delivery: &WebhookDelivery) -> RepoResult<()>;
+}
+
+pub trait EmailRepository: Debug + Send + Sync {
+    /// The templates a property changed from the defaults.
+    fn email_templates(&self, pid: Uuid) -> RepoResult<Vec<EmailTemplate>>;
+
+    /// Stores a template, replacing the property's previous one of its kind.
+    fn save_email_template(&self, template: EmailTemplate) -> RepoResult<()>;
+
+    /// Queues an email and returns its id.
+    fn insert_email(&self, new_email: NewEmail) -> RepoResult<i32>;
+
+    /// Emails of a property, newest first, at most `limit` of them.
+    fn emails(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<Email>>;
+
+    /// Pending emails whose next attempt is due at `now`, oldest first.
+    fn due_emails(&self, now: NaiveDateTime, limit: i64) -> RepoResult<Vec<Email>>;
+
+    /// Stores the outcome of an attempt.
+    fn update_email(&self, email: &Email) -> RepoResult<()>;
+
+    /// Whether an email of `kind` was already queued for the reservation.
+    fn has_email(&self, reservation_id: i32, kind: NotificationKind) -> RepoResult<bool>;
+
+    /// Deletes emails that are no longer pending and were queued before
+    /// `before`, and returns how many.
+    fn purge_emails(&self, before: NaiveDateTime) -> RepoResult<usize>;
+}
+
+pub trait SmsRepository: Debug + Send + Sync {
+    /// Stores a text message and returns its id.
+    fn insert_message(&self, new_message: NewSmsMessage) -> RepoResult<i32>;
+
+    /// Text messages of a property, newest first, at most `limit` of them.
+    fn messages(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<SmsMessage>>;
+
+    /// Queued messages whose next attempt is due at `now`, oldest first.
+    fn due_messages(&self, now: NaiveDateTime, limit: i64) -> RepoResult<Vec<SmsMessage>>;
+
+    /// The message the gateway knows as `provider_id`.
+    fn message_by_provider_id(&self, provider_id: &str) -> RepoResult<SmsMessage>;
+
+    /// Stores the outcome of an attempt or a status callback.
+    fn update_message(&self, message: &SmsMessage) -> RepoResult<()>;
+
+    /// Whether a message of `kind` was already stored for the reservation.
+    fn has_message(&self, reservation_id: i32, kind: NotificationKind) -> RepoResult<bool>;
+
+    /// Whether the E.164 number asked not to get messages.
+    fn opted_out(&self, phone: &str) -> RepoResult<bool>;
+
+    /// Records that the number opted out, or back in.
+    fn set_opted_out(&self, phone: &str, opted_out: bool, at: NaiveDateTime) -> RepoResult<()>;
+
+    /// Deletes messages that are no longer queued and were stored before
+    /// `before`, and returns how many.
+    fn purge_messages(&self, before: NaiveDateTime) -> RepoResult<usize>;
+}
+
+pub trait JobRepository: Debug + Send + Sync {
+    /// Stores a job and returns its id. A job with the same `unique_key` is
+    /// moved to the new `run_at` and made pending again instead.
+    fn schedule_job(&self, new_job: NewJob) -> RepoResult<i32>;
+
+    /// Marks up to `limit` jobs running until `locked_until` and returns
+    /// them: pending ones due at `now` and running ones whose lock expired.
+    /// Concurrent workers never claim the same job.
+    fn claim_jobs(
+        &self,
+        now: NaiveDateTime,
+        locked_until: NaiveDateTime,
+        limit: i64,
+    ) -> RepoResult<Vec<Job>>;
+
+    fn find_job(&self, job_id: i32) -> RepoResult<Job>;
+
+    /// Whether a job with `unique_key` is stored, whatever its status.
+    fn has_job(&self, unique_key: &str) -> RepoResult<bool>;
+
+    /// Jobs of a property and the periodic ones, newest first, at most
+    /// `limit` of them.
+    fn jobs(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<Job>>;
+
+    /// Stores the outcome of a run.
+    fn update_job(&self, job: &Job) -> RepoResult<()>;
+
+    /// Deletes done and failed jobs finished before `before`, and returns
+    /// how many.
+    fn purge_jobs(&self, before: NaiveDateTime) -> RepoResult<usize>;
+}
+
+pub trait GuestRepository: Debug + Send + Sync {
+    /// Guests of a property, ordered by name.
+    fn guests(&self, pid: Uuid) -> RepoResult<Vec<Guest>>;
+
+    fn find_guest(&self, guest_id: i32) -> RepoResult<Guest>;
+
+    /// The guest of a property a normalised contact belongs to.
+    fn guest_by_contact(&self, pid: Uuid, contact: &str) -> RepoResult<Guest>;
+
+    /// Stores a new guest with their normalised contacts and returns its id.
+    /// A contact that already belongs to a guest is a
+    /// [`RepositoryError::Conflict`] and nothing is stored.
+    fn insert_guest(&self, new_guest: NewGuest, contacts: &[String]) -> RepoResult<i32>;
+
+    /// Stores the name, notes and tags of a guest.
+    fn update_guest(&self, guest: &Guest) -> RepoResult<()>;
+
+    /// The normalised contacts of a guest, sorted.
+    fn guest_contacts(&self, guest_id: i32) -> RepoResult<Vec<String>>;
+
+    /// Replaces the contacts of a guest. A contact of another guest is a
+    /// [`RepositoryError::Conflict`] and nothing changes.
+    fn set_guest_contacts(&self, guest_id: i32, contacts: &[String]) -> RepoResult<()>;
+
+    /// The reservations of a guest, the latest first.
+    fn guest_reservations(&self, guest_id: i32) -> RepoResult<Vec<Reservation>>;
+
+    /// Links a reservation to a guest.
+    fn set_reservation_guest(&self, id: i32, guest_id: i32) -> RepoResult<()>;
+
+    /// Every contact of the guests of a property, with the guest it belongs
+    /// to.
+    fn property_guest_contacts(&self, pid: Uuid) -> RepoResult<Vec<(i32, String)>>;
+
+    /// Merges the guest `merge.merged_guest_id` into `guest`, whose name,
+    /// notes and tags are stored: its reservations, contacts and earlier
+    /// merges move over, the merge is recorded and the duplicate deleted.
+    fn merge_guests(&self, guest: &Guest, merge: NewGuestMerge) -> RepoResult<GuestMerge>;
+
+    /// The duplicates merged into a guest, the latest first.
+    fn guest_merges(&self, guest_id: i32) -> RepoResult<Vec<GuestMerge>>;
+}
+
+pub trait WaitlistRepository: Debug + Send + Sync {
+    /// The waitlist of a property for one day, oldest entry first.
+    fn waitlist(&self, pid: Uuid, date: NaiveDate) -> RepoResult<Vec<WaitlistEntry>>;
+
+    fn find_waitlist_entry(&self, entry_id: i32) -> RepoResult<WaitlistEntry>;
+
+    /// Stores a new entry and returns its id.
+    fn insert_waitlist_entry(&self, new_entry: NewWaitlistEntry) -> RepoResult<i32>;
+
+    /// Stores the quoted wait, status, notification time and reservation of
+    /// an entry.
+    fn update_waitlist_entry(&self, entry: &WaitlistEntry) -> RepoResult<()>;
+}
+
+pub trait SeriesRepository: Debug + Send + Sync {
+    /// The recurring series of a property, the latest first.
+    fn series(&self, pid: Uuid) -> RepoResult<Vec<ReservationSeries>>;
+
+    fn find_series(&self, series_id: i32) -> RepoResult<ReservationSeries>;
+
+    /// Stores a new series and returns its id.
+    fn insert_series(&self, new_series: NewReservationSeries) -> RepoResult<i32>;
+
+    /// Stores the seating, party size, time and cancellation of a series.
+    fn update_series(&self, series: &ReservationSeries) -> RepoResult<()>;
+
+    /// The reservations booked for a series, the earliest first.
+    fn occurrences(&self, series_id: i32) -> RepoResult<Vec<Reservation>>;
+
+    /// Deletes a series together with its occurrences and their history, as
+    /// when booking a new series failed half way.
+    fn delete_series(&self, series_id: i32) -> RepoResult<()>;
+}
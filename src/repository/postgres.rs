@@ -0,0 +1,1269 @@
+use super::{
+    containing, ApiKeyRepository, EmailRepository, GuestRepository, JobRepository,
+    PropertyRepository, RepoResult, ReservationRepository, SeriesRepository, SmsRepository,
+    WaitlistRepository, WebhookRepository,
+};
+use crate::errors::RepositoryError;
+use crate::models::{
+    ApiKey, Closure, DeliveryStatus, Email, EmailTemplate, EventDetails, Guest, GuestMerge,
+    HistoryEntry, Job, JobStatus, MessageStatus, NewApiKey, NewClosure, NewEmail, NewGuest,
+    NewGuestMerge, NewHistoryEntry, NewJob, NewProperty, NewPropertyTable, NewPropertyUser,
+    NewReservationNote, NewReservationSeries, NewResv, NewSmsMessage, NewWaitlistEntry, NewWebhook,
+    NewWebhookDelivery, NotificationKind, Property, PropertyTable, PropertyUsers, Reservation,
+    ReservationNote, ReservationSeries, ReservationStatus, SmsMessage, SpecialRequests,
+    TableAssignment, TableLayout, WaitlistEntry, Webhook, WebhookDelivery,
+};
+use crate::SharedPooledConnection;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use time::Time;
+use uuid::Uuid;
+
+/// Repository backed by the shared Postgres connection pool.
+#[derive(Debug, Clone)]
+pub struct PgRepository {
+    pool: SharedPooledConnection,
+}
+
+impl PgRepository {
+    pub fn new(pool: SharedPooledConnection) -> Self {
+        Self { pool }
+    }
+}
+
+impl PropertyRepository for PgRepository {
+    fn all(&self) -> RepoResult<Vec<Property>> {
+        use crate::schema::property::dsl::property;
+
+        Ok(property
+            .select(Property::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn find(&self, pid: Uuid) -> RepoResult<Property> {
+        use crate::schema::property::dsl::{property, property_id};
+
+        Ok(property
+            .filter(property_id.eq(pid))
+            .select(Property::as_select())
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn insert(&self, new_property: NewProperty) -> RepoResult<Uuid> {
+        use crate::schema::property::dsl::{property, property_id};
+
+        Ok(diesel::insert_into(property)
+            .values(&new_property)
+            .returning(property_id)
+            .get_result::<Uuid>(&mut self.pool.get()?)?)
+    }
+
+    fn tables(&self, pid: Uuid) -> RepoResult<Vec<PropertyTable>> {
+        use crate::schema::property_tables::dsl::{property_id, property_tables, table_name};
+
+        Ok(property_tables
+            .filter(property_id.eq(pid))
+            .order(table_name)
+            .select(PropertyTable::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn insert_table(&self, new_table: NewPropertyTable) -> RepoResult<i32> {
+        use crate::schema::property_tables::dsl::{property_tables, table_id};
+
+        Ok(diesel::insert_into(property_tables)
+            .values(&new_table)
+            .returning(table_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn set_table_section(&self, tid: i32, new_section: Option<String>) -> RepoResult<()> {
+        use crate::schema::property_tables::dsl::{property_tables, section, table_id};
+
+        let updated = diesel::update(property_tables.filter(table_id.eq(tid)))
+            .set(section.eq(new_section))
+            .execute(&mut self.pool.get()?)?;
+        if updated == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+        Ok(())
+    }
+
+    fn layouts(&self, pid: Uuid) -> RepoResult<Vec<TableLayout>> {
+        use crate::schema::property_tables;
+        use crate::schema::table_layouts;
+
+        Ok(table_layouts::table
+            .inner_join(property_tables::table)
+            .filter(property_tables::property_id.eq(pid))
+            .select(TableLayout::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn save_layout(&self, layout: &TableLayout) -> RepoResult<()> {
+        use crate::schema::table_layouts::dsl::{
+            height, rotation, shape, table_id, table_layouts, width, x, y,
+        };
+
+        diesel::insert_into(table_layouts)
+            .values(layout)
+            .on_conflict(table_id)
+            .do_update()
+            .set((
+                x.eq(layout.x),
+                y.eq(layout.y),
+                width.eq(layout.width),
+                height.eq(layout.height),
+                rotation.eq(layout.rotation),
+                shape.eq(layout.shape),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+
+    fn insert_user(&self, new_user: NewPropertyUser) -> RepoResult<i32> {
+        use crate::schema::propertyusers::dsl::{propertyusers, user_id};
+
+        Ok(diesel::insert_into(propertyusers)
+            .values(&new_user)
+            .returning(user_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn find_user(&self, pid: Uuid, name: &str) -> RepoResult<PropertyUsers> {
+        use crate::schema::propertyusers::dsl::{property_id, propertyusers, user_name};
+
+        Ok(propertyusers
+            .filter(property_id.eq(pid))
+            .filter(user_name.eq(name))
+            .select(PropertyUsers::as_select())
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn set_password(&self, pid: Uuid, password_hash: String) -> RepoResult<()> {
+        use crate::schema::property::dsl::{property, property_id, property_password};
+
+        match diesel::update(property.filter(property_id.eq(pid)))
+            .set(property_password.eq(password_hash))
+            .execute(&mut self.pool.get()?)?
+        {
+            0 => Err(RepositoryError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    fn set_user_password(&self, pid: Uuid, name: &str, password_hash: String) -> RepoResult<()> {
+        use crate::schema::propertyusers::dsl::{
+            property_id, propertyusers, user_name, user_password,
+        };
+
+        match diesel::update(
+            propertyusers
+                .filter(property_id.eq(pid))
+                .filter(user_name.eq(name)),
+        )
+        .set(user_password.eq(password_hash))
+        .execute(&mut self.pool.get()?)?
+        {
+            0 => Err(RepositoryError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    fn closures(&self, pid: Uuid) -> RepoResult<Vec<Closure>> {
+        use crate::schema::closures::dsl::{closure_id, closures, first_day, property_id};
+
+        Ok(closures
+            .filter(property_id.eq(pid))
+            .order((first_day, closure_id))
+            .select(Closure::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn insert_closure(&self, new_closure: NewClosure) -> RepoResult<i32> {
+        use crate::schema::closures::dsl::{closure_id, closures};
+
+        Ok(diesel::insert_into(closures)
+            .values(&new_closure)
+            .returning(closure_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn delete_closure(&self, pid: Uuid, cid: i32) -> RepoResult<()> {
+        use crate::schema::closures::dsl::{closure_id, closures, property_id};
+
+        match diesel::delete(
+            closures
+                .filter(property_id.eq(pid))
+                .filter(closure_id.eq(cid)),
+        )
+        .execute(&mut self.pool.get()?)?
+        {
+            0 => Err(RepositoryError::NotFound),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl ReservationRepository for PgRepository {
+    fn for_property(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<Reservation>> {
+        use crate::schema::reservation::dsl::{property_id, reservation};
+
+        Ok(reservation
+            .filter(property_id.eq(pid))
+            .limit(limit)
+            .select(Reservation::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn with_tag(&self, pid: Uuid, tag: &str, limit: i64) -> RepoResult<Vec<Reservation>> {
+        use crate::schema::reservation::dsl::{property_id, reservation, tags};
+
+        // The pattern narrows the rows down, whole tags are matched here.
+        Ok(reservation
+            .filter(property_id.eq(pid))
+            .filter(tags.ilike(containing(tag.trim())).escape('\\'))
+            .select(Reservation::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .filter(|resv| resv.has_tag(tag))
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    fn on_date(&self, pid: Uuid, date: NaiveDate) -> RepoResult<Vec<Reservation>> {
+        use crate::schema::reservation::dsl::{
+            property_id, reservation, reservation_date, reservation_time,
+        };
+
+        Ok(reservation
+            .filter(property_id.eq(pid))
+            .filter(reservation_date.eq(date))
+            .order(reservation_time)
+            .select(Reservation::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn count_for_property(&self, pid: Uuid) -> RepoResult<i64> {
+        use crate::schema::reservation::dsl::{property_id, reservation};
+
+        Ok(reservation
+            .filter(property_id.eq(pid))
+            .count()
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn insert(&self, new_resv: NewResv) -> RepoResult<i32> {
+        use crate::schema::reservation::dsl::{id, reservation};
+
+        Ok(diesel::insert_into(reservation)
+            .values(&new_resv)
+            .returning(id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn find(&self, rid: i32) -> RepoResult<Reservation> {
+        use crate::schema::reservation::dsl::{id, reservation};
+
+        Ok(reservation
+            .filter(id.eq(rid))
+            .select(Reservation::as_select())
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn set_status(&self, rid: i32, new_status: ReservationStatus) -> RepoResult<Reservation> {
+        use crate::schema::reservation::dsl::{id, no_show_at, reservation, status};
+
+        let target = diesel::update(reservation.filter(id.eq(rid)));
+        let conn = &mut self.pool.get()?;
+        Ok(if new_status == ReservationStatus::NoShow {
+            target
+                .set((
+                    status.eq(new_status),
+                    no_show_at.eq(Some(Utc::now().naive_utc())),
+                ))
+                .returning(Reservation::as_returning())
+                .get_result(conn)?
+        } else {
+            target
+                .set(status.eq(new_status))
+                .returning(Reservation::as_returning())
+                .get_result(conn)?
+        })
+    }
+
+    fn set_payment(
+        &self,
+        rid: i32,
+        method: serde_json::Value,
+        amount: i32,
+    ) -> RepoResult<Reservation> {
+        use crate::schema::reservation::dsl::{
+            advance, advance_amount, advance_method, id, reservation,
+        };
+
+        Ok(diesel::update(reservation.filter(id.eq(rid)))
+            .set((
+                advance.eq(true),
+                advance_method.eq(method),
+                advance_amount.eq(Some(amount)),
+            ))
+            .returning(Reservation::as_returning())
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn find_by_token(&self, token: &str) -> RepoResult<Reservation> {
+        use crate::schema::reservation::dsl::{confirmation_token, reservation};
+
+        Ok(reservation
+            .filter(confirmation_token.eq(token))
+            .select(Reservation::as_select())
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn reschedule(
+        &self,
+        rid: i32,
+        date: NaiveDate,
+        time: Time,
+        table: String,
+        guests: i32,
+    ) -> RepoResult<Reservation> {
+        use crate::schema::reservation::dsl::{
+            id, party_size, reservation, reservation_date, reservation_time, seating,
+        };
+
+        Ok(diesel::update(reservation.filter(id.eq(rid)))
+            .set((
+                reservation_date.eq(date),
+                reservation_time.eq(time),
+                seating.eq(table),
+                party_size.eq(guests),
+            ))
+            .returning(Reservation::as_returning())
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn insert_history(&self, entry: NewHistoryEntry) -> RepoResult<i32> {
+        use crate::schema::reservation_history::dsl::{entry_id, reservation_history};
+
+        Ok(diesel::insert_into(reservation_history)
+            .values(&entry)
+            .returning(entry_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn history(&self, rid: i32) -> RepoResult<Vec<HistoryEntry>> {
+        use crate::schema::reservation_history::dsl::{
+            entry_id, reservation_history, reservation_id,
+        };
+
+        Ok(reservation_history
+            .filter(reservation_id.eq(rid))
+            .order(entry_id)
+            .select(HistoryEntry::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn set_requests(
+        &self,
+        rid: i32,
+        special_requests: SpecialRequests,
+        new_tags: String,
+    ) -> RepoResult<Reservation> {
+        use crate::schema::reservation::dsl::{id, reservation, tags};
+
+        Ok(diesel::update(reservation.filter(id.eq(rid)))
+            .set((&special_requests, tags.eq(new_tags)))
+            .returning(Reservation::as_returning())
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn insert_note(&self, note: NewReservationNote) -> RepoResult<i32> {
+        use crate::schema::reservation_notes::dsl::{note_id, reservation_notes};
+
+        Ok(diesel::insert_into(reservation_notes)
+            .values(&note)
+            .returning(note_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn notes(&self, rid: i32) -> RepoResult<Vec<ReservationNote>> {
+        use crate::schema::reservation_notes::dsl::{note_id, reservation_id, reservation_notes};
+
+        Ok(reservation_notes
+            .filter(reservation_id.eq(rid))
+            .order(note_id)
+            .select(ReservationNote::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn table_assignments(&self, ids: &[i32]) -> RepoResult<Vec<TableAssignment>> {
+        use crate::schema::reservation_tables::dsl::{reservation_id, reservation_tables};
+
+        Ok(reservation_tables
+            .filter(reservation_id.eq_any(ids))
+            .select(TableAssignment::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn set_table_assignments(&self, rid: i32, table_ids: &[i32]) -> RepoResult<()> {
+        use crate::schema::reservation_tables::dsl::{reservation_id, reservation_tables};
+
+        let assignments: Vec<TableAssignment> = table_ids
+            .iter()
+            .map(|&table_id| TableAssignment {
+                reservation_id: rid,
+                table_id,
+            })
+            .collect();
+        let conn = &mut self.pool.get()?;
+        conn.transaction(|conn| {
+            diesel::delete(reservation_tables.filter(reservation_id.eq(rid))).execute(conn)?;
+            if !assignments.is_empty() {
+                diesel::insert_into(reservation_tables)
+                    .values(&assignments)
+                    .execute(conn)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn event_details(&self, rid: i32) -> RepoResult<Option<EventDetails>> {
+        use crate::schema::reservation_events::dsl::{reservation_events, reservation_id};
+
+        Ok(reservation_events
+            .filter(reservation_id.eq(rid))
+            .select(EventDetails::as_select())
+            .get_result(&mut self.pool.get()?)
+            .optional()?)
+    }
+
+    fn save_event_details(&self, details: &EventDetails) -> RepoResult<()> {
+        use crate::schema::reservation_events::dsl::{
+            menu, minimum_spend, reservation_events, reservation_id, section,
+        };
+
+        diesel::insert_into(reservation_events)
+            .values(details)
+            .on_conflict(reservation_id)
+            .do_update()
+            .set((
+                section.eq(&details.section),
+                menu.eq(&details.menu),
+                minimum_spend.eq(details.minimum_spend),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+}
+
+impl ApiKeyRepository for PgRepository {
+    fn keys(&self, pid: Uuid) -> RepoResult<Vec<ApiKey>> {
+        use crate::schema::api_keys::dsl::{api_keys, created_at, property_id};
+
+        Ok(api_keys
+            .filter(property_id.eq(pid))
+            .order(created_at.desc())
+            .select(ApiKey::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn key_by_prefix(&self, prefix: &str) -> RepoResult<ApiKey> {
+        use crate::schema::api_keys::dsl::{api_keys, key_prefix};
+
+        Ok(api_keys
+            .filter(key_prefix.eq(prefix))
+            .select(ApiKey::as_select())
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn insert_key(&self, new_key: NewApiKey) -> RepoResult<i32> {
+        use crate::schema::api_keys::dsl::{api_keys, key_id};
+
+        Ok(diesel::insert_into(api_keys)
+            .values(&new_key)
+            .returning(key_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn revoke_key(&self, pid: Uuid, kid: i32, at: NaiveDateTime) -> RepoResult<ApiKey> {
+        use crate::schema::api_keys::dsl::{api_keys, key_id, property_id, revoked_at};
+
+        Ok(
+            diesel::update(api_keys.filter(key_id.eq(kid)).filter(property_id.eq(pid)))
+                .set(revoked_at.eq(Some(at)))
+                .returning(ApiKey::as_returning())
+                .get_result(&mut self.pool.get()?)?,
+        )
+    }
+
+    fn touch_key(&self, kid: i32, at: NaiveDateTime) -> RepoResult<()> {
+        use crate::schema::api_keys::dsl::{api_keys, key_id, last_used_at};
+
+        diesel::update(api_keys.filter(key_id.eq(kid)))
+            .set(last_used_at.eq(Some(at)))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+}
+
+impl WebhookRepository for PgRepository {
+    fn webhooks(&self, pid: Uuid) -> RepoResult<Vec<Webhook>> {
+        use crate::schema::webhooks::dsl::{property_id, webhook_id, webhooks};
+
+        Ok(webhooks
+            .filter(property_id.eq(pid))
+            .order(webhook_id.asc())
+            .select(Webhook::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn find_webhook(&self, wid: i32) -> RepoResult<Webhook> {
+        use crate::schema::webhooks::dsl::{webhook_id, webhooks};
+
+        Ok(webhooks
+            .filter(webhook_id.eq(wid))
+            .select(Webhook::as_select())
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn insert_webhook(&self, new_webhook: NewWebhook) -> RepoResult<i32> {
+        use crate::schema::webhooks::dsl::{webhook_id, webhooks};
+
+        Ok(diesel::insert_into(webhooks)
+            .values(&new_webhook)
+            .returning(webhook_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn set_webhook_active(&self, pid: Uuid, wid: i32, is_active: bool) -> RepoResult<Webhook> {
+        use crate::schema::webhooks::dsl::{active, property_id, webhook_id, webhooks};
+
+        Ok(diesel::update(
+            webhooks
+                .filter(webhook_id.eq(wid))
+                .filter(property_id.eq(pid)),
+        )
+        .set(active.eq(is_active))
+        .returning(Webhook::as_returning())
+        .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn insert_delivery(&self, new_delivery: NewWebhookDelivery) -> RepoResult<i32> {
+        use crate::schema::webhook_deliveries::dsl::{delivery_id, webhook_deliveries};
+
+        Ok(diesel::insert_into(webhook_deliveries)
+            .values(&new_delivery)
+            .returning(delivery_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn find_delivery(&self, did: i32) -> RepoResult<WebhookDelivery> {
+        use crate::schema::webhook_deliveries::dsl::{delivery_id, webhook_deliveries};
+
+        Ok(webhook_deliveries
+            .filter(delivery_id.eq(did))
+            .select(WebhookDelivery::as_select())
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn deliveries(&self, wid: i32, limit: i64) -> RepoResult<Vec<WebhookDelivery>> {
+        use crate::schema::webhook_deliveries::dsl::{delivery_id, webhook_deliveries, webhook_id};
+
+        Ok(webhook_deliveries
+            .filter(webhook_id.eq(wid))
+            .order(delivery_id.desc())
+            .limit(limit)
+            .select(WebhookDelivery::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn due_deliveries(&self, now: NaiveDateTime, limit: i64) -> RepoResult<Vec<WebhookDelivery>> {
+        use crate::schema::webhook_deliveries::dsl::{next_attempt_at, status, webhook_deliveries};
+
+        Ok(webhook_deliveries
+            .filter(status.eq(DeliveryStatus::Pending))
+            .filter(next_attempt_at.le(now))
+            .order(next_attempt_at.asc())
+            .limit(limit)
+            .select(WebhookDelivery::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn update_delivery(&self, delivery: &WebhookDelivery) -> RepoResult<()> {
+        use crate::schema::webhook_deliveries::dsl::*;
+
+        diesel::update(webhook_deliveries.filter(delivery_id.eq(delivery.delivery_id)))
+            .set((
+                status.eq(delivery.status),
+                attempts.eq(delivery.attempts),
+                next_attempt_at.eq(delivery.next_attempt_at),
+                response_status.eq(delivery.response_status),
+                last_error.eq(&delivery.last_error),
+                delivered_at.eq(delivery.delivered_at),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+}
+
+impl EmailRepository for PgRepository {
+    fn email_templates(&self, pid: Uuid) -> RepoResult<Vec<EmailTemplate>> {
+        use crate::schema::email_templates::dsl::{email_templates, kind, property_id};
+
+        Ok(email_templates
+            .filter(property_id.eq(pid))
+            .order(kind)
+            .select(EmailTemplate::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn save_email_template(&self, template: EmailTemplate) -> RepoResult<()> {
+        use crate::schema::email_templates::dsl::{
+            body, email_templates, kind, property_id, subject,
+        };
+
+        diesel::insert_into(email_templates)
+            .values(&template)
+            .on_conflict((property_id, kind))
+            .do_update()
+            .set((subject.eq(&template.subject), body.eq(&template.body)))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+
+    fn insert_email(&self, new_email: NewEmail) -> RepoResult<i32> {
+        use crate::schema::emails::dsl::{email_id, emails};
+
+        Ok(diesel::insert_into(emails)
+            .values(&new_email)
+            .returning(email_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn emails(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<Email>> {
+        use crate::schema::emails::dsl::{email_id, emails, property_id};
+
+        Ok(emails
+            .filter(property_id.eq(pid))
+            .order(email_id.desc())
+            .limit(limit)
+            .select(Email::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn due_emails(&self, now: NaiveDateTime, limit: i64) -> RepoResult<Vec<Email>> {
+        use crate::schema::emails::dsl::{emails, next_attempt_at, status};
+
+        Ok(emails
+            .filter(status.eq(DeliveryStatus::Pending))
+            .filter(next_attempt_at.le(now))
+            .order(next_attempt_at.asc())
+            .limit(limit)
+            .select(Email::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn update_email(&self, email: &Email) -> RepoResult<()> {
+        use crate::schema::emails::dsl::*;
+
+        diesel::update(emails.filter(email_id.eq(email.email_id)))
+            .set((
+                status.eq(email.status),
+                attempts.eq(email.attempts),
+                next_attempt_at.eq(email.next_attempt_at),
+                last_error.eq(&email.last_error),
+                sent_at.eq(email.sent_at),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+
+    fn has_email(&self, rid: i32, email_kind: NotificationKind) -> RepoResult<bool> {
+        use crate::schema::emails::dsl::{emails, kind, reservation_id};
+
+        Ok(diesel::select(diesel::dsl::exists(
+            emails
+                .filter(reservation_id.eq(rid))
+                .filter(kind.eq(email_kind)),
+        ))
+        .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn purge_emails(&self, before: NaiveDateTime) -> RepoResult<usize> {
+        use crate::schema::emails::dsl::{created_at, emails, status};
+
+        Ok(diesel::delete(
+            emails
+                .filter(status.ne(DeliveryStatus::Pending))
+                .filter(created_at.lt(before)),
+        )
+        .execute(&mut self.pool.get()?)?)
+    }
+}
+
+impl SmsRepository for PgRepository {
+    fn insert_message(&self, new_message: NewSmsMessage) -> RepoResult<i32> {
+        use crate::schema::sms_messages::dsl::{message_id, sms_messages};
+
+        Ok(diesel::insert_into(sms_messages)
+            .values(&new_message)
+            .returning(message_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn messages(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<SmsMessage>> {
+        use crate::schema::sms_messages::dsl::{message_id, property_id, sms_messages};
+
+        Ok(sms_messages
+            .filter(property_id.eq(pid))
+            .order(message_id.desc())
+            .limit(limit)
+            .select(SmsMessage::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn due_messages(&self, now: NaiveDateTime, limit: i64) -> RepoResult<Vec<SmsMessage>> {
+        use crate::schema::sms_messages::dsl::{next_attempt_at, sms_messages, status};
+
+        Ok(sms_messages
+            .filter(status.eq(MessageStatus::Queued))
+            .filter(next_attempt_at.le(now))
+            .order(next_attempt_at.asc())
+            .limit(limit)
+            .select(SmsMessage::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn message_by_provider_id(&self, pid: &str) -> RepoResult<SmsMessage> {
+        use crate::schema::sms_messages::dsl::{provider_id, sms_messages};
+
+        Ok(sms_messages
+            .filter(provider_id.eq(pid))
+            .select(SmsMessage::as_select())
+            .first(&mut self.pool.get()?)?)
+    }
+
+    fn update_message(&self, message: &SmsMessage) -> RepoResult<()> {
+        use crate::schema::sms_messages::dsl::*;
+
+        diesel::update(sms_messages.filter(message_id.eq(message.message_id)))
+            .set((
+                status.eq(message.status),
+                attempts.eq(message.attempts),
+                next_attempt_at.eq(message.next_attempt_at),
+                provider_id.eq(&message.provider_id),
+                last_error.eq(&message.last_error),
+                sent_at.eq(message.sent_at),
+                delivered_at.eq(message.delivered_at),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+
+    fn has_message(&self, rid: i32, message_kind: NotificationKind) -> RepoResult<bool> {
+        use crate::schema::sms_messages::dsl::{kind, reservation_id, sms_messages};
+
+        Ok(diesel::select(diesel::dsl::exists(
+            sms_messages
+                .filter(reservation_id.eq(rid))
+                .filter(kind.eq(message_kind)),
+        ))
+        .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn opted_out(&self, number: &str) -> RepoResult<bool> {
+        use crate::schema::sms_opt_outs::dsl::{phone, sms_opt_outs};
+
+        Ok(
+            diesel::select(diesel::dsl::exists(sms_opt_outs.filter(phone.eq(number))))
+                .get_result(&mut self.pool.get()?)?,
+        )
+    }
+
+    fn set_opted_out(&self, number: &str, opted: bool, at: NaiveDateTime) -> RepoResult<()> {
+        use crate::schema::sms_opt_outs::dsl::{opted_out_at, phone, sms_opt_outs};
+
+        let mut conn = self.pool.get()?;
+        if opted {
+            diesel::insert_into(sms_opt_outs)
+                .values((phone.eq(number), opted_out_at.eq(at)))
+                .on_conflict_do_nothing()
+                .execute(&mut conn)?;
+        } else {
+            diesel::delete(sms_opt_outs.filter(phone.eq(number))).execute(&mut conn)?;
+        }
+        Ok(())
+    }
+
+    fn purge_messages(&self, before: NaiveDateTime) -> RepoResult<usize> {
+        use crate::schema::sms_messages::dsl::{created_at, sms_messages, status};
+
+        Ok(diesel::delete(
+            sms_messages
+                .filter(status.ne(MessageStatus::Queued))
+                .filter(created_at.lt(before)),
+        )
+        .execute(&mut self.pool.get()?)?)
+    }
+}
+
+impl JobRepository for PgRepository {
+    fn schedule_job(&self, new_job: NewJob) -> RepoResult<i32> {
+        use crate::schema::jobs::dsl::*;
+
+        Ok(diesel::insert_into(jobs)
+            .values(&new_job)
+            .on_conflict(unique_key)
+            .do_update()
+            .set((
+                run_at.eq(new_job.run_at),
+                status.eq(JobStatus::Pending),
+                attempts.eq(0),
+                last_error.eq(None::<String>),
+                locked_until.eq(None::<NaiveDateTime>),
+            ))
+            .returning(job_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn claim_jobs(
+        &self,
+        now: NaiveDateTime,
+        lock: NaiveDateTime,
+        limit: i64,
+    ) -> RepoResult<Vec<Job>> {
+        use crate::schema::jobs::dsl::*;
+
+        let mut conn = self.pool.get()?;
+        let mut claimed = conn.transaction::<_, RepositoryError, _>(|conn| {
+            let due: Vec<i32> = jobs
+                .filter(
+                    status
+                        .eq(JobStatus::Pending)
+                        .and(run_at.le(now))
+                        .or(status.eq(JobStatus::Running).and(locked_until.lt(now))),
+                )
+                .order(run_at.asc())
+                .limit(limit)
+                .select(job_id)
+                .for_update()
+                .skip_locked()
+                .load(conn)?;
+            Ok(diesel::update(jobs.filter(job_id.eq_any(due)))
+                .set((
+                    status.eq(JobStatus::Running),
+                    locked_until.eq(lock),
+                    attempts.eq(attempts + 1),
+                ))
+                .returning(Job::as_returning())
+                .get_results(conn)?)
+        })?;
+        claimed.sort_by_key(|job| job.run_at);
+        Ok(claimed)
+    }
+
+    fn find_job(&self, id: i32) -> RepoResult<Job> {
+        use crate::schema::jobs::dsl::{job_id, jobs};
+
+        Ok(jobs
+            .filter(job_id.eq(id))
+            .select(Job::as_select())
+            .first(&mut self.pool.get()?)?)
+    }
+
+    fn has_job(&self, key: &str) -> RepoResult<bool> {
+        use crate::schema::jobs::dsl::{jobs, unique_key};
+
+        Ok(
+            diesel::select(diesel::dsl::exists(jobs.filter(unique_key.eq(key))))
+                .get_result(&mut self.pool.get()?)?,
+        )
+    }
+
+    fn jobs(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<Job>> {
+        use crate::schema::jobs::dsl::{job_id, jobs, property_id};
+
+        Ok(jobs
+            .filter(property_id.eq(pid).or(property_id.is_null()))
+            .order(job_id.desc())
+            .limit(limit)
+            .select(Job::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn update_job(&self, job: &Job) -> RepoResult<()> {
+        use crate::schema::jobs::dsl::*;
+
+        diesel::update(jobs.filter(job_id.eq(job.job_id)))
+            .set((
+                run_at.eq(job.run_at),
+                status.eq(job.status),
+                attempts.eq(job.attempts),
+                last_error.eq(&job.last_error),
+                locked_until.eq(job.locked_until),
+                finished_at.eq(job.finished_at),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+
+    fn purge_jobs(&self, before: NaiveDateTime) -> RepoResult<usize> {
+        use crate::schema::jobs::dsl::{finished_at, jobs, status};
+
+        Ok(diesel::delete(
+            jobs.filter(status.eq_any([JobStatus::Done, JobStatus::Failed]))
+                .filter(finished_at.lt(before)),
+        )
+        .execute(&mut self.pool.get()?)?)
+    }
+}
+
+/// Fails with [`RepositoryError::Conflict`] when one of `contacts` belongs to
+/// a guest of the property other than `guest_id`.
+fn check_contacts_free(
+    conn: &mut PgConnection,
+    pid: Uuid,
+    guest_id: Option<i32>,
+    contacts: &[String],
+) -> RepoResult<()> {
+    use crate::schema::guest_contacts::dsl as gc;
+
+    let owners: Vec<(String, i32)> = gc::guest_contacts
+        .filter(gc::property_id.eq(pid))
+        .filter(gc::contact.eq_any(contacts))
+        .select((gc::contact, gc::guest_id))
+        .load(conn)?;
+    match owners
+        .into_iter()
+        .find(|(_, owner)| Some(*owner) != guest_id)
+    {
+        Some((contact, _)) => Err(RepositoryError::Conflict(format!(
+            "{contact} belongs to another guest"
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn insert_contacts(
+    conn: &mut PgConnection,
+    pid: Uuid,
+    guest_id: i32,
+    contacts: &[String],
+) -> RepoResult<()> {
+    use crate::schema::guest_contacts::dsl as gc;
+
+    diesel::insert_into(gc::guest_contacts)
+        .values(
+            contacts
+                .iter()
+                .map(|contact| {
+                    (
+                        gc::property_id.eq(pid),
+                        gc::contact.eq(contact),
+                        gc::guest_id.eq(guest_id),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        )
+        .execute(conn)?;
+    Ok(())
+}
+
+impl GuestRepository for PgRepository {
+    fn guests(&self, pid: Uuid) -> RepoResult<Vec<Guest>> {
+        use crate::schema::guests::dsl::{guest_id, guests, name, property_id};
+
+        Ok(guests
+            .filter(property_id.eq(pid))
+            .order((name.asc(), guest_id.asc()))
+            .select(Guest::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn find_guest(&self, id: i32) -> RepoResult<Guest> {
+        use crate::schema::guests::dsl::{guest_id, guests};
+
+        Ok(guests
+            .filter(guest_id.eq(id))
+            .select(Guest::as_select())
+            .first(&mut self.pool.get()?)?)
+    }
+
+    fn guest_by_contact(&self, pid: Uuid, value: &str) -> RepoResult<Guest> {
+        use crate::schema::guest_contacts::dsl as gc;
+        use crate::schema::guests::dsl::{guest_id, guests};
+
+        Ok(guests
+            .filter(
+                guest_id.eq_any(
+                    gc::guest_contacts
+                        .filter(gc::property_id.eq(pid))
+                        .filter(gc::contact.eq(value))
+                        .select(gc::guest_id),
+                ),
+            )
+            .select(Guest::as_select())
+            .first(&mut self.pool.get()?)?)
+    }
+
+    fn insert_guest(&self, new_guest: NewGuest, contacts: &[String]) -> RepoResult<i32> {
+        use crate::schema::guests::dsl::{guest_id, guests};
+
+        let mut conn = self.pool.get()?;
+        conn.transaction(|conn| {
+            check_contacts_free(conn, new_guest.property_id, None, contacts)?;
+            let id = diesel::insert_into(guests)
+                .values(&new_guest)
+                .returning(guest_id)
+                .get_result::<i32>(conn)?;
+            insert_contacts(conn, new_guest.property_id, id, contacts)?;
+            Ok(id)
+        })
+    }
+
+    fn update_guest(&self, guest: &Guest) -> RepoResult<()> {
+        use crate::schema::guests::dsl::*;
+
+        diesel::update(guests.filter(guest_id.eq(guest.guest_id)))
+            .set((
+                name.eq(&guest.name),
+                notes.eq(&guest.notes),
+                tags.eq(&guest.tags),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+
+    fn guest_contacts(&self, id: i32) -> RepoResult<Vec<String>> {
+        use crate::schema::guest_contacts::dsl::{contact, guest_contacts, guest_id};
+
+        Ok(guest_contacts
+            .filter(guest_id.eq(id))
+            .order(contact.asc())
+            .select(contact)
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn set_guest_contacts(&self, id: i32, contacts: &[String]) -> RepoResult<()> {
+        use crate::schema::guest_contacts::dsl as gc;
+
+        let pid = self.find_guest(id)?.property_id;
+        let mut conn = self.pool.get()?;
+        conn.transaction(|conn| {
+            check_contacts_free(conn, pid, Some(id), contacts)?;
+            diesel::delete(gc::guest_contacts.filter(gc::guest_id.eq(id))).execute(conn)?;
+            insert_contacts(conn, pid, id, contacts)
+        })
+    }
+
+    fn guest_reservations(&self, id: i32) -> RepoResult<Vec<Reservation>> {
+        use crate::schema::reservation::dsl::{
+            guest_id, reservation, reservation_date, reservation_time,
+        };
+
+        Ok(reservation
+            .filter(guest_id.eq(id))
+            .order((reservation_date.desc(), reservation_time.desc()))
+            .select(Reservation::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn set_reservation_guest(&self, rid: i32, guest: i32) -> RepoResult<()> {
+        use crate::schema::reservation::dsl::{guest_id, id, reservation};
+
+        diesel::update(reservation.filter(id.eq(rid)))
+            .set(guest_id.eq(guest))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+    fn property_guest_contacts(&self, pid: Uuid) -> RepoResult<Vec<(i32, String)>> {
+        use crate::schema::guest_contacts::dsl::{contact, guest_contacts, guest_id, property_id};
+
+        Ok(guest_contacts
+            .filter(property_id.eq(pid))
+            .order((guest_id.asc(), contact.asc()))
+            .select((guest_id, contact))
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn merge_guests(&self, guest: &Guest, merge: NewGuestMerge) -> RepoResult<GuestMerge> {
+        use crate::schema::{guest_contacts, guest_merges, guests, reservation};
+
+        let duplicate = merge.merged_guest_id;
+        let mut conn = self.pool.get()?;
+        conn.transaction(|conn| {
+            diesel::update(guests::table.filter(guests::guest_id.eq(guest.guest_id)))
+                .set((
+                    guests::name.eq(&guest.name),
+                    guests::notes.eq(&guest.notes),
+                    guests::tags.eq(&guest.tags),
+                ))
+                .execute(conn)?;
+            let moved =
+                diesel::update(reservation::table.filter(reservation::guest_id.eq(duplicate)))
+                    .set(reservation::guest_id.eq(guest.guest_id))
+                    .execute(conn)?;
+            diesel::update(guest_contacts::table.filter(guest_contacts::guest_id.eq(duplicate)))
+                .set(guest_contacts::guest_id.eq(guest.guest_id))
+                .execute(conn)?;
+            diesel::update(guest_merges::table.filter(guest_merges::guest_id.eq(duplicate)))
+                .set(guest_merges::guest_id.eq(guest.guest_id))
+                .execute(conn)?;
+            let recorded = diesel::insert_into(guest_merges::table)
+                .values((&merge, guest_merges::reservations.eq(moved as i32)))
+                .returning(GuestMerge::as_returning())
+                .get_result(conn)?;
+            diesel::delete(guests::table.filter(guests::guest_id.eq(duplicate))).execute(conn)?;
+            Ok(recorded)
+        })
+    }
+
+    fn guest_merges(&self, id: i32) -> RepoResult<Vec<GuestMerge>> {
+        use crate::schema::guest_merges::dsl::{guest_id, guest_merges, merge_id};
+
+        Ok(guest_merges
+            .filter(guest_id.eq(id))
+            .order(merge_id.desc())
+            .select(GuestMerge::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+}
+
+impl WaitlistRepository for PgRepository {
+    fn waitlist(&self, pid: Uuid, date: NaiveDate) -> RepoResult<Vec<WaitlistEntry>> {
+        use crate::schema::waitlist::dsl::{entry_id, property_id, waitlist, waitlist_date};
+
+        Ok(waitlist
+            .filter(property_id.eq(pid))
+            .filter(waitlist_date.eq(date))
+            .order(entry_id)
+            .select(WaitlistEntry::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn find_waitlist_entry(&self, eid: i32) -> RepoResult<WaitlistEntry> {
+        use crate::schema::waitlist::dsl::{entry_id, waitlist};
+
+        Ok(waitlist
+            .filter(entry_id.eq(eid))
+            .select(WaitlistEntry::as_select())
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn insert_waitlist_entry(&self, new_entry: NewWaitlistEntry) -> RepoResult<i32> {
+        use crate::schema::waitlist::dsl::{entry_id, waitlist};
+
+        Ok(diesel::insert_into(waitlist)
+            .values(&new_entry)
+            .returning(entry_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn update_waitlist_entry(&self, entry: &WaitlistEntry) -> RepoResult<()> {
+        use crate::schema::waitlist::dsl::{
+            entry_id, notified_at, quoted_wait, reservation_id, status, waitlist,
+        };
+
+        let updated = diesel::update(waitlist.filter(entry_id.eq(entry.entry_id)))
+            .set((
+                quoted_wait.eq(entry.quoted_wait),
+                status.eq(entry.status),
+                notified_at.eq(entry.notified_at),
+                reservation_id.eq(entry.reservation_id),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        if updated == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+        Ok(())
+    }
+}
+
+impl SeriesRepository for PgRepository {
+    fn series(&self, pid: Uuid) -> RepoResult<Vec<ReservationSeries>> {
+        use crate::schema::reservation_series::dsl::{property_id, reservation_series, series_id};
+
+        Ok(reservation_series
+            .filter(property_id.eq(pid))
+            .order(series_id.desc())
+            .select(ReservationSeries::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn find_series(&self, sid: i32) -> RepoResult<ReservationSeries> {
+        use crate::schema::reservation_series::dsl::{reservation_series, series_id};
+
+        Ok(reservation_series
+            .filter(series_id.eq(sid))
+            .select(ReservationSeries::as_select())
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn insert_series(&self, new_series: NewReservationSeries) -> RepoResult<i32> {
+        use crate::schema::reservation_series::dsl::{reservation_series, series_id};
+
+        Ok(diesel::insert_into(reservation_series)
+            .values(&new_series)
+            .returning(series_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn update_series(&self, series: &ReservationSeries) -> RepoResult<()> {
+        use crate::schema::reservation_series::dsl::{
+            cancelled_at, party_size, reservation_series, reservation_time, seating, series_id,
+        };
+
+        let updated = diesel::update(reservation_series.filter(series_id.eq(series.series_id)))
+            .set((
+                seating.eq(&series.seating),
+                party_size.eq(series.party_size),
+                reservation_time.eq(series.reservation_time),
+                cancelled_at.eq(series.cancelled_at),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        if updated == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+        Ok(())
+    }
+
+    fn occurrences(&self, sid: i32) -> RepoResult<Vec<Reservation>> {
+        use crate::schema::reservation::dsl::{
+            reservation, reservation_date, reservation_time, series_id,
+        };
+
+        Ok(reservation
+            .filter(series_id.eq(sid))
+            .order((reservation_date, reservation_time))
+            .select(Reservation::as_select())
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn delete_series(&self, sid: i32) -> RepoResult<()> {
+        use crate::schema::{reservation, reservation_history, reservation_series};
+
+        let mut conn = self.pool.get()?;
+        conn.transaction(|conn| {
+            let occurrences = reservation::table
+                .filter(reservation::series_id.eq(sid))
+                .select(reservation::id);
+            diesel::delete(
+                reservation_history::table
+                    .filter(reservation_history::reservation_id.eq_any(occurrences)),
+            )
+            .execute(conn)?;
+            diesel::delete(reservation::table.filter(reservation::series_id.eq(sid)))
+                .execute(conn)?;
+            let deleted = diesel::delete(
+                reservation_series::table.filter(reservation_series::series_id.eq(sid)),
+            )
+            .execute(conn)?;
+            if deleted == 0 {
+                return Err(RepositoryError::NotFound);
+            }
+            Ok(())
+        })
+    }
+}
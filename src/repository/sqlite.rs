@@ -0,0 +1,2035 @@
+use super::{
+    containing, ApiKeyRepository, EmailRepository, GuestRepository, JobRepository,
+    PropertyRepository, RepoResult, ReservationRepository, SeriesRepository, SmsRepository,
+    WaitlistRepository, WebhookRepository,
+};
+use crate::errors::RepositoryError;
+use crate::models::{
+    ApiKey, Closure, DeliveryStatus, Email, EmailTemplate, EventDetails, Guest, GuestMerge,
+    HistoryEntry, Job, JobKind, JobStatus, MessageStatus, NewApiKey, NewClosure, NewEmail,
+    NewGuest, NewGuestMerge, NewHistoryEntry, NewJob, NewProperty, NewPropertyTable,
+    NewPropertyUser, NewReservationNote, NewReservationSeries, NewResv, NewSmsMessage,
+    NewWaitlistEntry, NewWebhook, NewWebhookDelivery, NotificationKind, Occasion, Property,
+    PropertyTable, PropertyUsers, Recurrence, Reservation, ReservationNote, ReservationSeries,
+    ReservationStatus, SmsMessage, SpecialRequests, TableAssignment, TableLayout, TableShape,
+    WaitlistEntry, WaitlistStatus, Webhook, WebhookDelivery,
+};
+use crate::schema_sqlite::{
+    api_keys, closures, email_templates, emails, guest_contacts, guest_merges, guests, jobs,
+    property, property_tables, propertyusers, reservation, reservation_events, reservation_history,
+    reservation_notes, reservation_series, reservation_tables, sms_messages, sms_opt_outs,
+    table_layouts, waitlist, webhook_deliveries, webhooks,
+};
+use crate::SharedSqlitePool;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use time::Time;
+use uuid::Uuid;
+
+/// Repository backed by a SQLite database for single-venue deployments.
+///
+/// SQLite has no UUID or JSONB columns, those are stored as text and converted
+/// at this boundary so the rest of the crate keeps using the shared models.
+#[derive(Debug, Clone)]
+pub struct SqliteRepository {
+    pool: SharedSqlitePool,
+}
+
+impl SqliteRepository {
+    pub fn new(pool: SharedSqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = property)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct PropertyRow {
+    property_id: String,
+    property_name: String,
+    property_password: String,
+    property_email: String,
+    property_phone: String,
+}
+
+impl TryFrom<PropertyRow> for Property {
+    type Error = RepositoryError;
+
+    fn try_from(row: PropertyRow) -> Result<Self, Self::Error> {
+        Ok(Property {
+            property_id: parse_uuid(&row.property_id)?,
+            property_name: row.property_name,
+            property_password: row.property_password,
+            property_email: row.property_email,
+            property_phone: row.property_phone,
+        })
+    }
+}
+
+impl From<Property> for PropertyRow {
+    fn from(p: Property) -> Self {
+        PropertyRow {
+            property_id: p.property_id.to_string(),
+            property_name: p.property_name,
+            property_password: p.property_password,
+            property_email: p.property_email,
+            property_phone: p.property_phone,
+        }
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = property_tables)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct PropertyTableRow {
+    table_id: i32,
+    property_id: String,
+    table_name: String,
+    capacity: i32,
+    section: Option<String>,
+}
+
+impl TryFrom<PropertyTableRow> for PropertyTable {
+    type Error = RepositoryError;
+
+    fn try_from(row: PropertyTableRow) -> Result<Self, Self::Error> {
+        Ok(PropertyTable {
+            table_id: row.table_id,
+            property_id: parse_uuid(&row.property_id)?,
+            table_name: row.table_name,
+            capacity: row.capacity,
+            section: row.section,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = propertyusers)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct PropertyUserRow {
+    user_id: i32,
+    user_name: String,
+    user_password: String,
+    user_role: i32,
+    property_id: String,
+}
+
+impl TryFrom<PropertyUserRow> for PropertyUsers {
+    type Error = RepositoryError;
+
+    fn try_from(row: PropertyUserRow) -> Result<Self, Self::Error> {
+        Ok(PropertyUsers::new(
+            row.user_id,
+            row.user_name,
+            row.user_password,
+            row.user_role,
+            parse_uuid(&row.property_id)?,
+        ))
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = api_keys)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct ApiKeyRow {
+    key_id: i32,
+    property_id: String,
+    key_name: String,
+    key_prefix: String,
+    key_hash: String,
+    scopes: i32,
+    created_at: NaiveDateTime,
+    last_used_at: Option<NaiveDateTime>,
+    revoked_at: Option<NaiveDateTime>,
+}
+
+impl TryFrom<ApiKeyRow> for ApiKey {
+    type Error = RepositoryError;
+
+    fn try_from(row: ApiKeyRow) -> Result<Self, Self::Error> {
+        Ok(ApiKey {
+            key_id: row.key_id,
+            property_id: parse_uuid(&row.property_id)?,
+            key_name: row.key_name,
+            key_prefix: row.key_prefix,
+            key_hash: row.key_hash,
+            scopes: row.scopes,
+            created_at: row.created_at,
+            last_used_at: row.last_used_at,
+            revoked_at: row.revoked_at,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = webhooks)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct WebhookRow {
+    webhook_id: i32,
+    property_id: String,
+    url: String,
+    secret: String,
+    events: i32,
+    active: bool,
+    created_at: NaiveDateTime,
+}
+
+impl TryFrom<WebhookRow> for Webhook {
+    type Error = RepositoryError;
+
+    fn try_from(row: WebhookRow) -> Result<Self, Self::Error> {
+        Ok(Webhook {
+            webhook_id: row.webhook_id,
+            property_id: parse_uuid(&row.property_id)?,
+            url: row.url,
+            secret: row.secret,
+            events: row.events,
+            active: row.active,
+            created_at: row.created_at,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = webhook_deliveries)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct DeliveryRow {
+    delivery_id: i32,
+    webhook_id: i32,
+    event: String,
+    payload: String,
+    status: DeliveryStatus,
+    attempts: i32,
+    next_attempt_at: NaiveDateTime,
+    response_status: Option<i32>,
+    last_error: Option<String>,
+    created_at: NaiveDateTime,
+    delivered_at: Option<NaiveDateTime>,
+}
+
+impl TryFrom<DeliveryRow> for WebhookDelivery {
+    type Error = RepositoryError;
+
+    fn try_from(row: DeliveryRow) -> Result<Self, Self::Error> {
+        Ok(WebhookDelivery {
+            delivery_id: row.delivery_id,
+            webhook_id: row.webhook_id,
+            event: row.event,
+            payload: parse_json(&row.payload)?,
+            status: row.status,
+            attempts: row.attempts,
+            next_attempt_at: row.next_attempt_at,
+            response_status: row.response_status,
+            last_error: row.last_error,
+            created_at: row.created_at,
+            delivered_at: row.delivered_at,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = reservation)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct ReservationRow {
+    id: i32,
+    name: String,
+    contact: String,
+    seating: String,
+    specific_seating_requested: bool,
+    advance: bool,
+    advance_method: String,
+    advance_amount: Option<i32>,
+    confirmed: bool,
+    reservation_date: NaiveDate,
+    reservation_time: Time,
+    property_id: String,
+    status: ReservationStatus,
+    party_size: i32,
+    confirmation_token: Option<String>,
+    confirmation_code: Option<String>,
+    guest_id: Option<i32>,
+    occasion: Option<Occasion>,
+    dietary: String,
+    high_chairs: i32,
+    accessibility: String,
+    seating_request: String,
+    tags: String,
+    no_show_at: Option<NaiveDateTime>,
+    series_id: Option<i32>,
+}
+
+impl TryFrom<ReservationRow> for Reservation {
+    type Error = RepositoryError;
+
+    fn try_from(row: ReservationRow) -> Result<Self, Self::Error> {
+        Ok(Reservation {
+            id: row.id,
+            name: row.name,
+            contact: row.contact,
+            seating: row.seating,
+            specific_seating_requested: row.specific_seating_requested,
+            advance: row.advance,
+            advance_method: parse_json(&row.advance_method)?,
+            advance_amount: row.advance_amount,
+            confirmed: row.confirmed,
+            reservation_date: row.reservation_date,
+            reservation_time: row.reservation_time,
+            property_id: parse_uuid(&row.property_id)?,
+            status: row.status,
+            party_size: row.party_size,
+            confirmation_token: row.confirmation_token,
+            confirmation_code: row.confirmation_code,
+            guest_id: row.guest_id,
+            special_requests: SpecialRequests {
+                occasion: row.occasion,
+                dietary: row.dietary,
+                high_chairs: row.high_chairs,
+                accessibility: row.accessibility,
+                seating_request: row.seating_request,
+            },
+            tags: row.tags,
+            no_show_at: row.no_show_at,
+            series_id: row.series_id,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = email_templates)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct EmailTemplateRow {
+    property_id: String,
+    kind: NotificationKind,
+    subject: String,
+    body: String,
+}
+
+impl TryFrom<EmailTemplateRow> for EmailTemplate {
+    type Error = RepositoryError;
+
+    fn try_from(row: EmailTemplateRow) -> Result<Self, Self::Error> {
+        Ok(EmailTemplate {
+            property_id: parse_uuid(&row.property_id)?,
+            kind: row.kind,
+            subject: row.subject,
+            body: row.body,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = emails)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct EmailRow {
+    email_id: i32,
+    property_id: String,
+    reservation_id: Option<i32>,
+    kind: NotificationKind,
+    recipient: String,
+    reply_to: Option<String>,
+    subject: String,
+    body: String,
+    status: DeliveryStatus,
+    attempts: i32,
+    next_attempt_at: NaiveDateTime,
+    last_error: Option<String>,
+    created_at: NaiveDateTime,
+    sent_at: Option<NaiveDateTime>,
+}
+
+impl TryFrom<EmailRow> for Email {
+    type Error = RepositoryError;
+
+    fn try_from(row: EmailRow) -> Result<Self, Self::Error> {
+        Ok(Email {
+            email_id: row.email_id,
+            property_id: parse_uuid(&row.property_id)?,
+            reservation_id: row.reservation_id,
+            kind: row.kind,
+            recipient: row.recipient,
+            reply_to: row.reply_to,
+            subject: row.subject,
+            body: row.body,
+            status: row.status,
+            attempts: row.attempts,
+            next_attempt_at: row.next_attempt_at,
+            last_error: row.last_error,
+            created_at: row.created_at,
+            sent_at: row.sent_at,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = guests)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct GuestRow {
+    guest_id: i32,
+    property_id: String,
+    name: String,
+    notes: String,
+    tags: String,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = waitlist)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct WaitlistRow {
+    entry_id: i32,
+    property_id: String,
+    waitlist_date: NaiveDate,
+    name: String,
+    contact: String,
+    party_size: i32,
+    preferred_time: Option<Time>,
+    quoted_wait: i32,
+    status: WaitlistStatus,
+    notified_at: Option<NaiveDateTime>,
+    reservation_id: Option<i32>,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = closures)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct ClosureRow {
+    closure_id: i32,
+    property_id: String,
+    first_day: NaiveDate,
+    last_day: NaiveDate,
+    reason: String,
+    created_at: NaiveDateTime,
+}
+
+impl TryFrom<ClosureRow> for Closure {
+    type Error = RepositoryError;
+
+    fn try_from(row: ClosureRow) -> Result<Self, Self::Error> {
+        Ok(Closure {
+            closure_id: row.closure_id,
+            property_id: parse_uuid(&row.property_id)?,
+            first_day: row.first_day,
+            last_day: row.last_day,
+            reason: row.reason,
+            created_at: row.created_at,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = reservation_series)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct SeriesRow {
+    series_id: i32,
+    property_id: String,
+    name: String,
+    contact: String,
+    seating: String,
+    party_size: i32,
+    reservation_time: Time,
+    recurrence: Recurrence,
+    starts_on: NaiveDate,
+    ends_on: Option<NaiveDate>,
+    occurrences: Option<i32>,
+    cancelled_at: Option<NaiveDateTime>,
+    created_at: NaiveDateTime,
+}
+
+impl TryFrom<SeriesRow> for ReservationSeries {
+    type Error = RepositoryError;
+
+    fn try_from(row: SeriesRow) -> Result<Self, Self::Error> {
+        Ok(ReservationSeries {
+            series_id: row.series_id,
+            property_id: parse_uuid(&row.property_id)?,
+            name: row.name,
+            contact: row.contact,
+            seating: row.seating,
+            party_size: row.party_size,
+            reservation_time: row.reservation_time,
+            recurrence: row.recurrence,
+            starts_on: row.starts_on,
+            ends_on: row.ends_on,
+            occurrences: row.occurrences,
+            cancelled_at: row.cancelled_at,
+            created_at: row.created_at,
+        })
+    }
+}
+
+impl TryFrom<WaitlistRow> for WaitlistEntry {
+    type Error = RepositoryError;
+
+    fn try_from(row: WaitlistRow) -> Result<Self, Self::Error> {
+        Ok(WaitlistEntry {
+            entry_id: row.entry_id,
+            property_id: parse_uuid(&row.property_id)?,
+            waitlist_date: row.waitlist_date,
+            name: row.name,
+            contact: row.contact,
+            party_size: row.party_size,
+            preferred_time: row.preferred_time,
+            quoted_wait: row.quoted_wait,
+            status: row.status,
+            notified_at: row.notified_at,
+            reservation_id: row.reservation_id,
+            created_at: row.created_at,
+        })
+    }
+}
+
+impl TryFrom<GuestRow> for Guest {
+    type Error = RepositoryError;
+
+    fn try_from(row: GuestRow) -> Result<Self, Self::Error> {
+        Ok(Guest {
+            guest_id: row.guest_id,
+            property_id: parse_uuid(&row.property_id)?,
+            name: row.name,
+            notes: row.notes,
+            tags: row.tags,
+            created_at: row.created_at,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = guest_merges)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct GuestMergeRow {
+    merge_id: i32,
+    property_id: String,
+    guest_id: i32,
+    merged_guest_id: i32,
+    merged_name: String,
+    merged_contacts: String,
+    reservations: i32,
+    merged_by: String,
+    merged_at: NaiveDateTime,
+}
+
+impl TryFrom<GuestMergeRow> for GuestMerge {
+    type Error = RepositoryError;
+
+    fn try_from(row: GuestMergeRow) -> Result<Self, Self::Error> {
+        Ok(GuestMerge {
+            merge_id: row.merge_id,
+            property_id: parse_uuid(&row.property_id)?,
+            guest_id: row.guest_id,
+            merged_guest_id: row.merged_guest_id,
+            merged_name: row.merged_name,
+            merged_contacts: row.merged_contacts,
+            reservations: row.reservations,
+            merged_by: row.merged_by,
+            merged_at: row.merged_at,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = jobs)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct JobRow {
+    job_id: i32,
+    kind: JobKind,
+    property_id: Option<String>,
+    reservation_id: Option<i32>,
+    unique_key: Option<String>,
+    run_at: NaiveDateTime,
+    status: JobStatus,
+    attempts: i32,
+    last_error: Option<String>,
+    locked_until: Option<NaiveDateTime>,
+    created_at: NaiveDateTime,
+    finished_at: Option<NaiveDateTime>,
+}
+
+impl TryFrom<JobRow> for Job {
+    type Error = RepositoryError;
+
+    fn try_from(row: JobRow) -> Result<Self, Self::Error> {
+        Ok(Job {
+            job_id: row.job_id,
+            kind: row.kind,
+            property_id: row.property_id.as_deref().map(parse_uuid).transpose()?,
+            reservation_id: row.reservation_id,
+            unique_key: row.unique_key,
+            run_at: row.run_at,
+            status: row.status,
+            attempts: row.attempts,
+            last_error: row.last_error,
+            locked_until: row.locked_until,
+            created_at: row.created_at,
+            finished_at: row.finished_at,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = sms_messages)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct SmsMessageRow {
+    message_id: i32,
+    property_id: String,
+    reservation_id: Option<i32>,
+    kind: NotificationKind,
+    channel: String,
+    recipient: String,
+    body: String,
+    status: MessageStatus,
+    attempts: i32,
+    next_attempt_at: NaiveDateTime,
+    provider_id: Option<String>,
+    last_error: Option<String>,
+    created_at: NaiveDateTime,
+    sent_at: Option<NaiveDateTime>,
+    delivered_at: Option<NaiveDateTime>,
+}
+
+impl TryFrom<SmsMessageRow> for SmsMessage {
+    type Error = RepositoryError;
+
+    fn try_from(row: SmsMessageRow) -> Result<Self, Self::Error> {
+        Ok(SmsMessage {
+            message_id: row.message_id,
+            property_id: parse_uuid(&row.property_id)?,
+            reservation_id: row.reservation_id,
+            kind: row.kind,
+            channel: row.channel,
+            recipient: row.recipient,
+            body: row.body,
+            status: row.status,
+            attempts: row.attempts,
+            next_attempt_at: row.next_attempt_at,
+            provider_id: row.provider_id,
+            last_error: row.last_error,
+            created_at: row.created_at,
+            sent_at: row.sent_at,
+            delivered_at: row.delivered_at,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = reservation_history)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct HistoryRow {
+    entry_id: i32,
+    reservation_id: i32,
+    changed_at: NaiveDateTime,
+    changed_by: String,
+    summary: String,
+}
+
+impl From<HistoryRow> for HistoryEntry {
+    fn from(row: HistoryRow) -> Self {
+        HistoryEntry {
+            entry_id: row.entry_id,
+            reservation_id: row.reservation_id,
+            changed_at: row.changed_at,
+            changed_by: row.changed_by,
+            summary: row.summary,
+        }
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = reservation_notes)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct NoteRow {
+    note_id: i32,
+    reservation_id: i32,
+    author: String,
+    body: String,
+    created_at: NaiveDateTime,
+}
+
+impl From<NoteRow> for ReservationNote {
+    fn from(row: NoteRow) -> Self {
+        ReservationNote {
+            note_id: row.note_id,
+            reservation_id: row.reservation_id,
+            author: row.author,
+            body: row.body,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = reservation)]
+struct NewReservationRow {
+    name: String,
+    contact: String,
+    seating: String,
+    specific_seating_requested: bool,
+    advance: bool,
+    advance_method: String,
+    advance_amount: Option<i32>,
+    confirmed: bool,
+    reservation_date: NaiveDate,
+    reservation_time: Time,
+    property_id: String,
+    status: ReservationStatus,
+    party_size: i32,
+    confirmation_token: Option<String>,
+    confirmation_code: Option<String>,
+    guest_id: Option<i32>,
+    occasion: Option<Occasion>,
+    dietary: String,
+    high_chairs: i32,
+    accessibility: String,
+    seating_request: String,
+    tags: String,
+    series_id: Option<i32>,
+}
+
+impl From<NewResv> for NewReservationRow {
+    fn from(r: NewResv) -> Self {
+        NewReservationRow {
+            name: r.name,
+            contact: r.contact,
+            seating: r.seating,
+            specific_seating_requested: r.specific_seating_requested,
+            advance: r.advance,
+            advance_method: r.advance_method.to_string(),
+            advance_amount: r.advance_amount,
+            confirmed: r.confirmed,
+            reservation_date: r.reservation_date,
+            reservation_time: r.reservation_time,
+            property_id: r.property_id.to_string(),
+            status: r.status,
+            party_size: r.party_size,
+            confirmation_token: r.confirmation_token,
+            confirmation_code: r.confirmation_code,
+            guest_id: r.guest_id,
+            occasion: r.special_requests.occasion,
+            dietary: r.special_requests.dietary,
+            high_chairs: r.special_requests.high_chairs,
+            accessibility: r.special_requests.accessibility,
+            seating_request: r.special_requests.seating_request,
+            tags: r.tags,
+            series_id: r.series_id,
+        }
+    }
+}
+
+fn parse_uuid(value: &str) -> RepoResult<Uuid> {
+    Uuid::parse_str(value).map_err(|e| RepositoryError::Database(e.to_string()))
+}
+
+fn parse_json(value: &str) -> RepoResult<serde_json::Value> {
+    serde_json::from_str(value).map_err(|e| RepositoryError::Database(e.to_string()))
+}
+
+fn to_reservations(rows: Vec<ReservationRow>) -> RepoResult<Vec<Reservation>> {
+    rows.into_iter().map(Reservation::try_from).collect()
+}
+
+impl PropertyRepository for SqliteRepository {
+    fn all(&self) -> RepoResult<Vec<Property>> {
+        property::table
+            .select(PropertyRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(Property::try_from)
+            .collect()
+    }
+
+    fn find(&self, pid: Uuid) -> RepoResult<Property> {
+        property::table
+            .filter(property::property_id.eq(pid.to_string()))
+            .select(PropertyRow::as_select())
+            .get_result(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn insert(&self, new_property: NewProperty) -> RepoResult<Uuid> {
+        let row = PropertyRow::from(new_property.into_property());
+        let pid = diesel::insert_into(property::table)
+            .values(&row)
+            .returning(property::property_id)
+            .get_result::<String>(&mut self.pool.get()?)?;
+        parse_uuid(&pid)
+    }
+
+    fn tables(&self, pid: Uuid) -> RepoResult<Vec<PropertyTable>> {
+        property_tables::table
+            .filter(property_tables::property_id.eq(pid.to_string()))
+            .order(property_tables::table_name)
+            .select(PropertyTableRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(PropertyTable::try_from)
+            .collect()
+    }
+
+    fn insert_table(&self, new_table: NewPropertyTable) -> RepoResult<i32> {
+        Ok(diesel::insert_into(property_tables::table)
+            .values((
+                property_tables::property_id.eq(new_table.property_id.to_string()),
+                property_tables::table_name.eq(new_table.table_name),
+                property_tables::capacity.eq(new_table.capacity),
+                property_tables::section.eq(new_table.section),
+            ))
+            .returning(property_tables::table_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn set_table_section(&self, table_id: i32, section: Option<String>) -> RepoResult<()> {
+        let updated =
+            diesel::update(property_tables::table.filter(property_tables::table_id.eq(table_id)))
+                .set(property_tables::section.eq(section))
+                .execute(&mut self.pool.get()?)?;
+        if updated == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+        Ok(())
+    }
+
+    fn layouts(&self, pid: Uuid) -> RepoResult<Vec<TableLayout>> {
+        Ok(table_layouts::table
+            .inner_join(property_tables::table)
+            .filter(property_tables::property_id.eq(pid.to_string()))
+            .select((
+                table_layouts::table_id,
+                table_layouts::x,
+                table_layouts::y,
+                table_layouts::width,
+                table_layouts::height,
+                table_layouts::rotation,
+                table_layouts::shape,
+            ))
+            .load::<(i32, i32, i32, i32, i32, i32, TableShape)>(&mut self.pool.get()?)?
+            .into_iter()
+            .map(
+                |(table_id, x, y, width, height, rotation, shape)| TableLayout {
+                    table_id,
+                    x,
+                    y,
+                    width,
+                    height,
+                    rotation,
+                    shape,
+                },
+            )
+            .collect())
+    }
+
+    fn save_layout(&self, layout: &TableLayout) -> RepoResult<()> {
+        let values = (
+            table_layouts::x.eq(layout.x),
+            table_layouts::y.eq(layout.y),
+            table_layouts::width.eq(layout.width),
+            table_layouts::height.eq(layout.height),
+            table_layouts::rotation.eq(layout.rotation),
+            table_layouts::shape.eq(layout.shape),
+        );
+        diesel::insert_into(table_layouts::table)
+            .values((table_layouts::table_id.eq(layout.table_id), values))
+            .on_conflict(table_layouts::table_id)
+            .do_update()
+            .set(values)
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+
+    fn insert_user(&self, new_user: NewPropertyUser) -> RepoResult<i32> {
+        Ok(diesel::insert_into(propertyusers::table)
+            .values((
+                propertyusers::user_name.eq(new_user.user_name),
+                propertyusers::user_password.eq(new_user.user_password),
+                propertyusers::user_role.eq(new_user.user_role),
+                propertyusers::property_id.eq(new_user.property_id.to_string()),
+            ))
+            .returning(propertyusers::user_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn find_user(&self, pid: Uuid, user_name: &str) -> RepoResult<PropertyUsers> {
+        propertyusers::table
+            .filter(propertyusers::property_id.eq(pid.to_string()))
+            .filter(propertyusers::user_name.eq(user_name))
+            .select(PropertyUserRow::as_select())
+            .get_result(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn set_password(&self, pid: Uuid, password_hash: String) -> RepoResult<()> {
+        match diesel::update(property::table.filter(property::property_id.eq(pid.to_string())))
+            .set(property::property_password.eq(password_hash))
+            .execute(&mut self.pool.get()?)?
+        {
+            0 => Err(RepositoryError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    fn set_user_password(
+        &self,
+        pid: Uuid,
+        user_name: &str,
+        password_hash: String,
+    ) -> RepoResult<()> {
+        match diesel::update(
+            propertyusers::table
+                .filter(propertyusers::property_id.eq(pid.to_string()))
+                .filter(propertyusers::user_name.eq(user_name)),
+        )
+        .set(propertyusers::user_password.eq(password_hash))
+        .execute(&mut self.pool.get()?)?
+        {
+            0 => Err(RepositoryError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    fn closures(&self, pid: Uuid) -> RepoResult<Vec<Closure>> {
+        closures::table
+            .filter(closures::property_id.eq(pid.to_string()))
+            .order((closures::first_day, closures::closure_id))
+            .select(ClosureRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(Closure::try_from)
+            .collect()
+    }
+
+    fn insert_closure(&self, new_closure: NewClosure) -> RepoResult<i32> {
+        Ok(diesel::insert_into(closures::table)
+            .values((
+                closures::property_id.eq(new_closure.property_id.to_string()),
+                closures::first_day.eq(new_closure.first_day),
+                closures::last_day.eq(new_closure.last_day),
+                closures::reason.eq(new_closure.reason),
+            ))
+            .returning(closures::closure_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn delete_closure(&self, pid: Uuid, closure_id: i32) -> RepoResult<()> {
+        match diesel::delete(
+            closures::table
+                .filter(closures::property_id.eq(pid.to_string()))
+                .filter(closures::closure_id.eq(closure_id)),
+        )
+        .execute(&mut self.pool.get()?)?
+        {
+            0 => Err(RepositoryError::NotFound),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl ReservationRepository for SqliteRepository {
+    fn for_property(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<Reservation>> {
+        to_reservations(
+            reservation::table
+                .filter(reservation::property_id.eq(pid.to_string()))
+                .limit(limit)
+                .select(ReservationRow::as_select())
+                .load(&mut self.pool.get()?)?,
+        )
+    }
+
+    fn with_tag(&self, pid: Uuid, tag: &str, limit: i64) -> RepoResult<Vec<Reservation>> {
+        // SQLite's `LIKE` ignores ASCII case. The pattern narrows the rows
+        // down, whole tags are matched here.
+        Ok(to_reservations(
+            reservation::table
+                .filter(reservation::property_id.eq(pid.to_string()))
+                .filter(reservation::tags.like(containing(tag.trim())).escape('\\'))
+                .select(ReservationRow::as_select())
+                .load(&mut self.pool.get()?)?,
+        )?
+        .into_iter()
+        .filter(|resv| resv.has_tag(tag))
+        .take(limit.max(0) as usize)
+        .collect())
+    }
+
+    fn on_date(&self, pid: Uuid, date: NaiveDate) -> RepoResult<Vec<Reservation>> {
+        to_reservations(
+            reservation::table
+                .filter(reservation::property_id.eq(pid.to_string()))
+                .filter(reservation::reservation_date.eq(date))
+                .order(reservation::reservation_time)
+                .select(ReservationRow::as_select())
+                .load(&mut self.pool.get()?)?,
+        )
+    }
+
+    fn count_for_property(&self, pid: Uuid) -> RepoResult<i64> {
+        Ok(reservation::table
+            .filter(reservation::property_id.eq(pid.to_string()))
+            .count()
+            .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn insert(&self, new_resv: NewResv) -> RepoResult<i32> {
+        Ok(diesel::insert_into(reservation::table)
+            .values(NewReservationRow::from(new_resv))
+            .returning(reservation::id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn find(&self, id: i32) -> RepoResult<Reservation> {
+        reservation::table
+            .filter(reservation::id.eq(id))
+            .select(ReservationRow::as_select())
+            .get_result(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn set_status(&self, id: i32, status: ReservationStatus) -> RepoResult<Reservation> {
+        let target = diesel::update(reservation::table.filter(reservation::id.eq(id)));
+        let conn = &mut self.pool.get()?;
+        if status == ReservationStatus::NoShow {
+            target
+                .set((
+                    reservation::status.eq(status),
+                    reservation::no_show_at.eq(Some(Utc::now().naive_utc())),
+                ))
+                .returning(ReservationRow::as_returning())
+                .get_result(conn)?
+        } else {
+            target
+                .set(reservation::status.eq(status))
+                .returning(ReservationRow::as_returning())
+                .get_result(conn)?
+        }
+        .try_into()
+    }
+
+    fn set_payment(
+        &self,
+        id: i32,
+        advance_method: serde_json::Value,
+        advance_amount: i32,
+    ) -> RepoResult<Reservation> {
+        diesel::update(reservation::table.filter(reservation::id.eq(id)))
+            .set((
+                reservation::advance.eq(true),
+                reservation::advance_method.eq(advance_method.to_string()),
+                reservation::advance_amount.eq(Some(advance_amount)),
+            ))
+            .returning(ReservationRow::as_returning())
+            .get_result(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn find_by_token(&self, token: &str) -> RepoResult<Reservation> {
+        reservation::table
+            .filter(reservation::confirmation_token.eq(token))
+            .select(ReservationRow::as_select())
+            .get_result(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn reschedule(
+        &self,
+        id: i32,
+        date: NaiveDate,
+        time: Time,
+        seating: String,
+        party_size: i32,
+    ) -> RepoResult<Reservation> {
+        diesel::update(reservation::table.filter(reservation::id.eq(id)))
+            .set((
+                reservation::reservation_date.eq(date),
+                reservation::reservation_time.eq(time),
+                reservation::seating.eq(seating),
+                reservation::party_size.eq(party_size),
+            ))
+            .returning(ReservationRow::as_returning())
+            .get_result(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn insert_history(&self, entry: NewHistoryEntry) -> RepoResult<i32> {
+        Ok(diesel::insert_into(reservation_history::table)
+            .values((
+                reservation_history::reservation_id.eq(entry.reservation_id),
+                reservation_history::changed_by.eq(entry.changed_by),
+                reservation_history::summary.eq(entry.summary),
+            ))
+            .returning(reservation_history::entry_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn history(&self, id: i32) -> RepoResult<Vec<HistoryEntry>> {
+        Ok(reservation_history::table
+            .filter(reservation_history::reservation_id.eq(id))
+            .order(reservation_history::entry_id)
+            .select(HistoryRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(HistoryEntry::from)
+            .collect())
+    }
+
+    fn set_requests(
+        &self,
+        id: i32,
+        special_requests: SpecialRequests,
+        tags: String,
+    ) -> RepoResult<Reservation> {
+        diesel::update(reservation::table.filter(reservation::id.eq(id)))
+            .set((
+                reservation::occasion.eq(special_requests.occasion),
+                reservation::dietary.eq(special_requests.dietary),
+                reservation::high_chairs.eq(special_requests.high_chairs),
+                reservation::accessibility.eq(special_requests.accessibility),
+                reservation::seating_request.eq(special_requests.seating_request),
+                reservation::tags.eq(tags),
+            ))
+            .returning(ReservationRow::as_returning())
+            .get_result(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn insert_note(&self, note: NewReservationNote) -> RepoResult<i32> {
+        Ok(diesel::insert_into(reservation_notes::table)
+            .values((
+                reservation_notes::reservation_id.eq(note.reservation_id),
+                reservation_notes::author.eq(note.author),
+                reservation_notes::body.eq(note.body),
+            ))
+            .returning(reservation_notes::note_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn notes(&self, id: i32) -> RepoResult<Vec<ReservationNote>> {
+        Ok(reservation_notes::table
+            .filter(reservation_notes::reservation_id.eq(id))
+            .order(reservation_notes::note_id)
+            .select(NoteRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(ReservationNote::from)
+            .collect())
+    }
+
+    fn table_assignments(&self, ids: &[i32]) -> RepoResult<Vec<TableAssignment>> {
+        Ok(reservation_tables::table
+            .filter(reservation_tables::reservation_id.eq_any(ids))
+            .select((
+                reservation_tables::reservation_id,
+                reservation_tables::table_id,
+            ))
+            .load::<(i32, i32)>(&mut self.pool.get()?)?
+            .into_iter()
+            .map(|(reservation_id, table_id)| TableAssignment {
+                reservation_id,
+                table_id,
+            })
+            .collect())
+    }
+
+    fn set_table_assignments(&self, id: i32, table_ids: &[i32]) -> RepoResult<()> {
+        let rows: Vec<_> = table_ids
+            .iter()
+            .map(|&table_id| {
+                (
+                    reservation_tables::reservation_id.eq(id),
+                    reservation_tables::table_id.eq(table_id),
+                )
+            })
+            .collect();
+        let conn = &mut self.pool.get()?;
+        conn.transaction(|conn| {
+            diesel::delete(
+                reservation_tables::table.filter(reservation_tables::reservation_id.eq(id)),
+            )
+            .execute(conn)?;
+            if !rows.is_empty() {
+                diesel::insert_into(reservation_tables::table)
+                    .values(&rows)
+                    .execute(conn)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn event_details(&self, id: i32) -> RepoResult<Option<EventDetails>> {
+        Ok(reservation_events::table
+            .filter(reservation_events::reservation_id.eq(id))
+            .select((
+                reservation_events::reservation_id,
+                reservation_events::section,
+                reservation_events::menu,
+                reservation_events::minimum_spend,
+            ))
+            .get_result::<(i32, Option<String>, String, Option<i32>)>(&mut self.pool.get()?)
+            .optional()?
+            .map(
+                |(reservation_id, section, menu, minimum_spend)| EventDetails {
+                    reservation_id,
+                    section,
+                    menu,
+                    minimum_spend,
+                },
+            ))
+    }
+
+    fn save_event_details(&self, details: &EventDetails) -> RepoResult<()> {
+        diesel::insert_into(reservation_events::table)
+            .values((
+                reservation_events::reservation_id.eq(details.reservation_id),
+                reservation_events::section.eq(&details.section),
+                reservation_events::menu.eq(&details.menu),
+                reservation_events::minimum_spend.eq(details.minimum_spend),
+            ))
+            .on_conflict(reservation_events::reservation_id)
+            .do_update()
+            .set((
+                reservation_events::section.eq(&details.section),
+                reservation_events::menu.eq(&details.menu),
+                reservation_events::minimum_spend.eq(details.minimum_spend),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+}
+
+impl ApiKeyRepository for SqliteRepository {
+    fn keys(&self, pid: Uuid) -> RepoResult<Vec<ApiKey>> {
+        api_keys::table
+            .filter(api_keys::property_id.eq(pid.to_string()))
+            .order(api_keys::created_at.desc())
+            .select(ApiKeyRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(ApiKey::try_from)
+            .collect()
+    }
+
+    fn key_by_prefix(&self, prefix: &str) -> RepoResult<ApiKey> {
+        api_keys::table
+            .filter(api_keys::key_prefix.eq(prefix))
+            .select(ApiKeyRow::as_select())
+            .get_result(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn insert_key(&self, new_key: NewApiKey) -> RepoResult<i32> {
+        Ok(diesel::insert_into(api_keys::table)
+            .values((
+                api_keys::property_id.eq(new_key.property_id.to_string()),
+                api_keys::key_name.eq(new_key.key_name),
+                api_keys::key_prefix.eq(new_key.key_prefix),
+                api_keys::key_hash.eq(new_key.key_hash),
+                api_keys::scopes.eq(new_key.scopes),
+            ))
+            .returning(api_keys::key_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn revoke_key(&self, pid: Uuid, key_id: i32, at: NaiveDateTime) -> RepoResult<ApiKey> {
+        diesel::update(
+            api_keys::table
+                .filter(api_keys::key_id.eq(key_id))
+                .filter(api_keys::property_id.eq(pid.to_string())),
+        )
+        .set(api_keys::revoked_at.eq(Some(at)))
+        .returning(ApiKeyRow::as_returning())
+        .get_result(&mut self.pool.get()?)?
+        .try_into()
+    }
+
+    fn touch_key(&self, key_id: i32, at: NaiveDateTime) -> RepoResult<()> {
+        diesel::update(api_keys::table.filter(api_keys::key_id.eq(key_id)))
+            .set(api_keys::last_used_at.eq(Some(at)))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+}
+
+impl WebhookRepository for SqliteRepository {
+    fn webhooks(&self, pid: Uuid) -> RepoResult<Vec<Webhook>> {
+        webhooks::table
+            .filter(webhooks::property_id.eq(pid.to_string()))
+            .order(webhooks::webhook_id.asc())
+            .select(WebhookRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(Webhook::try_from)
+            .collect()
+    }
+
+    fn find_webhook(&self, webhook_id: i32) -> RepoResult<Webhook> {
+        webhooks::table
+            .filter(webhooks::webhook_id.eq(webhook_id))
+            .select(WebhookRow::as_select())
+            .get_result(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn insert_webhook(&self, new_webhook: NewWebhook) -> RepoResult<i32> {
+        Ok(diesel::insert_into(webhooks::table)
+            .values((
+                webhooks::property_id.eq(new_webhook.property_id.to_string()),
+                webhooks::url.eq(new_webhook.url),
+                webhooks::secret.eq(new_webhook.secret),
+                webhooks::events.eq(new_webhook.events),
+            ))
+            .returning(webhooks::webhook_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn set_webhook_active(&self, pid: Uuid, webhook_id: i32, active: bool) -> RepoResult<Webhook> {
+        diesel::update(
+            webhooks::table
+                .filter(webhooks::webhook_id.eq(webhook_id))
+                .filter(webhooks::property_id.eq(pid.to_string())),
+        )
+        .set(webhooks::active.eq(active))
+        .returning(WebhookRow::as_returning())
+        .get_result(&mut self.pool.get()?)?
+        .try_into()
+    }
+
+    fn insert_delivery(&self, new_delivery: NewWebhookDelivery) -> RepoResult<i32> {
+        Ok(diesel::insert_into(webhook_deliveries::table)
+            .values((
+                webhook_deliveries::webhook_id.eq(new_delivery.webhook_id),
+                webhook_deliveries::event.eq(new_delivery.event),
+                webhook_deliveries::payload.eq(new_delivery.payload.to_string()),
+                webhook_deliveries::next_attempt_at.eq(new_delivery.next_attempt_at),
+            ))
+            .returning(webhook_deliveries::delivery_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn find_delivery(&self, delivery_id: i32) -> RepoResult<WebhookDelivery> {
+        webhook_deliveries::table
+            .filter(webhook_deliveries::delivery_id.eq(delivery_id))
+            .select(DeliveryRow::as_select())
+            .get_result(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn deliveries(&self, webhook_id: i32, limit: i64) -> RepoResult<Vec<WebhookDelivery>> {
+        webhook_deliveries::table
+            .filter(webhook_deliveries::webhook_id.eq(webhook_id))
+            .order(webhook_deliveries::delivery_id.desc())
+            .limit(limit)
+            .select(DeliveryRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(WebhookDelivery::try_from)
+            .collect()
+    }
+
+    fn due_deliveries(&self, now: NaiveDateTime, limit: i64) -> RepoResult<Vec<WebhookDelivery>> {
+        webhook_deliveries::table
+            .filter(webhook_deliveries::status.eq(DeliveryStatus::Pending))
+            .filter(webhook_deliveries::next_attempt_at.le(now))
+            .order(webhook_deliveries::next_attempt_at.asc())
+            .limit(limit)
+            .select(DeliveryRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(WebhookDelivery::try_from)
+            .collect()
+    }
+
+    fn update_delivery(&self, delivery: &WebhookDelivery) -> RepoResult<()> {
+        diesel::update(
+            webhook_deliveries::table
+                .filter(webhook_deliveries::delivery_id.eq(delivery.delivery_id)),
+        )
+        .set((
+            webhook_deliveries::status.eq(delivery.status),
+            webhook_deliveries::attempts.eq(delivery.attempts),
+            webhook_deliveries::next_attempt_at.eq(delivery.next_attempt_at),
+            webhook_deliveries::response_status.eq(delivery.response_status),
+            webhook_deliveries::last_error.eq(&delivery.last_error),
+            webhook_deliveries::delivered_at.eq(delivery.delivered_at),
+        ))
+        .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+}
+
+impl EmailRepository for SqliteRepository {
+    fn email_templates(&self, pid: Uuid) -> RepoResult<Vec<EmailTemplate>> {
+        email_templates::table
+            .filter(email_templates::property_id.eq(pid.to_string()))
+            .order(email_templates::kind)
+            .select(EmailTemplateRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(EmailTemplate::try_from)
+            .collect()
+    }
+
+    fn save_email_template(&self, template: EmailTemplate) -> RepoResult<()> {
+        diesel::insert_into(email_templates::table)
+            .values((
+                email_templates::property_id.eq(template.property_id.to_string()),
+                email_templates::kind.eq(template.kind),
+                email_templates::subject.eq(&template.subject),
+                email_templates::body.eq(&template.body),
+            ))
+            .on_conflict((email_templates::property_id, email_templates::kind))
+            .do_update()
+            .set((
+                email_templates::subject.eq(&template.subject),
+                email_templates::body.eq(&template.body),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+
+    fn insert_email(&self, new_email: NewEmail) -> RepoResult<i32> {
+        Ok(diesel::insert_into(emails::table)
+            .values((
+                emails::property_id.eq(new_email.property_id.to_string()),
+                emails::reservation_id.eq(new_email.reservation_id),
+                emails::kind.eq(new_email.kind),
+                emails::recipient.eq(new_email.recipient),
+                emails::reply_to.eq(new_email.reply_to),
+                emails::subject.eq(new_email.subject),
+                emails::body.eq(new_email.body),
+                emails::next_attempt_at.eq(new_email.next_attempt_at),
+            ))
+            .returning(emails::email_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn emails(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<Email>> {
+        emails::table
+            .filter(emails::property_id.eq(pid.to_string()))
+            .order(emails::email_id.desc())
+            .limit(limit)
+            .select(EmailRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(Email::try_from)
+            .collect()
+    }
+
+    fn due_emails(&self, now: NaiveDateTime, limit: i64) -> RepoResult<Vec<Email>> {
+        emails::table
+            .filter(emails::status.eq(DeliveryStatus::Pending))
+            .filter(emails::next_attempt_at.le(now))
+            .order(emails::next_attempt_at.asc())
+            .limit(limit)
+            .select(EmailRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(Email::try_from)
+            .collect()
+    }
+
+    fn update_email(&self, email: &Email) -> RepoResult<()> {
+        diesel::update(emails::table.filter(emails::email_id.eq(email.email_id)))
+            .set((
+                emails::status.eq(email.status),
+                emails::attempts.eq(email.attempts),
+                emails::next_attempt_at.eq(email.next_attempt_at),
+                emails::last_error.eq(&email.last_error),
+                emails::sent_at.eq(email.sent_at),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+
+    fn has_email(&self, reservation_id: i32, kind: NotificationKind) -> RepoResult<bool> {
+        Ok(diesel::select(diesel::dsl::exists(
+            emails::table
+                .filter(emails::reservation_id.eq(reservation_id))
+                .filter(emails::kind.eq(kind)),
+        ))
+        .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn purge_emails(&self, before: NaiveDateTime) -> RepoResult<usize> {
+        Ok(diesel::delete(
+            emails::table
+                .filter(emails::status.ne(DeliveryStatus::Pending))
+                .filter(emails::created_at.lt(before)),
+        )
+        .execute(&mut self.pool.get()?)?)
+    }
+}
+
+impl SmsRepository for SqliteRepository {
+    fn insert_message(&self, new_message: NewSmsMessage) -> RepoResult<i32> {
+        Ok(diesel::insert_into(sms_messages::table)
+            .values((
+                sms_messages::property_id.eq(new_message.property_id.to_string()),
+                sms_messages::reservation_id.eq(new_message.reservation_id),
+                sms_messages::kind.eq(new_message.kind),
+                sms_messages::channel.eq(new_message.channel),
+                sms_messages::recipient.eq(new_message.recipient),
+                sms_messages::body.eq(new_message.body),
+                sms_messages::status.eq(new_message.status),
+                sms_messages::next_attempt_at.eq(new_message.next_attempt_at),
+            ))
+            .returning(sms_messages::message_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn messages(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<SmsMessage>> {
+        sms_messages::table
+            .filter(sms_messages::property_id.eq(pid.to_string()))
+            .order(sms_messages::message_id.desc())
+            .limit(limit)
+            .select(SmsMessageRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(SmsMessage::try_from)
+            .collect()
+    }
+
+    fn due_messages(&self, now: NaiveDateTime, limit: i64) -> RepoResult<Vec<SmsMessage>> {
+        sms_messages::table
+            .filter(sms_messages::status.eq(MessageStatus::Queued))
+            .filter(sms_messages::next_attempt_at.le(now))
+            .order(sms_messages::next_attempt_at.asc())
+            .limit(limit)
+            .select(SmsMessageRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(SmsMessage::try_from)
+            .collect()
+    }
+
+    fn message_by_provider_id(&self, provider_id: &str) -> RepoResult<SmsMessage> {
+        sms_messages::table
+            .filter(sms_messages::provider_id.eq(provider_id))
+            .select(SmsMessageRow::as_select())
+            .first(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn update_message(&self, message: &SmsMessage) -> RepoResult<()> {
+        diesel::update(sms_messages::table.filter(sms_messages::message_id.eq(message.message_id)))
+            .set((
+                sms_messages::status.eq(message.status),
+                sms_messages::attempts.eq(message.attempts),
+                sms_messages::next_attempt_at.eq(message.next_attempt_at),
+                sms_messages::provider_id.eq(&message.provider_id),
+                sms_messages::last_error.eq(&message.last_error),
+                sms_messages::sent_at.eq(message.sent_at),
+                sms_messages::delivered_at.eq(message.delivered_at),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+
+    fn has_message(&self, reservation_id: i32, kind: NotificationKind) -> RepoResult<bool> {
+        Ok(diesel::select(diesel::dsl::exists(
+            sms_messages::table
+                .filter(sms_messages::reservation_id.eq(reservation_id))
+                .filter(sms_messages::kind.eq(kind)),
+        ))
+        .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn opted_out(&self, phone: &str) -> RepoResult<bool> {
+        Ok(diesel::select(diesel::dsl::exists(
+            sms_opt_outs::table.filter(sms_opt_outs::phone.eq(phone)),
+        ))
+        .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn set_opted_out(&self, phone: &str, opted_out: bool, at: NaiveDateTime) -> RepoResult<()> {
+        let mut conn = self.pool.get()?;
+        if opted_out {
+            diesel::insert_into(sms_opt_outs::table)
+                .values((
+                    sms_opt_outs::phone.eq(phone),
+                    sms_opt_outs::opted_out_at.eq(at),
+                ))
+                .on_conflict_do_nothing()
+                .execute(&mut conn)?;
+        } else {
+            diesel::delete(sms_opt_outs::table.filter(sms_opt_outs::phone.eq(phone)))
+                .execute(&mut conn)?;
+        }
+        Ok(())
+    }
+
+    fn purge_messages(&self, before: NaiveDateTime) -> RepoResult<usize> {
+        Ok(diesel::delete(
+            sms_messages::table
+                .filter(sms_messages::status.ne(MessageStatus::Queued))
+                .filter(sms_messages::created_at.lt(before)),
+        )
+        .execute(&mut self.pool.get()?)?)
+    }
+}
+
+impl JobRepository for SqliteRepository {
+    fn schedule_job(&self, new_job: NewJob) -> RepoResult<i32> {
+        Ok(diesel::insert_into(jobs::table)
+            .values((
+                jobs::kind.eq(new_job.kind),
+                jobs::property_id.eq(new_job.property_id.map(|pid| pid.to_string())),
+                jobs::reservation_id.eq(new_job.reservation_id),
+                jobs::unique_key.eq(&new_job.unique_key),
+                jobs::run_at.eq(new_job.run_at),
+            ))
+            .on_conflict(jobs::unique_key)
+            .do_update()
+            .set((
+                jobs::run_at.eq(new_job.run_at),
+                jobs::status.eq(JobStatus::Pending),
+                jobs::attempts.eq(0),
+                jobs::last_error.eq(None::<String>),
+                jobs::locked_until.eq(None::<NaiveDateTime>),
+            ))
+            .returning(jobs::job_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn claim_jobs(
+        &self,
+        now: NaiveDateTime,
+        locked_until: NaiveDateTime,
+        limit: i64,
+    ) -> RepoResult<Vec<Job>> {
+        // SQLite serialises writers, the transaction is enough to keep two
+        // workers from claiming the same job.
+        let mut conn = self.pool.get()?;
+        let mut claimed = conn.immediate_transaction::<_, RepositoryError, _>(|conn| {
+            let due: Vec<i32> = jobs::table
+                .filter(
+                    jobs::status
+                        .eq(JobStatus::Pending)
+                        .and(jobs::run_at.le(now))
+                        .or(jobs::status
+                            .eq(JobStatus::Running)
+                            .and(jobs::locked_until.lt(now))),
+                )
+                .order(jobs::run_at.asc())
+                .limit(limit)
+                .select(jobs::job_id)
+                .load(conn)?;
+            diesel::update(jobs::table.filter(jobs::job_id.eq_any(due)))
+                .set((
+                    jobs::status.eq(JobStatus::Running),
+                    jobs::locked_until.eq(locked_until),
+                    jobs::attempts.eq(jobs::attempts + 1),
+                ))
+                .returning(JobRow::as_returning())
+                .get_results(conn)?
+                .into_iter()
+                .map(Job::try_from)
+                .collect::<RepoResult<Vec<Job>>>()
+        })?;
+        claimed.sort_by_key(|job| job.run_at);
+        Ok(claimed)
+    }
+
+    fn find_job(&self, job_id: i32) -> RepoResult<Job> {
+        jobs::table
+            .filter(jobs::job_id.eq(job_id))
+            .select(JobRow::as_select())
+            .first(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn has_job(&self, unique_key: &str) -> RepoResult<bool> {
+        Ok(diesel::select(diesel::dsl::exists(
+            jobs::table.filter(jobs::unique_key.eq(unique_key)),
+        ))
+        .get_result(&mut self.pool.get()?)?)
+    }
+
+    fn jobs(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<Job>> {
+        jobs::table
+            .filter(
+                jobs::property_id
+                    .eq(pid.to_string())
+                    .or(jobs::property_id.is_null()),
+            )
+            .order(jobs::job_id.desc())
+            .limit(limit)
+            .select(JobRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(Job::try_from)
+            .collect()
+    }
+
+    fn update_job(&self, job: &Job) -> RepoResult<()> {
+        diesel::update(jobs::table.filter(jobs::job_id.eq(job.job_id)))
+            .set((
+                jobs::run_at.eq(job.run_at),
+                jobs::status.eq(job.status),
+                jobs::attempts.eq(job.attempts),
+                jobs::last_error.eq(&job.last_error),
+                jobs::locked_until.eq(job.locked_until),
+                jobs::finished_at.eq(job.finished_at),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+
+    fn purge_jobs(&self, before: NaiveDateTime) -> RepoResult<usize> {
+        Ok(diesel::delete(
+            jobs::table
+                .filter(jobs::status.eq_any([JobStatus::Done, JobStatus::Failed]))
+                .filter(jobs::finished_at.lt(before)),
+        )
+        .execute(&mut self.pool.get()?)?)
+    }
+}
+
+/// Fails with [`RepositoryError::Conflict`] when one of `contacts` belongs to
+/// a guest of the property other than `guest_id`.
+fn check_contacts_free(
+    conn: &mut SqliteConnection,
+    pid: &str,
+    guest_id: Option<i32>,
+    contacts: &[String],
+) -> RepoResult<()> {
+    let owners: Vec<(String, i32)> = guest_contacts::table
+        .filter(guest_contacts::property_id.eq(pid))
+        .filter(guest_contacts::contact.eq_any(contacts))
+        .select((guest_contacts::contact, guest_contacts::guest_id))
+        .load(conn)?;
+    match owners
+        .into_iter()
+        .find(|(_, owner)| Some(*owner) != guest_id)
+    {
+        Some((contact, _)) => Err(RepositoryError::Conflict(format!(
+            "{contact} belongs to another guest"
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn insert_contacts(
+    conn: &mut SqliteConnection,
+    pid: &str,
+    guest_id: i32,
+    contacts: &[String],
+) -> RepoResult<()> {
+    diesel::insert_into(guest_contacts::table)
+        .values(
+            contacts
+                .iter()
+                .map(|contact| {
+                    (
+                        guest_contacts::property_id.eq(pid),
+                        guest_contacts::contact.eq(contact),
+                        guest_contacts::guest_id.eq(guest_id),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        )
+        .execute(conn)?;
+    Ok(())
+}
+
+impl GuestRepository for SqliteRepository {
+    fn guests(&self, pid: Uuid) -> RepoResult<Vec<Guest>> {
+        guests::table
+            .filter(guests::property_id.eq(pid.to_string()))
+            .order((guests::name.asc(), guests::guest_id.asc()))
+            .select(GuestRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(Guest::try_from)
+            .collect()
+    }
+
+    fn find_guest(&self, guest_id: i32) -> RepoResult<Guest> {
+        guests::table
+            .filter(guests::guest_id.eq(guest_id))
+            .select(GuestRow::as_select())
+            .first(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn guest_by_contact(&self, pid: Uuid, contact: &str) -> RepoResult<Guest> {
+        guests::table
+            .filter(
+                guests::guest_id.eq_any(
+                    guest_contacts::table
+                        .filter(guest_contacts::property_id.eq(pid.to_string()))
+                        .filter(guest_contacts::contact.eq(contact))
+                        .select(guest_contacts::guest_id),
+                ),
+            )
+            .select(GuestRow::as_select())
+            .first(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn insert_guest(&self, new_guest: NewGuest, contacts: &[String]) -> RepoResult<i32> {
+        let pid = new_guest.property_id.to_string();
+        let mut conn = self.pool.get()?;
+        conn.immediate_transaction(|conn| {
+            check_contacts_free(conn, &pid, None, contacts)?;
+            let guest_id = diesel::insert_into(guests::table)
+                .values((
+                    guests::property_id.eq(&pid),
+                    guests::name.eq(&new_guest.name),
+                    guests::notes.eq(&new_guest.notes),
+                    guests::tags.eq(&new_guest.tags),
+                ))
+                .returning(guests::guest_id)
+                .get_result::<i32>(conn)?;
+            insert_contacts(conn, &pid, guest_id, contacts)?;
+            Ok(guest_id)
+        })
+    }
+
+    fn update_guest(&self, guest: &Guest) -> RepoResult<()> {
+        diesel::update(guests::table.filter(guests::guest_id.eq(guest.guest_id)))
+            .set((
+                guests::name.eq(&guest.name),
+                guests::notes.eq(&guest.notes),
+                guests::tags.eq(&guest.tags),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+
+    fn guest_contacts(&self, guest_id: i32) -> RepoResult<Vec<String>> {
+        Ok(guest_contacts::table
+            .filter(guest_contacts::guest_id.eq(guest_id))
+            .order(guest_contacts::contact.asc())
+            .select(guest_contacts::contact)
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn set_guest_contacts(&self, guest_id: i32, contacts: &[String]) -> RepoResult<()> {
+        let pid = self.find_guest(guest_id)?.property_id.to_string();
+        let mut conn = self.pool.get()?;
+        conn.immediate_transaction(|conn| {
+            check_contacts_free(conn, &pid, Some(guest_id), contacts)?;
+            diesel::delete(guest_contacts::table.filter(guest_contacts::guest_id.eq(guest_id)))
+                .execute(conn)?;
+            insert_contacts(conn, &pid, guest_id, contacts)
+        })
+    }
+
+    fn guest_reservations(&self, guest_id: i32) -> RepoResult<Vec<Reservation>> {
+        to_reservations(
+            reservation::table
+                .filter(reservation::guest_id.eq(guest_id))
+                .order((
+                    reservation::reservation_date.desc(),
+                    reservation::reservation_time.desc(),
+                ))
+                .select(ReservationRow::as_select())
+                .load(&mut self.pool.get()?)?,
+        )
+    }
+
+    fn set_reservation_guest(&self, id: i32, guest_id: i32) -> RepoResult<()> {
+        diesel::update(reservation::table.filter(reservation::id.eq(id)))
+            .set(reservation::guest_id.eq(guest_id))
+            .execute(&mut self.pool.get()?)?;
+        Ok(())
+    }
+    fn property_guest_contacts(&self, pid: Uuid) -> RepoResult<Vec<(i32, String)>> {
+        Ok(guest_contacts::table
+            .filter(guest_contacts::property_id.eq(pid.to_string()))
+            .order((
+                guest_contacts::guest_id.asc(),
+                guest_contacts::contact.asc(),
+            ))
+            .select((guest_contacts::guest_id, guest_contacts::contact))
+            .load(&mut self.pool.get()?)?)
+    }
+
+    fn merge_guests(&self, guest: &Guest, merge: NewGuestMerge) -> RepoResult<GuestMerge> {
+        let duplicate = merge.merged_guest_id;
+        let mut conn = self.pool.get()?;
+        conn.immediate_transaction(|conn| {
+            diesel::update(guests::table.filter(guests::guest_id.eq(guest.guest_id)))
+                .set((
+                    guests::name.eq(&guest.name),
+                    guests::notes.eq(&guest.notes),
+                    guests::tags.eq(&guest.tags),
+                ))
+                .execute(conn)?;
+            let moved =
+                diesel::update(reservation::table.filter(reservation::guest_id.eq(duplicate)))
+                    .set(reservation::guest_id.eq(guest.guest_id))
+                    .execute(conn)?;
+            diesel::update(guest_contacts::table.filter(guest_contacts::guest_id.eq(duplicate)))
+                .set(guest_contacts::guest_id.eq(guest.guest_id))
+                .execute(conn)?;
+            diesel::update(guest_merges::table.filter(guest_merges::guest_id.eq(duplicate)))
+                .set(guest_merges::guest_id.eq(guest.guest_id))
+                .execute(conn)?;
+            let recorded = diesel::insert_into(guest_merges::table)
+                .values((
+                    guest_merges::property_id.eq(merge.property_id.to_string()),
+                    guest_merges::guest_id.eq(merge.guest_id),
+                    guest_merges::merged_guest_id.eq(duplicate),
+                    guest_merges::merged_name.eq(&merge.merged_name),
+                    guest_merges::merged_contacts.eq(&merge.merged_contacts),
+                    guest_merges::reservations.eq(moved as i32),
+                    guest_merges::merged_by.eq(&merge.merged_by),
+                ))
+                .returning(GuestMergeRow::as_returning())
+                .get_result(conn)?;
+            diesel::delete(guests::table.filter(guests::guest_id.eq(duplicate))).execute(conn)?;
+            recorded.try_into()
+        })
+    }
+
+    fn guest_merges(&self, guest_id: i32) -> RepoResult<Vec<GuestMerge>> {
+        guest_merges::table
+            .filter(guest_merges::guest_id.eq(guest_id))
+            .order(guest_merges::merge_id.desc())
+            .select(GuestMergeRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(GuestMerge::try_from)
+            .collect()
+    }
+}
+
+impl WaitlistRepository for SqliteRepository {
+    fn waitlist(&self, pid: Uuid, date: NaiveDate) -> RepoResult<Vec<WaitlistEntry>> {
+        waitlist::table
+            .filter(waitlist::property_id.eq(pid.to_string()))
+            .filter(waitlist::waitlist_date.eq(date))
+            .order(waitlist::entry_id)
+            .select(WaitlistRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(WaitlistEntry::try_from)
+            .collect()
+    }
+
+    fn find_waitlist_entry(&self, entry_id: i32) -> RepoResult<WaitlistEntry> {
+        waitlist::table
+            .filter(waitlist::entry_id.eq(entry_id))
+            .select(WaitlistRow::as_select())
+            .get_result(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn insert_waitlist_entry(&self, new_entry: NewWaitlistEntry) -> RepoResult<i32> {
+        Ok(diesel::insert_into(waitlist::table)
+            .values((
+                waitlist::property_id.eq(new_entry.property_id.to_string()),
+                waitlist::waitlist_date.eq(new_entry.waitlist_date),
+                waitlist::name.eq(new_entry.name),
+                waitlist::contact.eq(new_entry.contact),
+                waitlist::party_size.eq(new_entry.party_size),
+                waitlist::preferred_time.eq(new_entry.preferred_time),
+                waitlist::quoted_wait.eq(new_entry.quoted_wait),
+            ))
+            .returning(waitlist::entry_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn update_waitlist_entry(&self, entry: &WaitlistEntry) -> RepoResult<()> {
+        let updated = diesel::update(waitlist::table.filter(waitlist::entry_id.eq(entry.entry_id)))
+            .set((
+                waitlist::quoted_wait.eq(entry.quoted_wait),
+                waitlist::status.eq(entry.status),
+                waitlist::notified_at.eq(entry.notified_at),
+                waitlist::reservation_id.eq(entry.reservation_id),
+            ))
+            .execute(&mut self.pool.get()?)?;
+        if updated == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+        Ok(())
+    }
+}
+
+impl SeriesRepository for SqliteRepository {
+    fn series(&self, pid: Uuid) -> RepoResult<Vec<ReservationSeries>> {
+        reservation_series::table
+            .filter(reservation_series::property_id.eq(pid.to_string()))
+            .order(reservation_series::series_id.desc())
+            .select(SeriesRow::as_select())
+            .load(&mut self.pool.get()?)?
+            .into_iter()
+            .map(ReservationSeries::try_from)
+            .collect()
+    }
+
+    fn find_series(&self, series_id: i32) -> RepoResult<ReservationSeries> {
+        reservation_series::table
+            .filter(reservation_series::series_id.eq(series_id))
+            .select(SeriesRow::as_select())
+            .get_result(&mut self.pool.get()?)?
+            .try_into()
+    }
+
+    fn insert_series(&self, new_series: NewReservationSeries) -> RepoResult<i32> {
+        Ok(diesel::insert_into(reservation_series::table)
+            .values((
+                reservation_series::property_id.eq(new_series.property_id.to_string()),
+                reservation_series::name.eq(new_series.name),
+                reservation_series::contact.eq(new_series.contact),
+                reservation_series::seating.eq(new_series.seating),
+                reservation_series::party_size.eq(new_series.party_size),
+                reservation_series::reservation_time.eq(new_series.reservation_time),
+                reservation_series::recurrence.eq(new_series.recurrence),
+                reservation_series::starts_on.eq(new_series.starts_on),
+                reservation_series::ends_on.eq(new_series.ends_on),
+                reservation_series::occurrences.eq(new_series.occurrences),
+            ))
+            .returning(reservation_series::series_id)
+            .get_result::<i32>(&mut self.pool.get()?)?)
+    }
+
+    fn update_series(&self, series: &ReservationSeries) -> RepoResult<()> {
+        let updated = diesel::update(
+            reservation_series::table.filter(reservation_series::series_id.eq(series.series_id)),
+        )
+        .set((
+            reservation_series::seating.eq(&series.seating),
+            reservation_series::party_size.eq(series.party_size),
+            reservation_series::reservation_time.eq(series.reservation_time),
+            reservation_series::cancelled_at.eq(series.cancelled_at),
+        ))
+        .execute(&mut self.pool.get()?)?;
+        if updated == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+        Ok(())
+    }
+
+    fn occurrences(&self, series_id: i32) -> RepoResult<Vec<Reservation>> {
+        to_reservations(
+            reservation::table
+                .filter(reservation::series_id.eq(series_id))
+                .order((reservation::reservation_date, reservation::reservation_time))
+                .select(ReservationRow::as_select())
+                .load(&mut self.pool.get()?)?,
+        )
+    }
+
+    fn delete_series(&self, series_id: i32) -> RepoResult<()> {
+        let mut conn = self.pool.get()?;
+        conn.transaction(|conn| {
+            let occurrences = reservation::table
+                .filter(reservation::series_id.eq(series_id))
+                .select(reservation::id);
+            diesel::delete(
+                reservation_history::table
+                    .filter(reservation_history::reservation_id.eq_any(occurrences)),
+            )
+            .execute(conn)?;
+            diesel::delete(reservation::table.filter(reservation::series_id.eq(series_id)))
+                .execute(conn)?;
+            let deleted = diesel::delete(
+                reservation_series::table.filter(reservation_series::series_id.eq(series_id)),
+            )
+            .execute(conn)?;
+            if deleted == 0 {
+                return Err(RepositoryError::NotFound);
+            }
+            Ok(())
+        })
+    }
+}
@@ -1,5 +1,108 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    api_keys (key_id) {
+        key_id -> Int4,
+        property_id -> Uuid,
+        key_name -> Varchar,
+        key_prefix -> Varchar,
+        key_hash -> Varchar,
+        scopes -> Int4,
+        created_at -> Timestamp,
+        last_used_at -> Nullable<Timestamp>,
+        revoked_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    closures (closure_id) {
+        closure_id -> Int4,
+        property_id -> Uuid,
+        first_day -> Date,
+        last_day -> Date,
+        reason -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    email_templates (property_id, kind) {
+        property_id -> Uuid,
+        kind -> Int4,
+        subject -> Varchar,
+        body -> Text,
+    }
+}
+
+diesel::table! {
+    emails (email_id) {
+        email_id -> Int4,
+        property_id -> Uuid,
+        reservation_id -> Nullable<Int4>,
+        kind -> Int4,
+        recipient -> Varchar,
+        reply_to -> Nullable<Varchar>,
+        subject -> Varchar,
+        body -> Text,
+        status -> Int4,
+        attempts -> Int4,
+        next_attempt_at -> Timestamp,
+        last_error -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        sent_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    guest_contacts (property_id, contact) {
+        property_id -> Uuid,
+        contact -> Varchar,
+        guest_id -> Int4,
+    }
+}
+
+diesel::table! {
+    guest_merges (merge_id) {
+        merge_id -> Int4,
+        property_id -> Uuid,
+        guest_id -> Int4,
+        merged_guest_id -> Int4,
+        merged_name -> Varchar,
+        merged_contacts -> Varchar,
+        reservations -> Int4,
+        merged_by -> Varchar,
+        merged_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    guests (guest_id) {
+        guest_id -> Int4,
+        property_id -> Uuid,
+        name -> Varchar,
+        notes -> Varchar,
+        tags -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    jobs (job_id) {
+        job_id -> Int4,
+        kind -> Int4,
+        property_id -> Nullable<Uuid>,
+        reservation_id -> Nullable<Int4>,
+        unique_key -> Nullable<Varchar>,
+        run_at -> Timestamp,
+        status -> Int4,
+        attempts -> Int4,
+        last_error -> Nullable<Varchar>,
+        locked_until -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     myusers (id) {
         id -> Int4,
@@ -20,6 +123,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    property_tables (table_id) {
+        table_id -> Int4,
+        property_id -> Uuid,
+        table_name -> Varchar,
+        capacity -> Int4,
+        section -> Nullable<Varchar>,
+    }
+}
+
 diesel::table! {
     propertyusers (user_id) {
         user_id -> Int4,
@@ -44,6 +157,73 @@ diesel::table! {
         reservation_date -> Date,
         reservation_time -> Time,
         property_id -> Uuid,
+        status -> Int4,
+        party_size -> Int4,
+        confirmation_token -> Nullable<Varchar>,
+        confirmation_code -> Nullable<Varchar>,
+        guest_id -> Nullable<Int4>,
+        occasion -> Nullable<Int4>,
+        dietary -> Varchar,
+        high_chairs -> Int4,
+        accessibility -> Varchar,
+        seating_request -> Varchar,
+        tags -> Varchar,
+        no_show_at -> Nullable<Timestamp>,
+        series_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    reservation_events (reservation_id) {
+        reservation_id -> Int4,
+        section -> Nullable<Varchar>,
+        menu -> Varchar,
+        minimum_spend -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    reservation_history (entry_id) {
+        entry_id -> Int4,
+        reservation_id -> Int4,
+        changed_at -> Timestamp,
+        changed_by -> Varchar,
+        summary -> Varchar,
+    }
+}
+
+diesel::table! {
+    reservation_notes (note_id) {
+        note_id -> Int4,
+        reservation_id -> Int4,
+        author -> Varchar,
+        body -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    reservation_series (series_id) {
+        series_id -> Int4,
+        property_id -> Uuid,
+        name -> Varchar,
+        contact -> Varchar,
+        seating -> Varchar,
+        party_size -> Int4,
+        reservation_time -> Time,
+        recurrence -> Int4,
+        starts_on -> Date,
+        ends_on -> Nullable<Date>,
+        occurrences -> Nullable<Int4>,
+        cancelled_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    reservation_tables (reservation_id, table_id) {
+        reservation_id -> Int4,
+        table_id -> Int4,
     }
 }
 
@@ -55,14 +235,146 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    sms_messages (message_id) {
+        message_id -> Int4,
+        property_id -> Uuid,
+        reservation_id -> Nullable<Int4>,
+        kind -> Int4,
+        channel -> Varchar,
+        recipient -> Varchar,
+        body -> Text,
+        status -> Int4,
+        attempts -> Int4,
+        next_attempt_at -> Timestamp,
+        provider_id -> Nullable<Varchar>,
+        last_error -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        sent_at -> Nullable<Timestamp>,
+        delivered_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    sms_opt_outs (phone) {
+        phone -> Varchar,
+        opted_out_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    table_layouts (table_id) {
+        table_id -> Int4,
+        x -> Int4,
+        y -> Int4,
+        width -> Int4,
+        height -> Int4,
+        rotation -> Int4,
+        shape -> Int4,
+    }
+}
+
+diesel::table! {
+    waitlist (entry_id) {
+        entry_id -> Int4,
+        property_id -> Uuid,
+        waitlist_date -> Date,
+        name -> Varchar,
+        contact -> Varchar,
+        party_size -> Int4,
+        preferred_time -> Nullable<Time>,
+        quoted_wait -> Int4,
+        status -> Int4,
+        notified_at -> Nullable<Timestamp>,
+        reservation_id -> Nullable<Int4>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    webhook_deliveries (delivery_id) {
+        delivery_id -> Int4,
+        webhook_id -> Int4,
+        event -> Varchar,
+        payload -> Jsonb,
+        status -> Int4,
+        attempts -> Int4,
+        next_attempt_at -> Timestamp,
+        response_status -> Nullable<Int4>,
+        last_error -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        delivered_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    webhooks (webhook_id) {
+        webhook_id -> Int4,
+        property_id -> Uuid,
+        url -> Varchar,
+        secret -> Varchar,
+        events -> Int4,
+        active -> Bool,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::joinable!(api_keys -> property (property_id));
+diesel::joinable!(email_templates -> property (property_id));
+diesel::joinable!(closures -> property (property_id));
+diesel::joinable!(emails -> property (property_id));
+diesel::joinable!(emails -> reservation (reservation_id));
+diesel::joinable!(guest_contacts -> guests (guest_id));
+diesel::joinable!(guest_contacts -> property (property_id));
+diesel::joinable!(guest_merges -> guests (guest_id));
+diesel::joinable!(guest_merges -> property (property_id));
+diesel::joinable!(guests -> property (property_id));
+diesel::joinable!(jobs -> property (property_id));
+diesel::joinable!(jobs -> reservation (reservation_id));
+diesel::joinable!(property_tables -> property (property_id));
 diesel::joinable!(propertyusers -> property (property_id));
 diesel::joinable!(propertyusers -> roles (user_role));
+diesel::joinable!(reservation -> guests (guest_id));
 diesel::joinable!(reservation -> property (property_id));
+diesel::joinable!(reservation -> reservation_series (series_id));
+diesel::joinable!(reservation_events -> reservation (reservation_id));
+diesel::joinable!(reservation_history -> reservation (reservation_id));
+diesel::joinable!(reservation_notes -> reservation (reservation_id));
+diesel::joinable!(reservation_series -> property (property_id));
+diesel::joinable!(reservation_tables -> property_tables (table_id));
+diesel::joinable!(reservation_tables -> reservation (reservation_id));
+diesel::joinable!(sms_messages -> property (property_id));
+diesel::joinable!(sms_messages -> reservation (reservation_id));
+diesel::joinable!(table_layouts -> property_tables (table_id));
+diesel::joinable!(waitlist -> property (property_id));
+diesel::joinable!(waitlist -> reservation (reservation_id));
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
+diesel::joinable!(webhooks -> property (property_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    api_keys,
+    closures,
+    email_templates,
+    emails,
+    guest_contacts,
+    guest_merges,
+    guests,
+    jobs,
     myusers,
     property,
+    property_tables,
     propertyusers,
     reservation,
+    reservation_events,
+    reservation_history,
+    reservation_notes,
+    reservation_series,
+    reservation_tables,
     roles,
+    sms_messages,
+    sms_opt_outs,
+    table_layouts,
+    waitlist,
+    webhook_deliveries,
+    webhooks,
 );
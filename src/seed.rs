@@ -0,0 +1,160 @@
+//! Canonical rows every database needs and the demo data used for local
+//! development and tests.
+use crate::crud_properties::create_reservation;
+use crate::errors::RepositoryError;
+use crate::guests::NoShowPolicy;
+use crate::models::{
+    NewProperty, NewPropertyTable, NewPropertyUser, NewResv, PaymentMethod, PaymentMode, Role,
+};
+use crate::repository::{GuestRepository, PropertyRepository, RepoResult, ReservationRepository};
+use crate::{salt_password, Database};
+use chrono::{Days, Local};
+use diesel::RunQueryDsl;
+use time::macros::time;
+use uuid::Uuid;
+
+pub const DEMO_PROPERTY_NAME: &str = "Demo Café";
+/// Password of the demo property and all of its staff.
+pub const DEMO_PASSWORD: &str = "demo";
+
+/// Role rows keyed by the [`Role`] discriminants, `propertyusers.user_role`
+/// references them.
+pub const ROLES: [(Role, &str); 4] = [
+    (Role::Dev, "Dev"),
+    (Role::PropertyAdmin, "PropertyAdmin"),
+    (Role::PropertyManager, "PropertyManager"),
+    (Role::PropertyUser, "PropertyUser"),
+];
+
+/// Inserts the canonical roles, rows that already exist are left untouched.
+/// Returns the number of inserted rows.
+pub fn seed_roles(db: &Database) -> RepoResult<usize> {
+    let values: Vec<String> = ROLES
+        .iter()
+        .map(|(role, name)| format!("({}, '{}')", role.clone() as i32, name))
+        .collect();
+    let query = diesel::sql_query(format!(
+        r#"INSERT INTO "roles"("role_id", "role_name") VALUES {} ON CONFLICT ("role_id") DO NOTHING"#,
+        values.join(", ")
+    ));
+
+    Ok(match db {
+        Database::Postgres(pool) => query.execute(&mut pool.get()?)?,
+        #[cfg(feature = "sqlite")]
+        Database::Sqlite(pool) => query.execute(&mut pool.get()?)?,
+    })
+}
+
+/// Creates the demo property with its staff, tables and a week of
+/// reservations by seven regulars starting today.
+///
+/// Seeding twice returns the existing demo property instead of duplicating it.
+pub async fn seed_demo(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    guests: &dyn GuestRepository,
+    policy: &NoShowPolicy,
+) -> RepoResult<Uuid> {
+    if let Some(existing) = properties
+        .all()?
+        .into_iter()
+        .find(|p| p.property_name == DEMO_PROPERTY_NAME)
+    {
+        return Ok(existing.property_id);
+    }
+
+    let hash = |secret: &str| {
+        let secret = secret.to_string();
+        async move {
+            salt_password(secret)
+                .await
+                .map_err(|e| RepositoryError::Database(e.to_string()))
+        }
+    };
+
+    let pid = properties.insert(NewProperty::new(
+        Uuid::new_v4(),
+        DEMO_PROPERTY_NAME.to_string(),
+        hash(DEMO_PASSWORD).await?,
+        "hello@demo.cafe".to_string(),
+        "+910000000000".to_string(),
+    ))?;
+
+    for (user_name, role) in [
+        ("admin", Role::PropertyAdmin),
+        ("manager", Role::PropertyManager),
+        ("host", Role::PropertyUser),
+    ] {
+        properties.insert_user(NewPropertyUser::new(
+            user_name.to_string(),
+            hash(DEMO_PASSWORD).await?,
+            role as i32,
+            pid,
+        ))?;
+    }
+
+    let tables = [
+        ("T1", 2, "Window"),
+        ("T2", 2, "Window"),
+        ("T3", 4, "Main"),
+        ("T4", 4, "Main"),
+        ("T5", 6, "Main"),
+        ("P1", 8, "Patio"),
+    ];
+    for (name, capacity, section) in tables {
+        properties.insert_table(NewPropertyTable::new(
+            pid,
+            name.to_string(),
+            capacity,
+            Some(section.to_string()),
+        ))?;
+    }
+
+    let names = ["Asha", "Rahul", "Meera", "Vikram", "Priya", "Kabir", "Nila"];
+    let slots = [
+        (time!(12:30), "T1", 2),
+        (time!(19:00), "T3", 4),
+        (time!(20:30), "T5", 5),
+    ];
+    let today = Local::now().date_naive();
+    for day in 0..7u64 {
+        let date = today + Days::new(day);
+        for (n, (slot, table, party_size)) in slots.iter().enumerate() {
+            let regular = (day as usize + n) % names.len();
+            let paid = n == 2;
+            let advance_method = serde_json::to_value(PaymentMethod::new(
+                if paid {
+                    PaymentMode::Gpay
+                } else {
+                    PaymentMode::NotPaid
+                },
+                None,
+                None,
+                paid.then_some(today),
+            ))
+            .expect("failed to convert struct into json");
+            create_reservation(
+                properties,
+                reservations,
+                guests,
+                policy,
+                NewResv::new(
+                    format!("{} (demo)", names[regular]),
+                    format!("+91900000000{regular}"),
+                    table.to_string(),
+                    false,
+                    paid,
+                    advance_method,
+                    paid.then_some(500),
+                    true,
+                    date,
+                    *slot,
+                    pid,
+                )
+                .with_party_size(*party_size),
+            )?;
+        }
+    }
+
+    Ok(pid)
+}
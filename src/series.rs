@@ -0,0 +1,664 @@
+//! Recurring series of standing reservations.
+//!
+//! A regular booking the same table every Tuesday lunch is entered once as a
+//! [`ReservationSeries`] coming back every week, every other week or every
+//! month, until a last day or for a number of occurrences. Each occurrence is
+//! booked as an ordinary reservation pointing back at its series, so it shows
+//! up in the list, on the day sheet and in reminders like any other booking.
+//! Days the property is closed are skipped, and so are days the table of the
+//! series is taken or no table is free, and staff are told which and why.
+//!
+//! Staff change or cancel a single occurrence, or every upcoming occurrence
+//! of the series at once.
+use crate::booking::{add_closure, check_seating, day_holds, table_for};
+use crate::crud_properties::{cancel_reservation, create_reservation};
+use crate::errors::RepositoryError;
+use crate::guests::NoShowPolicy;
+use crate::manage::hhmm;
+use crate::models::{
+    Closure, HistoryEntry, NewClosure, NewHistoryEntry, NewReservationSeries, NewResv,
+    PaymentMethod, PaymentMode, PropertyTable, Recurrence, Reservation, ReservationSeries,
+    ReservationStatus, SeriesOverview, SeriesSchedule, SkippedOccurrence, WebhookEvent,
+};
+use crate::repository::{
+    GuestRepository, PropertyRepository, RepoResult, ReservationRepository, SeriesRepository,
+};
+use crate::waitlist::parse_time;
+use crate::AppState;
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use leptos::{expect_context, logging, server, ServerFnError};
+use std::str::FromStr;
+use time::Time;
+use uuid::Uuid;
+
+/// Most occurrences a series may have, two years of weekly bookings.
+pub const MAX_OCCURRENCES: usize = 104;
+
+/// The days a series falls on, from `starts_on` until `ends_on` or for
+/// `occurrences` days, whichever comes first.
+pub fn occurrence_dates(
+    recurrence: Recurrence,
+    starts_on: NaiveDate,
+    ends_on: Option<NaiveDate>,
+    occurrences: Option<i32>,
+) -> RepoResult<Vec<NaiveDate>> {
+    let count = match (ends_on, occurrences) {
+        (None, None) => {
+            return Err(RepositoryError::Invalid(
+                "a series needs a last day or a number of occurrences".into(),
+            ))
+        }
+        (Some(ends_on), _) if ends_on < starts_on => {
+            return Err(RepositoryError::Invalid(
+                "a series cannot end before it starts".into(),
+            ))
+        }
+        (_, Some(n)) if n < 1 => {
+            return Err(RepositoryError::Invalid(
+                "a series needs at least one occurrence".into(),
+            ))
+        }
+        (_, Some(n)) => n as usize,
+        (_, None) => usize::MAX,
+    };
+    let mut dates = Vec::new();
+    for n in 0.. {
+        let Some(date) = recurrence.nth(starts_on, n) else {
+            break;
+        };
+        if dates.len() == count || ends_on.is_some_and(|ends_on| date > ends_on) {
+            break;
+        }
+        if dates.len() == MAX_OCCURRENCES {
+            return Err(RepositoryError::Invalid(format!(
+                "a series can have at most {MAX_OCCURRENCES} occurrences"
+            )));
+        }
+        dates.push(date);
+    }
+    Ok(dates)
+}
+
+fn closed(closure: &Closure) -> String {
+    match closure.reason.as_str() {
+        "" => "closed".to_string(),
+        reason => format!("closed: {reason}"),
+    }
+}
+
+/// Whether an occurrence is still ahead and can be changed or cancelled.
+pub fn upcoming(resv: &Reservation, today: NaiveDate) -> bool {
+    resv.reservation_date >= today
+        && matches!(
+            resv.status,
+            ReservationStatus::Booked | ReservationStatus::Requested
+        )
+}
+
+/// Stores a series starting `today` at the earliest and books its
+/// occurrences, skipping closed days and those without a table. When an
+/// occurrence fails for any other reason the series is deleted again with
+/// whatever was booked of it.
+pub fn create_series(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    guests: &dyn GuestRepository,
+    policy: &NoShowPolicy,
+    repo: &dyn SeriesRepository,
+    mut new_series: NewReservationSeries,
+    today: NaiveDate,
+) -> RepoResult<SeriesSchedule> {
+    new_series.name = new_series.name.trim().to_string();
+    new_series.contact = new_series.contact.trim().to_string();
+    if new_series.name.is_empty() {
+        return Err(RepositoryError::Invalid("name is required".into()));
+    }
+    if new_series.contact.is_empty() {
+        return Err(RepositoryError::Invalid("contact is required".into()));
+    }
+    if new_series.party_size < 1 {
+        return Err(RepositoryError::Invalid(
+            "a series needs at least one guest".into(),
+        ));
+    }
+    if new_series.starts_on < today {
+        return Err(RepositoryError::Invalid(
+            "a series cannot start in the past".into(),
+        ));
+    }
+    let dates = occurrence_dates(
+        new_series.recurrence,
+        new_series.starts_on,
+        new_series.ends_on,
+        new_series.occurrences,
+    )?;
+    let pid = new_series.property_id;
+    let tables = properties.tables(pid)?;
+    new_series.seating = check_seating(&tables, &new_series.seating, new_series.party_size)?;
+    let closures = properties.closures(pid)?;
+    let series = repo.find_series(repo.insert_series(new_series)?)?;
+
+    // The series and its occurrences stand or fall together.
+    let (booked, skipped) = match book_occurrences(
+        properties,
+        reservations,
+        guests,
+        policy,
+        &tables,
+        &closures,
+        &series,
+        dates,
+    ) {
+        Ok(placed) => placed,
+        Err(e) => {
+            if let Err(cleanup) = repo.delete_series(series.series_id) {
+                logging::warn!("cannot delete series {}: {cleanup}", series.series_id);
+            }
+            return Err(e);
+        }
+    };
+    Ok(SeriesSchedule {
+        series,
+        booked,
+        skipped,
+    })
+}
+
+/// Books an occurrence of `series` on each of `dates`, skipping closed days
+/// and those without a table.
+#[allow(clippy::too_many_arguments)]
+fn book_occurrences(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    guests: &dyn GuestRepository,
+    policy: &NoShowPolicy,
+    tables: &[PropertyTable],
+    closures: &[Closure],
+    series: &ReservationSeries,
+    dates: Vec<NaiveDate>,
+) -> RepoResult<(Vec<Reservation>, Vec<SkippedOccurrence>)> {
+    let pid = series.property_id;
+    let advance_method =
+        serde_json::to_value(PaymentMethod::new(PaymentMode::NotPaid, None, None, None))
+            .map_err(|e| RepositoryError::Invalid(e.to_string()))?;
+    let (mut booked, mut skipped) = (Vec::new(), Vec::new());
+    for date in dates {
+        if let Some(closure) = closures.iter().find(|closure| closure.covers(date)) {
+            skipped.push(SkippedOccurrence {
+                date,
+                reason: closed(closure),
+            });
+            continue;
+        }
+        let placed = table_for(
+            tables,
+            &day_holds(reservations, tables, pid, date)?,
+            &series.seating,
+            series.party_size,
+            series.reservation_time,
+        )
+        .and_then(|table| {
+            let new_resv = NewResv::new(
+                series.name.clone(),
+                series.contact.clone(),
+                table,
+                !series.seating.is_empty(),
+                false,
+                advance_method.clone(),
+                None,
+                true,
+                date,
+                series.reservation_time,
+                pid,
+            )
+            .with_party_size(series.party_size)
+            .in_series(series.series_id);
+            create_reservation(properties, reservations, guests, policy, new_resv)
+        });
+        match placed {
+            Ok(rid) => booked.push(reservations.find(rid)?),
+            Err(RepositoryError::Conflict(reason)) => {
+                skipped.push(SkippedOccurrence { date, reason })
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok((booked, skipped))
+}
+
+/// Moves an occurrence to another time, table or party size on its day,
+/// or fails with the reason it cannot go there.
+fn move_occurrence(
+    reservations: &dyn ReservationRepository,
+    tables: &[PropertyTable],
+    resv: &Reservation,
+    time: Time,
+    seating: &str,
+    party_size: i32,
+) -> RepoResult<Reservation> {
+    let mut booked = day_holds(
+        reservations,
+        tables,
+        resv.property_id,
+        resv.reservation_date,
+    )?;
+    booked.retain(|other| other.id != resv.id);
+    let table = table_for(tables, &booked, seating, party_size, time)?;
+    let moved = reservations.reschedule(resv.id, resv.reservation_date, time, table, party_size)?;
+    reservations.set_table_assignments(resv.id, &[])?;
+    Ok(moved)
+}
+
+fn changes(resv: &Reservation) -> String {
+    format!(
+        "{} on {} for {}",
+        hhmm(resv.reservation_time),
+        resv.seating,
+        resv.party_size
+    )
+}
+
+/// Changes the time, table or party size of a single upcoming reservation,
+/// leaving the rest of its series as it is.
+pub fn edit_occurrence(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    id: i32,
+    time: Time,
+    seating: &str,
+    party_size: i32,
+) -> RepoResult<Reservation> {
+    let resv = reservations.find(id)?;
+    if !matches!(
+        resv.status,
+        ReservationStatus::Booked | ReservationStatus::Requested
+    ) {
+        return Err(RepositoryError::Conflict(format!(
+            "reservation {id} is {} and cannot be changed",
+            resv.status
+        )));
+    }
+    if party_size < 1 {
+        return Err(RepositoryError::Invalid(
+            "a reservation needs at least one guest".into(),
+        ));
+    }
+    let tables = properties.tables(resv.property_id)?;
+    let seating = check_seating(&tables, seating, party_size)?;
+    let moved = move_occurrence(reservations, &tables, &resv, time, &seating, party_size)?;
+    reservations.insert_history(NewHistoryEntry {
+        reservation_id: id,
+        changed_by: HistoryEntry::STAFF.to_string(),
+        summary: format!(
+            "changed from {} to {}, this occurrence only",
+            changes(&resv),
+            changes(&moved)
+        ),
+    })?;
+    Ok(moved)
+}
+
+/// Cancels a single occurrence, leaving the rest of its series booked.
+pub fn cancel_occurrence(
+    reservations: &dyn ReservationRepository,
+    id: i32,
+) -> RepoResult<Reservation> {
+    let cancelled = cancel_reservation(reservations, id)?;
+    reservations.insert_history(NewHistoryEntry {
+        reservation_id: id,
+        changed_by: HistoryEntry::STAFF.to_string(),
+        summary: "cancelled, this occurrence only".to_string(),
+    })?;
+    Ok(cancelled)
+}
+
+fn open_series(repo: &dyn SeriesRepository, series_id: i32) -> RepoResult<ReservationSeries> {
+    let series = repo.find_series(series_id)?;
+    if series.cancelled_at.is_some() {
+        return Err(RepositoryError::Conflict(format!(
+            "series {series_id} was cancelled"
+        )));
+    }
+    Ok(series)
+}
+
+/// Changes the time, table or party size of a series and of its upcoming
+/// occurrences. Occurrences that cannot move are left as they were and
+/// reported as skipped.
+#[allow(clippy::too_many_arguments)]
+pub fn edit_series(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    repo: &dyn SeriesRepository,
+    series_id: i32,
+    time: Time,
+    seating: &str,
+    party_size: i32,
+    today: NaiveDate,
+) -> RepoResult<SeriesSchedule> {
+    let mut series = open_series(repo, series_id)?;
+    if party_size < 1 {
+        return Err(RepositoryError::Invalid(
+            "a series needs at least one guest".into(),
+        ));
+    }
+    let tables = properties.tables(series.property_id)?;
+    series.seating = check_seating(&tables, seating, party_size)?;
+    series.party_size = party_size;
+    series.reservation_time = time;
+    repo.update_series(&series)?;
+
+    let (mut booked, mut skipped) = (Vec::new(), Vec::new());
+    for resv in repo.occurrences(series_id)? {
+        if !upcoming(&resv, today) {
+            continue;
+        }
+        match move_occurrence(
+            reservations,
+            &tables,
+            &resv,
+            time,
+            &series.seating,
+            party_size,
+        ) {
+            Ok(moved) => {
+                reservations.insert_history(NewHistoryEntry {
+                    reservation_id: resv.id,
+                    changed_by: HistoryEntry::STAFF.to_string(),
+                    summary: format!(
+                        "changed from {} to {} with its series",
+                        changes(&resv),
+                        changes(&moved)
+                    ),
+                })?;
+                booked.push(moved);
+            }
+            Err(RepositoryError::Conflict(reason)) => skipped.push(SkippedOccurrence {
+                date: resv.reservation_date,
+                reason,
+            }),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(SeriesSchedule {
+        series,
+        booked,
+        skipped,
+    })
+}
+
+/// Cancels the upcoming occurrences of a series and then the series, and
+/// returns the reservations cancelled. Past occurrences keep their status.
+/// When an occurrence cannot be cancelled the others still are, and the
+/// series stays open so that cancelling it again picks up the rest.
+pub fn cancel_series(
+    reservations: &dyn ReservationRepository,
+    repo: &dyn SeriesRepository,
+    series_id: i32,
+    today: NaiveDate,
+    now: NaiveDateTime,
+) -> RepoResult<Vec<Reservation>> {
+    let mut series = open_series(repo, series_id)?;
+    let mut cancelled = Vec::new();
+    let mut failed = Vec::new();
+    for resv in repo.occurrences(series_id)? {
+        if !upcoming(&resv, today) {
+            continue;
+        }
+        let cancelling = cancel_reservation(reservations, resv.id).and_then(|resv| {
+            reservations.insert_history(NewHistoryEntry {
+                reservation_id: resv.id,
+                changed_by: HistoryEntry::STAFF.to_string(),
+                summary: "cancelled with its series".to_string(),
+            })?;
+            Ok(resv)
+        });
+        match cancelling {
+            Ok(resv) => cancelled.push(resv),
+            Err(e) => failed.push(format!("reservation {}: {e}", resv.id)),
+        }
+    }
+    if !failed.is_empty() {
+        return Err(RepositoryError::Conflict(format!(
+            "series {series_id} stays open, cannot cancel {}",
+            failed.join("; ")
+        )));
+    }
+    series.cancelled_at = Some(now);
+    repo.update_series(&series)?;
+    Ok(cancelled)
+}
+
+/// The series of a property with their occurrences, the latest first.
+pub fn overview(repo: &dyn SeriesRepository, pid: Uuid) -> RepoResult<Vec<SeriesOverview>> {
+    repo.series(pid)?
+        .into_iter()
+        .map(|series| {
+            Ok(SeriesOverview {
+                occurrences: repo.occurrences(series.series_id)?,
+                series,
+            })
+        })
+        .collect()
+}
+
+/// An optional value typed into a form, `None` when left blank.
+fn optional<T: FromStr>(raw: &str, what: &str) -> RepoResult<Option<T>> {
+    match raw.trim() {
+        "" => Ok(None),
+        raw => raw
+            .parse()
+            .map(Some)
+            .map_err(|_| RepositoryError::Invalid(format!("{raw:?} is not a valid {what}"))),
+    }
+}
+
+fn parse_recurrence(raw: &str) -> RepoResult<Recurrence> {
+    Recurrence::ALL
+        .into_iter()
+        .find(|recurrence| recurrence.to_string().eq_ignore_ascii_case(raw.trim()))
+        .ok_or_else(|| RepositoryError::Invalid(format!("{raw:?} is not a recurrence")))
+}
+
+fn required_time(raw: &str) -> RepoResult<Time> {
+    parse_time(raw)?.ok_or_else(|| RepositoryError::Invalid("a time is required".into()))
+}
+
+#[server(name = ListSeries, prefix = "/api", endpoint = "list_series", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn list_series(pid: Uuid) -> Result<Vec<SeriesOverview>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    overview(state.series.as_ref(), pid).map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[server(name = AddSeries, prefix = "/api", endpoint = "add_series", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn add_series(
+    pid: Uuid,
+    name: String,
+    contact: String,
+    seating: String,
+    party_size: i32,
+    reservation_time: String,
+    recurrence: String,
+    starts_on: NaiveDate,
+    ends_on: String,
+    occurrences: String,
+) -> Result<SeriesSchedule, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    let created = state.properties.find(pid).and_then(|_| {
+        let new_series = NewReservationSeries {
+            property_id: pid,
+            name,
+            contact,
+            seating,
+            party_size,
+            reservation_time: required_time(&reservation_time)?,
+            recurrence: parse_recurrence(&recurrence)?,
+            starts_on,
+            ends_on: optional(&ends_on, "last day")?,
+            occurrences: optional(&occurrences, "number of occurrences")?,
+        };
+        create_series(
+            state.properties.as_ref(),
+            state.reservations.as_ref(),
+            state.guests.as_ref(),
+            &state.no_show_policy,
+            state.series.as_ref(),
+            new_series,
+            Local::now().date_naive(),
+        )
+    });
+    match created {
+        Ok(schedule) => {
+            for resv in &schedule.booked {
+                state.publish(WebhookEvent::ReservationCreated, resv);
+            }
+            Ok(schedule)
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+#[server(name = EditOccurrence, prefix = "/api", endpoint = "edit_occurrence", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn edit_one_occurrence(
+    id: i32,
+    reservation_time: String,
+    seating: String,
+    party_size: i32,
+) -> Result<Reservation, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    let edited = required_time(&reservation_time).and_then(|time| {
+        edit_occurrence(
+            state.properties.as_ref(),
+            state.reservations.as_ref(),
+            id,
+            time,
+            &seating,
+            party_size,
+        )
+    });
+    match edited {
+        Ok(resv) => {
+            state.publish(WebhookEvent::ReservationUpdated, &resv);
+            Ok(resv)
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+#[server(name = CancelOccurrence, prefix = "/api", endpoint = "cancel_occurrence", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn cancel_one_occurrence(id: i32) -> Result<Reservation, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    match cancel_occurrence(state.reservations.as_ref(), id) {
+        Ok(resv) => {
+            state.publish(WebhookEvent::ReservationCancelled, &resv);
+            Ok(resv)
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+#[server(name = EditSeries, prefix = "/api", endpoint = "edit_series", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn edit_whole_series(
+    series_id: i32,
+    reservation_time: String,
+    seating: String,
+    party_size: i32,
+) -> Result<SeriesSchedule, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    let edited = required_time(&reservation_time).and_then(|time| {
+        edit_series(
+            state.properties.as_ref(),
+            state.reservations.as_ref(),
+            state.series.as_ref(),
+            series_id,
+            time,
+            &seating,
+            party_size,
+            Local::now().date_naive(),
+        )
+    });
+    match edited {
+        Ok(schedule) => {
+            for resv in &schedule.booked {
+                state.publish(WebhookEvent::ReservationUpdated, resv);
+            }
+            Ok(schedule)
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+#[server(name = CancelSeries, prefix = "/api", endpoint = "cancel_series", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn cancel_whole_series(series_id: i32) -> Result<Vec<Reservation>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    let now = Local::now().naive_local();
+    match cancel_series(
+        state.reservations.as_ref(),
+        state.series.as_ref(),
+        series_id,
+        now.date(),
+        now,
+    ) {
+        Ok(cancelled) => {
+            for resv in &cancelled {
+                state.publish(WebhookEvent::ReservationCancelled, resv);
+            }
+            Ok(cancelled)
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+#[server(name = ListClosures, prefix = "/api", endpoint = "list_closures", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn list_closures(pid: Uuid) -> Result<Vec<Closure>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    state
+        .properties
+        .closures(pid)
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(name = AddClosure, prefix = "/api", endpoint = "add_closure", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn add_property_closure(
+    pid: Uuid,
+    first_day: NaiveDate,
+    last_day: String,
+    reason: String,
+) -> Result<Closure, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    state
+        .properties
+        .find(pid)
+        .and_then(|_| optional(&last_day, "last day"))
+        .and_then(|last_day| {
+            add_closure(
+                state.properties.as_ref(),
+                NewClosure {
+                    property_id: pid,
+                    first_day,
+                    last_day: last_day.unwrap_or(first_day),
+                    reason,
+                },
+            )
+        })
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(name = RemoveClosure, prefix = "/api", endpoint = "remove_closure", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn remove_closure(pid: Uuid, closure_id: i32) -> Result<(), ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    state
+        .properties
+        .delete_closure(pid, closure_id)
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
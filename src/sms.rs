@@ -0,0 +1,698 @@
+//! Text messages to guests, by SMS or WhatsApp.
+//!
+//! Messages go through the same kind of outbox as emails: [`notify`] writes a
+//! short text for the event into `sms_messages` and the worker started by
+//! [`spawn_worker`] hands due ones to the configured [`NotificationChannel`],
+//! retrying failures with [`backoff`]. Reminders are queued by the booking's
+//! reminder job like email reminders,
+//! [`crate::emails::REMINDER_HOURS`] before it starts. Guests on the waitlist
+//! are told a table is free with [`enqueue_table_ready`].
+//!
+//! Only reservations whose contact is a phone number get texts, the number is
+//! stored in E.164 form, see [`normalize_phone`]. Guests answering `STOP`
+//! are opted out and are not texted again until they answer `START`; texts
+//! queued for an opted-out number are kept with the status
+//! [`MessageStatus::OptedOut`].
+//!
+//! The gateway is any HTTP endpoint accepting
+//! `POST {"channel", "from", "to", "body"}` with `Authorization: Bearer
+//! <RESVM_SMS_TOKEN>` and answering `{"id": "<message id>"}`. It reports
+//! back, with the same bearer token, to `POST /sms/status`
+//! (`{"id", "status": "delivered" | "failed", "error"}`) and forwards
+//! replies to `POST /sms/inbound` (`{"from", "body"}`).
+use crate::api_keys::verify_admin;
+use crate::emails::{address, public_url};
+use crate::errors::RepositoryError;
+use crate::models::{
+    MessageStatus, NewSmsMessage, NotificationKind, Reservation, SmsMessage, WaitlistEntry,
+    WebhookEvent,
+};
+use crate::repository::{PropertyRepository, RepoResult, SmsRepository};
+use crate::webhooks::backoff;
+use crate::AppState;
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use chrono::{NaiveDateTime, Utc};
+use leptos::{expect_context, logging, server, ServerFnError};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use time::Time;
+use uuid::Uuid;
+
+/// Attempts after which a message is given up and marked failed.
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// Messages handed to the channel per worker pass.
+const BATCH: i64 = 50;
+
+/// Seconds the gateway has to accept a message.
+const TIMEOUT_SECS: u64 = 10;
+
+/// Replies that opt a number out, compared case-insensitively.
+pub const STOP_WORDS: [&str; 6] = ["STOP", "STOPALL", "UNSUBSCRIBE", "CANCEL", "END", "QUIT"];
+
+/// Replies that opt a number back in.
+pub const START_WORDS: [&str; 2] = ["START", "UNSTOP"];
+
+/// Sends one text, blocking until the gateway accepted or refused it.
+pub trait NotificationChannel: Debug + Send + Sync {
+    /// Stored with every message, `sms` or `whatsapp`.
+    fn name(&self) -> &str;
+
+    /// Sends `body` to the E.164 number `to` and returns the gateway's id of
+    /// the message, if it gave one.
+    fn send(&self, to: &str, body: &str) -> Result<Option<String>, String>;
+}
+
+#[derive(Serialize)]
+struct GatewayRequest<'a> {
+    channel: &'a str,
+    from: &'a str,
+    to: &'a str,
+    body: &'a str,
+}
+
+#[derive(Deserialize)]
+struct GatewayResponse {
+    id: Option<String>,
+}
+
+/// Sends through an HTTP SMS or WhatsApp gateway.
+pub struct HttpGateway {
+    url: String,
+    token: String,
+    from: String,
+    channel: String,
+}
+
+impl HttpGateway {
+    pub fn new(url: String, token: String, from: String, channel: String) -> Self {
+        Self {
+            url,
+            token,
+            from,
+            channel,
+        }
+    }
+}
+
+impl Debug for HttpGateway {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpGateway")
+            .field("url", &self.url)
+            .field("from", &self.from)
+            .field("channel", &self.channel)
+            .finish_non_exhaustive()
+    }
+}
+
+impl NotificationChannel for HttpGateway {
+    fn name(&self) -> &str {
+        &self.channel
+    }
+
+    fn send(&self, to: &str, body: &str) -> Result<Option<String>, String> {
+        // A blocking client must not be dropped on an async thread, so each
+        // send builds its own on the worker's blocking thread.
+        let response = reqwest::blocking::Client::new()
+            .post(&self.url)
+            .bearer_auth(&self.token)
+            .timeout(std::time::Duration::from_secs(TIMEOUT_SECS))
+            .json(&GatewayRequest {
+                channel: &self.channel,
+                from: &self.from,
+                to,
+                body,
+            })
+            .send()
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("gateway answered {status}"));
+        }
+        Ok(response
+            .json::<GatewayResponse>()
+            .ok()
+            .and_then(|answer| answer.id))
+    }
+}
+
+/// Writes every message as a JSON file into a directory, to try texts out
+/// locally. The file name is the message's id.
+#[derive(Debug)]
+pub struct FileChannel {
+    dir: PathBuf,
+    channel: String,
+}
+
+impl FileChannel {
+    pub fn new(dir: impl Into<PathBuf>, channel: String) -> Self {
+        Self {
+            dir: dir.into(),
+            channel,
+        }
+    }
+}
+
+impl NotificationChannel for FileChannel {
+    fn name(&self) -> &str {
+        &self.channel
+    }
+
+    fn send(&self, to: &str, body: &str) -> Result<Option<String>, String> {
+        let id = Uuid::new_v4().simple().to_string();
+        let message = serde_json::json!({ "channel": self.channel, "to": to, "body": body });
+        std::fs::write(self.dir.join(format!("{id}.json")), message.to_string())
+            .map_err(|e| e.to_string())?;
+        Ok(Some(id))
+    }
+}
+
+/// Keeps sent texts in memory, for tests.
+#[derive(Debug, Default)]
+pub struct MemoryChannel {
+    sent: Mutex<Vec<(String, String)>>,
+    failing: Mutex<bool>,
+}
+
+impl MemoryChannel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `(to, body)` of the texts accepted so far.
+    pub fn sent(&self) -> Vec<(String, String)> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    /// Makes every following send fail, or succeed again.
+    pub fn set_failing(&self, failing: bool) {
+        *self.failing.lock().unwrap() = failing;
+    }
+}
+
+impl NotificationChannel for MemoryChannel {
+    fn name(&self) -> &str {
+        "sms"
+    }
+
+    fn send(&self, to: &str, body: &str) -> Result<Option<String>, String> {
+        if *self.failing.lock().unwrap() {
+            return Err("gateway unavailable".into());
+        }
+        let mut sent = self.sent.lock().unwrap();
+        sent.push((to.to_string(), body.to_string()));
+        Ok(Some(format!("memory-{}", sent.len())))
+    }
+}
+
+/// The channel configured by `RESVM_SMS_URL` or `RESVM_SMS_DIR`, named by
+/// `RESVM_SMS_CHANNEL` (`sms` unless set) and sending from
+/// `RESVM_SMS_FROM`. `None` when neither is set, texts are then off.
+pub fn channel_from_env() -> Result<Option<Arc<dyn NotificationChannel>>, String> {
+    let channel = std::env::var("RESVM_SMS_CHANNEL").unwrap_or_else(|_| "sms".into());
+    if channel != "sms" && channel != "whatsapp" {
+        return Err(format!(
+            "RESVM_SMS_CHANNEL must be sms or whatsapp, not {channel:?}"
+        ));
+    }
+    if let Ok(url) = std::env::var("RESVM_SMS_URL") {
+        reqwest::Url::parse(&url).map_err(|e| format!("RESVM_SMS_URL is not a url: {e}"))?;
+        let token = std::env::var("RESVM_SMS_TOKEN")
+            .map_err(|_| "RESVM_SMS_URL needs RESVM_SMS_TOKEN".to_string())?;
+        let from = std::env::var("RESVM_SMS_FROM").unwrap_or_default();
+        return Ok(Some(Arc::new(HttpGateway::new(url, token, from, channel))));
+    }
+    if let Ok(dir) = std::env::var("RESVM_SMS_DIR") {
+        std::fs::create_dir_all(&dir).map_err(|e| format!("cannot create {dir}: {e}"))?;
+        return Ok(Some(Arc::new(FileChannel::new(dir, channel))));
+    }
+    Ok(None)
+}
+
+/// The calling code numbers without one are read in, from
+/// `RESVM_SMS_COUNTRY_CODE`.
+pub(crate) fn default_country_code() -> Option<String> {
+    std::env::var("RESVM_SMS_COUNTRY_CODE").ok()
+}
+
+/// `raw` as an E.164 number, `+` and 8 to 15 digits, or `None` when it is
+/// not a phone number.
+///
+/// Spaces, dashes, dots, brackets and a `(0)` trunk prefix are dropped. A
+/// leading `+` or `00` starts the calling code, any other number is
+/// national and gets `country_code`, without its leading `0`.
+pub fn normalize_phone(raw: &str, country_code: Option<&str>) -> Option<String> {
+    let raw = raw.trim().replace("(0)", "");
+    if raw.is_empty()
+        || !raw
+            .chars()
+            .all(|c| c.is_ascii_digit() || " -.()+".contains(c))
+        || raw.rfind('+').is_some_and(|at| at > 0)
+    {
+        return None;
+    }
+    let digits: String = raw.chars().filter(char::is_ascii_digit).collect();
+    let number = if raw.starts_with('+') {
+        digits
+    } else if let Some(international) = digits.strip_prefix("00") {
+        international.to_string()
+    } else {
+        let code = country_code?.trim().trim_start_matches('+');
+        if code.is_empty() || !code.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        format!("{code}{}", digits.strip_prefix('0').unwrap_or(&digits))
+    };
+    ((8..=15).contains(&number.len()) && !number.starts_with('0')).then(|| format!("+{number}"))
+}
+
+/// The guest's number, when the contact of a reservation is one.
+pub fn recipient(resv: &Reservation) -> Option<String> {
+    number(&resv.contact)
+}
+
+/// `contact` in E.164 form, unless it is an email address.
+fn number(contact: &str) -> Option<String> {
+    if address(contact).is_some() {
+        return None;
+    }
+    normalize_phone(contact, default_country_code().as_deref())
+}
+
+/// The text of a message of `kind`, short enough for one or two SMS.
+pub fn text(property_name: &str, kind: NotificationKind, resv: &Reservation) -> String {
+    let date = resv.reservation_date.format("%d %b");
+    let time = format!(
+        "{:02}:{:02}",
+        resv.reservation_time.hour(),
+        resv.reservation_time.minute()
+    );
+    let manage_url = format!(
+        "{}/manage/{}",
+        public_url(),
+        resv.confirmation_token.as_deref().unwrap_or_default()
+    );
+    let code = resv.confirmation_code.clone().unwrap_or_default();
+    let party = resv.party_size;
+    let status = resv.status.to_string().to_lowercase();
+    let text = match kind {
+        NotificationKind::Confirmation => format!(
+            "{property_name}: your table for {party} on {date} at {time} is {status}, \
+             code {code}. Change or cancel: {manage_url}"
+        ),
+        NotificationKind::Modification => format!(
+            "{property_name}: your booking is now for {party} on {date} at {time}, \
+             code {code}. {manage_url}"
+        ),
+        NotificationKind::Cancellation => format!(
+            "{property_name}: your booking for {party} on {date} at {time}, code {code}, \
+             was cancelled."
+        ),
+        NotificationKind::Reminder => format!(
+            "{property_name}: see you on {date} at {time}, table for {party}. \
+             Plans changed? {manage_url}"
+        ),
+        NotificationKind::TableReady => table_ready(property_name, party, &date.to_string(), &time),
+    };
+    format!("{text} Reply STOP to opt out.")
+}
+
+fn table_ready(property_name: &str, party: i32, date: &str, time: &str) -> String {
+    format!(
+        "{property_name}: a table for {party} is free on {date} at {time}. \
+         Please come to the host stand or reply if you no longer need it."
+    )
+}
+
+/// Writes the text of `kind` for a reservation into the outbox and returns
+/// its id, `None` when the guest has no phone number. Texts for numbers that
+/// opted out are stored as [`MessageStatus::OptedOut`] and never sent.
+pub fn enqueue(
+    properties: &dyn PropertyRepository,
+    repo: &dyn SmsRepository,
+    channel: &str,
+    kind: NotificationKind,
+    resv: &Reservation,
+) -> RepoResult<Option<i32>> {
+    let Some(to) = recipient(resv) else {
+        return Ok(None);
+    };
+    let property = properties.find(resv.property_id)?;
+    let status = if repo.opted_out(&to)? {
+        MessageStatus::OptedOut
+    } else {
+        MessageStatus::Queued
+    };
+    repo.insert_message(NewSmsMessage {
+        property_id: resv.property_id,
+        reservation_id: Some(resv.id),
+        kind,
+        channel: channel.to_string(),
+        body: text(&property.property_name, kind, resv),
+        recipient: to,
+        status,
+        next_attempt_at: Utc::now().naive_utc(),
+    })
+    .map(Some)
+}
+
+/// Tells a guest on the waitlist that a table for them is free at `time`,
+/// `None` when their contact is not a phone number.
+pub fn enqueue_table_ready(
+    properties: &dyn PropertyRepository,
+    repo: &dyn SmsRepository,
+    channel: &str,
+    entry: &WaitlistEntry,
+    time: Time,
+) -> RepoResult<Option<i32>> {
+    let Some(to) = number(&entry.contact) else {
+        return Ok(None);
+    };
+    let property = properties.find(entry.property_id)?;
+    let status = if repo.opted_out(&to)? {
+        MessageStatus::OptedOut
+    } else {
+        MessageStatus::Queued
+    };
+    let text = table_ready(
+        &property.property_name,
+        entry.party_size,
+        &entry.waitlist_date.format("%d %b").to_string(),
+        &format!("{:02}:{:02}", time.hour(), time.minute()),
+    );
+    repo.insert_message(NewSmsMessage {
+        property_id: entry.property_id,
+        reservation_id: None,
+        kind: NotificationKind::TableReady,
+        channel: channel.to_string(),
+        body: format!("{text} Reply STOP to opt out."),
+        recipient: to,
+        status,
+        next_attempt_at: Utc::now().naive_utc(),
+    })
+    .map(Some)
+}
+
+/// Queues the text a reservation event calls for, see [`AppState::publish`].
+pub fn notify(
+    properties: &dyn PropertyRepository,
+    repo: &dyn SmsRepository,
+    channel: &str,
+    event: WebhookEvent,
+    resv: &Reservation,
+) {
+    let Some(kind) = NotificationKind::for_event(event, resv.status) else {
+        return;
+    };
+    if let Err(e) = enqueue(properties, repo, channel, kind, resv) {
+        logging::warn!("cannot queue {kind} text for reservation {}: {e}", resv.id);
+    }
+}
+
+/// Queues the reminder of a booking unless it already got one, and returns
+/// whether it did.
+pub fn remind(
+    properties: &dyn PropertyRepository,
+    repo: &dyn SmsRepository,
+    channel: &str,
+    resv: &Reservation,
+) -> RepoResult<bool> {
+    if recipient(resv).is_none() || repo.has_message(resv.id, NotificationKind::Reminder)? {
+        return Ok(false);
+    }
+    Ok(enqueue(properties, repo, channel, NotificationKind::Reminder, resv)?.is_some())
+}
+
+/// Sends one message and returns it with the outcome applied. A number that
+/// opted out since the message was queued is not texted.
+pub fn attempt(
+    channel: &dyn NotificationChannel,
+    mut message: SmsMessage,
+    opted_out: bool,
+    now: NaiveDateTime,
+) -> SmsMessage {
+    if opted_out {
+        message.status = MessageStatus::OptedOut;
+        return message;
+    }
+    message.attempts += 1;
+    match channel.send(&message.recipient, &message.body) {
+        Ok(provider_id) => {
+            message.status = MessageStatus::Sent;
+            message.provider_id = provider_id;
+            message.last_error = None;
+            message.sent_at = Some(now);
+        }
+        Err(error) => {
+            message.last_error = Some(error);
+            if message.attempts >= MAX_ATTEMPTS {
+                message.status = MessageStatus::Failed;
+            } else {
+                message.next_attempt_at = now + backoff(message.attempts);
+            }
+        }
+    }
+    message
+}
+
+/// Sends every message that is due and returns how many were attempted. A
+/// message that cannot be checked or stored is logged and left for the next
+/// run.
+pub fn deliver_due(
+    repo: &dyn SmsRepository,
+    channel: &dyn NotificationChannel,
+) -> RepoResult<usize> {
+    let mut attempted = 0;
+    for message in repo.due_messages(Utc::now().naive_utc(), BATCH)? {
+        let message_id = message.message_id;
+        let stored = repo.opted_out(&message.recipient).and_then(|opted_out| {
+            attempted += 1;
+            repo.update_message(&attempt(
+                channel,
+                message,
+                opted_out,
+                Utc::now().naive_utc(),
+            ))
+        });
+        if let Err(e) = stored {
+            logging::warn!("cannot send text {message_id}: {e}");
+        }
+    }
+    Ok(attempted)
+}
+
+/// Sends due texts every `interval` for the lifetime of the server.
+pub fn spawn_worker(
+    state: AppState,
+    channel: Arc<dyn NotificationChannel>,
+    interval: std::time::Duration,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let state = state.clone();
+            let channel = channel.clone();
+            let pass = tokio::task::spawn_blocking(move || {
+                deliver_due(state.sms.as_ref(), channel.as_ref())
+            })
+            .await;
+            match pass {
+                Ok(Err(e)) => logging::warn!("text delivery failed: {e}"),
+                Err(e) => logging::warn!("text worker stopped a pass: {e}"),
+                Ok(Ok(_)) => {}
+            }
+        }
+    });
+}
+
+/// Applies a delivery report of the gateway to the message it sent as
+/// `provider_id`. `sent` and other interim states change nothing.
+pub fn record_status(
+    repo: &dyn SmsRepository,
+    provider_id: &str,
+    status: &str,
+    error: Option<String>,
+    now: NaiveDateTime,
+) -> RepoResult<SmsMessage> {
+    let mut message = repo.message_by_provider_id(provider_id)?;
+    match status.to_ascii_lowercase().as_str() {
+        "delivered" => {
+            message.status = MessageStatus::Delivered;
+            message.delivered_at = Some(now);
+        }
+        "failed" | "undelivered" | "rejected" => {
+            message.status = MessageStatus::Failed;
+            message.last_error = error.or_else(|| Some(format!("gateway reported {status}")));
+        }
+        "queued" | "accepted" | "sent" => return Ok(message),
+        other => {
+            return Err(RepositoryError::Invalid(format!(
+                "unknown delivery status {other:?}"
+            )))
+        }
+    }
+    repo.update_message(&message)?;
+    Ok(message)
+}
+
+/// Applies a guest's reply: a stop word opts the number out, a start word
+/// back in. Returns whether the number is now opted out, `None` when the
+/// reply was neither.
+pub fn record_reply(
+    repo: &dyn SmsRepository,
+    from: &str,
+    body: &str,
+    now: NaiveDateTime,
+) -> RepoResult<Option<bool>> {
+    let phone = normalize_phone(from, default_country_code().as_deref())
+        .ok_or_else(|| RepositoryError::Invalid(format!("{from:?} is not a phone number")))?;
+    let word = body.trim().to_ascii_uppercase();
+    let opted_out = if STOP_WORDS.contains(&word.as_str()) {
+        true
+    } else if START_WORDS.contains(&word.as_str()) {
+        false
+    } else {
+        return Ok(None);
+    };
+    repo.set_opted_out(&phone, opted_out, now)?;
+    Ok(Some(opted_out))
+}
+
+#[derive(Clone)]
+struct Callbacks {
+    repo: Arc<dyn SmsRepository>,
+    token: Arc<str>,
+}
+
+impl Callbacks {
+    fn authorize(&self, headers: &HeaderMap) -> Result<(), StatusCode> {
+        let sent = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim);
+        match sent {
+            Some(token) if !self.token.is_empty() && token == &*self.token => Ok(()),
+            _ => Err(StatusCode::UNAUTHORIZED),
+        }
+    }
+}
+
+/// A delivery report, posted by the gateway to `/sms/status`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusReport {
+    pub id: String,
+    pub status: String,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// A guest's reply, forwarded by the gateway to `/sms/inbound`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InboundMessage {
+    pub from: String,
+    pub body: String,
+}
+
+async fn status_report(
+    State(callbacks): State<Callbacks>,
+    headers: HeaderMap,
+    Json(report): Json<StatusReport>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    callbacks
+        .authorize(&headers)
+        .map_err(|code| (code, String::new()))?;
+    tokio::task::spawn_blocking(move || {
+        record_status(
+            callbacks.repo.as_ref(),
+            &report.id,
+            &report.status,
+            report.error,
+            Utc::now().naive_utc(),
+        )
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map(|_| StatusCode::NO_CONTENT)
+    .map_err(|e| (e.status_code(), e.to_string()))
+}
+
+async fn inbound(
+    State(callbacks): State<Callbacks>,
+    headers: HeaderMap,
+    Json(message): Json<InboundMessage>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    callbacks
+        .authorize(&headers)
+        .map_err(|code| (code, String::new()))?;
+    tokio::task::spawn_blocking(move || {
+        record_reply(
+            callbacks.repo.as_ref(),
+            &message.from,
+            &message.body,
+            Utc::now().naive_utc(),
+        )
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map(|_| StatusCode::NO_CONTENT)
+    .map_err(|e| (e.status_code(), e.to_string()))
+}
+
+/// The gateway's callbacks, authenticated with `RESVM_SMS_TOKEN`.
+pub fn router(state: &AppState) -> Router<AppState> {
+    let callbacks = Callbacks {
+        repo: state.sms.clone(),
+        token: std::env::var("RESVM_SMS_TOKEN").unwrap_or_default().into(),
+    };
+    Router::new()
+        .route("/sms/status", post(status_report))
+        .route("/sms/inbound", post(inbound))
+        .with_state(callbacks)
+}
+
+#[server(name = ListSmsMessages, prefix = "/api", endpoint = "list_sms_messages", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn list_sms_messages(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+) -> Result<Vec<SmsMessage>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| state.sms.messages(pid, 50))
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Opts a number out, or back in, for a guest who asked staff to.
+#[server(name = SetSmsOptOut, prefix = "/api", endpoint = "set_sms_opt_out", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn set_sms_opt_out(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    phone: String,
+    opted_out: bool,
+) -> Result<String, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| {
+            let number =
+                normalize_phone(&phone, default_country_code().as_deref()).ok_or_else(|| {
+                    RepositoryError::Invalid(format!("{phone:?} is not a phone number"))
+                })?;
+            state
+                .sms
+                .set_opted_out(&number, opted_out, Utc::now().naive_utc())?;
+            Ok(number)
+        })
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
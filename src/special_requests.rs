@@ -0,0 +1,252 @@
+//! Special requests, staff notes and tags of reservations.
+//!
+//! Guests say what they need when booking: an occasion, dietary needs, high
+//! chairs, accessibility and the seating they asked for, kept as
+//! [`SpecialRequests`]. Staff add tags to filter the reservation list by and
+//! internal [`ReservationNote`]s signed with their admin user name. The
+//! [`day_sheet`] puts all of it next to each booking of a day, tags
+//! highlighted.
+use crate::api_keys::verify_admin;
+use crate::errors::RepositoryError;
+use crate::guests::{no_show_count, normalize_tags};
+use crate::models::{
+    DaySheetEntry, HistoryEntry, NewHistoryEntry, NewReservationNote, Occasion, Reservation,
+    ReservationNote, SpecialRequests, WebhookEvent,
+};
+use crate::repository::{GuestRepository, RepoResult, ReservationRepository};
+use crate::AppState;
+use chrono::NaiveDate;
+use leptos::{expect_context, server, ServerFnError};
+use uuid::Uuid;
+
+/// Longest text accepted for a single request.
+pub const MAX_REQUEST_LEN: usize = 200;
+/// Longest staff note accepted.
+pub const MAX_NOTE_LEN: usize = 2000;
+
+/// `requests` trimmed, or why they cannot be stored for a party of
+/// `party_size`.
+pub fn check_requests(
+    mut requests: SpecialRequests,
+    party_size: i32,
+) -> RepoResult<SpecialRequests> {
+    for text in [
+        &mut requests.dietary,
+        &mut requests.accessibility,
+        &mut requests.seating_request,
+    ] {
+        *text = text.trim().to_string();
+        if text.chars().count() > MAX_REQUEST_LEN {
+            return Err(RepositoryError::Invalid(format!(
+                "a request is limited to {MAX_REQUEST_LEN} characters"
+            )));
+        }
+    }
+    if !(0..=party_size).contains(&requests.high_chairs) {
+        return Err(RepositoryError::Invalid(format!(
+            "between 0 and {party_size} high chairs can be requested"
+        )));
+    }
+    Ok(requests)
+}
+
+/// An occasion as written in a form, `None` when left blank.
+pub fn parse_occasion(raw: &str) -> RepoResult<Option<Occasion>> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    Occasion::ALL
+        .into_iter()
+        .find(|occasion| occasion.to_string().eq_ignore_ascii_case(raw))
+        .map(Some)
+        .ok_or_else(|| RepositoryError::Invalid(format!("{raw} is not an occasion")))
+}
+
+/// Replaces the special requests and tags of a reservation and records the
+/// change in its history.
+pub fn update_requests(
+    repo: &dyn ReservationRepository,
+    id: i32,
+    requests: SpecialRequests,
+    tags: &str,
+) -> RepoResult<Reservation> {
+    let resv = repo.find(id)?;
+    let requests = check_requests(requests, resv.party_size)?;
+    let tags = normalize_tags(tags);
+    if requests == resv.special_requests && tags == resv.tags {
+        return Ok(resv);
+    }
+    let summary = match (requests != resv.special_requests, tags != resv.tags) {
+        (true, true) => "special requests and tags updated".to_string(),
+        (true, false) => "special requests updated".to_string(),
+        (false, _) => format!("tags set to {tags:?}"),
+    };
+    let resv = repo.set_requests(id, requests, tags)?;
+    repo.insert_history(NewHistoryEntry {
+        reservation_id: id,
+        changed_by: HistoryEntry::STAFF.to_string(),
+        summary,
+    })?;
+    Ok(resv)
+}
+
+/// Leaves a note from `author` on a reservation and returns it.
+pub fn add_note(
+    repo: &dyn ReservationRepository,
+    id: i32,
+    author: &str,
+    body: &str,
+) -> RepoResult<ReservationNote> {
+    let (author, body) = (author.trim(), body.trim());
+    if author.is_empty() {
+        return Err(RepositoryError::Invalid("a note needs an author".into()));
+    }
+    if body.is_empty() {
+        return Err(RepositoryError::Invalid("a note cannot be empty".into()));
+    }
+    if body.chars().count() > MAX_NOTE_LEN {
+        return Err(RepositoryError::Invalid(format!(
+            "a note is limited to {MAX_NOTE_LEN} characters"
+        )));
+    }
+    repo.find(id)?;
+    let note_id = repo.insert_note(NewReservationNote {
+        reservation_id: id,
+        author: author.to_string(),
+        body: body.to_string(),
+    })?;
+    repo.notes(id)?
+        .into_iter()
+        .find(|note| note.note_id == note_id)
+        .ok_or(RepositoryError::NotFound)
+}
+
+/// The reservations of a day still expected or at the table, by time, with
+/// their notes, the tags of the reservation and its guest, the guest's
+/// no-shows and any event details.
+pub fn day_sheet(
+    repo: &dyn ReservationRepository,
+    guests: &dyn GuestRepository,
+    pid: Uuid,
+    date: NaiveDate,
+) -> RepoResult<Vec<DaySheetEntry>> {
+    let mut reservations: Vec<Reservation> = repo
+        .on_date(pid, date)?
+        .into_iter()
+        .filter(|resv| resv.status.is_active())
+        .collect();
+    reservations.sort_by_key(|resv| (resv.reservation_time, resv.id));
+    reservations
+        .into_iter()
+        .map(|resv| {
+            let (guest_tags, no_shows) = match resv.guest_id {
+                Some(guest_id) => (
+                    guests.find_guest(guest_id)?.tags,
+                    no_show_count(guests, guest_id)?,
+                ),
+                None => (String::new(), 0),
+            };
+            let tags = normalize_tags(&format!("{},{}", resv.tags, guest_tags))
+                .split(", ")
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect();
+            Ok(DaySheetEntry {
+                notes: repo.notes(resv.id)?,
+                event: repo.event_details(resv.id)?,
+                reservation: resv,
+                tags,
+                no_shows,
+            })
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+#[server(name = SetSpecialRequests, prefix = "/api", endpoint = "set_special_requests", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn set_special_requests(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    id: i32,
+    occasion: String,
+    dietary: String,
+    high_chairs: i32,
+    accessibility: String,
+    seating_request: String,
+    tags: String,
+) -> Result<Reservation, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| reservation_of(state.reservations.as_ref(), pid, id))
+        .and_then(|_| parse_occasion(&occasion))
+        .and_then(|occasion| {
+            update_requests(
+                state.reservations.as_ref(),
+                id,
+                SpecialRequests {
+                    occasion,
+                    dietary,
+                    high_chairs,
+                    accessibility,
+                    seating_request,
+                },
+                &tags,
+            )
+        })
+        .inspect(|resv| state.publish(WebhookEvent::ReservationDetailsChanged, resv))
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// The reservation, when it belongs to the property.
+fn reservation_of(
+    reservations: &dyn ReservationRepository,
+    pid: Uuid,
+    id: i32,
+) -> RepoResult<Reservation> {
+    let resv = reservations.find(id)?;
+    if resv.property_id != pid {
+        return Err(RepositoryError::NotFound);
+    }
+    Ok(resv)
+}
+
+#[server(name = AddReservationNote, prefix = "/api", endpoint = "add_reservation_note", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn add_reservation_note(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    id: i32,
+    body: String,
+) -> Result<ReservationNote, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    // Notes are signed by the admin who wrote them.
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| reservation_of(state.reservations.as_ref(), pid, id))
+        .and_then(|_| add_note(state.reservations.as_ref(), id, &user_name, &body))
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(name = GetDaySheet, prefix = "/api", endpoint = "day_sheet", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn get_day_sheet(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    date: NaiveDate,
+) -> Result<Vec<DaySheetEntry>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| {
+            day_sheet(
+                state.reservations.as_ref(),
+                state.guests.as_ref(),
+                pid,
+                date,
+            )
+        })
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
@@ -0,0 +1,338 @@
+//! Waitlist of guests hoping for a table on a full day.
+//!
+//! Guests who could not get a table, whether calling ahead for a later time
+//! or standing at the door, are put on the waitlist of the property for that
+//! day with the wait they were quoted. Each open entry shows the table that
+//! could seat it, found like online bookings find one with [`free_table`],
+//! so staff see when a cancellation, a no-show or a finished table frees one
+//! up. Staff then [`notify`] the guest through email or text and [`promote`]
+//! the entry into a reservation once they take the table.
+use crate::booking::{
+    check_not_seated, check_seating, day_holds, free_table, table_for, without_seated,
+};
+use crate::crud_properties::{create_reservation, parse_reservation_time, time_of_day};
+use crate::emails;
+use crate::errors::RepositoryError;
+use crate::guests::NoShowPolicy;
+use crate::models::{
+    NewResv, NewWaitlistEntry, PaymentMethod, PaymentMode, PropertyTable, Reservation,
+    ReservationStatus, WaitingGuest, WaitlistEntry, WaitlistStatus, WebhookEvent,
+};
+use crate::repository::{
+    EmailRepository, GuestRepository, PropertyRepository, RepoResult, ReservationRepository,
+    SmsRepository, WaitlistRepository,
+};
+use crate::sms;
+use crate::AppState;
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use leptos::{expect_context, server, ServerFnError};
+use time::Time;
+use uuid::Uuid;
+
+/// A time as typed into a form, `HH:MM` or `HHMM`, `None` when left blank.
+pub fn parse_time(raw: &str) -> RepoResult<Option<Time>> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    parse_reservation_time(&raw.replace(':', "")).map(Some)
+}
+
+fn open_entry(repo: &dyn WaitlistRepository, entry_id: i32) -> RepoResult<WaitlistEntry> {
+    let entry = repo.find_waitlist_entry(entry_id)?;
+    if !entry.status.is_open() {
+        return Err(RepositoryError::Conflict(format!(
+            "{} is no longer waiting, the entry is {}",
+            entry.name, entry.status
+        )));
+    }
+    Ok(entry)
+}
+
+/// Puts a guest on the waitlist and returns the stored entry.
+pub fn add(
+    repo: &dyn WaitlistRepository,
+    mut new_entry: NewWaitlistEntry,
+) -> RepoResult<WaitlistEntry> {
+    new_entry.name = new_entry.name.trim().to_string();
+    new_entry.contact = new_entry.contact.trim().to_string();
+    if new_entry.name.is_empty() {
+        return Err(RepositoryError::Invalid("name is required".into()));
+    }
+    if new_entry.contact.is_empty() {
+        return Err(RepositoryError::Invalid("contact is required".into()));
+    }
+    if new_entry.party_size < 1 {
+        return Err(RepositoryError::Invalid(
+            "a waitlist entry needs at least one guest".into(),
+        ));
+    }
+    if new_entry.quoted_wait < 0 {
+        return Err(RepositoryError::Invalid(
+            "the quoted wait cannot be negative".into(),
+        ));
+    }
+    let entry_id = repo.insert_waitlist_entry(new_entry)?;
+    repo.find_waitlist_entry(entry_id)
+}
+
+/// When the guest of `entry` wants to sit: the time they asked for, or `now`
+/// for walk-ins.
+pub fn wanted_at(entry: &WaitlistEntry, now: NaiveDateTime) -> Time {
+    entry.preferred_time.unwrap_or_else(|| time_of_day(now))
+}
+
+/// The smallest table free for the entry when its guest wants to sit.
+/// Finished tables count as free, tables of parties still seated do not.
+pub fn free_table_for(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    entry: &WaitlistEntry,
+    now: NaiveDateTime,
+) -> RepoResult<Option<PropertyTable>> {
+    let tables = properties.tables(entry.property_id)?;
+    let booked = holds(reservations, &tables, entry)?;
+    let (free, others) = without_seated(&tables, &booked);
+    Ok(free_table(
+        &free,
+        &others,
+        wanted_at(entry, now),
+        entry.party_size,
+    ))
+}
+
+/// The tables held on the day of `entry` by guests that have not left yet.
+fn holds(
+    reservations: &dyn ReservationRepository,
+    tables: &[PropertyTable],
+    entry: &WaitlistEntry,
+) -> RepoResult<Vec<Reservation>> {
+    Ok(
+        day_holds(reservations, tables, entry.property_id, entry.waitlist_date)?
+            .into_iter()
+            .filter(|resv| resv.status != ReservationStatus::Completed)
+            .collect(),
+    )
+}
+
+/// The waitlist of a day, oldest first, with a free table for each entry
+/// still waiting.
+pub fn waiting(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    repo: &dyn WaitlistRepository,
+    pid: Uuid,
+    date: NaiveDate,
+    now: NaiveDateTime,
+) -> RepoResult<Vec<WaitingGuest>> {
+    repo.waitlist(pid, date)?
+        .into_iter()
+        .map(|entry| {
+            let free_table = if entry.status.is_open() {
+                free_table_for(properties, reservations, &entry, now)?.map(|table| table.table_name)
+            } else {
+                None
+            };
+            Ok(WaitingGuest { entry, free_table })
+        })
+        .collect()
+}
+
+/// Tells the guest of an open entry that their table is ready, by email
+/// and by text when those can reach their contact, and marks the entry
+/// notified.
+pub fn notify(
+    properties: &dyn PropertyRepository,
+    repo: &dyn WaitlistRepository,
+    mail: Option<&dyn EmailRepository>,
+    texts: Option<(&dyn SmsRepository, &str)>,
+    entry_id: i32,
+    now: NaiveDateTime,
+) -> RepoResult<WaitlistEntry> {
+    let mut entry = open_entry(repo, entry_id)?;
+    let time = wanted_at(&entry, now);
+    let mut queued = false;
+    if let Some(mail) = mail {
+        queued |= emails::enqueue_table_ready(properties, mail, &entry, time)?.is_some();
+    }
+    if let Some((texts, channel)) = texts {
+        queued |= sms::enqueue_table_ready(properties, texts, channel, &entry, time)?.is_some();
+    }
+    if !queued {
+        return Err(RepositoryError::Invalid(format!(
+            "no email or text can be queued for {:?}, check that sending is set up",
+            entry.contact
+        )));
+    }
+    entry.status = WaitlistStatus::Notified;
+    entry.notified_at = Some(now);
+    repo.update_waitlist_entry(&entry)?;
+    Ok(entry)
+}
+
+/// Books the guest of an open entry at `time`, or when they want to sit, on
+/// `seating` when it is a free table that seats them, or the smallest free
+/// table, and marks the entry promoted. Tables of parties still seated are
+/// never free.
+#[allow(clippy::too_many_arguments)]
+pub fn promote(
+    properties: &dyn PropertyRepository,
+    reservations: &dyn ReservationRepository,
+    guests: &dyn GuestRepository,
+    policy: &NoShowPolicy,
+    repo: &dyn WaitlistRepository,
+    entry_id: i32,
+    time: Option<Time>,
+    seating: Option<String>,
+    now: NaiveDateTime,
+) -> RepoResult<Reservation> {
+    let mut entry = open_entry(repo, entry_id)?;
+    if let Some(time) = time {
+        entry.preferred_time = Some(time);
+    }
+    let time = wanted_at(&entry, now);
+    let tables = properties.tables(entry.property_id)?;
+    let seating = check_seating(
+        &tables,
+        seating.as_deref().unwrap_or_default(),
+        entry.party_size,
+    )?;
+    let booked = holds(reservations, &tables, &entry)?;
+    check_not_seated(&booked, &seating)?;
+    let (free, others) = without_seated(&tables, &booked);
+    let seating = table_for(&free, &others, &seating, entry.party_size, time)?;
+    let advance_method =
+        serde_json::to_value(PaymentMethod::new(PaymentMode::NotPaid, None, None, None))
+            .map_err(|e| RepositoryError::Invalid(e.to_string()))?;
+    let new_resv = NewResv::new(
+        entry.name.clone(),
+        entry.contact.clone(),
+        seating,
+        false,
+        false,
+        advance_method,
+        None,
+        true,
+        entry.waitlist_date,
+        time,
+        entry.property_id,
+    )
+    .with_party_size(entry.party_size);
+    let rid = create_reservation(properties, reservations, guests, policy, new_resv)?;
+    entry.status = WaitlistStatus::Promoted;
+    entry.reservation_id = Some(rid);
+    repo.update_waitlist_entry(&entry)?;
+    reservations.find(rid)
+}
+
+/// Takes an open entry off the waitlist.
+pub fn remove(repo: &dyn WaitlistRepository, entry_id: i32) -> RepoResult<WaitlistEntry> {
+    let mut entry = open_entry(repo, entry_id)?;
+    entry.status = WaitlistStatus::Removed;
+    repo.update_waitlist_entry(&entry)?;
+    Ok(entry)
+}
+
+#[server(name = ListWaitlist, prefix = "/api", endpoint = "list_waitlist", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn list_waitlist(pid: Uuid, date: NaiveDate) -> Result<Vec<WaitingGuest>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    waiting(
+        state.properties.as_ref(),
+        state.reservations.as_ref(),
+        state.waitlist.as_ref(),
+        pid,
+        date,
+        Local::now().naive_local(),
+    )
+    .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[server(name = AddToWaitlist, prefix = "/api", endpoint = "add_to_waitlist", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn add_to_waitlist(
+    pid: Uuid,
+    waitlist_date: NaiveDate,
+    name: String,
+    contact: String,
+    party_size: i32,
+    preferred_time: String,
+    quoted_wait: i32,
+) -> Result<WaitlistEntry, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    state
+        .properties
+        .find(pid)
+        .and_then(|_| parse_time(&preferred_time))
+        .and_then(|preferred_time| {
+            add(
+                state.waitlist.as_ref(),
+                NewWaitlistEntry {
+                    property_id: pid,
+                    waitlist_date,
+                    name,
+                    contact,
+                    party_size,
+                    preferred_time,
+                    quoted_wait,
+                },
+            )
+        })
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(name = NotifyWaitlistGuest, prefix = "/api", endpoint = "notify_waitlist_guest", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn notify_waitlist_guest(entry_id: i32) -> Result<WaitlistEntry, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    notify(
+        state.properties.as_ref(),
+        state.waitlist.as_ref(),
+        state.mail.is_some().then_some(state.emails.as_ref()),
+        state
+            .channel
+            .as_ref()
+            .map(|channel| (state.sms.as_ref(), channel.name())),
+        entry_id,
+        Local::now().naive_local(),
+    )
+    .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(name = PromoteWaitlistEntry, prefix = "/api", endpoint = "promote_waitlist_entry", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn promote_waitlist_entry(
+    entry_id: i32,
+    reservation_time: String,
+    seating: String,
+) -> Result<Reservation, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    let promoted = parse_time(&reservation_time).and_then(|time| {
+        promote(
+            state.properties.as_ref(),
+            state.reservations.as_ref(),
+            state.guests.as_ref(),
+            &state.no_show_policy,
+            state.waitlist.as_ref(),
+            entry_id,
+            time,
+            Some(seating),
+            Local::now().naive_local(),
+        )
+    });
+    match promoted {
+        Ok(resv) => {
+            state.publish(WebhookEvent::ReservationCreated, &resv);
+            Ok(resv)
+        }
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+#[server(name = RemoveWaitlistEntry, prefix = "/api", endpoint = "remove_waitlist_entry", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn remove_waitlist_entry(entry_id: i32) -> Result<WaitlistEntry, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    remove(state.waitlist.as_ref(), entry_id).map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
@@ -0,0 +1,344 @@
+//! Outgoing webhooks for reservation events.
+//!
+//! Events are not sent inline: [`notify`] queues one delivery per subscribed
+//! webhook of the property and the worker started by [`spawn_worker`] posts
+//! them. A failed attempt is retried with exponential backoff until
+//! [`MAX_ATTEMPTS`], every attempt is kept in the delivery log and any
+//! delivery can be replayed from the webhooks page.
+//!
+//! Each request carries `X-Resvm-Event`, `X-Resvm-Delivery`,
+//! `X-Resvm-Timestamp` and `X-Resvm-Signature: sha256=<hex>`, the HMAC-SHA256
+//! of `<timestamp>.<body>` keyed with the webhook secret. Receivers should
+//! recompute it with [`sign`] and reject stale timestamps.
+use crate::api_keys::verify_admin;
+use crate::errors::RepositoryError;
+use crate::models::{
+    DeliveryStatus, NewWebhook, NewWebhookDelivery, Reservation, Webhook, WebhookDelivery,
+    WebhookEvent,
+};
+use crate::repository::{RepoResult, WebhookRepository};
+use crate::AppState;
+use chrono::{Duration, NaiveDateTime, Utc};
+use hmac::{Hmac, Mac};
+use leptos::{expect_context, logging, server, ServerFnError};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Attempts after which a delivery is given up and marked failed.
+pub const MAX_ATTEMPTS: i32 = 8;
+
+/// Deliveries posted per worker pass.
+const BATCH: i64 = 50;
+
+/// Seconds the receiver has to answer a delivery.
+const TIMEOUT_SECS: u64 = 10;
+
+/// Delay before the attempt following `attempts` failed ones: 30 seconds,
+/// doubling each time, at most six hours.
+pub fn backoff(attempts: i32) -> Duration {
+    let exponent = attempts.clamp(1, 16) as u32 - 1;
+    Duration::seconds(30 * 2i64.pow(exponent)).min(Duration::hours(6))
+}
+
+/// Hex HMAC-SHA256 of `<timestamp>.<body>` keyed with `secret`.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Whether `signature` is the hex [`sign`]ature of `<timestamp>.<body>`,
+/// compared in constant time.
+pub fn verify(secret: &str, timestamp: i64, body: &[u8], signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Queues `event` for every active webhook of the property subscribed to it
+/// and returns the number of queued deliveries.
+pub fn enqueue(
+    repo: &dyn WebhookRepository,
+    pid: Uuid,
+    event: WebhookEvent,
+    data: serde_json::Value,
+) -> RepoResult<usize> {
+    let now = Utc::now().naive_utc();
+    let payload = serde_json::json!({
+        "event": event.name(),
+        "property_id": pid,
+        "occurred_at": now.and_utc().to_rfc3339(),
+        "data": data,
+    });
+    let mut queued = 0;
+    for webhook in repo.webhooks(pid)? {
+        if webhook.active && webhook.subscribes(event) {
+            repo.insert_delivery(NewWebhookDelivery {
+                webhook_id: webhook.webhook_id,
+                event: event.name().to_string(),
+                payload: payload.clone(),
+                next_attempt_at: now,
+            })?;
+            queued += 1;
+        }
+    }
+    Ok(queued)
+}
+
+/// Queues `event` for a reservation, see [`AppState::publish`].
+pub fn notify(repo: &dyn WebhookRepository, event: WebhookEvent, resv: &Reservation) {
+    let queued = serde_json::to_value(resv)
+        .map_err(|e| RepositoryError::Invalid(e.to_string()))
+        .and_then(|data| enqueue(repo, resv.property_id, event, data));
+    if let Err(e) = queued {
+        logging::warn!(
+            "cannot queue {} for reservation {}: {e}",
+            event.name(),
+            resv.id
+        );
+    }
+}
+
+/// Posts a delivery once and returns it with the outcome applied.
+pub async fn attempt(
+    client: &reqwest::Client,
+    webhook: &Webhook,
+    mut delivery: WebhookDelivery,
+    now: NaiveDateTime,
+) -> WebhookDelivery {
+    delivery.attempts += 1;
+    let outcome = if webhook.active {
+        let body = delivery.payload.to_string();
+        let timestamp = now.and_utc().timestamp();
+        client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Resvm-Event", &delivery.event)
+            .header("X-Resvm-Delivery", delivery.delivery_id)
+            .header("X-Resvm-Timestamp", timestamp)
+            .header(
+                "X-Resvm-Signature",
+                format!(
+                    "sha256={}",
+                    sign(&webhook.secret, timestamp, body.as_bytes())
+                ),
+            )
+            .timeout(std::time::Duration::from_secs(TIMEOUT_SECS))
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| (None, e.to_string()))
+            .and_then(|response| {
+                let status = response.status();
+                if status.is_success() {
+                    Ok(status.as_u16() as i32)
+                } else {
+                    Err((
+                        Some(status.as_u16() as i32),
+                        format!("receiver answered {status}"),
+                    ))
+                }
+            })
+    } else {
+        Err((None, "webhook is disabled".to_string()))
+    };
+
+    match outcome {
+        Ok(code) => {
+            delivery.status = DeliveryStatus::Delivered;
+            delivery.response_status = Some(code);
+            delivery.last_error = None;
+            delivery.delivered_at = Some(now);
+        }
+        Err((code, error)) => {
+            delivery.response_status = code;
+            delivery.last_error = Some(error);
+            if !webhook.active || delivery.attempts >= MAX_ATTEMPTS {
+                delivery.status = DeliveryStatus::Failed;
+            } else {
+                delivery.next_attempt_at = now + backoff(delivery.attempts);
+            }
+        }
+    }
+    delivery
+}
+
+/// Posts every delivery that is due and returns how many were attempted. A
+/// delivery that cannot be attempted or stored is logged and left for the
+/// next run, without holding up the rest of the batch.
+pub async fn deliver_due(
+    repo: &dyn WebhookRepository,
+    client: &reqwest::Client,
+) -> RepoResult<usize> {
+    let mut attempted = 0;
+    for delivery in repo.due_deliveries(Utc::now().naive_utc(), BATCH)? {
+        let delivery_id = delivery.delivery_id;
+        let stored = match repo.find_webhook(delivery.webhook_id) {
+            Ok(webhook) => {
+                let delivery = attempt(client, &webhook, delivery, Utc::now().naive_utc()).await;
+                attempted += 1;
+                repo.update_delivery(&delivery)
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = stored {
+            logging::warn!("cannot deliver webhook delivery {delivery_id}: {e}");
+        }
+    }
+    Ok(attempted)
+}
+
+/// Runs [`deliver_due`] every `interval` for the lifetime of the server.
+pub fn spawn_worker(repo: Arc<dyn WebhookRepository>, interval: std::time::Duration) {
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = deliver_due(repo.as_ref(), &client).await {
+                logging::warn!("webhook delivery failed: {e}");
+            }
+        }
+    });
+}
+
+/// Queues a fresh copy of a delivery of the property and returns its id.
+pub fn replay(repo: &dyn WebhookRepository, pid: Uuid, delivery_id: i32) -> RepoResult<i32> {
+    let delivery = repo.find_delivery(delivery_id)?;
+    if repo.find_webhook(delivery.webhook_id)?.property_id != pid {
+        return Err(RepositoryError::NotFound);
+    }
+    repo.insert_delivery(NewWebhookDelivery {
+        webhook_id: delivery.webhook_id,
+        event: delivery.event,
+        payload: delivery.payload,
+        next_attempt_at: Utc::now().naive_utc(),
+    })
+}
+
+/// A webhook together with its latest deliveries.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookLog {
+    pub webhook: Webhook,
+    pub deliveries: Vec<WebhookDelivery>,
+}
+
+#[server(name = ListWebhooks, prefix = "/api", endpoint = "list_webhooks", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn list_webhooks(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+) -> Result<Vec<WebhookLog>, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| state.webhooks.webhooks(pid))
+        .and_then(|webhooks| {
+            webhooks
+                .into_iter()
+                .map(|webhook| {
+                    Ok(WebhookLog {
+                        deliveries: state.webhooks.deliveries(webhook.webhook_id, 20)?,
+                        webhook,
+                    })
+                })
+                .collect()
+        })
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[server(name = CreateWebhook, prefix = "/api", endpoint = "create_webhook", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn create_webhook(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    url: String,
+    reservation_created: Option<bool>,
+    reservation_updated: Option<bool>,
+    reservation_cancelled: Option<bool>,
+    reservation_seated: Option<bool>,
+    payment_recorded: Option<bool>,
+    reservation_details_changed: Option<bool>,
+) -> Result<i32, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    let events: Vec<WebhookEvent> = [
+        (reservation_created, WebhookEvent::ReservationCreated),
+        (reservation_updated, WebhookEvent::ReservationUpdated),
+        (reservation_cancelled, WebhookEvent::ReservationCancelled),
+        (reservation_seated, WebhookEvent::ReservationSeated),
+        (payment_recorded, WebhookEvent::PaymentRecorded),
+        (
+            reservation_details_changed,
+            WebhookEvent::ReservationDetailsChanged,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(checked, event)| checked.unwrap_or(false).then_some(event))
+    .collect();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| {
+            let url = url.trim();
+            if !(url.starts_with("http://") || url.starts_with("https://"))
+                || reqwest::Url::parse(url).is_err()
+            {
+                return Err(RepositoryError::Invalid(format!(
+                    "{url:?} is not an http(s) URL"
+                )));
+            }
+            if events.is_empty() {
+                return Err(RepositoryError::Invalid(
+                    "a webhook needs at least one event".into(),
+                ));
+            }
+            state.webhooks.insert_webhook(NewWebhook {
+                property_id: pid,
+                url: url.to_string(),
+                secret: Uuid::new_v4().simple().to_string(),
+                events: WebhookEvent::mask(&events),
+            })
+        })
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(name = SetWebhookActive, prefix = "/api", endpoint = "set_webhook_active", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn set_webhook_active(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    webhook_id: i32,
+    active: bool,
+) -> Result<Webhook, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| state.webhooks.set_webhook_active(pid, webhook_id, active))
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(name = ReplayDelivery, prefix = "/api", endpoint = "replay_delivery", input = Json, output = Json, encoding = "Url", impl_from = true)]
+pub async fn replay_delivery(
+    pid: Uuid,
+    user_name: String,
+    password: String,
+    delivery_id: i32,
+) -> Result<i32, ServerFnError> {
+    let state = expect_context::<AppState>();
+
+    verify_admin(state.properties.as_ref(), pid, &user_name, &password)
+        .and_then(|_| replay(state.webhooks.as_ref(), pid, delivery_id))
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
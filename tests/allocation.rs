@@ -0,0 +1,213 @@
+//! The table plan proposing tables for a day's reservations against the
+//! in-memory repository.
+mod common;
+
+use chrono::{Days, Local, NaiveDate};
+use common::{book, booking, property_with_tables};
+use resvm::allocation::{accept_plan, plan_tables};
+use resvm::booking::{day_holds, free_table};
+use resvm::emails::{self, MemoryMailer};
+use resvm::models::{Reservation, ReservationStatus, TablePlan, WebhookEvent};
+use resvm::repository::{InMemoryRepository, PropertyRepository, ReservationRepository};
+use resvm::sms::{self, MemoryChannel, NotificationChannel};
+use time::macros::time;
+use time::Time;
+use uuid::Uuid;
+
+fn property(repo: &InMemoryRepository) -> Uuid {
+    property_with_tables(
+        repo,
+        &[
+            ("T1", 2, Some("Window")),
+            ("T2", 2, Some("Window")),
+            ("T3", 4, Some("Main")),
+            ("T4", 4, Some("Main")),
+            ("T5", 6, Some("Main")),
+        ],
+    )
+}
+
+/// Books a party of `party_size` on `seating`, `specific` when the guest
+/// asked for it.
+fn party(
+    repo: &InMemoryRepository,
+    pid: Uuid,
+    date: NaiveDate,
+    at: Time,
+    party_size: i32,
+    seating: &str,
+    specific: bool,
+) -> i32 {
+    let mut new_resv = booking(pid, seating, date, at).with_party_size(party_size);
+    new_resv.name = format!("Party of {party_size}");
+    new_resv.specific_seating_requested = specific;
+    book(repo, new_resv)
+}
+
+/// The proposed tables of each reservation, by time.
+fn proposed(plan: &TablePlan) -> Vec<(i32, String, bool)> {
+    plan.placed
+        .iter()
+        .map(|seat| (seat.reservation.id, seat.tables.join("+"), seat.fixed))
+        .collect()
+}
+
+#[test]
+fn the_plan_seats_the_largest_parties_first_without_changing_anything() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let date = Local::now().date_naive() + Days::new(1);
+    let couple = party(&repo, pid, date, time!(19:00), 2, "T5", false);
+    let six = party(&repo, pid, date, time!(19:00), 6, "", false);
+    let three = party(&repo, pid, date, time!(19:00), 3, "", false);
+
+    let plan = plan_tables(&repo, &repo, pid, date).unwrap();
+    assert_eq!(
+        proposed(&plan),
+        [
+            (couple, "T1".to_string(), false),
+            (six, "T5".to_string(), false),
+            (three, "T3".to_string(), false)
+        ]
+    );
+    assert_eq!(plan.wasted_seats, 1);
+    assert!(plan.unplaced.is_empty());
+    assert_eq!(
+        ReservationRepository::find(&repo, couple).unwrap().seating,
+        "T5"
+    );
+
+    let moved = accept_plan(&repo, &repo, pid, date).unwrap();
+    assert_eq!(moved.len(), 3);
+    assert_eq!(
+        ReservationRepository::find(&repo, couple).unwrap().seating,
+        "T1"
+    );
+    assert_eq!(
+        repo.history(couple).unwrap()[0].summary,
+        "moved from T5 to T1 by the table plan"
+    );
+    assert_eq!(
+        repo.history(six).unwrap()[0].summary,
+        "assigned T5 by the table plan"
+    );
+    assert!(accept_plan(&repo, &repo, pid, date).unwrap().is_empty());
+}
+
+#[test]
+fn the_plan_keeps_requested_and_seated_tables_and_respects_turn_times() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let date = Local::now().date_naive() + Days::new(1);
+    let asked = party(&repo, pid, date, time!(19:00), 2, "T5", true);
+    let seated = party(&repo, pid, date, time!(19:00), 2, "T1", false);
+    repo.set_status(seated, ReservationStatus::Seated).unwrap();
+    let six = party(&repo, pid, date, time!(19:00), 6, "", false);
+    let late = party(&repo, pid, date, time!(20:30), 4, "", false);
+    let later = party(&repo, pid, date, time!(21:30), 4, "", false);
+    let crowd = party(&repo, pid, date, time!(18:00), 20, "", false);
+
+    let plan = plan_tables(&repo, &repo, pid, date).unwrap();
+    assert_eq!(
+        proposed(&plan),
+        [
+            (asked, "T5".to_string(), true),
+            (six, "T3+T4".to_string(), false),
+            (later, "T3".to_string(), false)
+        ]
+    );
+    assert_eq!(plan.wasted_seats, 4 + 2);
+    let unplaced: Vec<(i32, &str)> = plan
+        .unplaced
+        .iter()
+        .map(|unplaced| (unplaced.reservation.id, unplaced.reason.as_str()))
+        .collect();
+    assert_eq!(
+        unplaced,
+        [
+            (crowd, "no table or tables pushed together seat 20"),
+            (late, "every table for 4 is taken around 20:30")
+        ]
+    );
+}
+
+#[test]
+fn seated_parties_keep_their_tables_all_evening() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let date = Local::now().date_naive() + Days::new(1);
+    let lingering = party(&repo, pid, date, time!(16:00), 2, "T1", false);
+    repo.set_status(lingering, ReservationStatus::Seated)
+        .unwrap();
+    let couple = party(&repo, pid, date, time!(21:00), 2, "", false);
+
+    let plan = plan_tables(&repo, &repo, pid, date).unwrap();
+    assert_eq!(proposed(&plan), [(couple, "T2".to_string(), false)]);
+
+    // Once they are done the table is free again.
+    repo.set_status(lingering, ReservationStatus::Completed)
+        .unwrap();
+    let plan = plan_tables(&repo, &repo, pid, date).unwrap();
+    assert_eq!(proposed(&plan), [(couple, "T1".to_string(), false)]);
+}
+
+#[test]
+fn accepted_combinations_hold_every_table() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let date = Local::now().date_naive() + Days::new(1);
+    party(&repo, pid, date, time!(19:00), 6, "T5", true);
+    let eight = party(&repo, pid, date, time!(19:00), 8, "", false);
+
+    let moved = accept_plan(&repo, &repo, pid, date).unwrap();
+    assert_eq!(moved.len(), 1);
+    assert_eq!(moved[0].id, eight);
+    assert_eq!(moved[0].seating, "T3+T4");
+    assert_eq!(repo.table_assignments(&[eight]).unwrap().len(), 2);
+
+    let tables = repo.tables(pid).unwrap();
+    let holds = day_holds(&repo, &tables, pid, date).unwrap();
+    assert_eq!(
+        free_table(&tables, &holds, time!(19:30), 3).map(|table| table.table_name),
+        None
+    );
+    assert_eq!(
+        free_table(&tables, &holds, time!(19:30), 2).map(|table| table.table_name),
+        Some("T1".to_string())
+    );
+    assert!(plan_tables(&repo, &repo, pid, date)
+        .unwrap()
+        .placed
+        .iter()
+        .all(|seat| !seat.moves()));
+}
+
+#[test]
+fn guests_are_not_told_about_a_new_table() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let date = Local::now().date_naive() + Days::new(1);
+    party(&repo, pid, date, time!(19:00), 2, "T5", false);
+    let moved = accept_plan(&repo, &repo, pid, date).unwrap();
+    assert_eq!(moved.len(), 1);
+
+    let (mailer, channel) = (MemoryMailer::new(), MemoryChannel::new());
+    let texted = Reservation {
+        contact: "+442079460000".into(),
+        ..moved[0].clone()
+    };
+    let event = WebhookEvent::ReservationDetailsChanged;
+    emails::notify(&repo, &repo, event, &moved[0]);
+    sms::notify(&repo, &repo, channel.name(), event, &texted);
+    assert_eq!(emails::deliver_due(&repo, &mailer).unwrap(), 0);
+    assert_eq!(sms::deliver_due(&repo, &channel).unwrap(), 0);
+    assert!(mailer.sent().is_empty());
+    assert!(channel.sent().is_empty());
+
+    // A change the guest made would have reached them.
+    let event = WebhookEvent::ReservationUpdated;
+    emails::notify(&repo, &repo, event, &moved[0]);
+    sms::notify(&repo, &repo, channel.name(), event, &texted);
+    assert_eq!(emails::deliver_due(&repo, &mailer).unwrap(), 1);
+    assert_eq!(sms::deliver_due(&repo, &channel).unwrap(), 1);
+}
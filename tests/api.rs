@@ -0,0 +1,20 @@
+//! Validates the query parameters of the REST API.
+use resvm::api::v1::{ReservationQuery, MAX_LIMIT};
+use resvm::api::ApiError;
+
+#[test]
+fn listing_limits_stay_within_bounds() {
+    let query = |limit| ReservationQuery {
+        limit,
+        ..Default::default()
+    };
+    assert_eq!(query(None).limit().unwrap(), 50);
+    assert_eq!(query(Some(1)).limit().unwrap(), 1);
+    assert_eq!(query(Some(MAX_LIMIT)).limit().unwrap(), MAX_LIMIT);
+    for limit in [0, -1, i64::MIN, MAX_LIMIT + 1, i64::MAX] {
+        assert!(matches!(
+            query(Some(limit)).limit(),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+}
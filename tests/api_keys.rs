@@ -0,0 +1,158 @@
+//! Property API keys and what they let a caller reach, against the in-memory
+//! repository.
+mod common;
+
+use chrono::{Days, Local, Utc};
+use common::{book, booking, property};
+use resvm::api::{ApiCaller, ApiError, ApiKeys};
+use resvm::api_keys::{issue_key, verify_key};
+use resvm::errors::RepositoryError;
+use resvm::models::{ApiKey, ApiScope};
+use resvm::repository::{ApiKeyRepository, InMemoryRepository};
+use time::macros::time;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn only_active_keys_with_their_secret_are_accepted() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let issued = issue_key(&repo, pid, "POS".into(), &[ApiScope::ReadReservations])
+        .await
+        .unwrap();
+
+    let key = verify_key(&repo, &issued.key).unwrap();
+    assert_eq!((key.key_id, key.property_id), (issued.key_id, pid));
+    assert!(repo.keys(pid).unwrap()[0].last_used_at.is_some());
+
+    let (prefix, _) = issued.key.rsplit_once('_').unwrap();
+    for wrong in [
+        format!("{prefix}_wrong"),
+        format!("{prefix}_"),
+        "rvm_unknown_secret".to_string(),
+        issued.key.replacen("rvm", "xyz", 1),
+    ] {
+        assert_eq!(
+            verify_key(&repo, &wrong).unwrap_err(),
+            RepositoryError::NotFound
+        );
+    }
+
+    // Revoking a key only works from its own property.
+    assert!(repo
+        .revoke_key(Uuid::new_v4(), issued.key_id, Utc::now().naive_utc())
+        .is_err());
+    assert!(verify_key(&repo, &issued.key).is_ok());
+    repo.revoke_key(pid, issued.key_id, Utc::now().naive_utc())
+        .unwrap();
+    assert_eq!(
+        verify_key(&repo, &issued.key).unwrap_err(),
+        RepositoryError::NotFound
+    );
+
+    assert!(matches!(
+        issue_key(&repo, pid, " ".into(), &[ApiScope::ReadReservations]).await,
+        Err(RepositoryError::Invalid(_))
+    ));
+    assert!(matches!(
+        issue_key(&repo, pid, "POS".into(), &[]).await,
+        Err(RepositoryError::Invalid(_))
+    ));
+}
+
+#[tokio::test]
+async fn keys_reach_their_own_property_within_their_scopes() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let other = property(&repo);
+    let issued = issue_key(&repo, pid, "Website".into(), &[ApiScope::ReadAvailability])
+        .await
+        .unwrap();
+    let caller = ApiCaller::Property(verify_key(&repo, &issued.key).unwrap());
+
+    assert!(caller.allow(pid, ApiScope::ReadAvailability).is_ok());
+    assert!(matches!(
+        caller.allow(pid, ApiScope::CreateReservations),
+        Err(ApiError::Forbidden)
+    ));
+    assert!(matches!(
+        caller.allow(other, ApiScope::ReadAvailability),
+        Err(ApiError::Forbidden)
+    ));
+    assert!(matches!(caller.operator(), Err(ApiError::Forbidden)));
+    assert!(ApiCaller::Operator
+        .allow(other, ApiScope::CreateReservations)
+        .is_ok());
+}
+
+#[tokio::test]
+async fn reservations_of_other_properties_look_like_unknown_ids() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let other = property(&repo);
+    let date = Local::now().date_naive() + Days::new(1);
+    let own = book(&repo, booking(pid, "T1", date, time!(19:00)));
+    let theirs = book(&repo, booking(other, "T1", date, time!(19:00)));
+    let reader = issue_key(&repo, pid, "CRM".into(), &[ApiScope::ReadReservations])
+        .await
+        .unwrap();
+    let reader = ApiCaller::Property(verify_key(&repo, &reader.key).unwrap());
+
+    let scope = ApiScope::ReadReservations;
+    assert_eq!(reader.find_reservation(&repo, own, scope).unwrap().id, own);
+    for id in [theirs, theirs + 100] {
+        assert!(matches!(
+            reader.find_reservation(&repo, id, scope),
+            Err(ApiError::Repository(RepositoryError::NotFound))
+        ));
+    }
+    assert_eq!(
+        ApiCaller::Operator
+            .find_reservation(&repo, theirs, scope)
+            .unwrap()
+            .id,
+        theirs
+    );
+
+    // Without the scope, existing and unknown ids are both forbidden.
+    let booker = issue_key(&repo, pid, "Widget".into(), &[ApiScope::CreateReservations])
+        .await
+        .unwrap();
+    let booker = ApiCaller::Property(verify_key(&repo, &booker.key).unwrap());
+    for id in [own, theirs, theirs + 100] {
+        assert!(matches!(
+            booker.find_reservation(&repo, id, scope),
+            Err(ApiError::Forbidden)
+        ));
+    }
+}
+
+#[tokio::test]
+async fn listed_keys_hide_their_hash_and_read_back() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    issue_key(&repo, pid, "POS".into(), &[ApiScope::ReadReservations])
+        .await
+        .unwrap();
+    let key = repo.keys(pid).unwrap().pop().unwrap();
+    assert!(!key.key_hash.is_empty());
+
+    let json = serde_json::to_value(&key).unwrap();
+    assert!(json.get("key_hash").is_none());
+    let listed: ApiKey = serde_json::from_value(json).unwrap();
+    assert_eq!(
+        (listed.key_id, listed.key_prefix),
+        (key.key_id, key.key_prefix)
+    );
+    assert!(listed.key_hash.is_empty());
+}
+
+#[test]
+fn operator_keys_match_whole_keys_only() {
+    let keys = ApiKeys::new(vec!["ops-secret".into(), "backup-secret".into()]);
+    assert!(keys.accepts("ops-secret"));
+    assert!(keys.accepts("backup-secret"));
+    for wrong in ["", "ops", "ops-secret ", "ops-secretx", "OPS-SECRET"] {
+        assert!(!keys.accepts(wrong));
+    }
+    assert!(!ApiKeys::default().accepts(""));
+}
@@ -0,0 +1,186 @@
+//! Free tables, availability and the guards of the online booking form
+//! against the in-memory repository.
+mod common;
+
+use chrono::{Days, Local};
+use common::{book, booking, property_with_tables};
+use resvm::booking::{
+    add_closure, availability, check_form, form_token, free_table, request_table, slots,
+    RateLimiter, BOOKING_DAYS, MAX_FORM_AGE_SECS, MIN_FILL_SECS,
+};
+use resvm::errors::RepositoryError;
+use resvm::guests::NoShowPolicy;
+use resvm::models::{NewClosure, ReservationStatus};
+use resvm::repository::{InMemoryRepository, PropertyRepository, ReservationRepository};
+use std::net::IpAddr;
+use std::time::Duration;
+use time::macros::time;
+use uuid::Uuid;
+
+const TABLES: [(&str, i32, Option<&str>); 3] = [("T1", 2, None), ("T2", 4, None), ("T3", 6, None)];
+
+#[test]
+fn the_smallest_free_table_is_picked() {
+    let repo = InMemoryRepository::new();
+    let pid = property_with_tables(&repo, &TABLES);
+    let date = Local::now().date_naive() + Days::new(1);
+    let tables = repo.tables(pid).unwrap();
+    let free = |party_size| {
+        let booked = repo.on_date(pid, date).unwrap();
+        free_table(&tables, &booked, time!(19:00), party_size).map(|table| table.table_name)
+    };
+
+    assert_eq!(free(2).as_deref(), Some("T1"));
+    assert_eq!(free(3).as_deref(), Some("T2"));
+    assert_eq!(free(7), None);
+
+    // A named table is held, an unnamed booking takes the smallest that fits.
+    book(&repo, booking(pid, "t1", date, time!(18:00)));
+    assert_eq!(free(2).as_deref(), Some("T2"));
+    let unnamed = book(&repo, booking(pid, "", date, time!(20:30)));
+    assert_eq!(free(2).as_deref(), Some("T3"));
+    assert_eq!(free(6).as_deref(), Some("T3"));
+
+    // Cancelled bookings and those a turn away hold nothing.
+    repo.set_status(unnamed, ReservationStatus::Cancelled)
+        .unwrap();
+    assert_eq!(free(2).as_deref(), Some("T2"));
+    let booked = repo.on_date(pid, date).unwrap();
+    assert_eq!(
+        free_table(&tables, &booked, time!(20:00), 2).map(|table| table.table_name),
+        Some("T1".to_string())
+    );
+}
+
+#[test]
+fn slots_are_offered_while_a_table_is_free() {
+    let repo = InMemoryRepository::new();
+    let pid = property_with_tables(&repo, &TABLES[..1]);
+    let today = Local::now().date_naive();
+    let date = today + Days::new(1);
+
+    assert_eq!(availability(&repo, &repo, pid, date, 2).unwrap(), slots());
+    book(&repo, booking(pid, "T1", date, time!(19:00)));
+    let offered = availability(&repo, &repo, pid, date, 2).unwrap();
+    for taken in [time!(17:30), time!(19:00), time!(20:30)] {
+        assert!(!offered.contains(&taken));
+    }
+    assert!(offered.contains(&time!(17:00)));
+    assert!(offered.contains(&time!(21:00)));
+
+    for (date, party_size) in [
+        (today - Days::new(1), 2),
+        (today + Days::new(BOOKING_DAYS + 1), 2),
+        (date, 0),
+        (date, 3),
+    ] {
+        assert!(availability(&repo, &repo, pid, date, party_size)
+            .unwrap()
+            .is_empty());
+    }
+
+    let closed = date + Days::new(1);
+    add_closure(
+        &repo,
+        NewClosure {
+            property_id: pid,
+            first_day: closed,
+            last_day: closed,
+            reason: "Inventory".into(),
+        },
+    )
+    .unwrap();
+    assert!(availability(&repo, &repo, pid, closed, 2)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn forms_must_be_signed_filled_by_hand_and_fresh() {
+    let pid = Uuid::new_v4();
+    let issued_at = 1_700_000_000;
+    let token = form_token(pid, issued_at);
+    let now = issued_at + MIN_FILL_SECS;
+
+    assert!(check_form(pid, issued_at, &token, None, now).is_ok());
+    assert!(check_form(pid, issued_at, &token, Some(""), now).is_ok());
+    for (pid, issued_at, token, honeypot, now) in [
+        (pid, issued_at, token.as_str(), Some("http://spam"), now),
+        (pid, issued_at, token.as_str(), None, issued_at + 1),
+        (
+            pid,
+            issued_at,
+            token.as_str(),
+            None,
+            issued_at + MAX_FORM_AGE_SECS + 1,
+        ),
+        (pid, issued_at - 60, token.as_str(), None, now),
+        (Uuid::new_v4(), issued_at, token.as_str(), None, now),
+        (pid, issued_at, "not hex", None, now),
+        (pid, issued_at, &token[..10], None, now),
+    ] {
+        assert!(matches!(
+            check_form(pid, issued_at, token, honeypot, now),
+            Err(RepositoryError::Invalid(_))
+        ));
+    }
+}
+
+#[test]
+fn clients_are_limited_per_window() {
+    let limiter = RateLimiter::new(2, Duration::from_millis(200));
+    let (ada, grace): (IpAddr, IpAddr) = ("10.0.0.1".parse().unwrap(), "::1".parse().unwrap());
+
+    assert!(limiter.allow(ada));
+    assert!(limiter.allow(ada));
+    assert!(!limiter.allow(ada));
+    assert!(limiter.allow(grace));
+
+    std::thread::sleep(Duration::from_millis(250));
+    assert!(limiter.allow(ada));
+}
+
+#[test]
+fn concurrent_guests_cannot_both_get_the_last_table() {
+    let repo = InMemoryRepository::new();
+    let pid = property_with_tables(&repo, &TABLES[..1]);
+    let date = Local::now().date_naive() + Days::new(1);
+
+    let outcomes: Vec<Result<i32, RepositoryError>> = std::thread::scope(|scope| {
+        let guests: Vec<_> = (0..8)
+            .map(|_| {
+                scope.spawn(|| {
+                    request_table(
+                        &repo,
+                        &repo,
+                        &repo,
+                        &NoShowPolicy::default(),
+                        booking(pid, "", date, time!(19:00)),
+                    )
+                })
+            })
+            .collect();
+        guests
+            .into_iter()
+            .map(|guest| guest.join().unwrap())
+            .collect()
+    });
+    assert_eq!(outcomes.iter().filter(|outcome| outcome.is_ok()).count(), 1);
+    assert!(outcomes
+        .iter()
+        .filter_map(|outcome| outcome.as_ref().err())
+        .all(|e| matches!(e, RepositoryError::Conflict(_))));
+    assert_eq!(repo.on_date(pid, date).unwrap()[0].seating, "T1");
+
+    // Times that are not offered are refused outright.
+    assert!(matches!(
+        request_table(
+            &repo,
+            &repo,
+            &repo,
+            &NoShowPolicy::default(),
+            booking(pid, "", date, time!(19:10))
+        ),
+        Err(RepositoryError::Conflict(_))
+    ));
+}
@@ -0,0 +1,204 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::PgConnection;
+use leptos::LeptosOptions;
+use resvm::crud_properties::create_reservation;
+use resvm::errors::RepositoryError;
+use resvm::guests::NoShowPolicy;
+use resvm::models::{
+    EventDetails, HistoryEntry, NewHistoryEntry, NewProperty, NewPropertyTable, NewReservationNote,
+    NewResv, Reservation, ReservationNote, ReservationStatus, SpecialRequests, TableAssignment,
+};
+use resvm::repository::{
+    InMemoryRepository, PropertyRepository, RepoResult, ReservationRepository,
+};
+use resvm::{AppState, Database};
+use serde_json::json;
+use std::sync::Arc;
+use time::Time;
+use uuid::Uuid;
+
+/// Stores Chez Ada, a property without tables.
+pub fn property(repo: &InMemoryRepository) -> Uuid {
+    PropertyRepository::insert(
+        repo,
+        NewProperty::new(
+            Uuid::new_v4(),
+            "Chez Ada".into(),
+            String::new(),
+            "host@chez-ada.test".into(),
+            String::new(),
+        ),
+    )
+    .unwrap()
+}
+
+/// Stores Chez Ada with `tables`, each a name, a capacity and a section.
+pub fn property_with_tables(
+    repo: &InMemoryRepository,
+    tables: &[(&str, i32, Option<&str>)],
+) -> Uuid {
+    let pid = property(repo);
+    for (name, capacity, section) in tables {
+        repo.insert_table(NewPropertyTable::new(
+            pid,
+            name.to_string(),
+            *capacity,
+            section.map(str::to_string),
+        ))
+        .unwrap();
+    }
+    pid
+}
+
+/// A confirmed booking for two by Grace, paid nothing in advance.
+pub fn booking(pid: Uuid, seating: &str, date: NaiveDate, at: Time) -> NewResv {
+    NewResv::new(
+        "Grace".into(),
+        "grace@example.com".into(),
+        seating.into(),
+        false,
+        false,
+        json!({ "mode_of_payment": "NotPaid" }),
+        None,
+        true,
+        date,
+        at,
+        pid,
+    )
+}
+
+/// A booking like [`booking`] at table T1, made by `name` with `contact`.
+pub fn booking_by(pid: Uuid, name: &str, contact: &str, date: NaiveDate, at: Time) -> NewResv {
+    let mut new_resv = booking(pid, "T1", date, at);
+    new_resv.name = name.into();
+    new_resv.contact = contact.into();
+    new_resv
+}
+
+/// Books `new_resv` through the booking rules and returns its id.
+pub fn book(repo: &InMemoryRepository, new_resv: NewResv) -> i32 {
+    book_under(repo, &NoShowPolicy::default(), new_resv)
+}
+
+/// Books `new_resv` like [`book`], with `policy` for repeat no-shows.
+pub fn book_under(repo: &InMemoryRepository, policy: &NoShowPolicy, new_resv: NewResv) -> i32 {
+    create_reservation(repo, repo, repo, policy, new_resv).unwrap()
+}
+
+/// Stores `new_resv` as is, such as a booking in the past the rules would
+/// refuse, and returns its id.
+pub fn store(repo: &InMemoryRepository, new_resv: NewResv) -> i32 {
+    ReservationRepository::insert(repo, new_resv).unwrap()
+}
+
+/// The time of day of `at`, to the minute.
+pub fn time_of(at: NaiveDateTime) -> Time {
+    Time::from_hms(at.hour() as u8, at.minute() as u8, 0).unwrap()
+}
+
+/// Server state backed by `repo`, without mail or texts. Its Postgres pool
+/// never opens a connection.
+pub fn state(repo: Arc<InMemoryRepository>) -> AppState {
+    let pool = Pool::builder()
+        .min_idle(Some(0))
+        .build_unchecked(ConnectionManager::<PgConnection>::new("postgres://unused"));
+    AppState::with_repository(
+        LeptosOptions::default(),
+        Database::Postgres(Arc::new(pool)),
+        repo,
+    )
+}
+
+/// The in-memory repository, failing to change the status of one
+/// reservation.
+#[derive(Debug)]
+pub struct Stuck {
+    pub repo: InMemoryRepository,
+    pub id: i32,
+}
+
+impl ReservationRepository for Stuck {
+    fn for_property(&self, pid: Uuid, limit: i64) -> RepoResult<Vec<Reservation>> {
+        self.repo.for_property(pid, limit)
+    }
+    fn with_tag(&self, pid: Uuid, tag: &str, limit: i64) -> RepoResult<Vec<Reservation>> {
+        self.repo.with_tag(pid, tag, limit)
+    }
+    fn on_date(&self, pid: Uuid, date: NaiveDate) -> RepoResult<Vec<Reservation>> {
+        self.repo.on_date(pid, date)
+    }
+    fn count_for_property(&self, pid: Uuid) -> RepoResult<i64> {
+        self.repo.count_for_property(pid)
+    }
+    fn insert(&self, new_resv: NewResv) -> RepoResult<i32> {
+        ReservationRepository::insert(&self.repo, new_resv)
+    }
+    fn find(&self, id: i32) -> RepoResult<Reservation> {
+        ReservationRepository::find(&self.repo, id)
+    }
+    fn set_status(&self, id: i32, status: ReservationStatus) -> RepoResult<Reservation> {
+        if id == self.id {
+            return Err(RepositoryError::Conflict(format!(
+                "reservation {id} is locked"
+            )));
+        }
+        self.repo.set_status(id, status)
+    }
+    fn set_payment(
+        &self,
+        id: i32,
+        advance_method: serde_json::Value,
+        advance_amount: i32,
+    ) -> RepoResult<Reservation> {
+        self.repo.set_payment(id, advance_method, advance_amount)
+    }
+    fn find_by_token(&self, token: &str) -> RepoResult<Reservation> {
+        self.repo.find_by_token(token)
+    }
+    fn reschedule(
+        &self,
+        id: i32,
+        date: NaiveDate,
+        time: Time,
+        seating: String,
+        party_size: i32,
+    ) -> RepoResult<Reservation> {
+        self.repo.reschedule(id, date, time, seating, party_size)
+    }
+    fn insert_history(&self, entry: NewHistoryEntry) -> RepoResult<i32> {
+        self.repo.insert_history(entry)
+    }
+    fn history(&self, id: i32) -> RepoResult<Vec<HistoryEntry>> {
+        self.repo.history(id)
+    }
+    fn set_requests(
+        &self,
+        id: i32,
+        special_requests: SpecialRequests,
+        tags: String,
+    ) -> RepoResult<Reservation> {
+        self.repo.set_requests(id, special_requests, tags)
+    }
+    fn insert_note(&self, note: NewReservationNote) -> RepoResult<i32> {
+        self.repo.insert_note(note)
+    }
+    fn notes(&self, id: i32) -> RepoResult<Vec<ReservationNote>> {
+        self.repo.notes(id)
+    }
+    fn table_assignments(&self, ids: &[i32]) -> RepoResult<Vec<TableAssignment>> {
+        self.repo.table_assignments(ids)
+    }
+    fn set_table_assignments(&self, id: i32, table_ids: &[i32]) -> RepoResult<()> {
+        self.repo.set_table_assignments(id, table_ids)
+    }
+    fn event_details(&self, id: i32) -> RepoResult<Option<EventDetails>> {
+        self.repo.event_details(id)
+    }
+    fn save_event_details(&self, details: &EventDetails) -> RepoResult<()> {
+        self.repo.save_event_details(details)
+    }
+}
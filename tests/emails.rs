@@ -0,0 +1,85 @@
+//! Renders, queues and sends guest emails through the in-memory transport.
+mod common;
+
+use chrono::{Duration, Local, NaiveDateTime};
+use common::{booking_by, property, store, time_of};
+use resvm::emails::{deliver_due, notify, MemoryMailer};
+use resvm::models::{
+    DeliveryStatus, EmailTemplate, NotificationKind, ReservationStatus, WebhookEvent,
+};
+use resvm::repository::{EmailRepository, InMemoryRepository, ReservationRepository};
+use uuid::Uuid;
+
+/// Stores a booking by Ada for three, reached at `contact`.
+fn book(repo: &InMemoryRepository, pid: Uuid, contact: &str, at: NaiveDateTime) -> i32 {
+    store(
+        repo,
+        booking_by(pid, "Ada", contact, at.date(), time_of(at)).with_party_size(3),
+    )
+}
+
+#[test]
+fn reservation_events_email_the_guest() {
+    let repo = InMemoryRepository::new();
+    let mailer = MemoryMailer::new();
+    let pid = property(&repo);
+
+    // Confirmations render the default template.
+    let in_two_days = Local::now().naive_local() + Duration::days(2);
+    let rid = book(&repo, pid, "ada@example.com", in_two_days);
+    let resv = ReservationRepository::find(&repo, rid).unwrap();
+    notify(&repo, &repo, WebhookEvent::ReservationCreated, &resv);
+    assert_eq!(deliver_due(&repo, &mailer).unwrap(), 1);
+    let sent = mailer.sent().pop().unwrap();
+    assert_eq!(sent.kind, NotificationKind::Confirmation);
+    assert_eq!(sent.recipient, "ada@example.com");
+    assert_eq!(sent.reply_to.as_deref(), Some("host@chez-ada.test"));
+    assert_eq!(sent.subject, "Your booking at Chez Ada");
+    assert!(sent
+        .body
+        .contains(resv.confirmation_code.as_deref().unwrap()));
+    assert!(sent.body.contains(&format!(
+        "/manage/{}",
+        resv.confirmation_token.as_deref().unwrap()
+    )));
+
+    // An edited template replaces the default one.
+    repo.save_email_template(EmailTemplate {
+        property_id: pid,
+        kind: NotificationKind::Cancellation,
+        subject: "Sorry to see you go, {{name}}".into(),
+        body: "{{party_size}} seats released.".into(),
+    })
+    .unwrap();
+    let cancelled = repo.set_status(rid, ReservationStatus::Cancelled).unwrap();
+    notify(&repo, &repo, WebhookEvent::ReservationCancelled, &cancelled);
+    deliver_due(&repo, &mailer).unwrap();
+    let sent = mailer.sent().pop().unwrap();
+    assert_eq!(sent.subject, "Sorry to see you go, Ada");
+    assert_eq!(sent.body, "3 seats released.");
+
+    // Phone contacts and seated guests get nothing.
+    let phoned = book(&repo, pid, "+44 20 7946 0000", in_two_days);
+    let phoned = ReservationRepository::find(&repo, phoned).unwrap();
+    notify(&repo, &repo, WebhookEvent::ReservationCreated, &phoned);
+    notify(&repo, &repo, WebhookEvent::ReservationSeated, &resv);
+    assert_eq!(deliver_due(&repo, &mailer).unwrap(), 0);
+
+    // A failing transport keeps the email pending for a retry.
+    let bob = book(&repo, pid, "bob@example.com", in_two_days);
+    let bob = ReservationRepository::find(&repo, bob).unwrap();
+    notify(&repo, &repo, WebhookEvent::ReservationCreated, &bob);
+    mailer.set_failing(true);
+    assert_eq!(deliver_due(&repo, &mailer).unwrap(), 1);
+    let confirmation = repo
+        .emails(pid, 10)
+        .unwrap()
+        .into_iter()
+        .find(|email| email.reservation_id == Some(bob.id))
+        .unwrap();
+    assert_eq!(confirmation.kind, NotificationKind::Confirmation);
+    assert_eq!(confirmation.status, DeliveryStatus::Pending);
+    assert_eq!(confirmation.attempts, 1);
+    assert!(confirmation.last_error.is_some());
+    assert_eq!(deliver_due(&repo, &mailer).unwrap(), 0);
+}
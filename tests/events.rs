@@ -0,0 +1,164 @@
+//! Group bookings over several tables, section buyouts and event details
+//! against the in-memory repository.
+mod common;
+
+use chrono::{Days, Local, NaiveDate};
+use common::{book, booking, property_with_tables};
+use resvm::booking::{day_holds, free_table};
+use resvm::crud_properties::cancel_reservation;
+use resvm::errors::RepositoryError;
+use resvm::events::{assign_tables, book_section, group_bookings, set_event};
+use resvm::models::{HistoryEntry, PropertyTable};
+use resvm::repository::{InMemoryRepository, PropertyRepository, ReservationRepository};
+use resvm::special_requests::day_sheet;
+use time::macros::time;
+use time::Time;
+use uuid::Uuid;
+
+fn property(repo: &InMemoryRepository) -> Uuid {
+    property_with_tables(
+        repo,
+        &[
+            ("T1", 4, Some("Main")),
+            ("T2", 6, Some("Main")),
+            ("P1", 8, Some("Terrace")),
+            ("P2", 8, Some("Terrace")),
+        ],
+    )
+}
+
+/// Books a party of `party_size` at 19:00 without a table asked for.
+fn party(repo: &InMemoryRepository, pid: Uuid, date: NaiveDate, party_size: i32) -> i32 {
+    book(
+        repo,
+        booking(pid, "", date, time!(19:00)).with_party_size(party_size),
+    )
+}
+
+fn free_at(
+    repo: &InMemoryRepository,
+    pid: Uuid,
+    date: NaiveDate,
+    at: Time,
+    party_size: i32,
+) -> Option<String> {
+    let tables: Vec<PropertyTable> = repo.tables(pid).unwrap();
+    free_table(
+        &tables,
+        &day_holds(repo, &tables, pid, date).unwrap(),
+        at,
+        party_size,
+    )
+    .map(|table| table.table_name)
+}
+
+#[test]
+fn a_group_holds_every_table_it_is_assigned() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let date = Local::now().date_naive() + Days::new(1);
+    let rid = party(&repo, pid, date, 10);
+
+    let group = assign_tables(&repo, &repo, rid, &["t1".into(), " T2 ".into()]).unwrap();
+    assert_eq!(group.reservation.seating, "T1+T2");
+    assert_eq!(group.tables, ["T1", "T2"]);
+    assert_eq!(repo.history(rid).unwrap()[0].summary, "assigned T1+T2");
+    assert_eq!(
+        repo.history(rid).unwrap()[0].changed_by,
+        HistoryEntry::STAFF
+    );
+
+    // Only the terrace is left around the group's time.
+    assert_eq!(
+        free_at(&repo, pid, date, time!(19:00), 2).as_deref(),
+        Some("P1")
+    );
+    assert_eq!(
+        free_at(&repo, pid, date, time!(22:00), 2).as_deref(),
+        Some("T1")
+    );
+    let other = party(&repo, pid, date, 4);
+    assert!(matches!(
+        assign_tables(&repo, &repo, other, &["T2".into()]),
+        Err(RepositoryError::Conflict(_))
+    ));
+
+    assert!(matches!(
+        assign_tables(&repo, &repo, rid, &["T1".into()]),
+        Err(RepositoryError::Invalid(_))
+    ));
+    assert!(matches!(
+        assign_tables(&repo, &repo, rid, &["Bar".into()]),
+        Err(RepositoryError::Invalid(_))
+    ));
+    assert!(matches!(
+        assign_tables(&repo, &repo, rid, &[]),
+        Err(RepositoryError::Invalid(_))
+    ));
+
+    // Cancelling lets go of the tables.
+    cancel_reservation(&repo, rid).unwrap();
+    assert_eq!(
+        free_at(&repo, pid, date, time!(19:00), 6).as_deref(),
+        Some("T2")
+    );
+}
+
+#[test]
+fn a_buyout_holds_the_whole_section() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let date = Local::now().date_naive() + Days::new(1);
+    let rid = party(&repo, pid, date, 12);
+
+    let group = book_section(&repo, &repo, rid, "terrace").unwrap();
+    assert_eq!(group.reservation.seating, "P1+P2");
+    assert_eq!(group.event.unwrap().section.as_deref(), Some("Terrace"));
+    assert_eq!(free_at(&repo, pid, date, time!(19:30), 8), None);
+    assert_eq!(
+        free_at(&repo, pid, date, time!(19:30), 6).as_deref(),
+        Some("T2")
+    );
+    assert!(matches!(
+        book_section(&repo, &repo, rid, "Garden"),
+        Err(RepositoryError::Invalid(_))
+    ));
+
+    // Moving the party onto tables ends the buyout.
+    let group = assign_tables(&repo, &repo, rid, &["P1".into(), "T1".into()]).unwrap();
+    assert_eq!(group.event.unwrap().section, None);
+    assert_eq!(
+        free_at(&repo, pid, date, time!(19:30), 8).as_deref(),
+        Some("P2")
+    );
+}
+
+#[test]
+fn event_details_are_kept_with_the_booking_and_on_the_day_sheet() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let date = Local::now().date_naive() + Days::new(1);
+    let rid = party(&repo, pid, date, 16);
+    book_section(&repo, &repo, rid, "Terrace").unwrap();
+
+    let group = set_event(&repo, &repo, rid, " Set menu B ", Some(1500)).unwrap();
+    let event = group.event.unwrap();
+    assert_eq!(event.menu, "Set menu B");
+    assert_eq!(
+        event.summary(),
+        "buyout of Terrace; Set menu B; minimum spend 1500"
+    );
+    assert_eq!(
+        repo.history(rid).unwrap().last().unwrap().summary,
+        "event details set to buyout of Terrace; Set menu B; minimum spend 1500"
+    );
+    assert!(matches!(
+        set_event(&repo, &repo, rid, "", Some(-1)),
+        Err(RepositoryError::Invalid(_))
+    ));
+
+    let sheet = day_sheet(&repo, &repo, pid, date).unwrap();
+    assert_eq!(sheet[0].event.as_ref(), Some(&event));
+    let day = group_bookings(&repo, &repo, pid, date).unwrap();
+    assert_eq!(day[0].tables, ["P1", "P2"]);
+}
@@ -0,0 +1,221 @@
+//! The floor plan editor and the live floor view against the in-memory
+//! repository.
+mod common;
+
+use chrono::{Days, Local, NaiveDate};
+use common::{book, booking, property_with_tables};
+use resvm::crud_properties::cancel_reservation;
+use resvm::errors::RepositoryError;
+use resvm::events::assign_tables;
+use resvm::floor::{floor_plan, floor_view, place_table};
+use resvm::floor::{FLOOR_HEIGHT, FLOOR_WIDTH};
+use resvm::models::{FloorTable, ReservationStatus, TableLayout, TableShape};
+use resvm::repository::{InMemoryRepository, PropertyRepository, ReservationRepository};
+use time::macros::time;
+use time::Time;
+use uuid::Uuid;
+
+const TABLES: [(&str, i32, Option<&str>); 7] = [
+    ("T1", 2, Some("Main")),
+    ("T2", 2, Some("Main")),
+    ("T3", 4, Some("Main")),
+    ("T4", 4, Some("Main")),
+    ("T5", 6, Some("Main")),
+    ("T6", 6, Some("Main")),
+    ("T7", 8, Some("Main")),
+];
+
+fn property(repo: &InMemoryRepository) -> Uuid {
+    property_with_tables(repo, &TABLES)
+}
+
+/// Books a party of `party_size` on `seating`.
+fn party(
+    repo: &InMemoryRepository,
+    pid: Uuid,
+    date: NaiveDate,
+    at: Time,
+    seating: &str,
+    party_size: i32,
+) -> i32 {
+    let mut new_resv = booking(pid, seating, date, at).with_party_size(party_size);
+    new_resv.name = format!("Party at {seating}");
+    book(repo, new_resv)
+}
+
+fn spot<'a>(floor: &'a [FloorTable], name: &str) -> &'a FloorTable {
+    floor
+        .iter()
+        .find(|spot| spot.table.table_name == name)
+        .unwrap()
+}
+
+/// Who holds each table of the floor, by table name.
+fn holders(floor: &[FloorTable]) -> Vec<(&str, Option<i32>)> {
+    floor
+        .iter()
+        .map(|spot| {
+            (
+                spot.table.table_name.as_str(),
+                spot.reservation.as_ref().map(|resv| resv.id),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn tables_are_laid_out_in_rows_until_placed() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+
+    let floor = floor_plan(&repo, pid).unwrap();
+    assert_eq!(floor.len(), 7);
+    let corners: Vec<(i32, i32)> = floor
+        .iter()
+        .map(|spot| (spot.layout.x, spot.layout.y))
+        .collect();
+    assert_eq!(corners[..2], [(20, 20), (150, 20)]);
+    assert_eq!(corners[6], (20, 120));
+    assert!(floor.iter().all(|spot| spot.reservation.is_none()));
+
+    let t3 = spot(&floor, "T3").table.table_id;
+    let placed = place_table(
+        &repo,
+        pid,
+        TableLayout {
+            table_id: t3,
+            x: 400,
+            y: 300,
+            width: 80,
+            height: 80,
+            rotation: -45,
+            shape: TableShape::Round,
+        },
+        " Terrace ",
+    )
+    .unwrap();
+    assert_eq!(placed.layout.rotation, 315);
+    assert_eq!(placed.table.section.as_deref(), Some("Terrace"));
+
+    let floor = floor_plan(&repo, pid).unwrap();
+    assert_eq!(spot(&floor, "T3").layout, placed.layout);
+    assert_eq!(spot(&floor, "T3").table.section.as_deref(), Some("Terrace"));
+    assert_eq!(
+        (spot(&floor, "T4").layout.x, spot(&floor, "T4").layout.y),
+        (410, 20)
+    );
+
+    place_table(&repo, pid, placed.layout, "").unwrap();
+    assert_eq!(repo.tables(pid).unwrap()[2].section, None);
+}
+
+#[test]
+fn many_tables_still_fit_on_the_floor() {
+    let repo = InMemoryRepository::new();
+    let names: Vec<String> = (1..=60).map(|n| format!("T{n:02}")).collect();
+    let tables: Vec<(&str, i32, Option<&str>)> =
+        names.iter().map(|name| (name.as_str(), 4, None)).collect();
+    let pid = property_with_tables(&repo, &tables);
+
+    let floor = floor_plan(&repo, pid).unwrap();
+    assert_eq!(floor.len(), 60);
+    for spot in &floor {
+        let layout = spot.layout;
+        assert!(layout.x >= 0 && layout.x + layout.width <= FLOOR_WIDTH);
+        assert!(layout.y >= 0 && layout.y + layout.height <= FLOOR_HEIGHT);
+        // What is laid out can be saved as it is.
+        place_table(&repo, pid, layout, "").unwrap();
+    }
+    let rows: Vec<i32> = floor.iter().step_by(6).map(|spot| spot.layout.y).collect();
+    let height = floor[0].layout.height;
+    assert!(rows.windows(2).all(|pair| pair[0] + height <= pair[1]));
+}
+
+#[test]
+fn tables_must_fit_on_the_floor() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let layout = spot(&floor_plan(&repo, pid).unwrap(), "T1").layout;
+
+    for wrong in [
+        TableLayout { x: -1, ..layout },
+        TableLayout { x: 760, ..layout },
+        TableLayout { y: 560, ..layout },
+        TableLayout {
+            x: i32::MAX,
+            ..layout
+        },
+        TableLayout {
+            y: i32::MAX,
+            ..layout
+        },
+        TableLayout {
+            width: 10,
+            ..layout
+        },
+        TableLayout {
+            height: 301,
+            ..layout
+        },
+    ] {
+        assert!(matches!(
+            place_table(&repo, pid, wrong, ""),
+            Err(RepositoryError::Invalid(_))
+        ));
+    }
+
+    let other = property(&repo);
+    assert!(matches!(
+        place_table(&repo, other, layout, ""),
+        Err(RepositoryError::NotFound)
+    ));
+}
+
+#[test]
+fn the_floor_shows_who_holds_each_table_at_a_time() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let date = Local::now().date_naive() + Days::new(1);
+    let booked = party(&repo, pid, date, time!(19:00), "T1", 2);
+    let seated = party(&repo, pid, date, time!(18:30), "T2", 2);
+    repo.set_status(seated, ReservationStatus::Seated).unwrap();
+    let later = party(&repo, pid, date, time!(20:00), "T2", 2);
+    let group = party(&repo, pid, date, time!(19:00), "", 8);
+    assign_tables(&repo, &repo, group, &["T3".into(), "T4".into()]).unwrap();
+    let cancelled = party(&repo, pid, date, time!(19:00), "T5", 4);
+    cancel_reservation(&repo, cancelled).unwrap();
+
+    let floor = floor_view(&repo, &repo, pid, date, time!(19:30)).unwrap();
+    assert_eq!(
+        holders(&floor),
+        [
+            ("T1", Some(booked)),
+            ("T2", Some(seated)),
+            ("T3", Some(group)),
+            ("T4", Some(group)),
+            ("T5", None),
+            ("T6", None),
+            ("T7", None)
+        ]
+    );
+    assert_eq!(
+        spot(&floor, "T2").reservation.as_ref().unwrap().status,
+        ReservationStatus::Seated
+    );
+
+    // A seated party keeps its table however long it stays.
+    let floor = floor_view(&repo, &repo, pid, date, time!(22:30)).unwrap();
+    assert_eq!(
+        holders(&floor)[..3],
+        [("T1", None), ("T2", Some(seated)), ("T3", None)]
+    );
+
+    // Once the seated party has left, T2 shows the next booking.
+    repo.set_status(seated, ReservationStatus::Completed)
+        .unwrap();
+    let floor = floor_view(&repo, &repo, pid, date, time!(21:30)).unwrap();
+    assert_eq!(
+        holders(&floor)[..3],
+        [("T1", None), ("T2", Some(later)), ("T3", None)]
+    );
+}
@@ -0,0 +1,282 @@
+//! Matches bookings to guest profiles and sums up their visits against the
+//! in-memory repository.
+mod common;
+
+use chrono::{Days, Local};
+use common::{book, booking_by, property};
+use resvm::errors::RepositoryError;
+use resvm::guests::{
+    find_duplicates, link_reservations, merge, name_similarity, normalize_contact, normalize_tags,
+    profile,
+};
+use resvm::models::ReservationStatus;
+use resvm::repository::{GuestRepository, InMemoryRepository, ReservationRepository};
+use serde_json::json;
+use time::macros::time;
+
+#[test]
+fn contacts_and_tags_are_normalised() {
+    assert_eq!(
+        normalize_contact(" Ada@Example.COM ").as_deref(),
+        Some("ada@example.com")
+    );
+    assert_eq!(
+        normalize_contact("+44 (0)20 7946-0000").as_deref(),
+        Some("+442079460000")
+    );
+    assert_eq!(normalize_contact("ask for Ada at the bar"), None);
+    assert_eq!(
+        normalize_tags(" VIP, nut allergy,,vip , "),
+        "VIP, nut allergy"
+    );
+}
+
+#[test]
+fn bookings_with_the_same_contact_share_a_guest() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+    let last_week = today - Days::new(7);
+
+    let first = book(
+        &repo,
+        booking_by(
+            pid,
+            " Ada Lovelace ",
+            "+44 20 7946 0000",
+            last_week,
+            time!(19:30),
+        ),
+    );
+    let second = book(
+        &repo,
+        booking_by(
+            pid,
+            "Ada L.",
+            "0044 (0)20-7946-0000",
+            today + Days::new(3),
+            time!(19:30),
+        )
+        .with_party_size(4),
+    );
+    let emailed = book(
+        &repo,
+        booking_by(pid, "Ada", "ADA@example.com", today, time!(19:30)),
+    );
+    let unmatched = book(
+        &repo,
+        booking_by(pid, "Walk-in", "at the bar", today, time!(19:30)),
+    );
+
+    let guest_id = ReservationRepository::find(&repo, first)
+        .unwrap()
+        .guest_id
+        .unwrap();
+    assert_eq!(
+        ReservationRepository::find(&repo, second).unwrap().guest_id,
+        Some(guest_id)
+    );
+    assert_ne!(
+        ReservationRepository::find(&repo, emailed)
+            .unwrap()
+            .guest_id,
+        Some(guest_id)
+    );
+    assert_eq!(
+        ReservationRepository::find(&repo, unmatched)
+            .unwrap()
+            .guest_id,
+        None
+    );
+    // The first booking names the guest.
+    assert_eq!(repo.find_guest(guest_id).unwrap().name, "Ada Lovelace");
+    assert_eq!(repo.guests(pid).unwrap().len(), 2);
+    // Other properties do not share guests.
+    let other = property(&repo);
+    let elsewhere = book(
+        &repo,
+        booking_by(other, "Ada", "+442079460000", today, time!(19:30)),
+    );
+    assert_ne!(
+        ReservationRepository::find(&repo, elsewhere)
+            .unwrap()
+            .guest_id,
+        Some(guest_id)
+    );
+
+    repo.set_status(first, ReservationStatus::Completed)
+        .unwrap();
+    repo.set_payment(first, json!({ "mode_of_payment": "Card" }), 1500)
+        .unwrap();
+    let ada = profile(&repo, guest_id, today).unwrap();
+    assert_eq!(ada.phones, ["+442079460000"]);
+    assert!(ada.emails.is_empty());
+    assert_eq!(
+        ada.reservations.iter().map(|r| r.id).collect::<Vec<_>>(),
+        [second, first]
+    );
+    assert_eq!(ada.visits, 1);
+    assert_eq!(ada.upcoming, 1);
+    assert_eq!(ada.no_shows, 0);
+    assert_eq!(ada.total_spend, 1500);
+
+    repo.set_status(second, ReservationStatus::NoShow).unwrap();
+    let ada = profile(&repo, guest_id, today).unwrap();
+    assert_eq!((ada.upcoming, ada.no_shows), (0, 1));
+}
+
+#[test]
+fn contacts_belong_to_one_guest_and_old_bookings_are_linked() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+
+    // Stored straight away, as before guest profiles.
+    let old = ReservationRepository::insert(
+        &repo,
+        booking_by(
+            pid,
+            "Grace",
+            "grace@example.com",
+            today - Days::new(30),
+            time!(19:30),
+        ),
+    )
+    .unwrap();
+    let newer = book(
+        &repo,
+        booking_by(pid, "Grace H.", "Grace@Example.com", today, time!(19:30)),
+    );
+    let ada = book(
+        &repo,
+        booking_by(pid, "Ada", "+442079460000", today, time!(19:30)),
+    );
+    assert_eq!(link_reservations(&repo, &repo, pid).unwrap(), 1);
+    assert_eq!(link_reservations(&repo, &repo, pid).unwrap(), 0);
+    let grace = ReservationRepository::find(&repo, newer)
+        .unwrap()
+        .guest_id
+        .unwrap();
+    assert_eq!(
+        ReservationRepository::find(&repo, old).unwrap().guest_id,
+        Some(grace)
+    );
+
+    // Ada's number cannot be added to Grace.
+    let ada = ReservationRepository::find(&repo, ada)
+        .unwrap()
+        .guest_id
+        .unwrap();
+    assert!(matches!(
+        repo.set_guest_contacts(grace, &["grace@example.com".into(), "+442079460000".into()]),
+        Err(RepositoryError::Conflict(_))
+    ));
+    assert_eq!(repo.guest_contacts(grace).unwrap(), ["grace@example.com"]);
+    repo.set_guest_contacts(grace, &["+15550100".into(), "grace@example.com".into()])
+        .unwrap();
+    assert_eq!(
+        repo.guest_by_contact(pid, "+15550100").unwrap().guest_id,
+        grace
+    );
+    assert_eq!(repo.guest_contacts(ada).unwrap(), ["+442079460000"]);
+}
+
+#[test]
+fn names_are_compared_loosely() {
+    assert_eq!(name_similarity("Rahul  KUMAR", "rahul kumar."), 1.0);
+    assert_eq!(name_similarity("Rahul K", "Rahul Kumar"), 0.9);
+    assert!(name_similarity("Jon Smith", "John Smith") >= 0.85);
+    assert!(name_similarity("Asha", "Rahul") < 0.5);
+    assert_eq!(name_similarity("", "Rahul"), 0.0);
+}
+
+#[test]
+fn duplicates_are_found_and_merged() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+    let guest_of = |rid: i32| {
+        ReservationRepository::find(&repo, rid)
+            .unwrap()
+            .guest_id
+            .unwrap()
+    };
+
+    let rahul = guest_of(book(
+        &repo,
+        booking_by(
+            pid,
+            "Rahul Kumar",
+            "+44 20 7946 0000",
+            today - Days::new(14),
+            time!(19:30),
+        ),
+    ));
+    // A trunk 0 left in after the calling code.
+    let typo_rid = book(
+        &repo,
+        booking_by(pid, "Rahul K", "+44 020 7946 0000", today, time!(19:30)),
+    );
+    repo.set_payment(typo_rid, json!({ "mode_of_payment": "Cash" }), 800)
+        .unwrap();
+    let typo = guest_of(typo_rid);
+    let dotted = guest_of(book(
+        &repo,
+        booking_by(
+            pid,
+            "Someone",
+            "r.kumar+dinner@example.com",
+            today,
+            time!(19:30),
+        ),
+    ));
+    let plain = guest_of(book(
+        &repo,
+        booking_by(pid, "R.", "rkumar@example.com", today, time!(19:30)),
+    ));
+    book(
+        &repo,
+        booking_by(pid, "Grace", "+15550199", today, time!(19:30)),
+    );
+
+    let pairs = find_duplicates(&repo, pid).unwrap();
+    let ids: Vec<(i32, i32)> = pairs
+        .iter()
+        .map(|pair| (pair.guest.guest_id, pair.duplicate.guest_id))
+        .collect();
+    assert_eq!(ids, [(rahul, typo), (dotted, plain)]);
+    assert_eq!(pairs[0].reasons.len(), 2);
+    assert!(pairs[0].reasons[0].contains("+4402079460000"));
+
+    let mut kept = repo.find_guest(rahul).unwrap();
+    kept.tags = "VIP".into();
+    repo.update_guest(&kept).unwrap();
+    let mut merged = repo.find_guest(typo).unwrap();
+    merged.tags = "vip, window seat".into();
+    merged.notes = "Prefers the terrace".into();
+    repo.update_guest(&merged).unwrap();
+
+    let recorded = merge(&repo, rahul, typo, "admin").unwrap();
+    assert_eq!(recorded.merged_name, "Rahul K");
+    assert_eq!(recorded.merged_contacts, "+4402079460000");
+    assert_eq!(recorded.reservations, 1);
+    assert_eq!(recorded.merged_by, "admin");
+    assert!(repo.find_guest(typo).is_err());
+    assert_eq!(guest_of(typo_rid), rahul);
+
+    let rahul = profile(&repo, rahul, today).unwrap();
+    assert_eq!(rahul.guest.tags, "VIP, window seat");
+    assert_eq!(rahul.guest.notes, "Prefers the terrace");
+    assert_eq!(rahul.phones, ["+4402079460000", "+442079460000"]);
+    assert_eq!(rahul.reservations.len(), 2);
+    assert_eq!(rahul.total_spend, 800);
+    assert_eq!(rahul.merges.len(), 1);
+    assert_eq!(
+        repo.guest_by_contact(pid, "+4402079460000")
+            .unwrap()
+            .guest_id,
+        rahul.guest.guest_id
+    );
+    assert_eq!(find_duplicates(&repo, pid).unwrap().len(), 1);
+    assert!(merge(&repo, dotted, dotted, "admin").is_err());
+}
@@ -0,0 +1,238 @@
+//! Schedules, claims and finishes background jobs against the in-memory
+//! repository.
+mod common;
+
+use chrono::{Duration, Local, NaiveDateTime, Timelike, Utc};
+use common::{booking, booking_by, property, state, store, time_of};
+use resvm::emails::MemoryMailer;
+use resvm::guests::NoShowPolicy;
+use resvm::jobs::{
+    finish, mark_no_shows, purge_expired, run_due, schedule_missing_reminders, schedule_periodic,
+    schedule_reminder, MAX_ATTEMPTS,
+};
+use resvm::models::{HistoryEntry, JobKind, JobStatus, NotificationKind, ReservationStatus};
+use resvm::repository::{
+    EmailRepository, InMemoryRepository, JobRepository, ReservationRepository, SmsRepository,
+};
+use resvm::sms::MemoryChannel;
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn book(repo: &InMemoryRepository, pid: Uuid, at: NaiveDateTime) -> i32 {
+    store(repo, booking(pid, "T1", at.date(), time_of(at)))
+}
+
+#[test]
+fn reminders_follow_their_booking() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let in_two_days = Local::now().naive_local() + Duration::days(2);
+    let rid = book(&repo, pid, in_two_days);
+    let resv = ReservationRepository::find(&repo, rid).unwrap();
+
+    let job_id = schedule_reminder(&repo, &resv).unwrap().unwrap();
+    let job = repo.find_job(job_id).unwrap();
+    assert_eq!(job.kind, JobKind::Reminder);
+    assert_eq!(job.reservation_id, Some(rid));
+    assert_eq!(job.status, JobStatus::Pending);
+    let first_run = job.run_at;
+
+    // Moving the booking moves the same job.
+    let moved = repo
+        .reschedule(
+            rid,
+            resv.reservation_date + Duration::days(1),
+            resv.reservation_time,
+            resv.seating.clone(),
+            resv.party_size,
+        )
+        .unwrap();
+    assert_eq!(schedule_reminder(&repo, &moved).unwrap(), Some(job_id));
+    assert_eq!(
+        repo.find_job(job_id).unwrap().run_at,
+        first_run + Duration::days(1)
+    );
+    assert_eq!(repo.jobs(pid, 10).unwrap().len(), 1);
+
+    // Nothing is scheduled for bookings that are not `Booked`.
+    let cancelled = repo.set_status(rid, ReservationStatus::Cancelled).unwrap();
+    assert_eq!(schedule_reminder(&repo, &cancelled).unwrap(), None);
+}
+
+#[test]
+fn bookings_without_a_reminder_job_get_one_on_start() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let now = Local::now().naive_local();
+    let reminded = book(&repo, pid, now + Duration::days(2));
+    schedule_reminder(
+        &repo,
+        &ReservationRepository::find(&repo, reminded).unwrap(),
+    )
+    .unwrap();
+    let missing = book(&repo, pid, now + Duration::days(3));
+    book(&repo, pid, now - Duration::hours(3));
+    let cancelled = book(&repo, pid, now + Duration::days(4));
+    repo.set_status(cancelled, ReservationStatus::Cancelled)
+        .unwrap();
+
+    assert_eq!(
+        schedule_missing_reminders(&repo, &repo, &repo, now).unwrap(),
+        1
+    );
+    let jobs = repo.jobs(pid, 10).unwrap();
+    assert_eq!(jobs.len(), 2);
+    assert!(jobs.iter().any(|job| job.reservation_id == Some(missing)));
+    assert_eq!(
+        schedule_missing_reminders(&repo, &repo, &repo, now).unwrap(),
+        0
+    );
+}
+
+#[test]
+fn due_reminder_jobs_email_and_text_the_guest() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let mailer = Arc::new(MemoryMailer::new());
+    let channel = Arc::new(MemoryChannel::new());
+    let state = state(repo.clone())
+        .with_mail(mailer.clone())
+        .with_channel(channel.clone());
+    let pid = property(&repo);
+    let now = Local::now().naive_local();
+    let soon = now + Duration::hours(20);
+    let emailed = book(&repo, pid, soon);
+    let texted = store(
+        &repo,
+        booking_by(pid, "Ada", "+44 20 7946 0000", soon.date(), time_of(soon)),
+    );
+    let cancelled = book(&repo, pid, soon);
+    let later = book(&repo, pid, now + Duration::days(3));
+    for rid in [emailed, texted, cancelled, later] {
+        schedule_reminder(
+            repo.as_ref(),
+            &ReservationRepository::find(repo.as_ref(), rid).unwrap(),
+        )
+        .unwrap();
+    }
+    repo.set_status(cancelled, ReservationStatus::Cancelled)
+        .unwrap();
+
+    // Reminders within a day are due, the cancelled booking gets none.
+    assert_eq!(run_due(&state).unwrap(), 3);
+    let emails = repo.emails(pid, 10).unwrap();
+    assert_eq!(emails.len(), 1);
+    assert_eq!(
+        (emails[0].reservation_id, emails[0].kind),
+        (Some(emailed), NotificationKind::Reminder)
+    );
+    let messages = repo.messages(pid, 10).unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(
+        (messages[0].reservation_id, messages[0].kind),
+        (Some(texted), NotificationKind::Reminder)
+    );
+    for job in repo.jobs(pid, 10).unwrap() {
+        let expected = if job.reservation_id == Some(later) {
+            JobStatus::Pending
+        } else {
+            JobStatus::Done
+        };
+        assert_eq!(job.status, expected);
+    }
+
+    // Each reminder is queued once.
+    assert_eq!(run_due(&state).unwrap(), 0);
+    assert_eq!(repo.emails(pid, 10).unwrap().len(), 1);
+    assert_eq!(repo.messages(pid, 10).unwrap().len(), 1);
+}
+
+#[test]
+fn jobs_are_claimed_once_retried_and_rescheduled() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let now = Utc::now().naive_utc();
+    let lease = now + Duration::minutes(5);
+    schedule_periodic(&repo, now).unwrap();
+    let rid = book(&repo, pid, Local::now().naive_local() + Duration::hours(3));
+    let reminder = schedule_reminder(&repo, &ReservationRepository::find(&repo, rid).unwrap())
+        .unwrap()
+        .unwrap();
+
+    // The periodic jobs and the overdue reminder are due, and claimed once.
+    let claimed = repo.claim_jobs(now, lease, 10).unwrap();
+    assert_eq!(claimed.len(), 3);
+    assert!(claimed
+        .iter()
+        .all(|job| job.status == JobStatus::Running && job.attempts == 1));
+    assert!(repo.claim_jobs(now, lease, 10).unwrap().is_empty());
+    // Other properties see the periodic jobs but not the reminder.
+    assert_eq!(repo.jobs(Uuid::new_v4(), 10).unwrap().len(), 2);
+
+    // A job whose worker died is claimed again once the lease ran out.
+    let after_lease = lease + Duration::seconds(1);
+    let reclaimed = repo.claim_jobs(after_lease, after_lease, 10).unwrap();
+    assert_eq!(reclaimed.len(), 3);
+
+    // A periodic job is due again one period later.
+    let no_shows = reclaimed
+        .iter()
+        .find(|job| job.kind == JobKind::MarkNoShows)
+        .unwrap()
+        .clone();
+    let done = finish(no_shows, Ok(()), now);
+    assert_eq!(done.status, JobStatus::Pending);
+    assert_eq!(done.attempts, 0);
+    assert_eq!(done.run_at, now + Duration::minutes(5));
+    assert_eq!(done.finished_at, Some(now));
+
+    // A one-off job is retried with backoff, then given up.
+    let mut job = repo.find_job(reminder).unwrap();
+    job = finish(job, Err("smtp down".into()), now);
+    assert_eq!(job.status, JobStatus::Pending);
+    assert!(job.run_at > now);
+    assert_eq!(job.last_error.as_deref(), Some("smtp down"));
+    job.attempts = MAX_ATTEMPTS;
+    let failed = finish(job, Err("smtp down".into()), now);
+    assert_eq!(failed.status, JobStatus::Failed);
+    assert_eq!(failed.finished_at, Some(now));
+    repo.update_job(&failed).unwrap();
+
+    // Finished jobs are purged after retention, periodic ones stay.
+    repo.update_job(&done).unwrap();
+    assert_eq!(
+        purge_expired(&repo, &repo, &repo, now + Duration::seconds(1)).unwrap(),
+        1
+    );
+    assert!(repo.find_job(reminder).is_err());
+    assert_eq!(repo.jobs(pid, 10).unwrap().len(), 2);
+}
+
+#[test]
+fn bookings_past_the_grace_period_become_no_shows() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let now = Local::now().naive_local().with_second(0).unwrap();
+    let late = book(&repo, pid, now - Duration::hours(2));
+    let just_started = book(&repo, pid, now - Duration::minutes(10));
+    let seated = book(&repo, pid, now - Duration::hours(1));
+    repo.set_status(seated, ReservationStatus::Seated).unwrap();
+
+    let marked = mark_no_shows(&repo, &repo, &NoShowPolicy::default(), now).unwrap();
+    assert_eq!(marked.len(), 1);
+    assert_eq!(marked[0].id, late);
+    assert_eq!(
+        ReservationRepository::find(&repo, late).unwrap().status,
+        ReservationStatus::NoShow
+    );
+    assert_eq!(
+        ReservationRepository::find(&repo, just_started)
+            .unwrap()
+            .status,
+        ReservationStatus::Booked
+    );
+    let history = repo.history(late).unwrap();
+    assert_eq!(history[0].changed_by, HistoryEntry::SYSTEM);
+    assert!(mark_no_shows(&repo, &repo, &NoShowPolicy::default(), now)
+        .unwrap()
+        .is_empty());
+}
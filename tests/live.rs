@@ -0,0 +1,43 @@
+//! The live feed of reservation changes and the tokens guarding it.
+use chrono::Utc;
+use resvm::live::{check_feed_token, feed_token, ReservationFeed, FEED_TOKEN_HOURS};
+use resvm::models::WebhookEvent;
+use tokio_stream::StreamExt;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn subscribers_only_see_their_property() {
+    let feed = ReservationFeed::new();
+    let (pid, other) = (Uuid::new_v4(), Uuid::new_v4());
+    let mut changes = Box::pin(feed.subscribe(pid));
+
+    feed.publish(other, 1, WebhookEvent::ReservationCreated);
+    feed.publish(pid, 2, WebhookEvent::ReservationSeated);
+    let change = changes.next().await.unwrap();
+    assert_eq!(change.property_id, pid);
+    assert_eq!(change.reservation_id, 2);
+    assert_eq!(change.event, "reservation.seated");
+}
+
+#[test]
+fn feed_tokens_are_bound_to_a_property_and_expire() {
+    let (pid, other) = (Uuid::new_v4(), Uuid::new_v4());
+    let now = Utc::now().timestamp();
+    let token = feed_token(pid, now + FEED_TOKEN_HOURS * 60 * 60);
+
+    assert!(check_feed_token(pid, &token, now));
+    assert!(!check_feed_token(other, &token, now));
+    assert!(!check_feed_token(
+        pid,
+        &token,
+        now + FEED_TOKEN_HOURS * 60 * 60
+    ));
+
+    // Moving the expiry breaks the signature.
+    let (_, signature) = token.split_once('.').unwrap();
+    let extended = format!("{}.{signature}", now + 100 * 60 * 60);
+    assert!(!check_feed_token(pid, &extended, now));
+    for wrong in ["", "garbage", "1.2.3", &format!("{}.", now + 60)] {
+        assert!(!check_feed_token(pid, wrong, now));
+    }
+}
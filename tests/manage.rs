@@ -0,0 +1,159 @@
+//! Guest self-service through the manage-booking link, against the
+//! in-memory repository.
+mod common;
+
+use chrono::{Days, Duration, Local};
+use common::{book, booking, property_with_tables};
+use resvm::errors::RepositoryError;
+use resvm::manage::{can_change, cancel, guest_history, modify, CHANGE_CUTOFF_HOURS};
+use resvm::models::{HistoryEntry, NewHistoryEntry, ReservationStatus};
+use resvm::repository::{InMemoryRepository, ReservationRepository};
+use time::macros::time;
+
+fn token_of(repo: &InMemoryRepository, rid: i32) -> String {
+    repo.find(rid).unwrap().confirmation_token.unwrap()
+}
+
+#[test]
+fn guests_move_their_booking_to_a_free_slot() {
+    let repo = InMemoryRepository::new();
+    let pid = property_with_tables(&repo, &[("T1", 2, None), ("T2", 4, None)]);
+    let date = Local::now().date_naive() + Days::new(3);
+    let now = Local::now().naive_local();
+    let rid = book(&repo, booking(pid, "", date, time!(19:00)));
+    book(&repo, booking(pid, "T2", date, time!(17:00)));
+    let token = token_of(&repo, rid);
+
+    let moved = modify(&repo, &repo, &token, date, time!(20:00), 4, now).unwrap();
+    assert_eq!(
+        (moved.reservation_time, moved.party_size),
+        (time!(20:00), 4)
+    );
+    assert_eq!(moved.seating, "T2");
+    let history = repo.history(rid).unwrap();
+    assert_eq!(history.last().unwrap().changed_by, HistoryEntry::GUEST);
+    assert!(history.last().unwrap().summary.starts_with("moved from"));
+
+    for (time, party_size) in [(time!(20:00), 4), (time!(20:10), 2), (time!(18:00), 4)] {
+        assert!(modify(&repo, &repo, &token, date, time, party_size, now).is_err());
+    }
+    assert_eq!(
+        modify(&repo, &repo, "unknown", date, time!(19:00), 2, now).unwrap_err(),
+        RepositoryError::NotFound
+    );
+}
+
+#[test]
+fn changes_close_before_the_reservation() {
+    let repo = InMemoryRepository::new();
+    let pid = property_with_tables(&repo, &[("T1", 2, None)]);
+    let date = Local::now().date_naive() + Days::new(3);
+    let rid = book(&repo, booking(pid, "", date, time!(19:00)));
+    let resv = repo.find(rid).unwrap();
+    let token = token_of(&repo, rid);
+    let starts_at = resv.starts_at();
+    let cutoff = starts_at - Duration::hours(CHANGE_CUTOFF_HOURS);
+
+    assert!(can_change(&resv, cutoff));
+    assert!(!can_change(&resv, cutoff + Duration::minutes(1)));
+    assert!(matches!(
+        modify(
+            &repo,
+            &repo,
+            &token,
+            date,
+            time!(17:00),
+            2,
+            cutoff + Duration::minutes(1)
+        ),
+        Err(RepositoryError::Conflict(_))
+    ));
+    assert!(matches!(
+        cancel(&repo, &token, cutoff + Duration::minutes(1)),
+        Err(RepositoryError::Conflict(_))
+    ));
+
+    // The new slot has to be outside the window as well.
+    let early = starts_at - Duration::minutes(150);
+    assert!(matches!(
+        modify(&repo, &repo, &token, date, time!(17:00), 2, early),
+        Err(RepositoryError::Conflict(_))
+    ));
+    let moved = modify(
+        &repo,
+        &repo,
+        &token,
+        date,
+        time!(17:00),
+        2,
+        starts_at - Duration::hours(5),
+    );
+    assert_eq!(moved.unwrap().reservation_time, time!(17:00));
+
+    let cancelled = cancel(&repo, &token, cutoff - Duration::hours(4)).unwrap();
+    assert_eq!(cancelled.status, ReservationStatus::Cancelled);
+    assert_eq!(
+        repo.history(rid).unwrap().last().unwrap().summary,
+        "cancelled"
+    );
+    assert!(!can_change(&cancelled, cutoff - Duration::hours(4)));
+    assert!(matches!(
+        cancel(&repo, &token, cutoff - Duration::hours(4)),
+        Err(RepositoryError::Conflict(_))
+    ));
+}
+
+#[test]
+fn the_token_stays_with_the_guest() {
+    let repo = InMemoryRepository::new();
+    let pid = property_with_tables(&repo, &[("T1", 2, None)]);
+    let date = Local::now().date_naive() + Days::new(3);
+    let resv = repo
+        .find(book(&repo, booking(pid, "", date, time!(19:00))))
+        .unwrap();
+    let token = resv.confirmation_token.clone().unwrap();
+    let code = resv.confirmation_code.clone().unwrap();
+
+    assert_eq!(code.len(), 8);
+    assert!(!token.to_uppercase().starts_with(&code));
+    let json = serde_json::to_value(&resv).unwrap();
+    assert_eq!(json["confirmation_code"], code.as_str());
+    assert!(json.get("confirmation_token").is_none());
+    assert!(!json.to_string().contains(&token));
+}
+
+#[test]
+fn guests_only_see_their_own_changes() {
+    let repo = InMemoryRepository::new();
+    let pid = property_with_tables(&repo, &[("T1", 2, None), ("T2", 4, None)]);
+    let date = Local::now().date_naive() + Days::new(3);
+    let rid = book(&repo, booking(pid, "", date, time!(19:00)));
+    let token = token_of(&repo, rid);
+    for (changed_by, summary) in [
+        (HistoryEntry::STAFF, "moved to T2 by the table plan"),
+        (HistoryEntry::SYSTEM, "deposit requested after 2 no-shows"),
+    ] {
+        repo.insert_history(NewHistoryEntry {
+            reservation_id: rid,
+            changed_by: changed_by.into(),
+            summary: summary.into(),
+        })
+        .unwrap();
+    }
+    modify(
+        &repo,
+        &repo,
+        &token,
+        date,
+        time!(20:00),
+        2,
+        Local::now().naive_local(),
+    )
+    .unwrap();
+
+    assert_eq!(repo.history(rid).unwrap().len(), 3);
+    let history = guest_history(&repo, rid).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].changed_by, HistoryEntry::GUEST);
+    assert!(history[0].summary.starts_with("moved from"));
+}
@@ -0,0 +1,175 @@
+//! No-shows, guest reliability and the deposits asked of repeat no-shows
+//! against the in-memory repository.
+mod common;
+
+use chrono::{Days, Duration, Local, NaiveDate, Timelike, Utc};
+use common::{book_under, booking_by, property, store, time_of, Stuck};
+use resvm::errors::RepositoryError;
+use resvm::guests::{check_deposit, profile, reliability, NoShowPolicy};
+use resvm::jobs::mark_no_shows;
+use resvm::models::{HistoryEntry, NewResv, ReservationStatus};
+use resvm::repository::{InMemoryRepository, ReservationRepository};
+use serde_json::json;
+use time::macros::time;
+use time::Time;
+use uuid::Uuid;
+
+/// Every test of this file runs with the same policy.
+const POLICY: NoShowPolicy = NoShowPolicy {
+    grace_minutes: 60,
+    repeat_after: 2,
+    deposit: Some(500),
+};
+
+fn booking(pid: Uuid, date: NaiveDate, at: Time) -> NewResv {
+    booking_by(pid, "Ada", "ada@example.com", date, at)
+}
+
+/// Books Ada `times` times in the past and marks each a no-show.
+fn no_shows(repo: &InMemoryRepository, pid: Uuid, times: u64) {
+    let today = Local::now().date_naive();
+    for day in 1..=times {
+        let rid = book_under(
+            repo,
+            &POLICY,
+            booking(pid, today - Days::new(day), time!(19:00)),
+        );
+        repo.set_status(rid, ReservationStatus::NoShow).unwrap();
+    }
+}
+
+#[test]
+fn no_shows_are_marked_after_the_grace_period_with_a_timestamp() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let now = Local::now().naive_local().with_second(0).unwrap();
+    let at = |ago: Duration| {
+        let start = now - ago;
+        store(&repo, booking(pid, start.date(), time_of(start)))
+    };
+    let late = at(Duration::minutes(90));
+    let within_grace = at(Duration::minutes(45));
+
+    let before = Utc::now().naive_utc();
+    let marked = mark_no_shows(&repo, &repo, &POLICY, now).unwrap();
+    assert_eq!(marked.len(), 1);
+    assert_eq!(marked[0].id, late);
+    let no_show_at = marked[0].no_show_at.unwrap();
+    assert!(before <= no_show_at && no_show_at <= Utc::now().naive_utc());
+    assert_eq!(
+        repo.history(late).unwrap()[0].summary,
+        "marked as no-show 60 minutes after the booking"
+    );
+    let waiting = ReservationRepository::find(&repo, within_grace).unwrap();
+    assert_eq!(waiting.status, ReservationStatus::Booked);
+    assert_eq!(waiting.no_show_at, None);
+}
+
+#[test]
+fn reservations_that_cannot_be_marked_do_not_stop_the_others() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let now = Local::now().naive_local().with_second(0).unwrap();
+    let at = |ago: Duration| {
+        let start = now - ago;
+        store(&repo, booking(pid, start.date(), time_of(start)))
+    };
+    let stuck = at(Duration::minutes(120));
+    let late = at(Duration::minutes(90));
+    let repo = Stuck { repo, id: stuck };
+
+    let marked = mark_no_shows(&repo.repo, &repo, &POLICY, now).unwrap();
+    assert_eq!(marked.len(), 1);
+    assert_eq!(marked[0].id, late);
+    assert_eq!(
+        ReservationRepository::find(&repo, stuck).unwrap().status,
+        ReservationStatus::Booked
+    );
+    assert!(repo.history(stuck).unwrap().is_empty());
+
+    // The next run marks it once it can.
+    let repo = Stuck { id: 0, ..repo };
+    let marked = mark_no_shows(&repo.repo, &repo, &POLICY, now).unwrap();
+    assert_eq!(marked.len(), 1);
+    assert_eq!(marked[0].id, stuck);
+}
+
+#[test]
+fn profiles_count_no_shows_and_reliability() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    no_shows(&repo, pid, 1);
+    let today = Local::now().date_naive();
+    for day in 2..5 {
+        let rid = book_under(
+            &repo,
+            &POLICY,
+            booking(pid, today - Days::new(day), time!(19:00)),
+        );
+        repo.set_status(rid, ReservationStatus::Completed).unwrap();
+    }
+
+    let resv = repo.for_property(pid, 1).unwrap().remove(0);
+    let ada = profile(&repo, resv.guest_id.unwrap(), today).unwrap();
+    assert_eq!((ada.visits, ada.no_shows), (3, 1));
+    assert_eq!(ada.reliability, Some(75));
+    assert_eq!(reliability(0, 0), None);
+    assert_eq!(reliability(0, 2), Some(0));
+}
+
+#[test]
+fn repeat_no_shows_are_warned_about_and_held_for_a_deposit() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let tomorrow = Local::now().date_naive() + Days::new(1);
+
+    // A single no-show is not a repeat yet.
+    no_shows(&repo, pid, 1);
+    let rid = book_under(&repo, &POLICY, booking(pid, tomorrow, time!(19:00)));
+    assert_eq!(
+        ReservationRepository::find(&repo, rid).unwrap().status,
+        ReservationStatus::Booked
+    );
+    assert!(repo.history(rid).unwrap().is_empty());
+
+    no_shows(&repo, pid, 1);
+    let rid = book_under(&repo, &POLICY, booking(pid, tomorrow, time!(20:00)));
+    let held = ReservationRepository::find(&repo, rid).unwrap();
+    assert_eq!(held.status, ReservationStatus::Requested);
+    assert!(!held.confirmed);
+    let history = repo.history(rid).unwrap();
+    assert_eq!(history[0].changed_by, HistoryEntry::SYSTEM);
+    assert_eq!(
+        history[0].summary,
+        "guest did not turn up 2 times, held as a request until an advance of 500 is paid"
+    );
+    assert!(matches!(
+        check_deposit(&repo, &POLICY, &held),
+        Err(RepositoryError::Conflict(_))
+    ));
+
+    // Without a deposit in the policy the booking stands, with a warning.
+    let lenient = NoShowPolicy {
+        deposit: None,
+        ..POLICY
+    };
+    let rid = book_under(&repo, &lenient, booking(pid, tomorrow, time!(17:00)));
+    assert_eq!(
+        ReservationRepository::find(&repo, rid).unwrap().status,
+        ReservationStatus::Booked
+    );
+
+    // Paying the deposit when booking keeps the booking, with a warning.
+    let mut paid = booking(pid, tomorrow, time!(21:00));
+    paid.advance = true;
+    paid.advance_amount = Some(500);
+    paid.advance_method = json!({ "mode_of_payment": "Card" });
+    let rid = book_under(&repo, &POLICY, paid);
+    let booked = ReservationRepository::find(&repo, rid).unwrap();
+    assert_eq!(booked.status, ReservationStatus::Booked);
+    assert_eq!(
+        repo.history(rid).unwrap()[0].summary,
+        "guest did not turn up 2 times"
+    );
+    assert!(check_deposit(&repo, &POLICY, &booked).is_ok());
+}
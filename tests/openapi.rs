@@ -0,0 +1,25 @@
+//! The committed `openapi.json` is the contract shared with integration
+//! partners. Regenerate it after changing the API with
+//! `UPDATE_OPENAPI=1 cargo test --test openapi`.
+use std::{env, fs};
+
+const SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+#[test]
+fn openapi_matches_committed_spec() {
+    let spec = resvm::api::openapi()
+        .to_pretty_json()
+        .expect("failed to render the OpenAPI document");
+
+    if env::var("UPDATE_OPENAPI").is_ok() {
+        fs::write(SNAPSHOT, format!("{spec}\n")).expect("failed to write openapi.json");
+        return;
+    }
+
+    let committed = fs::read_to_string(SNAPSHOT).expect("openapi.json is missing");
+    assert!(
+        committed.trim_end() == spec,
+        "the API handlers no longer match openapi.json, \
+         run `UPDATE_OPENAPI=1 cargo test --test openapi` and review the diff"
+    );
+}
@@ -0,0 +1,104 @@
+//! The booking rules of `create_reservation` against the in-memory
+//! repository.
+mod common;
+
+use chrono::{Days, Local};
+use common::{book, booking, property, property_with_tables};
+use resvm::crud_properties::create_reservation;
+use resvm::errors::RepositoryError;
+use resvm::guests::NoShowPolicy;
+use resvm::models::ReservationStatus;
+use resvm::repository::{InMemoryRepository, PropertyRepository, ReservationRepository};
+use serde_json::json;
+use time::macros::time;
+
+#[test]
+fn a_booking_needs_a_name_a_contact_and_guests() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let date = Local::now().date_naive() + Days::new(1);
+
+    let rid = book(&repo, booking(pid, "T1", date, time!(19:00)));
+    let resv = ReservationRepository::find(&repo, rid).unwrap();
+    assert_eq!(resv.status, ReservationStatus::Booked);
+    assert_eq!(resv.party_size, 2);
+
+    let mut nameless = booking(pid, "T1", date, time!(19:00));
+    nameless.name = "  ".into();
+    let mut unreachable = booking(pid, "T1", date, time!(19:00));
+    unreachable.contact = String::new();
+    let empty = booking(pid, "T1", date, time!(19:00)).with_party_size(0);
+    for wrong in [nameless, unreachable, empty] {
+        assert!(matches!(
+            create_reservation(&repo, &repo, &repo, &NoShowPolicy::default(), wrong),
+            Err(RepositoryError::Invalid(_))
+        ));
+    }
+    assert_eq!(repo.count_for_property(pid).unwrap(), 1);
+}
+
+#[test]
+fn an_advance_needs_an_amount_and_a_mode_of_payment() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let date = Local::now().date_naive() + Days::new(1);
+    let advance = |amount: Option<i32>, mode: &str| {
+        let mut new_resv = booking(pid, "T1", date, time!(19:00));
+        new_resv.advance = true;
+        new_resv.advance_amount = amount;
+        new_resv.advance_method = json!({ "mode_of_payment": mode });
+        new_resv
+    };
+
+    for wrong in [
+        advance(None, "Card"),
+        advance(Some(0), "Cash"),
+        advance(Some(500), "NotPaid"),
+    ] {
+        assert!(matches!(
+            create_reservation(&repo, &repo, &repo, &NoShowPolicy::default(), wrong),
+            Err(RepositoryError::Invalid(_))
+        ));
+    }
+    let rid = book(&repo, advance(Some(500), "Card"));
+    let resv = ReservationRepository::find(&repo, rid).unwrap();
+    assert_eq!(resv.advance_amount, Some(500));
+}
+
+#[test]
+fn a_requested_seating_is_held_for_a_turn() {
+    let repo = InMemoryRepository::new();
+    let pid = property_with_tables(&repo, &[("T1", 2, None), ("T2", 4, None)]);
+    let date = Local::now().date_naive() + Days::new(1);
+    let specific = |seating: &str, date, at| {
+        let mut new_resv = booking(pid, seating, date, at);
+        new_resv.specific_seating_requested = true;
+        new_resv
+    };
+    let clashes = |new_resv| {
+        matches!(
+            create_reservation(&repo, &repo, &repo, &NoShowPolicy::default(), new_resv),
+            Err(RepositoryError::Conflict(_))
+        )
+    };
+
+    let first = book(&repo, specific("T1", date, time!(19:00)));
+    for at in [time!(19:00), time!(17:30), time!(20:30)] {
+        assert!(clashes(specific(" t1 ", date, at)));
+    }
+    // Other tables, other days and times a turn away still go.
+    book(&repo, specific("T2", date, time!(19:00)));
+    book(&repo, specific("T1", date + Days::new(1), time!(19:00)));
+    book(&repo, specific("T1", date, time!(21:00)));
+
+    // A cancelled booking frees its seating.
+    repo.set_status(first, ReservationStatus::Cancelled)
+        .unwrap();
+    book(&repo, specific("T1", date, time!(18:00)));
+
+    // A group booking holds every table assigned to it.
+    let group = book(&repo, booking(pid, "T1", date + Days::new(2), time!(19:00)));
+    let t2 = repo.tables(pid).unwrap()[1].table_id;
+    repo.set_table_assignments(group, &[t2]).unwrap();
+    assert!(clashes(specific("T2", date + Days::new(2), time!(20:00))));
+}
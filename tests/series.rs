@@ -0,0 +1,368 @@
+//! Recurring series of standing reservations against the in-memory
+//! repository.
+mod common;
+
+use chrono::{Days, Local, NaiveDate};
+use common::{book, booking, property_with_tables, Stuck};
+use resvm::booking::{add_closure, availability};
+use resvm::errors::RepositoryError;
+use resvm::guests::NoShowPolicy;
+use resvm::models::{
+    HistoryEntry, NewClosure, NewHistoryEntry, NewReservationSeries, Recurrence, ReservationStatus,
+    SeriesSchedule, SkippedOccurrence,
+};
+use resvm::repository::{InMemoryRepository, RepoResult, ReservationRepository, SeriesRepository};
+use resvm::series::{
+    cancel_occurrence, cancel_series, create_series, edit_occurrence, edit_series,
+    occurrence_dates, MAX_OCCURRENCES,
+};
+use time::macros::time;
+use uuid::Uuid;
+
+fn property(repo: &InMemoryRepository) -> Uuid {
+    property_with_tables(repo, &[("T1", 4, None), ("T2", 6, None)])
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn weeks(start: NaiveDate, n: u64) -> NaiveDate {
+    start + Days::new(7 * n)
+}
+
+fn lunches(pid: Uuid, starts_on: NaiveDate, occurrences: i32) -> NewReservationSeries {
+    NewReservationSeries {
+        property_id: pid,
+        name: " Acme Ltd ".into(),
+        contact: "office@acme.test".into(),
+        seating: "t1".into(),
+        party_size: 4,
+        reservation_time: time!(12:30),
+        recurrence: Recurrence::Weekly,
+        starts_on,
+        ends_on: None,
+        occurrences: Some(occurrences),
+    }
+}
+
+/// Stores `new_series` as of `today` and books its occurrences.
+fn book_series(
+    repo: &InMemoryRepository,
+    new_series: NewReservationSeries,
+    today: NaiveDate,
+) -> RepoResult<SeriesSchedule> {
+    create_series(
+        repo,
+        repo,
+        repo,
+        &NoShowPolicy::default(),
+        repo,
+        new_series,
+        today,
+    )
+}
+
+#[test]
+fn occurrences_follow_the_recurrence_until_the_end_or_count() {
+    let start = date(2025, 1, 31);
+    assert_eq!(
+        occurrence_dates(Recurrence::Weekly, start, None, Some(3)).unwrap(),
+        [date(2025, 1, 31), date(2025, 2, 7), date(2025, 2, 14)]
+    );
+    assert_eq!(
+        occurrence_dates(Recurrence::Biweekly, start, Some(date(2025, 3, 13)), None).unwrap(),
+        [date(2025, 1, 31), date(2025, 2, 14), date(2025, 2, 28)]
+    );
+    // Months without the day take their last one, the end date and the
+    // count both stop a series.
+    assert_eq!(
+        occurrence_dates(Recurrence::Monthly, start, Some(date(2025, 6, 1)), Some(4)).unwrap(),
+        [
+            date(2025, 1, 31),
+            date(2025, 2, 28),
+            date(2025, 3, 31),
+            date(2025, 4, 30)
+        ]
+    );
+
+    for (ends_on, occurrences) in [
+        (None, None),
+        (Some(date(2025, 1, 30)), None),
+        (None, Some(0)),
+        (None, Some(MAX_OCCURRENCES as i32 + 1)),
+    ] {
+        assert!(matches!(
+            occurrence_dates(Recurrence::Weekly, start, ends_on, occurrences),
+            Err(RepositoryError::Invalid(_))
+        ));
+    }
+}
+
+#[test]
+fn occurrences_skip_closures_and_taken_tables() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+    let start = today + Days::new(7);
+    add_closure(
+        &repo,
+        NewClosure {
+            property_id: pid,
+            first_day: weeks(start, 1),
+            last_day: weeks(start, 1),
+            reason: " Refurbishment ".into(),
+        },
+    )
+    .unwrap();
+    book(&repo, booking(pid, "T1", weeks(start, 2), time!(13:00)));
+
+    let schedule = book_series(&repo, lunches(pid, start, 4), today).unwrap();
+    assert_eq!(schedule.series.name, "Acme Ltd");
+    assert_eq!(schedule.series.seating, "T1");
+    assert_eq!(
+        schedule
+            .booked
+            .iter()
+            .map(|resv| resv.reservation_date)
+            .collect::<Vec<_>>(),
+        [start, weeks(start, 3)]
+    );
+    assert!(schedule.booked.iter().all(|resv| resv.seating == "T1"
+        && resv.series_id == Some(schedule.series.series_id)
+        && resv.status == ReservationStatus::Booked));
+    assert_eq!(
+        schedule.skipped,
+        [
+            SkippedOccurrence {
+                date: weeks(start, 1),
+                reason: "closed: Refurbishment".into()
+            },
+            SkippedOccurrence {
+                date: weeks(start, 2),
+                reason: "T1 is taken by Grace at 13:00".into()
+            },
+        ]
+    );
+    assert_eq!(
+        repo.occurrences(schedule.series.series_id).unwrap().len(),
+        2
+    );
+
+    // Closed days offer no slots online either.
+    assert!(availability(&repo, &repo, pid, weeks(start, 1), 2)
+        .unwrap()
+        .is_empty());
+    assert!(!availability(&repo, &repo, pid, weeks(start, 2), 2)
+        .unwrap()
+        .is_empty());
+
+    let mut too_big = lunches(pid, start, 2);
+    too_big.party_size = 5;
+    assert!(matches!(
+        book_series(&repo, too_big, today),
+        Err(RepositoryError::Invalid(_))
+    ));
+    assert!(matches!(
+        book_series(&repo, lunches(pid, today - Days::new(1), 2), today),
+        Err(RepositoryError::Invalid(_))
+    ));
+}
+
+#[test]
+fn one_occurrence_or_the_whole_series_can_change() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+    let start = today + Days::new(7);
+    let schedule = book_series(&repo, lunches(pid, start, 3), today).unwrap();
+    let sid = schedule.series.series_id;
+    let (first, second, third) = (
+        schedule.booked[0].id,
+        schedule.booked[1].id,
+        schedule.booked[2].id,
+    );
+
+    let moved = edit_occurrence(&repo, &repo, first, time!(13:00), "T2", 6).unwrap();
+    assert_eq!(
+        (
+            moved.reservation_time,
+            moved.seating.as_str(),
+            moved.party_size
+        ),
+        (time!(13:00), "T2", 6)
+    );
+    assert_eq!(
+        repo.history(first).unwrap()[0].summary,
+        "changed from 12:30 on T1 for 4 to 13:00 on T2 for 6, this occurrence only"
+    );
+    let untouched = ReservationRepository::find(&repo, second).unwrap();
+    assert_eq!(untouched.reservation_time, time!(12:30));
+
+    // T2 is taken on the third lunch, that one stays as it was.
+    book(&repo, booking(pid, "T2", weeks(start, 2), time!(12:00)));
+    let changed = edit_series(&repo, &repo, &repo, sid, time!(12:00), "T2", 5, today).unwrap();
+    assert_eq!(changed.series.reservation_time, time!(12:00));
+    assert_eq!(
+        changed
+            .booked
+            .iter()
+            .map(|resv| resv.id)
+            .collect::<Vec<_>>(),
+        [first, second]
+    );
+    assert_eq!(changed.skipped.len(), 1);
+    assert_eq!(changed.skipped[0].date, weeks(start, 2));
+    let kept = ReservationRepository::find(&repo, third).unwrap();
+    assert_eq!((kept.seating.as_str(), kept.party_size), ("T1", 4));
+    assert_eq!(repo.find_series(sid).unwrap().party_size, 5);
+
+    assert!(matches!(
+        edit_series(&repo, &repo, &repo, sid, time!(12:00), "Bar", 2, today),
+        Err(RepositoryError::Invalid(_))
+    ));
+}
+
+#[test]
+fn one_occurrence_or_the_whole_series_can_be_cancelled() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+    let schedule = book_series(&repo, lunches(pid, today, 4), today).unwrap();
+    let sid = schedule.series.series_id;
+
+    let cancelled = cancel_occurrence(&repo, schedule.booked[1].id).unwrap();
+    assert_eq!(cancelled.status, ReservationStatus::Cancelled);
+    assert_eq!(
+        repo.history(cancelled.id).unwrap()[0].summary,
+        "cancelled, this occurrence only"
+    );
+
+    // Cancelling from the third week on leaves the earlier lunches alone.
+    let later = weeks(today, 2);
+    let cancelled = cancel_series(
+        &repo,
+        &repo,
+        sid,
+        later,
+        later.and_hms_opt(9, 0, 0).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        cancelled.iter().map(|resv| resv.id).collect::<Vec<_>>(),
+        [schedule.booked[2].id, schedule.booked[3].id]
+    );
+    assert_eq!(
+        repo.history(schedule.booked[3].id).unwrap()[0].summary,
+        "cancelled with its series"
+    );
+    let statuses: Vec<ReservationStatus> = repo
+        .occurrences(sid)
+        .unwrap()
+        .into_iter()
+        .map(|resv| resv.status)
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            ReservationStatus::Booked,
+            ReservationStatus::Cancelled,
+            ReservationStatus::Cancelled,
+            ReservationStatus::Cancelled
+        ]
+    );
+    assert!(repo.find_series(sid).unwrap().cancelled_at.is_some());
+    assert!(matches!(
+        cancel_series(
+            &repo,
+            &repo,
+            sid,
+            later,
+            later.and_hms_opt(9, 0, 0).unwrap()
+        ),
+        Err(RepositoryError::Conflict(_))
+    ));
+    assert!(matches!(
+        edit_series(&repo, &repo, &repo, sid, time!(12:00), "", 2, today),
+        Err(RepositoryError::Conflict(_))
+    ));
+}
+
+#[test]
+fn a_series_stays_open_until_every_occurrence_is_cancelled() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+    let schedule = book_series(&repo, lunches(pid, today, 3), today).unwrap();
+    let sid = schedule.series.series_id;
+    let now = today.and_hms_opt(9, 0, 0).unwrap();
+
+    // The second lunch cannot be cancelled, the others still are.
+    let stuck = Stuck {
+        repo,
+        id: schedule.booked[1].id,
+    };
+    assert!(matches!(
+        cancel_series(&stuck, &stuck.repo, sid, today, now),
+        Err(RepositoryError::Conflict(_))
+    ));
+    let statuses = |repo: &InMemoryRepository| -> Vec<ReservationStatus> {
+        repo.occurrences(sid)
+            .unwrap()
+            .into_iter()
+            .map(|resv| resv.status)
+            .collect()
+    };
+    assert_eq!(
+        statuses(&stuck.repo),
+        [
+            ReservationStatus::Cancelled,
+            ReservationStatus::Booked,
+            ReservationStatus::Cancelled
+        ]
+    );
+    assert_eq!(stuck.repo.find_series(sid).unwrap().cancelled_at, None);
+
+    // Cancelling again picks up the rest and closes the series.
+    let repo = stuck.repo;
+    let cancelled = cancel_series(&repo, &repo, sid, today, now).unwrap();
+    assert_eq!(
+        cancelled.iter().map(|resv| resv.id).collect::<Vec<_>>(),
+        [schedule.booked[1].id]
+    );
+    assert_eq!(statuses(&repo), [ReservationStatus::Cancelled; 3]);
+    assert_eq!(repo.find_series(sid).unwrap().cancelled_at, Some(now));
+}
+
+#[test]
+fn a_deleted_series_takes_its_occurrences_along() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+    let other = book(&repo, booking(pid, "T2", today, time!(12:30)));
+    let schedule = book_series(&repo, lunches(pid, today, 2), today).unwrap();
+    let sid = schedule.series.series_id;
+    repo.insert_history(NewHistoryEntry {
+        reservation_id: schedule.booked[0].id,
+        changed_by: HistoryEntry::STAFF.to_string(),
+        summary: "moved".into(),
+    })
+    .unwrap();
+
+    repo.delete_series(sid).unwrap();
+    assert_eq!(
+        repo.find_series(sid).unwrap_err(),
+        RepositoryError::NotFound
+    );
+    assert!(repo.occurrences(sid).unwrap().is_empty());
+    assert_eq!(
+        ReservationRepository::find(&repo, schedule.booked[0].id).unwrap_err(),
+        RepositoryError::NotFound
+    );
+    assert!(repo.history(schedule.booked[0].id).unwrap().is_empty());
+    assert!(ReservationRepository::find(&repo, other).is_ok());
+    assert_eq!(
+        repo.delete_series(sid).unwrap_err(),
+        RepositoryError::NotFound
+    );
+}
@@ -0,0 +1,191 @@
+//! Queues and sends guest texts through the in-memory channel and through an
+//! HTTP gateway listening on localhost.
+mod common;
+
+use axum::{extract::State, http::HeaderMap, routing::post, Json, Router};
+use chrono::{Duration, Local, NaiveDateTime, Utc};
+use common::{booking_by, property, store, time_of};
+use resvm::models::{MessageStatus, NotificationKind, ReservationStatus, WebhookEvent};
+use resvm::repository::{InMemoryRepository, ReservationRepository, SmsRepository};
+use resvm::sms::{
+    deliver_due, normalize_phone, notify, record_reply, record_status, HttpGateway, MemoryChannel,
+    NotificationChannel,
+};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Stores a booking by Ada for two, reached at `contact`.
+fn book(repo: &InMemoryRepository, pid: Uuid, contact: &str, at: NaiveDateTime) -> i32 {
+    store(
+        repo,
+        booking_by(pid, "Ada", contact, at.date(), time_of(at)),
+    )
+}
+
+#[test]
+fn phone_numbers_are_normalized_to_e164() {
+    let uk = Some("44");
+    assert_eq!(
+        normalize_phone("+44 20 7946 0000", None).as_deref(),
+        Some("+442079460000")
+    );
+    assert_eq!(
+        normalize_phone("+44 (0)20-7946-0000", None).as_deref(),
+        Some("+442079460000")
+    );
+    assert_eq!(
+        normalize_phone("0044 20 7946 0000", None).as_deref(),
+        Some("+442079460000")
+    );
+    assert_eq!(
+        normalize_phone("020 7946 0000", uk).as_deref(),
+        Some("+442079460000")
+    );
+    assert_eq!(
+        normalize_phone("(020) 7946.0000", Some("+44")).as_deref(),
+        Some("+442079460000")
+    );
+    // National numbers need a calling code to go with.
+    assert_eq!(normalize_phone("020 7946 0000", None), None);
+    assert_eq!(normalize_phone("ada@example.com", uk), None);
+    assert_eq!(normalize_phone("+44 20 +7946", None), None);
+    assert_eq!(normalize_phone("+123", None), None);
+    assert_eq!(normalize_phone("+1234567890123456", None), None);
+    assert_eq!(normalize_phone("", uk), None);
+}
+
+#[test]
+fn texts_are_sent_tracked_and_respect_opt_outs() {
+    let repo = InMemoryRepository::new();
+    let channel = MemoryChannel::new();
+    let pid = property(&repo);
+
+    // Phone contacts get a confirmation, email contacts no text.
+    let in_two_days = Local::now().naive_local() + Duration::days(2);
+    let rid = book(&repo, pid, "+44 20 7946 0000", in_two_days);
+    let resv = ReservationRepository::find(&repo, rid).unwrap();
+    notify(&repo, &repo, "sms", WebhookEvent::ReservationCreated, &resv);
+    let emailed = book(&repo, pid, "ada@example.com", in_two_days);
+    let emailed = ReservationRepository::find(&repo, emailed).unwrap();
+    notify(
+        &repo,
+        &repo,
+        "sms",
+        WebhookEvent::ReservationCreated,
+        &emailed,
+    );
+    assert_eq!(deliver_due(&repo, &channel).unwrap(), 1);
+    let (to, body) = channel.sent().pop().unwrap();
+    assert_eq!(to, "+442079460000");
+    assert!(body.starts_with("Chez Ada: "));
+    assert!(body.contains(resv.confirmation_code.as_deref().unwrap()));
+    assert!(body.ends_with("Reply STOP to opt out."));
+
+    // The gateway's delivery report is stored against the message.
+    let message = repo.messages(pid, 10).unwrap().pop().unwrap();
+    assert_eq!(message.reservation_id, Some(rid));
+    assert_eq!(message.kind, NotificationKind::Confirmation);
+    assert_eq!(message.status, MessageStatus::Sent);
+    let provider_id = message.provider_id.unwrap();
+    let now = Utc::now().naive_utc();
+    record_status(&repo, &provider_id, "sent", None, now).unwrap();
+    let delivered = record_status(&repo, &provider_id, "delivered", None, now).unwrap();
+    assert_eq!(delivered.status, MessageStatus::Delivered);
+    assert_eq!(delivered.delivered_at, Some(now));
+    assert!(record_status(&repo, "unknown", "delivered", None, now).is_err());
+
+    // STOP opts the number out, texts are kept but not sent.
+    assert_eq!(
+        record_reply(&repo, "+442079460000", " stop ", now).unwrap(),
+        Some(true)
+    );
+    assert_eq!(
+        record_reply(&repo, "+442079460000", "thanks!", now).unwrap(),
+        None
+    );
+    let cancelled = repo.set_status(rid, ReservationStatus::Cancelled).unwrap();
+    notify(
+        &repo,
+        &repo,
+        "sms",
+        WebhookEvent::ReservationCancelled,
+        &cancelled,
+    );
+    assert_eq!(deliver_due(&repo, &channel).unwrap(), 0);
+    let skipped = repo.messages(pid, 10).unwrap().remove(0);
+    assert_eq!(skipped.kind, NotificationKind::Cancellation);
+    assert_eq!(skipped.status, MessageStatus::OptedOut);
+    assert_eq!(channel.sent().len(), 1);
+
+    // START opts back in; failed texts are retried.
+    assert_eq!(
+        record_reply(&repo, "+44 20 7946 0000", "START", now).unwrap(),
+        Some(false)
+    );
+    let again = book(&repo, pid, "+44 20 7946 0000", in_two_days);
+    let again = ReservationRepository::find(&repo, again).unwrap();
+    notify(
+        &repo,
+        &repo,
+        "sms",
+        WebhookEvent::ReservationCreated,
+        &again,
+    );
+    channel.set_failing(true);
+    assert_eq!(deliver_due(&repo, &channel).unwrap(), 1);
+    let confirmation = repo
+        .messages(pid, 10)
+        .unwrap()
+        .into_iter()
+        .find(|message| message.reservation_id == Some(again.id))
+        .unwrap();
+    assert_eq!(confirmation.kind, NotificationKind::Confirmation);
+    assert_eq!(confirmation.status, MessageStatus::Queued);
+    assert_eq!(confirmation.attempts, 1);
+    assert!(confirmation.last_error.is_some());
+    assert_eq!(deliver_due(&repo, &channel).unwrap(), 0);
+}
+
+type Received = Arc<Mutex<Vec<(HeaderMap, Value)>>>;
+
+async fn gateway(
+    State(received): State<Received>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Json<Value> {
+    received.lock().unwrap().push((headers, body));
+    Json(json!({ "id": "gw-1" }))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn the_http_gateway_posts_each_text() {
+    let received = Received::default();
+    let socket = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/messages", socket.local_addr().unwrap());
+    let app = Router::new()
+        .route("/messages", post(gateway))
+        .with_state(received.clone());
+    tokio::spawn(async move { axum::serve(socket, app).await.unwrap() });
+
+    let sent = tokio::task::spawn_blocking(move || {
+        let gateway = HttpGateway::new(url, "t0ken".into(), "ChezAda".into(), "whatsapp".into());
+        assert_eq!(gateway.name(), "whatsapp");
+        gateway.send("+442079460000", "See you soon")
+    })
+    .await
+    .unwrap();
+    assert_eq!(sent, Ok(Some("gw-1".to_string())));
+
+    let (headers, body) = received.lock().unwrap().pop().unwrap();
+    assert_eq!(headers.get("authorization").unwrap(), "Bearer t0ken");
+    assert_eq!(
+        body,
+        json!({
+            "channel": "whatsapp",
+            "from": "ChezAda",
+            "to": "+442079460000",
+            "body": "See you soon",
+        })
+    );
+}
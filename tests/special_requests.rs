@@ -0,0 +1,211 @@
+//! Special requests, staff notes, tags and the day sheet against the
+//! in-memory repository.
+mod common;
+
+use chrono::{Days, Local};
+use common::{book, booking_by, property};
+use resvm::crud_properties::create_reservation;
+use resvm::errors::RepositoryError;
+use resvm::guests::NoShowPolicy;
+use resvm::models::{Occasion, Reservation, ReservationStatus, SpecialRequests};
+use resvm::repository::{GuestRepository, InMemoryRepository, ReservationRepository};
+use resvm::special_requests::{add_note, day_sheet, parse_occasion, update_requests};
+use time::macros::time;
+use uuid::Uuid;
+
+#[test]
+fn requests_are_checked_when_booking() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+
+    let mut new_resv = booking_by(pid, "Ada", "+442079460000", today, time!(19:30))
+        .with_party_size(3)
+        .with_special_requests(SpecialRequests {
+            occasion: Some(Occasion::Birthday),
+            dietary: "  vegan ".into(),
+            high_chairs: 1,
+            ..SpecialRequests::default()
+        });
+    new_resv.tags = "vip, VIP,press".into();
+    let rid = book(&repo, new_resv);
+    let resv = ReservationRepository::find(&repo, rid).unwrap();
+    assert_eq!(resv.special_requests.dietary, "vegan");
+    assert_eq!(resv.tags, "vip, press");
+    assert_eq!(
+        resv.special_requests.summary(),
+        "Birthday; 1 high chair; vegan"
+    );
+
+    let too_many = booking_by(pid, "Ada", "+442079460000", today, time!(20:00))
+        .with_special_requests(SpecialRequests {
+            high_chairs: 3,
+            ..SpecialRequests::default()
+        });
+    assert!(matches!(
+        create_reservation(&repo, &repo, &repo, &NoShowPolicy::default(), too_many),
+        Err(RepositoryError::Invalid(_))
+    ));
+
+    assert_eq!(
+        parse_occasion(" anniversary").unwrap(),
+        Some(Occasion::Anniversary)
+    );
+    assert_eq!(parse_occasion("").unwrap(), None);
+    assert!(parse_occasion("wake").is_err());
+}
+
+#[test]
+fn tags_and_requests_are_updated_with_history() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+    let rid = book(
+        &repo,
+        booking_by(pid, "Ada", "+442079460000", today, time!(19:30)),
+    );
+
+    let resv = update_requests(
+        &repo,
+        rid,
+        SpecialRequests {
+            accessibility: "wheelchair".into(),
+            seating_request: "by the window".into(),
+            ..SpecialRequests::default()
+        },
+        "VIP",
+    )
+    .unwrap();
+    assert_eq!(resv.special_requests.accessibility, "wheelchair");
+    assert!(resv.has_tag("vip"));
+    assert!(!resv.has_tag("press"));
+    // Saving the same values again changes nothing.
+    update_requests(&repo, rid, resv.special_requests.clone(), "VIP").unwrap();
+    update_requests(&repo, rid, resv.special_requests.clone(), "VIP, regular").unwrap();
+    let history: Vec<String> = repo
+        .history(rid)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.summary)
+        .collect();
+    assert_eq!(
+        history,
+        [
+            "special requests and tags updated",
+            "tags set to \"VIP, regular\""
+        ]
+    );
+}
+
+#[test]
+fn tag_searches_look_past_the_latest_bookings() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let date = Local::now().date_naive() + Days::new(1);
+    let tagged: Vec<i32> = ["Regular", "press, VIP", "vip", "VIPs", ""]
+        .into_iter()
+        .map(|tags| {
+            let rid = book(
+                &repo,
+                booking_by(pid, "Ada", "ada@example.com", date, time!(19:00)),
+            );
+            repo.set_requests(rid, SpecialRequests::default(), tags.into())
+                .unwrap();
+            rid
+        })
+        .collect();
+
+    let ids = |found: Vec<Reservation>| found.into_iter().map(|resv| resv.id).collect::<Vec<_>>();
+    assert_eq!(ids(repo.with_tag(pid, " VIP ", 10).unwrap()), tagged[1..3]);
+    assert_eq!(ids(repo.with_tag(pid, "vip", 1).unwrap()), tagged[1..2]);
+    assert!(repo.with_tag(pid, "v_p", 10).unwrap().is_empty());
+    assert!(repo.with_tag(Uuid::new_v4(), "vip", 10).unwrap().is_empty());
+}
+
+#[test]
+fn notes_need_an_author_and_a_body() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let rid = book(
+        &repo,
+        booking_by(
+            pid,
+            "Ada",
+            "+442079460000",
+            Local::now().date_naive(),
+            time!(19:30),
+        ),
+    );
+
+    assert!(add_note(&repo, rid, " ", "Called to confirm").is_err());
+    assert!(add_note(&repo, rid, "Sam", "  ").is_err());
+    assert!(matches!(
+        add_note(&repo, rid + 1, "Sam", "Called to confirm"),
+        Err(RepositoryError::NotFound)
+    ));
+    let note = add_note(&repo, rid, " Sam ", "Called to confirm").unwrap();
+    assert_eq!(
+        (note.author.as_str(), note.body.as_str()),
+        ("Sam", "Called to confirm")
+    );
+    add_note(&repo, rid, "Priya", "Cake arrives at 19:00").unwrap();
+    let authors: Vec<String> = repo
+        .notes(rid)
+        .unwrap()
+        .into_iter()
+        .map(|note| note.author)
+        .collect();
+    assert_eq!(authors, ["Sam", "Priya"]);
+}
+
+#[test]
+fn day_sheet_lists_active_bookings_with_highlighted_tags() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+
+    let late = book(
+        &repo,
+        booking_by(pid, "Ada", "+442079460000", today, time!(21:00)),
+    );
+    let early = book(
+        &repo,
+        booking_by(pid, "Grace", "grace@example.com", today, time!(18:00)),
+    );
+    let cancelled = book(
+        &repo,
+        booking_by(pid, "Alan", "alan@example.com", today, time!(19:00)),
+    );
+    repo.set_status(cancelled, ReservationStatus::Cancelled)
+        .unwrap();
+    book(
+        &repo,
+        booking_by(
+            pid,
+            "Ada",
+            "+442079460000",
+            today + Days::new(1),
+            time!(19:00),
+        ),
+    );
+
+    let ada = ReservationRepository::find(&repo, late).unwrap();
+    let mut guest = repo.find_guest(ada.guest_id.unwrap()).unwrap();
+    guest.tags = "regular, vip".into();
+    repo.update_guest(&guest).unwrap();
+    update_requests(
+        &repo,
+        late,
+        SpecialRequests::default(),
+        "VIP, birthday cake",
+    )
+    .unwrap();
+    add_note(&repo, late, "Sam", "Table by the window").unwrap();
+
+    let sheet = day_sheet(&repo, &repo, pid, today).unwrap();
+    let ids: Vec<i32> = sheet.iter().map(|entry| entry.reservation.id).collect();
+    assert_eq!(ids, [early, late]);
+    assert!(sheet[0].tags.is_empty());
+    assert_eq!(sheet[1].tags, ["VIP", "birthday cake", "regular"]);
+    assert_eq!(sheet[1].notes.len(), 1);
+}
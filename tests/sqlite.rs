@@ -0,0 +1,352 @@
+//! Runs the SQLite migrations on an in-memory database and round-trips the
+//! repository through them.
+#![cfg(feature = "sqlite")]
+mod common;
+
+use chrono::{Duration, NaiveDate, Utc};
+use common::booking;
+use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::SqliteConnection;
+use resvm::migrations::{check_schema, run_migrations};
+use resvm::models::{
+    DeliveryStatus, HistoryEntry, JobKind, JobStatus, MessageStatus, NewEmail, NewGuest,
+    NewHistoryEntry, NewJob, NewProperty, NewPropertyTable, NewSmsMessage, NewWaitlistEntry,
+    NotificationKind, ReservationStatus, SpecialRequests, TableLayout, TableShape, WaitlistStatus,
+};
+use resvm::repository::{
+    EmailRepository, GuestRepository, JobRepository, PropertyRepository, ReservationRepository,
+    SmsRepository, SqliteRepository, WaitlistRepository,
+};
+use resvm::Database;
+use std::sync::Arc;
+use time::macros::time;
+use uuid::Uuid;
+
+/// A migrated in-memory database. Every connection to `:memory:` opens a
+/// database of its own, so the pool holds a single one.
+fn repository() -> SqliteRepository {
+    let pool = Arc::new(
+        Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .unwrap(),
+    );
+    let db = Database::Sqlite(pool.clone());
+    assert!(!run_migrations(&db).unwrap().is_empty());
+    check_schema(&db).unwrap();
+    SqliteRepository::new(pool)
+}
+
+fn property(repo: &SqliteRepository) -> Uuid {
+    PropertyRepository::insert(
+        repo,
+        NewProperty::new(
+            Uuid::new_v4(),
+            "Chez Ada".into(),
+            String::new(),
+            "host@chez-ada.test".into(),
+            String::new(),
+        ),
+    )
+    .unwrap()
+}
+
+fn date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2030, 5, 17).unwrap()
+}
+
+#[test]
+fn properties_keep_their_tables_and_layouts() {
+    let repo = repository();
+    let pid = property(&repo);
+    assert_eq!(
+        PropertyRepository::find(&repo, pid).unwrap().property_name,
+        "Chez Ada"
+    );
+    assert_eq!(repo.all().unwrap().len(), 1);
+    assert!(PropertyRepository::find(&repo, Uuid::new_v4()).is_err());
+
+    let t1 = repo
+        .insert_table(NewPropertyTable::new(pid, "T1".into(), 2, None))
+        .unwrap();
+    let t2 = repo
+        .insert_table(NewPropertyTable::new(
+            pid,
+            "T2".into(),
+            6,
+            Some("Terrace".into()),
+        ))
+        .unwrap();
+    repo.set_table_section(t1, Some("Bar".into())).unwrap();
+    let tables = repo.tables(pid).unwrap();
+    assert_eq!(
+        tables
+            .iter()
+            .map(|t| (t.table_id, t.capacity, t.section.as_deref()))
+            .collect::<Vec<_>>(),
+        [(t1, 2, Some("Bar")), (t2, 6, Some("Terrace"))]
+    );
+
+    let layout = TableLayout {
+        table_id: t2,
+        x: 120,
+        y: 40,
+        width: 90,
+        height: 90,
+        rotation: 45,
+        shape: TableShape::Round,
+    };
+    repo.save_layout(&layout).unwrap();
+    // Saving again replaces the table's layout.
+    let moved = TableLayout { x: 200, ..layout };
+    repo.save_layout(&moved).unwrap();
+    assert_eq!(repo.layouts(pid).unwrap(), [moved]);
+}
+
+#[test]
+fn reservations_keep_their_history_and_tables() {
+    let repo = repository();
+    let pid = property(&repo);
+    let t1 = repo
+        .insert_table(NewPropertyTable::new(pid, "T1".into(), 4, None))
+        .unwrap();
+    let t2 = repo
+        .insert_table(NewPropertyTable::new(pid, "T2".into(), 4, None))
+        .unwrap();
+
+    let rid = ReservationRepository::insert(
+        &repo,
+        booking(pid, "T1", date(), time!(19:30)).with_party_size(3),
+    )
+    .unwrap();
+    let resv = ReservationRepository::find(&repo, rid).unwrap();
+    assert_eq!(resv.name, "Grace");
+    assert_eq!(resv.party_size, 3);
+    assert_eq!(resv.reservation_time, time!(19:30));
+    assert_eq!(resv.status, ReservationStatus::Booked);
+    let token = resv.confirmation_token.clone().unwrap();
+    assert_eq!(repo.find_by_token(&token).unwrap().id, rid);
+
+    ReservationRepository::insert(
+        &repo,
+        booking(pid, "T2", date() + Duration::days(1), time!(12:00)),
+    )
+    .unwrap();
+    assert_eq!(repo.for_property(pid, 10).unwrap().len(), 2);
+    assert_eq!(repo.for_property(pid, 1).unwrap().len(), 1);
+    assert_eq!(repo.count_for_property(pid).unwrap(), 2);
+    repo.set_requests(rid, SpecialRequests::default(), "press, VIP".into())
+        .unwrap();
+    let tagged = repo.with_tag(pid, "vip", 10).unwrap();
+    assert_eq!(tagged.iter().map(|r| r.id).collect::<Vec<_>>(), [rid]);
+    assert!(repo.with_tag(pid, "v%", 10).unwrap().is_empty());
+    let on_date = repo.on_date(pid, date()).unwrap();
+    assert_eq!(on_date.iter().map(|r| r.id).collect::<Vec<_>>(), [rid]);
+
+    let seated = repo.set_status(rid, ReservationStatus::Seated).unwrap();
+    assert_eq!(seated.status, ReservationStatus::Seated);
+    let moved = repo
+        .reschedule(rid, date(), time!(20:00), "T2".into(), 4)
+        .unwrap();
+    assert_eq!(
+        (moved.reservation_time, moved.party_size),
+        (time!(20:00), 4)
+    );
+
+    repo.insert_history(NewHistoryEntry {
+        reservation_id: rid,
+        changed_by: HistoryEntry::STAFF.into(),
+        summary: "Moved to 20:00".into(),
+    })
+    .unwrap();
+    let history = repo.history(rid).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].changed_by, HistoryEntry::STAFF);
+    assert_eq!(history[0].summary, "Moved to 20:00");
+
+    repo.set_table_assignments(rid, &[t1, t2]).unwrap();
+    let held = |repo: &SqliteRepository| {
+        repo.table_assignments(&[rid])
+            .unwrap()
+            .into_iter()
+            .map(|a| a.table_id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(held(&repo), [t1, t2]);
+    repo.set_table_assignments(rid, &[t2]).unwrap();
+    assert_eq!(held(&repo), [t2]);
+}
+
+#[test]
+fn guests_are_found_by_any_contact() {
+    let repo = repository();
+    let pid = property(&repo);
+    let contacts = ["grace@example.com".to_string(), "+442079460000".to_string()];
+    let gid = repo
+        .insert_guest(NewGuest::new(pid, "Grace".into()), &contacts)
+        .unwrap();
+    assert_eq!(
+        repo.guest_by_contact(pid, "+442079460000")
+            .unwrap()
+            .guest_id,
+        gid
+    );
+    assert_eq!(
+        repo.guest_contacts(gid).unwrap(),
+        ["+442079460000", "grace@example.com"]
+    );
+
+    let mut guest = repo.find_guest(gid).unwrap();
+    guest.tags = "VIP, nut allergy".into();
+    repo.update_guest(&guest).unwrap();
+    assert_eq!(
+        repo.find_guest(gid).unwrap().tag_list().collect::<Vec<_>>(),
+        ["VIP", "nut allergy"]
+    );
+
+    let rid =
+        ReservationRepository::insert(&repo, booking(pid, "T1", date(), time!(19:00))).unwrap();
+    repo.set_reservation_guest(rid, gid).unwrap();
+    let booked = repo.guest_reservations(gid).unwrap();
+    assert_eq!(booked.iter().map(|r| r.id).collect::<Vec<_>>(), [rid]);
+    assert_eq!(repo.guests(pid).unwrap().len(), 1);
+}
+
+#[test]
+fn jobs_are_claimed_once_until_their_lock_expires() {
+    let repo = repository();
+    let pid = property(&repo);
+    let now = Utc::now().naive_utc();
+    let job_id = repo
+        .schedule_job(NewJob {
+            kind: JobKind::MarkNoShows,
+            property_id: Some(pid),
+            reservation_id: None,
+            unique_key: Some("no-shows".into()),
+            run_at: now - Duration::minutes(1),
+        })
+        .unwrap();
+    assert!(repo.has_job("no-shows").unwrap());
+    assert!(!repo.has_job("purge").unwrap());
+
+    let claimed = repo
+        .claim_jobs(now, now + Duration::minutes(5), 10)
+        .unwrap();
+    assert_eq!(
+        claimed.iter().map(|j| j.job_id).collect::<Vec<_>>(),
+        [job_id]
+    );
+    assert_eq!(claimed[0].status, JobStatus::Running);
+    assert!(repo
+        .claim_jobs(now, now + Duration::minutes(5), 10)
+        .unwrap()
+        .is_empty());
+
+    let mut job = repo.find_job(job_id).unwrap();
+    job.status = JobStatus::Done;
+    job.finished_at = Some(now);
+    repo.update_job(&job).unwrap();
+    assert_eq!(repo.jobs(pid, 10).unwrap()[0].status, JobStatus::Done);
+    assert_eq!(repo.purge_jobs(now + Duration::minutes(1)).unwrap(), 1);
+}
+
+#[test]
+fn emails_and_texts_are_queued_and_updated() {
+    let repo = repository();
+    let pid = property(&repo);
+    let rid =
+        ReservationRepository::insert(&repo, booking(pid, "T1", date(), time!(19:00))).unwrap();
+    let now = Utc::now().naive_utc();
+
+    let email_id = repo
+        .insert_email(NewEmail {
+            property_id: pid,
+            reservation_id: Some(rid),
+            kind: NotificationKind::Confirmation,
+            recipient: "grace@example.com".into(),
+            reply_to: Some("host@chez-ada.test".into()),
+            subject: "Your booking at Chez Ada".into(),
+            body: "See you soon".into(),
+            next_attempt_at: now,
+        })
+        .unwrap();
+    assert!(repo.has_email(rid, NotificationKind::Confirmation).unwrap());
+    assert!(!repo.has_email(rid, NotificationKind::Reminder).unwrap());
+    let mut email = repo.due_emails(now, 10).unwrap().pop().unwrap();
+    assert_eq!(email.email_id, email_id);
+    assert_eq!(email.status, DeliveryStatus::Pending);
+    email.status = DeliveryStatus::Delivered;
+    email.attempts = 1;
+    email.sent_at = Some(now);
+    repo.update_email(&email).unwrap();
+    assert!(repo.due_emails(now, 10).unwrap().is_empty());
+    assert_eq!(
+        repo.emails(pid, 10).unwrap()[0].status,
+        DeliveryStatus::Delivered
+    );
+
+    let message_id = repo
+        .insert_message(NewSmsMessage {
+            property_id: pid,
+            reservation_id: Some(rid),
+            kind: NotificationKind::Reminder,
+            channel: "sms".into(),
+            recipient: "+442079460000".into(),
+            body: "Chez Ada: see you tonight".into(),
+            status: MessageStatus::Queued,
+            next_attempt_at: now,
+        })
+        .unwrap();
+    assert!(repo.has_message(rid, NotificationKind::Reminder).unwrap());
+    let mut message = repo.due_messages(now, 10).unwrap().pop().unwrap();
+    assert_eq!(message.message_id, message_id);
+    message.status = MessageStatus::Sent;
+    message.provider_id = Some("SM123".into());
+    repo.update_message(&message).unwrap();
+    assert_eq!(
+        repo.message_by_provider_id("SM123").unwrap().message_id,
+        message_id
+    );
+    assert_eq!(repo.messages(pid, 10).unwrap().len(), 1);
+
+    assert!(!repo.opted_out("+442079460000").unwrap());
+    repo.set_opted_out("+442079460000", true, now).unwrap();
+    assert!(repo.opted_out("+442079460000").unwrap());
+    repo.set_opted_out("+442079460000", false, now).unwrap();
+    assert!(!repo.opted_out("+442079460000").unwrap());
+}
+
+#[test]
+fn waitlist_entries_move_through_their_states() {
+    let repo = repository();
+    let pid = property(&repo);
+    let entry_id = repo
+        .insert_waitlist_entry(NewWaitlistEntry {
+            property_id: pid,
+            waitlist_date: date(),
+            name: "Grace".into(),
+            contact: "grace@example.com".into(),
+            party_size: 2,
+            preferred_time: Some(time!(19:15)),
+            quoted_wait: 20,
+        })
+        .unwrap();
+    let waiting = repo.waitlist(pid, date()).unwrap();
+    assert_eq!(waiting.len(), 1);
+    assert_eq!(waiting[0].status, WaitlistStatus::Waiting);
+    assert_eq!(waiting[0].preferred_time, Some(time!(19:15)));
+    assert!(repo
+        .waitlist(pid, date() + Duration::days(1))
+        .unwrap()
+        .is_empty());
+
+    let rid =
+        ReservationRepository::insert(&repo, booking(pid, "T1", date(), time!(19:15))).unwrap();
+    let mut entry = repo.find_waitlist_entry(entry_id).unwrap();
+    entry.status = WaitlistStatus::Promoted;
+    entry.reservation_id = Some(rid);
+    repo.update_waitlist_entry(&entry).unwrap();
+    let promoted = repo.find_waitlist_entry(entry_id).unwrap();
+    assert_eq!(promoted.status, WaitlistStatus::Promoted);
+    assert_eq!(promoted.reservation_id, Some(rid));
+}
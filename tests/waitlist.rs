@@ -0,0 +1,284 @@
+//! The waitlist against the in-memory repository.
+mod common;
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use common::{book, booking, property_with_tables};
+use resvm::errors::RepositoryError;
+use resvm::guests::NoShowPolicy;
+use resvm::models::{NewWaitlistEntry, NotificationKind, ReservationStatus, WaitlistStatus};
+use resvm::repository::{
+    EmailRepository, InMemoryRepository, ReservationRepository, WaitlistRepository,
+};
+use resvm::waitlist::{add, notify, parse_time, promote, remove, waiting};
+use time::macros::time;
+use uuid::Uuid;
+
+fn property(repo: &InMemoryRepository) -> Uuid {
+    property_with_tables(repo, &[("T1", 2, None), ("T2", 4, None)])
+}
+
+fn entry(pid: Uuid, date: NaiveDate, contact: &str, party_size: i32) -> NewWaitlistEntry {
+    NewWaitlistEntry {
+        property_id: pid,
+        waitlist_date: date,
+        name: " Ada ".into(),
+        contact: contact.into(),
+        party_size,
+        preferred_time: Some(time!(19:30)),
+        quoted_wait: 20,
+    }
+}
+
+fn evening(date: NaiveDate) -> NaiveDateTime {
+    date.and_hms_opt(19, 0, 0).unwrap()
+}
+
+#[test]
+fn entries_are_checked_when_added() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+
+    let added = add(&repo, entry(pid, today, "ada@example.com", 2)).unwrap();
+    assert_eq!(added.name, "Ada");
+    assert_eq!(added.status, WaitlistStatus::Waiting);
+
+    assert!(matches!(
+        add(&repo, entry(pid, today, " ", 2)),
+        Err(RepositoryError::Invalid(_))
+    ));
+    assert!(matches!(
+        add(&repo, entry(pid, today, "ada@example.com", 0)),
+        Err(RepositoryError::Invalid(_))
+    ));
+    assert_eq!(repo.waitlist(pid, today).unwrap().len(), 1);
+
+    assert_eq!(parse_time("19:30").unwrap(), Some(time!(19:30)));
+    assert_eq!(parse_time("1930").unwrap(), Some(time!(19:30)));
+    assert_eq!(parse_time(" ").unwrap(), None);
+    assert!(parse_time("7pm").is_err());
+}
+
+#[test]
+fn a_freed_table_is_suggested() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+    let first = book(&repo, booking(pid, "T1", today, time!(19:00)));
+    book(&repo, booking(pid, "T2", today, time!(19:00)));
+    let added = add(&repo, entry(pid, today, "ada@example.com", 2)).unwrap();
+
+    let list = waiting(&repo, &repo, &repo, pid, today, evening(today)).unwrap();
+    assert_eq!(list[0].entry.entry_id, added.entry_id);
+    assert_eq!(list[0].free_table, None);
+
+    repo.set_status(first, ReservationStatus::Completed)
+        .unwrap();
+    let list = waiting(&repo, &repo, &repo, pid, today, evening(today)).unwrap();
+    assert_eq!(list[0].free_table.as_deref(), Some("T1"));
+
+    remove(&repo, added.entry_id).unwrap();
+    let list = waiting(&repo, &repo, &repo, pid, today, evening(today)).unwrap();
+    assert_eq!(list[0].entry.status, WaitlistStatus::Removed);
+    assert_eq!(list[0].free_table, None);
+}
+
+#[test]
+fn guests_are_notified_once_reachable() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+    let by_email = add(&repo, entry(pid, today, "ada@example.com", 2)).unwrap();
+    let by_phone = add(&repo, entry(pid, today, "+442079460000", 2)).unwrap();
+
+    let notified = notify(
+        &repo,
+        &repo,
+        Some(&repo),
+        None,
+        by_email.entry_id,
+        evening(today),
+    )
+    .unwrap();
+    assert_eq!(notified.status, WaitlistStatus::Notified);
+    assert_eq!(notified.notified_at, Some(evening(today)));
+    let emails = repo.emails(pid, 10).unwrap();
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].kind, NotificationKind::TableReady);
+    assert_eq!(emails[0].recipient, "ada@example.com");
+
+    assert!(matches!(
+        notify(
+            &repo,
+            &repo,
+            Some(&repo),
+            None,
+            by_phone.entry_id,
+            evening(today)
+        ),
+        Err(RepositoryError::Invalid(_))
+    ));
+    assert_eq!(
+        repo.find_waitlist_entry(by_phone.entry_id).unwrap().status,
+        WaitlistStatus::Waiting
+    );
+}
+
+#[test]
+fn promotion_books_the_free_table() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+    book(&repo, booking(pid, "T1", today, time!(19:00)));
+    let big = add(&repo, entry(pid, today, "ada@example.com", 6)).unwrap();
+    let added = add(&repo, entry(pid, today, "ada@example.com", 3)).unwrap();
+
+    assert!(matches!(
+        promote(
+            &repo,
+            &repo,
+            &repo,
+            &NoShowPolicy::default(),
+            &repo,
+            big.entry_id,
+            None,
+            None,
+            evening(today)
+        ),
+        Err(RepositoryError::Conflict(_))
+    ));
+
+    let resv = promote(
+        &repo,
+        &repo,
+        &repo,
+        &NoShowPolicy::default(),
+        &repo,
+        added.entry_id,
+        None,
+        None,
+        evening(today),
+    )
+    .unwrap();
+    assert_eq!(resv.seating, "T2");
+    assert_eq!(resv.party_size, 3);
+    assert_eq!(resv.reservation_time, time!(19:30));
+    assert!(resv.guest_id.is_some());
+    let promoted = repo.find_waitlist_entry(added.entry_id).unwrap();
+    assert_eq!(promoted.status, WaitlistStatus::Promoted);
+    assert_eq!(promoted.reservation_id, Some(resv.id));
+
+    assert!(matches!(
+        promote(
+            &repo,
+            &repo,
+            &repo,
+            &NoShowPolicy::default(),
+            &repo,
+            added.entry_id,
+            None,
+            None,
+            evening(today)
+        ),
+        Err(RepositoryError::Conflict(_))
+    ));
+    assert!(remove(&repo, added.entry_id).is_err());
+}
+
+#[test]
+fn promotion_onto_a_named_table_checks_it() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+    book(&repo, booking(pid, "T2", today, time!(18:00)));
+    let added = add(&repo, entry(pid, today, "ada@example.com", 3)).unwrap();
+    let promote_to = |seating: &str| {
+        promote(
+            &repo,
+            &repo,
+            &repo,
+            &NoShowPolicy::default(),
+            &repo,
+            added.entry_id,
+            None,
+            Some(seating.into()),
+            evening(today),
+        )
+    };
+
+    for seating in ["T9", "T1"] {
+        assert!(matches!(
+            promote_to(seating),
+            Err(RepositoryError::Invalid(_))
+        ));
+    }
+    assert!(matches!(
+        promote_to("t2"),
+        Err(RepositoryError::Conflict(_))
+    ));
+    assert_eq!(
+        repo.find_waitlist_entry(added.entry_id).unwrap().status,
+        WaitlistStatus::Waiting
+    );
+
+    let resv = promote(
+        &repo,
+        &repo,
+        &repo,
+        &NoShowPolicy::default(),
+        &repo,
+        added.entry_id,
+        Some(time!(20:00)),
+        Some("t2".into()),
+        evening(today),
+    )
+    .unwrap();
+    assert_eq!(
+        (resv.seating.as_str(), resv.reservation_time),
+        ("T2", time!(20:00))
+    );
+}
+
+#[test]
+fn tables_of_long_seated_parties_stay_taken() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let today = Local::now().date_naive();
+    let lingering = book(&repo, booking(pid, "T1", today, time!(16:30)));
+    repo.set_status(lingering, ReservationStatus::Seated)
+        .unwrap();
+    let added = add(&repo, entry(pid, today, "ada@example.com", 2)).unwrap();
+
+    // Seated more than two hours before 19:30, the party still holds T1.
+    let list = waiting(&repo, &repo, &repo, pid, today, evening(today)).unwrap();
+    assert_eq!(list[0].free_table.as_deref(), Some("T2"));
+    let promote_to = |seating: Option<&str>| {
+        promote(
+            &repo,
+            &repo,
+            &repo,
+            &NoShowPolicy::default(),
+            &repo,
+            added.entry_id,
+            None,
+            seating.map(str::to_string),
+            evening(today),
+        )
+    };
+    assert!(matches!(
+        promote_to(Some("T1")),
+        Err(RepositoryError::Conflict(_))
+    ));
+    assert_eq!(promote_to(None).unwrap().seating, "T2");
+
+    // Once they left, T1 is free again.
+    repo.set_status(lingering, ReservationStatus::Completed)
+        .unwrap();
+    let next = add(&repo, entry(pid, today, "grace@example.com", 2)).unwrap();
+    let list = waiting(&repo, &repo, &repo, pid, today, evening(today)).unwrap();
+    let next = list
+        .iter()
+        .find(|waiting| waiting.entry.entry_id == next.entry_id)
+        .unwrap();
+    assert_eq!(next.free_table.as_deref(), Some("T1"));
+}
@@ -0,0 +1,123 @@
+//! Seating walk-ins against the in-memory repository.
+mod common;
+
+use chrono::{Local, NaiveDateTime};
+use common::{book, booking, property_with_tables};
+use resvm::crud_properties::{seat_walk_in, WALK_IN};
+use resvm::errors::RepositoryError;
+use resvm::guests::NoShowPolicy;
+use resvm::models::{NotificationKind, Reservation, ReservationStatus, WebhookEvent};
+use resvm::repository::{InMemoryRepository, RepoResult, ReservationRepository};
+use time::macros::time;
+use uuid::Uuid;
+
+fn property(repo: &InMemoryRepository) -> Uuid {
+    property_with_tables(repo, &[("T1", 2, None), ("T2", 4, None)])
+}
+
+fn evening() -> NaiveDateTime {
+    Local::now().date_naive().and_hms_opt(19, 5, 42).unwrap()
+}
+
+/// Seats a walk-in this evening.
+fn walk_in(
+    repo: &InMemoryRepository,
+    pid: Uuid,
+    party_size: i32,
+    seating: &str,
+    name: &str,
+    contact: &str,
+) -> RepoResult<Reservation> {
+    seat_walk_in(
+        repo,
+        repo,
+        repo,
+        &NoShowPolicy::default(),
+        pid,
+        party_size,
+        seating,
+        name,
+        contact,
+        evening(),
+    )
+}
+
+#[test]
+fn walk_ins_are_seated_now_on_a_free_table() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+
+    let resv = walk_in(&repo, pid, 2, "", "", "").unwrap();
+    assert_eq!(resv.status, ReservationStatus::Seated);
+    assert_eq!(resv.seating, "T1");
+    assert_eq!(resv.reservation_date, evening().date());
+    assert_eq!(resv.reservation_time, time!(19:05));
+    assert_eq!(
+        (resv.name.as_str(), resv.contact.as_str()),
+        (WALK_IN, WALK_IN)
+    );
+    assert_eq!(resv.guest_id, None);
+
+    let next = walk_in(&repo, pid, 2, "", "Ada", "").unwrap();
+    assert_eq!(next.seating, "T2");
+    assert_eq!(next.name, "Ada");
+    assert!(matches!(
+        walk_in(&repo, pid, 2, "", "", ""),
+        Err(RepositoryError::Conflict(_))
+    ));
+    assert_eq!(repo.on_date(pid, evening().date()).unwrap().len(), 2);
+
+    // Walk-ins are not sent a confirmation.
+    assert_eq!(
+        NotificationKind::for_event(WebhookEvent::ReservationCreated, resv.status),
+        None
+    );
+}
+
+#[test]
+fn the_table_asked_for_must_be_free() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+
+    assert!(matches!(
+        walk_in(&repo, pid, 3, "T1", "", ""),
+        Err(RepositoryError::Invalid(_))
+    ));
+    let resv = walk_in(&repo, pid, 2, "t1", "", "").unwrap();
+    assert_eq!(resv.seating, "T1");
+    assert!(matches!(
+        walk_in(&repo, pid, 2, "T1", "", ""),
+        Err(RepositoryError::Conflict(_))
+    ));
+    assert!(matches!(
+        walk_in(&repo, pid, 2, "Bar", "", ""),
+        Err(RepositoryError::Invalid(_))
+    ));
+    assert!(matches!(
+        walk_in(&repo, pid, 0, "T2", "", ""),
+        Err(RepositoryError::Invalid(_))
+    ));
+
+    repo.set_status(resv.id, ReservationStatus::Completed)
+        .unwrap();
+    let again = walk_in(&repo, pid, 2, "", "", "+442079460000").unwrap();
+    assert_eq!(again.seating, "T1");
+    assert!(again.guest_id.is_some());
+}
+
+#[test]
+fn a_table_booked_soon_is_not_given_away() {
+    let repo = InMemoryRepository::new();
+    let pid = property(&repo);
+    let date = evening().date();
+    book(&repo, booking(pid, "T2", date, time!(20:30)));
+    book(&repo, booking(pid, "", date, time!(21:30)));
+
+    assert!(matches!(
+        walk_in(&repo, pid, 2, "T2", "", ""),
+        Err(RepositoryError::Conflict(_))
+    ));
+    // The booking at 21:30 is more than a turn away.
+    let resv = walk_in(&repo, pid, 2, "T1", "", "").unwrap();
+    assert_eq!(resv.seating, "T1");
+}
@@ -0,0 +1,140 @@
+//! Delivers webhooks to a listener on localhost, the same way a POS or CRM
+//! receives them.
+use axum::{body::Bytes, extract::State, http::HeaderMap, http::StatusCode, routing::post, Router};
+use chrono::{Duration, Utc};
+use resvm::models::{DeliveryStatus, NewWebhook, NewWebhookDelivery, WebhookEvent};
+use resvm::repository::{InMemoryRepository, WebhookRepository};
+use resvm::webhooks::{backoff, deliver_due, enqueue, replay, sign};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+#[derive(Clone, Default)]
+struct Listener {
+    status: Arc<Mutex<StatusCode>>,
+    received: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+}
+
+async fn receive(State(listener): State<Listener>, headers: HeaderMap, body: Bytes) -> StatusCode {
+    listener.received.lock().unwrap().push((headers, body));
+    *listener.status.lock().unwrap()
+}
+
+async fn listen(listener: Listener) -> String {
+    let socket = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", socket.local_addr().unwrap());
+    let app = Router::new()
+        .route("/hook", post(receive))
+        .with_state(listener);
+    tokio::spawn(async move { axum::serve(socket, app).await.unwrap() });
+    url
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> &'a str {
+    headers.get(name).unwrap().to_str().unwrap()
+}
+
+#[tokio::test]
+async fn deliveries_are_signed_retried_and_replayed() {
+    let listener = Listener::default();
+    *listener.status.lock().unwrap() = StatusCode::OK;
+    let url = listen(listener.clone()).await;
+
+    let repo = InMemoryRepository::new();
+    let client = reqwest::Client::new();
+    let pid = Uuid::new_v4();
+    let webhook_id = repo
+        .insert_webhook(NewWebhook {
+            property_id: pid,
+            url,
+            secret: "s3cret".into(),
+            events: WebhookEvent::mask(&[WebhookEvent::ReservationCreated]),
+        })
+        .unwrap();
+
+    // Only subscribed events are queued.
+    let data = json!({ "id": 7, "name": "Ada" });
+    assert_eq!(
+        enqueue(&repo, pid, WebhookEvent::PaymentRecorded, data.clone()).unwrap(),
+        0
+    );
+    assert_eq!(
+        enqueue(&repo, pid, WebhookEvent::ReservationCreated, data).unwrap(),
+        1
+    );
+
+    assert_eq!(deliver_due(&repo, &client).await.unwrap(), 1);
+    let (headers, body) = listener.received.lock().unwrap().pop().unwrap();
+    assert_eq!(header(&headers, "x-resvm-event"), "reservation.created");
+    let timestamp: i64 = header(&headers, "x-resvm-timestamp").parse().unwrap();
+    assert_eq!(
+        header(&headers, "x-resvm-signature"),
+        format!("sha256={}", sign("s3cret", timestamp, &body))
+    );
+    let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(payload["data"]["name"], "Ada");
+
+    let delivered = &repo.deliveries(webhook_id, 10).unwrap()[0];
+    assert_eq!(delivered.status, DeliveryStatus::Delivered);
+    assert_eq!(delivered.response_status, Some(200));
+
+    // A failing receiver keeps the delivery pending with a backoff.
+    *listener.status.lock().unwrap() = StatusCode::INTERNAL_SERVER_ERROR;
+    let replayed = replay(&repo, pid, delivered.delivery_id).unwrap();
+    assert_eq!(deliver_due(&repo, &client).await.unwrap(), 1);
+    let failed = repo.find_delivery(replayed).unwrap();
+    assert_eq!(failed.status, DeliveryStatus::Pending);
+    assert_eq!(failed.attempts, 1);
+    assert_eq!(failed.response_status, Some(500));
+    assert!(failed.next_attempt_at >= failed.created_at + backoff(1));
+    assert_eq!(deliver_due(&repo, &client).await.unwrap(), 0);
+
+    // Deliveries of another property cannot be replayed.
+    assert!(replay(&repo, Uuid::new_v4(), replayed).is_err());
+}
+
+#[tokio::test]
+async fn a_broken_delivery_does_not_hold_up_the_batch() {
+    let listener = Listener::default();
+    *listener.status.lock().unwrap() = StatusCode::OK;
+    let url = listen(listener.clone()).await;
+
+    let repo = InMemoryRepository::new();
+    let client = reqwest::Client::new();
+    let pid = Uuid::new_v4();
+    let webhook_id = repo
+        .insert_webhook(NewWebhook {
+            property_id: pid,
+            url,
+            secret: "s3cret".into(),
+            events: WebhookEvent::mask(&[WebhookEvent::ReservationCreated]),
+        })
+        .unwrap();
+    // A delivery whose webhook is gone comes first in the batch.
+    let orphan = repo
+        .insert_delivery(NewWebhookDelivery {
+            webhook_id: webhook_id + 1,
+            event: WebhookEvent::ReservationCreated.name().to_string(),
+            payload: json!({}),
+            next_attempt_at: Utc::now().naive_utc() - Duration::minutes(1),
+        })
+        .unwrap();
+    enqueue(
+        &repo,
+        pid,
+        WebhookEvent::ReservationCreated,
+        json!({ "id": 7 }),
+    )
+    .unwrap();
+
+    assert_eq!(deliver_due(&repo, &client).await.unwrap(), 1);
+    assert_eq!(listener.received.lock().unwrap().len(), 1);
+    assert_eq!(
+        repo.deliveries(webhook_id, 10).unwrap()[0].status,
+        DeliveryStatus::Delivered
+    );
+    assert_eq!(
+        repo.find_delivery(orphan).unwrap().status,
+        DeliveryStatus::Pending
+    );
+}